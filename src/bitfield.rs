@@ -0,0 +1,252 @@
+/// Extract a bitfield of a register into another register.
+///
+/// This evaluates to a `&'static str` which writes to `dst` the `width` bits
+/// of `src` starting at bit `lsb`, zero-extended, with the instructions of the
+/// target:
+///
+/// | Architecture          | Sequence                                                    |
+/// |-----------------------|-------------------------------------------------------------|
+/// | x86, x86-64           | `mov dst, src`, `shr dst, lsb`, `and dst, (1 << width) - 1` |
+/// | x86, x86-64 with BMI1 | `mov dst, lsb \| width << 8`, `bextr dst, src, dst`         |
+/// | ARM, AArch64          | `ubfx dst, src, #lsb, #width`                               |
+///
+/// `lsb` and `width` must be immediates or placeholders of `const` operands,
+/// as the masks and the control word of `bextr` are written as expressions of
+/// them, which the assembler folds into constants. `dst` and `src` are
+/// transcribed by [`asm_block!`], so they can be registers or placeholders of
+/// the same width, and `dst` must not be `src` with BMI1, which is detected
+/// with the target features of the crate. The immediate of `and` is
+/// sign-extended from 32 bits on x86-64, so a 64-bit field is at most 31 bits
+/// wide there.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::bfextract;
+/// # #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+/// # {
+/// let x: u64;
+/// unsafe {
+///     asm!(bfextract!({x}, {y}, 4, 8), x = out(reg) x, y = in(reg) 0xabcdu64, options(pure, nomem, nostack));
+/// }
+/// assert_eq!(x, 0xbc);
+/// # }
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! bfextract {
+    ($dst: tt, $src: tt, $lsb: tt, $width: tt $(,)?) => {
+        $crate::__asm_bitfield!(extract $dst $src $lsb $width)
+    };
+}
+
+/// Insert the low bits of a register into a bitfield of another register.
+///
+/// This evaluates to a `&'static str` which replaces the `width` bits of `dst`
+/// starting at bit `lsb` with the low `width` bits of `src`, leaving the other
+/// bits of `dst`, as in [`bfextract!`]:
+///
+/// | Architecture | Sequence                                                                      |
+/// |--------------|-------------------------------------------------------------------------------|
+/// | x86, x86-64  | `src` masked and shifted into `tmp`, `and dst, ~(mask << lsb)`, `or dst, tmp` |
+/// | ARM, AArch64 | `bfi dst, src, #lsb, #width`                                                  |
+///
+/// x86 has no such instruction, so a temporary register must be given after
+/// the width there, e.g. `bfinsert!(rax, rcx, 4, 8, rdx)`, which is ignored
+/// elsewhere. The same rules as for [`bfextract!`] apply to the arguments, and
+/// the mask of `dst` is sign-extended from 32 bits on x86-64, so a 64-bit
+/// field must end below bit 31 there.
+///
+/// # Example
+/// ```
+/// use asm_block::bfinsert;
+/// # #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+/// assert_eq!(bfinsert!({x}, {y}, 4, 8), "bfi {x}, {y}, # 4 , # 8 \n");
+/// ```
+///
+/// [`bfextract!`]: macro.bfextract.html
+#[macro_export]
+macro_rules! bfinsert {
+    ($dst: tt, $src: tt, $lsb: tt, $width: tt $(, $tmp: tt)? $(,)?) => {
+        $crate::__asm_bitfield!(insert $dst $src $lsb $width $($tmp)?)
+    };
+}
+
+/// Write the mask of a bitfield as an immediate.
+///
+/// This evaluates to a `&'static str` with an expression of the `bits` of
+/// the mask and its `shift`, which defaults to 0, so that fragments working
+/// on device registers can name their fields instead of spelling out magic
+/// numbers. The expression is folded into a constant by the assembler, so
+/// `bits` and `shift` can be literals or placeholders of `const` operands,
+/// and the mask is `(2 << (bits - 1)) - 1` rather than `(1 << bits) - 1`, so
+/// that it also holds when `bits` is 64. The result can be spliced into a
+/// block with `@inline(...)`, e.g. after `#` on ARM and AArch64, and the
+/// rules of the target for immediates still apply, e.g. the sign extension
+/// from 32 bits of most instructions of x86-64.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, mask};
+/// assert_eq!(mask!(bits = 12, shift = 20), "(((2 << (12 - 1)) - 1) << 20 )");
+/// assert_eq!(
+///     asm_block! { and {x:e}, @inline(mask!(bits = {n})); },
+///     "and {x:e}, (((2 << ({n}- 1)) - 1) << 0)\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! mask {
+    (bits = $bits: tt $(, shift = $shift: tt)? $(,)?) => {
+        concat!(
+            "(((2 << (",
+            $crate::asm_block!($bits),
+            "- 1)) - 1) << ",
+            $crate::mask!(@shift $($shift)?),
+            ")"
+        )
+    };
+    (@shift) => { "0" };
+    (@shift $shift: tt) => { $crate::asm_block!($shift) };
+    ($($token: tt)*) => {
+        compile_error!("expected `bits = <bits>`, optionally followed by `, shift = <shift>`")
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_bitfield {
+    (extract $dst: tt $src: tt $lsb: tt $width: tt) => {
+        $crate::__asm_bitfield_extract!($dst $src $lsb $width)
+    };
+    (insert $dst: tt $src: tt $lsb: tt $width: tt $tmp: tt) => {
+        concat!(
+            $crate::asm_block!(mov $tmp, $src; and $tmp, (1 << $width) - 1; shl $tmp, $lsb;),
+            $crate::asm_block!(and $dst,),
+            "~(((1 << ",
+            $crate::asm_block!($width),
+            ") - 1) << ",
+            $crate::asm_block!($lsb),
+            ")\n",
+            $crate::asm_block!(or $dst, $tmp;)
+        )
+    };
+    (insert $dst: tt $src: tt $lsb: tt $width: tt) => {
+        compile_error!("inserting a bitfield on x86 needs a temporary register, e.g. `bfinsert!(rax, rcx, 4, 8, rdx)`")
+    };
+}
+
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "bmi1")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_bitfield_extract {
+    ($dst: tt $src: tt $lsb: tt $width: tt) => {
+        $crate::asm_block!(mov $dst, $src; shr $dst, $lsb; and $dst, (1 << $width) - 1;)
+    };
+}
+
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "bmi1"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_bitfield_extract {
+    ($dst: tt $src: tt $lsb: tt $width: tt) => {
+        $crate::asm_block!(mov $dst, $lsb | $width << 8; bextr $dst, $src, $dst;)
+    };
+}
+
+#[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_bitfield {
+    (extract $dst: tt $src: tt $lsb: tt $width: tt) => {
+        $crate::asm_block!(ubfx $dst, $src, #$lsb, #$width;)
+    };
+    (insert $dst: tt $src: tt $lsb: tt $width: tt $($tmp: tt)?) => {
+        $crate::asm_block!(bfi $dst, $src, #$lsb, #$width;)
+    };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_bitfield {
+    ($($token: tt)*) => {
+        compile_error!("bitfields are only supported on x86, x86_64, ARM and AArch64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(bfextract, bfinsert, mask)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_bitfield() {
+        assert_eq!(
+            bfinsert!({d}, {s}, 4, 8, {t}),
+            concat!(
+                "mov {t}, {s}\n",
+                "and {t}, (1 << 8 ) - 1 \n",
+                "shl {t}, 4 \n",
+                "and {d}, ~(((1 << 8 ) - 1) << 4 )\n",
+                "or {d}, {t}\n",
+            )
+        );
+
+        let (x, mut y): (u64, u32);
+        y = 0x1234_5678;
+        unsafe {
+            core::arch::asm!(
+                bfextract!({x}, {s}, 12, 20),
+                bfinsert!({y:e}, {s:e}, {lsb}, 8, {t:e}),
+                x = out(reg) x,
+                y = inout(reg) y,
+                s = in(reg) 0xfedc_ba98_7654_3210u64,
+                t = out(reg) _,
+                lsb = const 16,
+                options(pure, nomem, nostack),
+            );
+        }
+        assert_eq!(x, 0x76543);
+        assert_eq!(y, 0x1210_5678);
+    }
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask!(bits = 1), "(((2 << (1 - 1)) - 1) << 0)");
+        assert_eq!(mask!(bits = {w}, shift = {s},), "(((2 << ({w}- 1)) - 1) << {s})");
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_mask_value() {
+        let (x, y, z): (u64, u64, u64);
+        unsafe {
+            core::arch::asm!(
+                concat!("mov {x}, ", mask!(bits = 12, shift = 20)),
+                concat!("mov {y}, ", mask!(bits = 64)),
+                concat!("mov {z}, ", mask!(bits = {n}, shift = 60)),
+                x = out(reg) x,
+                y = out(reg) y,
+                z = out(reg) z,
+                n = const 4,
+                options(pure, nomem, nostack),
+            );
+        }
+        assert_eq!(x, 0xfff0_0000);
+        assert_eq!(y, u64::MAX);
+        assert_eq!(z, 0xf000_0000_0000_0000);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_bitfield_assemble() {
+        use crate::test::{assert_assembles, Arch};
+        #[cfg(target_arch = "x86_64")]
+        let (arch, code) = (Arch::X86_64, concat!(bfextract!(eax, ecx, 0, 32), bfinsert!(rax, rcx, 20, 11, rdx)));
+        #[cfg(target_arch = "aarch64")]
+        let (arch, code) = (Arch::AArch64, concat!(bfextract!(w0, w1, 0, 32), bfinsert!(x0, x1, 20, 44)));
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        assert_assembles(arch, code);
+    }
+}
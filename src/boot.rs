@@ -0,0 +1,194 @@
+/// Emit a Multiboot2 header.
+///
+/// This evaluates to a `&'static str` which places the header in the given
+/// section, aligned to 8 bytes, with its length and checksum computed by the
+/// assembler from the labels around it, followed by the tags of the list,
+/// each padded to 8 bytes, and the end tag:
+///
+/// | Tag                                  | Type | Contents                                |
+/// |--------------------------------------|------|-----------------------------------------|
+/// | `info_request([id, ...])`            | 1    | types of the information requested      |
+/// | `entry(sym)`                         | 3    | 32-bit entry point                      |
+/// | `console(flags)`                     | 4    | console flags                           |
+/// | `framebuffer(width, height, depth)`  | 5    | preferred framebuffer mode              |
+/// | `module_align()`                     | 6    | modules aligned to pages                |
+/// | `efi_boot_services()`                | 7    | boot services not exited                |
+/// | `entry_efi32(sym)`                   | 8    | entry point of EFI i386                 |
+/// | `entry_efi64(sym)`                   | 9    | entry point of EFI amd64                |
+/// | `relocatable(min, max, align, pref)` | 10   | range and placement of a relocation     |
+///
+/// A tag is marked as optional, so that the boot loader may ignore it, by
+/// wrapping it in `optional(...)`. The numeric labels `42070` to `42073` are
+/// used, and the section must be placed in the first 32 KiB of the image by
+/// the linker script. The arguments are transcribed by [`asm_block!`], so the
+/// entry points can be symbols or placeholders of `sym` operands.
+///
+/// Limine boots kernels with this header as well, while its own protocol is
+/// made of requests in Rust statics, which need no assembly.
+///
+/// # Example
+/// ```
+/// use asm_block::multiboot2_header;
+/// # #[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+/// assert_eq!(
+///     multiboot2_header!(".multiboot2", [optional(framebuffer(1024, 768, 32)), entry(_start)]),
+///     ".pushsection .multiboot2, \"a\"\n.balign 8\n42070:\n\
+///      .long 0xe85250d6\n.long 0\n.long 42071f - 42070b\n\
+///      .long 0x100000000 - (0xe85250d6 + 0 + (42071f - 42070b))\n\
+///      .balign 8\n42072:\n.short 5, 1\n.long 42073f - 42072b\n.long 1024 , 768 , 32 \n42073:\n\
+///      .balign 8\n42072:\n.short 3, 0\n.long 42073f - 42072b\n.long _start \n42073:\n\
+///      .balign 8\n.short 0, 0\n.long 8\n42071:\n.popsection\n"
+/// );
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! multiboot2_header {
+    // one tag with its header, whose size is measured by the assembler
+    (@tag $flags: tt optional ($name: ident $args: tt)) => {
+        $crate::multiboot2_header!(@tag 1 $name $args)
+    };
+    (@tag $flags: tt info_request ([$($id: tt),+ $(,)?])) => {
+        $crate::multiboot2_header!(@wrap 1 $flags $crate::asm_block!(.long $($id),+;))
+    };
+    (@tag $flags: tt entry ($entry: tt)) => {
+        $crate::multiboot2_header!(@wrap 3 $flags $crate::asm_block!(.long $entry;))
+    };
+    (@tag $flags: tt console ($console: tt)) => {
+        $crate::multiboot2_header!(@wrap 4 $flags $crate::asm_block!(.long $console;))
+    };
+    (@tag $flags: tt framebuffer ($width: tt, $height: tt, $depth: tt $(,)?)) => {
+        $crate::multiboot2_header!(@wrap 5 $flags $crate::asm_block!(.long $width, $height, $depth;))
+    };
+    (@tag $flags: tt module_align ()) => {
+        $crate::multiboot2_header!(@wrap 6 $flags "")
+    };
+    (@tag $flags: tt efi_boot_services ()) => {
+        $crate::multiboot2_header!(@wrap 7 $flags "")
+    };
+    (@tag $flags: tt entry_efi32 ($entry: tt)) => {
+        $crate::multiboot2_header!(@wrap 8 $flags $crate::asm_block!(.long $entry;))
+    };
+    (@tag $flags: tt entry_efi64 ($entry: tt)) => {
+        $crate::multiboot2_header!(@wrap 9 $flags $crate::asm_block!(.long $entry;))
+    };
+    (@tag $flags: tt relocatable ($min: tt, $max: tt, $align: tt, $preference: tt $(,)?)) => {
+        $crate::multiboot2_header!(@wrap 10 $flags $crate::asm_block!(.long $min, $max, $align, $preference;))
+    };
+    (@tag $flags: tt $name: ident $args: tt) => {
+        compile_error!(concat!("unknown Multiboot2 tag `", stringify!($name), stringify!($args), "`"))
+    };
+    (@wrap $type: literal $flags: literal $body: expr) => {
+        concat!(
+            ".balign 8\n42072:\n.short ", $type, ", ", $flags, "\n.long 42073f - 42072b\n",
+            $body,
+            "42073:\n"
+        )
+    };
+
+    ($section: literal, [$($name: ident $args: tt),* $(,)?] $(,)?) => {
+        concat!(
+            $crate::__asm_boot!(@begin $section),
+            ".balign 8\n42070:\n",
+            ".long 0xe85250d6\n.long ", $crate::__asm_boot!(@architecture), "\n.long 42071f - 42070b\n",
+            ".long 0x100000000 - (0xe85250d6 + ", $crate::__asm_boot!(@architecture), " + (42071f - 42070b))\n",
+            $($crate::multiboot2_header!(@tag 0 $name $args),)*
+            ".balign 8\n.short 0, 0\n.long 8\n42071:\n",
+            $crate::__asm_section!(@end)
+        )
+    };
+}
+
+/// Emit a Multiboot header, of the first version of the protocol.
+///
+/// This evaluates to a `&'static str` which places the magic, the flags and
+/// their checksum in the given section, aligned to 4 bytes, which must be
+/// placed in the first 8 KiB of the image. The flags are transcribed by
+/// [`asm_block!`], e.g. `3` to request modules aligned to pages and the map of
+/// the memory. The fields of the address and of the video mode are not
+/// emitted, so bits 2 and 16 of the flags must not be set.
+///
+/// # Example
+/// ```
+/// use asm_block::multiboot_header;
+/// # #[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+/// assert_eq!(
+///     multiboot_header!(".multiboot", flags = 3),
+///     ".pushsection .multiboot, \"a\"\n.balign 4\n\
+///      .long 0x1badb002\n.long 3 \n.long 0x100000000 - (0x1badb002 + 3 )\n.popsection\n"
+/// );
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! multiboot_header {
+    ($section: literal, flags = $flags: tt $(,)?) => {
+        concat!(
+            $crate::__asm_boot!(@begin $section),
+            ".balign 4\n.long 0x1badb002\n",
+            $crate::asm_block!(.long $flags;),
+            ".long 0x100000000 - (0x1badb002 + ",
+            $crate::asm_block!($flags),
+            ")\n",
+            $crate::__asm_section!(@end)
+        )
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_boot {
+    (@begin $section: literal) => { $crate::__asm_section!(@begin $section, "a") };
+    (@architecture) => { 0 };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_boot {
+    ($($token: tt)*) => {
+        compile_error!("Multiboot headers are only supported on x86 and x86_64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(multiboot2_header, multiboot_header)]
+mod tests {
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_multiboot() {
+        let (magic, length, sum, tag, end): (u32, u32, u32, u64, u64);
+        unsafe {
+            core::arch::asm!(
+                "lea {p}, [rip + 42070f]",
+                multiboot2_header!(".rodata.multiboot2", [
+                    info_request([4, 6]),
+                    optional(module_align()),
+                    relocatable(0x100000, 0xffffffff, 4096, 1),
+                ]),
+                "mov {m:e}, [{p}]",
+                "mov {l:e}, [{p} + 8]",
+                "mov {s:e}, [{p}]",
+                "add {s:e}, [{p} + 4]",
+                "add {s:e}, [{p} + 8]",
+                "add {s:e}, [{p} + 12]",
+                "mov {t}, [{p} + 16]",
+                "add {p}, {l:r}",
+                "mov {e}, [{p} - 8]",
+                p = out(reg) _,
+                m = out(reg) magic,
+                l = out(reg) length,
+                s = out(reg) sum,
+                t = out(reg) tag,
+                e = out(reg) end,
+                options(pure, readonly, nostack),
+            );
+        }
+        assert_eq!(magic, 0xe852_50d6);
+        assert_eq!(length, 16 + 16 + 8 + 24 + 8);
+        assert_eq!(sum, 0);
+        assert_eq!(tag, 0x0000_0010_0000_0001);
+        assert_eq!(end, 0x0000_0008_0000_0000);
+    }
+}
@@ -0,0 +1,257 @@
+/// Request the encoding of an x86 branch.
+///
+/// This evaluates to a `&'static str` with the branch prefixed by the `{disp8}`
+/// or `{disp32}` pseudo prefix of LLVM, which is understood in both the Intel
+/// and the AT&T syntax, unlike the `short` keyword of Intel syntax which has
+/// no counterpart in AT&T syntax. The braces are escaped, so the result must
+/// go through an [`asm!`] or [`global_asm!`] template.
+///
+/// `near` always produces a 32-bit displacement, e.g. to reserve room in a
+/// patch site. `short` is only a preference: the assembler still relaxes the
+/// branch to a 32-bit displacement if the target is out of range, which can
+/// be caught by checking the size of the encoding with the `std` feature.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_branch};
+/// let code = asm_block! {
+///     @inline(asm_branch!(near jne 1f));
+///     @inline(asm_branch!(short jmp {target}));
+///     1:
+/// };
+/// assert_eq!(code, "{{disp32}} jne 1f \n{{disp8}} jmp {target}\n1 :");
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+/// [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+#[macro_export]
+macro_rules! asm_branch {
+    (short $mnemonic: ident $($target: tt)+) => {
+        concat!("{{disp8}} ", $crate::asm_block!($mnemonic $($target)+))
+    };
+    (near $mnemonic: ident $($target: tt)+) => {
+        concat!("{{disp32}} ", $crate::asm_block!($mnemonic $($target)+))
+    };
+    ($($token: tt)*) => {
+        compile_error!("expected `short` or `near` followed by a branch, e.g. `near jne 1f`")
+    };
+}
+
+/// Emit a veneer reaching a target at any distance on AArch64 or ARM.
+///
+/// Direct branches only reach ±128 MiB on AArch64 and ±32 MiB on ARM, which a
+/// large [`global_asm!`] body or a patched image may exceed. This evaluates to
+/// a `&'static str` defining the label `name`, from which the code loads the
+/// absolute address of the target from a literal next to it and branches
+/// there, so that branches to `name` reach the target:
+/// - `aarch64` loads the address into `x16`, which the procedure call
+///   standard reserves for this purpose, and branches with `br`.
+/// - `arm` loads the address into `pc`, which also switches to Thumb if the
+///   target is a Thumb function. The veneer itself is in A32, aligned to 4
+///   bytes, and the assembler is switched back to Thumb after it on targets
+///   with the `thumb-mode` feature.
+///
+/// The literal is placed under the numeric label `42110`, so that a `1f` of
+/// the surrounding code is not bound by the veneer.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_veneer;
+/// assert_eq!(
+///     asm_veneer!(aarch64, far_away_veneer, far_away),
+///     "far_away_veneer:ldr x16 , 42110f \nbr x16 \n.p2align 3 \n42110:\n.quad far_away \n"
+/// );
+/// ```
+///
+/// [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+#[macro_export]
+macro_rules! asm_veneer {
+    (aarch64, $name: tt, $target: tt $(,)?) => {
+        concat!(
+            $crate::asm_block! {
+                $name:
+                ldr x16, 42110f;
+                br x16;
+                .p2align 3;
+            },
+            "42110:\n",
+            $crate::asm_block!(.quad $target;)
+        )
+    };
+    (arm, $name: tt, $target: tt $(,)?) => {
+        concat!(
+            $crate::asm_block! {
+                .arm;
+                .balign 4;
+                $name:
+                ldr pc, 42110f;
+            },
+            "42110:\n",
+            $crate::asm_block!(.word $target;),
+            $crate::__asm_veneer_mode!()
+        )
+    };
+    ($($token: tt)*) => {
+        compile_error!("expected `aarch64` or `arm`, the name of the veneer and the target")
+    };
+}
+
+#[cfg(all(target_arch = "arm", target_feature = "thumb-mode"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_veneer_mode {
+    () => { ".thumb\n" };
+}
+
+#[cfg(not(all(target_arch = "arm", target_feature = "thumb-mode")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_veneer_mode {
+    () => { "" };
+}
+
+/// Add a condition code to an ARM or AArch64 mnemonic.
+///
+/// Fragments generic over a condition take it as a parameter, but
+/// `macro_rules!` cannot paste it onto the mnemonic, and the two architectures
+/// spell the result differently. This evaluates to a `&'static str` with the
+/// conditional mnemonic of the target:
+///
+/// | Architecture | `cc!(b, ne)` | `cc!(add, eq)` |
+/// |--------------|--------------|----------------|
+/// | ARM          | `bne`        | `addeq`        |
+/// | AArch64      | `b.ne`       | invalid        |
+///
+/// AArch64 only pastes conditions onto branches, e.g. `b.ne` or `bc.ne`,
+/// while the other instructions take the condition as an operand, e.g. `csel
+/// x0, x1, x2, ne`, which needs no pasting. The condition is one of `eq`, `ne`, `cs`,
+/// `hs`, `cc`, `lo`, `mi`, `pl`, `vs`, `vc`, `hi`, `ls`, `ge`, `lt`, `gt`,
+/// `le` and `al`, and anything else fails to compile. The result can be
+/// spliced into a block with `@inline(...)`.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, cc};
+/// macro_rules! branch_if {
+///     ($cond: ident, $target: tt) => {
+///         asm_block! {
+///             @inline(cc!(b, $cond)) $target;
+///         }
+///     };
+/// }
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(branch_if!(hs, 1f), "b.hs 1f \n");
+/// # #[cfg(target_arch = "arm")]
+/// assert_eq!(branch_if!(hs, 1f), "bhs 1f \n");
+/// ```
+#[macro_export]
+macro_rules! cc {
+    ($mnemonic: ident, $cond: ident $(,)?) => {
+        $crate::__asm_cc!($mnemonic $cond)
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cc {
+    ($mnemonic: ident $cond: ident) => { $crate::__asm_cond!(".", $mnemonic, $cond) };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cc {
+    ($mnemonic: ident $cond: ident) => { $crate::__asm_cond!("", $mnemonic, $cond) };
+}
+
+#[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cc {
+    ($($token: tt)*) => {
+        compile_error!("condition codes are only supported on ARM and AArch64")
+    };
+}
+
+// join the mnemonic and the condition with the separator of the target,
+// checking that the condition exists
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cond {
+    (@join $separator: literal, $mnemonic: ident, $cond: ident) => {
+        concat!(stringify!($mnemonic), $separator, stringify!($cond), " ")
+    };
+    ($separator: literal, $mnemonic: ident, eq) => { $crate::__asm_cond!(@join $separator, $mnemonic, eq) };
+    ($separator: literal, $mnemonic: ident, ne) => { $crate::__asm_cond!(@join $separator, $mnemonic, ne) };
+    ($separator: literal, $mnemonic: ident, cs) => { $crate::__asm_cond!(@join $separator, $mnemonic, cs) };
+    ($separator: literal, $mnemonic: ident, hs) => { $crate::__asm_cond!(@join $separator, $mnemonic, hs) };
+    ($separator: literal, $mnemonic: ident, cc) => { $crate::__asm_cond!(@join $separator, $mnemonic, cc) };
+    ($separator: literal, $mnemonic: ident, lo) => { $crate::__asm_cond!(@join $separator, $mnemonic, lo) };
+    ($separator: literal, $mnemonic: ident, mi) => { $crate::__asm_cond!(@join $separator, $mnemonic, mi) };
+    ($separator: literal, $mnemonic: ident, pl) => { $crate::__asm_cond!(@join $separator, $mnemonic, pl) };
+    ($separator: literal, $mnemonic: ident, vs) => { $crate::__asm_cond!(@join $separator, $mnemonic, vs) };
+    ($separator: literal, $mnemonic: ident, vc) => { $crate::__asm_cond!(@join $separator, $mnemonic, vc) };
+    ($separator: literal, $mnemonic: ident, hi) => { $crate::__asm_cond!(@join $separator, $mnemonic, hi) };
+    ($separator: literal, $mnemonic: ident, ls) => { $crate::__asm_cond!(@join $separator, $mnemonic, ls) };
+    ($separator: literal, $mnemonic: ident, ge) => { $crate::__asm_cond!(@join $separator, $mnemonic, ge) };
+    ($separator: literal, $mnemonic: ident, lt) => { $crate::__asm_cond!(@join $separator, $mnemonic, lt) };
+    ($separator: literal, $mnemonic: ident, gt) => { $crate::__asm_cond!(@join $separator, $mnemonic, gt) };
+    ($separator: literal, $mnemonic: ident, le) => { $crate::__asm_cond!(@join $separator, $mnemonic, le) };
+    ($separator: literal, $mnemonic: ident, al) => { $crate::__asm_cond!(@join $separator, $mnemonic, al) };
+    ($separator: literal, $mnemonic: ident, $cond: ident) => {
+        compile_error!(concat!("unknown condition code `", stringify!($cond), "`"))
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_branch, asm_veneer)]
+mod tests {
+    #[test]
+    fn test_branch() {
+        assert_eq!(asm_branch!(short jmp 2b), "{{disp8}} jmp 2b ");
+        assert_eq!(asm_branch!(near je {l}), "{{disp32}} je {l}");
+        assert_eq!(asm_branch!(short jae 1f), "{{disp8}} jae 1f ");
+    }
+
+    #[test]
+    fn test_veneer() {
+        assert_eq!(
+            asm_veneer!(aarch64, {v}, {f}),
+            "{v}:ldr x16 , 42110f \nbr x16 \n.p2align 3 \n42110:\n.quad {f}\n"
+        );
+        assert_eq!(
+            asm_veneer!(arm, v, f,),
+            ".arm \n.balign 4 \nv:ldr pc , 42110f \n42110:\n.word f \n"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_veneer_assemble() {
+        use crate::test::{assert_assembles, Arch};
+        // a `1f` of the surrounding Thumb code crosses the veneer
+        let code = concat!(
+            ".thumb\nb 1f\n",
+            asm_veneer!(arm, veneer, target),
+            ".thumb\n1:\nbl veneer\ntarget:\nbx lr\n"
+        );
+        assert_assembles(Arch::Arm, code);
+        let code = concat!("b 1f\n", asm_veneer!(aarch64, veneer, target), "1:\ntarget:\nret\n");
+        assert_assembles(Arch::AArch64, code);
+    }
+
+    #[test]
+    fn test_cond() {
+        assert_eq!(crate::__asm_cond!(".", b, ne), "b.ne ");
+        assert_eq!(crate::__asm_cond!(".", bc, al), "bc.al ");
+        assert_eq!(crate::__asm_cond!("", add, eq), "addeq ");
+        assert_eq!(crate::__asm_cond!("", ldr, lo), "ldrlo ");
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn test_cc() {
+        assert_eq!(cc!(b, ge), "b.ge ");
+    }
+}
@@ -0,0 +1,307 @@
+/// Call a function with the Microsoft x64 calling convention.
+///
+/// This evaluates to a `&'static str` which moves up to four arguments into
+/// `rcx`, `rdx`, `r8` and `r9`, allocates the 32 bytes of shadow space the
+/// callee may spill them to, calls the function, and releases the shadow
+/// space. The arguments and the function are transcribed by [`asm_block!`],
+/// so they can be placeholders, registers or immediates, and an argument
+/// already in its register, e.g. `rcx` first, is not moved.
+///
+/// With `align` first, the stack pointer is saved on the stack and aligned to
+/// 16 bytes around the call, and restored afterwards, for blocks which moved
+/// it, e.g. with `push`. This clobbers the flags, which the callee does anyway.
+///
+/// The moves are done in order, so an argument must not be read from the
+/// register of an earlier argument. The simplest way is to pass the arguments
+/// as explicit register operands, e.g. `in("rcx") a`, and name them here.
+/// [`asm!`] aligns the stack pointer for a call at the start of a block
+/// without `nostack`, which is kept by the shadow space, so the block must not
+/// have the `nostack` option, nor move the stack pointer before the call
+/// without `align`. The registers clobbered by the callee are declared with
+/// `clobber_abi("win64")`, and the result is in `rax`.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::call_win64;
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// extern "win64" fn sub(a: u64, b: u64) -> u64 {
+///     a - b
+/// }
+/// let r: u64;
+/// unsafe {
+///     asm!(
+///         call_win64!({f}, rcx, 3),
+///         f = sym sub,
+///         in("rcx") 5,
+///         out("rax") r,
+///         clobber_abi("win64"),
+///     );
+/// }
+/// assert_eq!(r, 2);
+/// # }
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+#[macro_export]
+macro_rules! call_win64 {
+    (align, $function: tt $(, $argument: tt)* $(,)?) => {
+        concat!(
+            $crate::__asm_call_arguments!([rcx rdx r8 r9] $($argument)*),
+            $crate::__asm_call_arguments!(@align),
+            "sub rsp, 32\n",
+            $crate::asm_block!(call $function;),
+            "add rsp, 32\n",
+            $crate::__asm_call_arguments!(@restore)
+        )
+    };
+    ($function: tt $(, $argument: tt)* $(,)?) => {
+        concat!(
+            $crate::__asm_call_arguments!([rcx rdx r8 r9] $($argument)*),
+            "sub rsp, 32\n",
+            $crate::asm_block!(call $function;),
+            "add rsp, 32\n"
+        )
+    };
+}
+
+/// Call a function with the System V AMD64 calling convention.
+///
+/// This evaluates to a `&'static str` which moves up to six arguments into
+/// `rdi`, `rsi`, `rdx`, `rcx`, `r8` and `r9`, and calls the function, like
+/// [`call_win64!`] does for the Microsoft x64 calling convention, including
+/// the realignment of the stack pointer with `align` first. The same rules
+/// apply, with the registers clobbered by the callee declared with
+/// `clobber_abi("sysv64")`, so that portable call sequences can pick either
+/// of them by target.
+///
+/// # Example
+/// ```
+/// use asm_block::call_sysv;
+/// assert_eq!(
+///     call_sysv!({f}, rdi, {x}, 0),
+///     "mov rsi , {x}\nmov rdx , 0 \ncall {f}\n"
+/// );
+/// ```
+///
+/// [`call_win64!`]: macro.call_win64.html
+#[macro_export]
+macro_rules! call_sysv {
+    (align, $function: tt $(, $argument: tt)* $(,)?) => {
+        concat!(
+            $crate::__asm_call_arguments!([rdi rsi rdx rcx r8 r9] $($argument)*),
+            $crate::__asm_call_arguments!(@align),
+            $crate::asm_block!(call $function;),
+            $crate::__asm_call_arguments!(@restore)
+        )
+    };
+    ($function: tt $(, $argument: tt)* $(,)?) => {
+        concat!(
+            $crate::__asm_call_arguments!([rdi rsi rdx rcx r8 r9] $($argument)*),
+            $crate::asm_block!(call $function;)
+        )
+    };
+}
+
+/// Call a function with the procedure call standard of AArch64.
+///
+/// This evaluates to a `&'static str` which moves up to eight arguments into
+/// `x0` to `x7`, and up to eight floating-point or vector arguments after a
+/// `;` into `v0` to `v7`, and calls the function with `bl`, or with `blr` for
+/// an address in a register written as `blr <register>`. Like the x86
+/// helpers, an argument already in its register is not moved, the moves are
+/// done in order, integer arguments first, and the vector arguments must be
+/// registers or placeholders of `vreg` operands, which are moved as a whole.
+///
+/// The stack pointer of AArch64 is always aligned to 16 bytes, so there is
+/// nothing to realign. The call overwrites the link register `x30`, which is
+/// declared along with the other registers clobbered by the callee with
+/// `clobber_abi("C")`, and the result is in `x0` or `v0`.
+///
+/// # Example
+/// ```
+/// use asm_block::call_aapcs64;
+/// assert_eq!(
+///     call_aapcs64!({f}, x0, {n}; {x}),
+///     "mov x1 , {n}\nmov v0.16b, {x}.16b\nbl {f}\n"
+/// );
+/// assert_eq!(call_aapcs64!(blr x9; v0, v1), "blr x9 \n");
+/// ```
+#[macro_export]
+macro_rules! call_aapcs64 {
+    (@call blr $function: tt) => {
+        $crate::asm_block!(blr $function;)
+    };
+    (@call $function: tt) => {
+        $crate::asm_block!(bl $function;)
+    };
+
+    (blr $function: tt $(, $argument: tt)* $(; $($vector: tt),* $(,)?)?) => {
+        concat!(
+            $crate::__asm_call_arguments!([x0 x1 x2 x3 x4 x5 x6 x7] $($argument)*),
+            $crate::__asm_call_arguments!(@vector [v0 v1 v2 v3 v4 v5 v6 v7] $($($vector)*)?),
+            $crate::call_aapcs64!(@call blr $function)
+        )
+    };
+    ($function: tt $(, $argument: tt)* $(; $($vector: tt),* $(,)?)?) => {
+        concat!(
+            $crate::__asm_call_arguments!([x0 x1 x2 x3 x4 x5 x6 x7] $($argument)*),
+            $crate::__asm_call_arguments!(@vector [v0 v1 v2 v3 v4 v5 v6 v7] $($($vector)*)?),
+            $crate::call_aapcs64!(@call $function)
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_call_arguments {
+    // save the stack pointer twice, so that it is at `[rsp + 8]` whether
+    // aligning it moved it by 8 bytes or not
+    (@align) => { "push rsp\npush qword ptr [rsp]\nand rsp, -16\n" };
+    (@restore) => { "mov rsp, [rsp + 8]\n" };
+    (@move rdi rdi) => { "" };
+    (@move rsi rsi) => { "" };
+    (@move rdx rdx) => { "" };
+    (@move rcx rcx) => { "" };
+    (@move r8 r8) => { "" };
+    (@move r9 r9) => { "" };
+    (@move x0 x0) => { "" };
+    (@move x1 x1) => { "" };
+    (@move x2 x2) => { "" };
+    (@move x3 x3) => { "" };
+    (@move x4 x4) => { "" };
+    (@move x5 x5) => { "" };
+    (@move x6 x6) => { "" };
+    (@move x7 x7) => { "" };
+    (@move $register: ident $argument: tt) => {
+        $crate::asm_block!(mov $register, $argument;)
+    };
+
+    ([$($register: ident)*]) => { "" };
+    ([$register: ident $($rest: ident)*] $argument: tt $($arguments: tt)*) => {
+        concat!(
+            $crate::__asm_call_arguments!(@move $register $argument),
+            $crate::__asm_call_arguments!([$($rest)*] $($arguments)*)
+        )
+    };
+    ([] $($arguments: tt)+) => {
+        compile_error!("too many arguments, passing arguments on the stack is not supported")
+    };
+
+    (@vector_move v0 v0) => { "" };
+    (@vector_move v1 v1) => { "" };
+    (@vector_move v2 v2) => { "" };
+    (@vector_move v3 v3) => { "" };
+    (@vector_move v4 v4) => { "" };
+    (@vector_move v5 v5) => { "" };
+    (@vector_move v6 v6) => { "" };
+    (@vector_move v7 v7) => { "" };
+    (@vector_move $register: ident $argument: ident) => {
+        concat!("mov ", stringify!($register), ".16b, ", stringify!($argument), ".16b\n")
+    };
+    (@vector_move $register: ident $argument: tt) => {
+        concat!("mov ", stringify!($register), ".16b, ", $crate::asm_block!($argument), ".16b\n")
+    };
+    (@vector [$($register: ident)*]) => { "" };
+    (@vector [$register: ident $($rest: ident)*] $argument: tt $($arguments: tt)*) => {
+        concat!(
+            $crate::__asm_call_arguments!(@vector_move $register $argument),
+            $crate::__asm_call_arguments!(@vector [$($rest)*] $($arguments)*)
+        )
+    };
+    (@vector [] $($arguments: tt)+) => {
+        compile_error!("too many vector arguments, passing arguments on the stack is not supported")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block, call_win64, call_sysv, call_aapcs64)]
+mod tests {
+    #[test]
+    fn test_call() {
+        assert_eq!(call_sysv!(foo), "call foo \n");
+        assert_eq!(
+            call_win64!({f}, {a}, rdx, 1, r9,),
+            "mov rcx , {a}\nmov r8 , 1 \nsub rsp, 32\ncall {f}\nadd rsp, 32\n"
+        );
+    }
+
+    #[test]
+    fn test_align() {
+        assert_eq!(
+            call_sysv!(align, {f}, {a}),
+            concat!(
+                "mov rdi , {a}\n",
+                "push rsp\n",
+                "push qword ptr [rsp]\n",
+                "and rsp, -16\n",
+                "call {f}\n",
+                "mov rsp, [rsp + 8]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_aapcs64() {
+        assert_eq!(call_aapcs64!(foo), "bl foo \n");
+        assert_eq!(
+            call_aapcs64!(blr {p}, {a}, x1; v3, v1, v2,),
+            "mov x0 , {a}\nmov v0.16b, v3.16b\nblr {p}\n"
+        );
+        #[cfg(feature = "std")]
+        crate::test::assert_assembles(crate::test::Arch::AArch64, call_aapcs64!(foo, 1, x5; v3));
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn test_call_aapcs64() {
+        extern "C" fn mad(a: u64, b: u64, c: f64) -> f64 {
+            (a * b) as f64 + c
+        }
+        let r: f64;
+        unsafe {
+            core::arch::asm!(
+                call_aapcs64!({f}, 6, x1; {c}),
+                f = sym mad,
+                c = in(vreg) 0.5f64,
+                in("x1") 7,
+                lateout("d0") r,
+                clobber_abi("C"),
+            );
+        }
+        assert_eq!(r, 42.5);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_call_sysv() {
+        extern "sysv64" fn mad(a: u64, b: u64, c: u64) -> u64 {
+            a * b + c
+        }
+        let r: u64;
+        unsafe {
+            core::arch::asm!(
+                call_sysv!({f}, 6, rsi, 4),
+                f = sym mad,
+                in("rsi") 7,
+                out("rax") r,
+                clobber_abi("sysv64"),
+            );
+        }
+        assert_eq!(r, 46);
+        let r: u64;
+        unsafe {
+            core::arch::asm!(
+                "push rax",
+                call_sysv!(align, {f}, 2, 3, 4),
+                "pop rcx",
+                f = sym mad,
+                out("rax") r,
+                clobber_abi("sysv64"),
+            );
+        }
+        assert_eq!(r, 10);
+    }
+}
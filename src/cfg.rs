@@ -0,0 +1,1317 @@
+/// Expand the first block whose predicate holds for the target.
+///
+/// This evaluates to a `&'static str` with the translation by [`asm_block!`]
+/// of the first block whose predicate holds, or of the block after `else`,
+/// or to an empty string if there is none, and can be spliced into a block
+/// with `@inline(...)`. It is the chained form of `@cfg(...)` in a block, as
+/// `if` and `else if`, so that the variants of a kernel stay in one fragment:
+/// only the selected block is expanded, and the others may use fragments
+/// which fail to compile for the target.
+///
+/// The predicates are written as for `cfg`, with `not(...)`, `all(...)` and
+/// `any(...)` around `unix`, `windows`, `target_arch = "..."`,
+/// `target_endian = "..."`, `target_pointer_width = "..."`,
+/// `target_os = "..."`, `target_env = "..."`, `target_family = "..."`, or
+/// `target_feature = "..."` for the following features, which fails to
+/// compile for the others since the predicates are answered by a macro per
+/// feature:
+///
+/// - x86, x86-64: `sse2`, `sse3`, `ssse3`, `sse4.1`, `sse4.2`, `popcnt`,
+///   `avx`, `avx2`, `fma`, `f16c`, `bmi1`, `bmi2`, `lzcnt`, `adx`, `aes`,
+///   `pclmulqdq`, `sha`, `avx512f`, `avx512bw`, `avx512vl`
+/// - AArch64: `neon`, `aes`, `crc`, `lse`, `rdm`, `dotprod`, `sha2`, `sha3`,
+///   `sve`, `sve2`
+/// - ARM: `v7`, `thumb2`, `dsp`, `mclass`, `neon`
+/// - RISC-V: `m`, `a`, `f`, `d`, `c`, `v`, `zba`, `zbb`, `zbs`
+///
+/// They are evaluated when this crate is compiled, which is for the same
+/// target as the crate using it, with the same target features, e.g. those
+/// enabled by `-C target-feature` or `-C target-cpu`. Features enabled for a
+/// function with `#[target_feature]` are not seen.
+///
+/// Likewise, `target_os` is answered on `linux`, `android`, `macos`, `ios`,
+/// `tvos`, `watchos`, `visionos`, `windows`, `freebsd`, `netbsd`, `openbsd`,
+/// `dragonfly`, `solaris`, `illumos`, `fuchsia`, `redox`, `haiku`, `hermit`,
+/// `uefi` and `none`, and `target_env` on `gnu`, `musl`, `msvc`, `uclibc`,
+/// `newlib`, `sgx` and `""`. On the other targets, they never hold.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_cfg};
+/// macro_rules! xor_block {
+///     ($dst: tt, $src: tt) => {
+///         asm_cfg! {
+///             target_feature = "avx2" => { vpxor $dst, $dst, [$src]; },
+///             target_feature = "sse2" => { pxor $dst, [$src]; },
+///             else => { @inline(compile_error!("SSE2 is required")) },
+///         }
+///     };
+/// }
+/// # #[cfg(all(target_feature = "sse2", not(target_feature = "avx2")))]
+/// assert_eq!(
+///     asm_block!(@inline(xor_block!({x}, {p})) add {p}, 16;),
+///     "pxor {x}, [{p}] \nadd {p}, 16 \n"
+/// );
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! asm_cfg {
+    () => { "" };
+    (else => { $($token: tt)* } $(,)?) => {
+        $crate::asm_block!($($token)*)
+    };
+    ($name: ident $(= $value: tt)? $(($($inner: tt)*))? => { $($token: tt)* } $(, $($rest: tt)*)?) => {
+        $crate::__asm_cfg!(
+            @eval [$name $(= $value)? $(($($inner)*))?]
+            [$crate::asm_block!($($token)*)]
+            [$crate::asm_cfg!($($($rest)*)?)]
+        )
+    };
+}
+
+// `macro_rules!` cannot evaluate `cfg` predicates, so every key is answered
+// by a hidden macro with one definition per value, of which only the one
+// matching the target of the crate is compiled, as `__asm_block_origin!` is
+// for the features. The branches are passed along as bracketed tokens and
+// only the selected one is transcribed, so the other is never expanded.
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg {
+    (@pick [$($tokens: tt)*]) => { $($tokens)* };
+
+    // evaluate the predicate, and pick `then` if it holds and `else` otherwise
+    (@eval [not ($($predicate: tt)*)] $then: tt $else: tt) => {
+        $crate::__asm_cfg!(@eval [$($predicate)*] $else $then)
+    };
+    (@eval [all ()] $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    (@eval [all ($name: ident $(= $value: tt)? $(($($inner: tt)*))? $(, $($rest: tt)*)?)] $then: tt $else: tt) => {
+        $crate::__asm_cfg!(
+            @eval [$name $(= $value)? $(($($inner)*))?]
+            [$crate::__asm_cfg!(@eval [all ($($($rest)*)?)] $then $else)]
+            $else
+        )
+    };
+    (@eval [any ()] $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+    (@eval [any ($name: ident $(= $value: tt)? $(($($inner: tt)*))? $(, $($rest: tt)*)?)] $then: tt $else: tt) => {
+        $crate::__asm_cfg!(
+            @eval [$name $(= $value)? $(($($inner)*))?]
+            $then
+            [$crate::__asm_cfg!(@eval [any ($($($rest)*)?)] $then $else)]
+        )
+    };
+    (@eval [target_arch = $value: tt] $then: tt $else: tt) => {
+        $crate::__asm_cfg_target_arch!($value $then $else)
+    };
+    (@eval [target_feature = $value: tt] $then: tt $else: tt) => {
+        $crate::__asm_cfg_target_feature!($value $then $else)
+    };
+    (@eval [target_endian = $value: tt] $then: tt $else: tt) => {
+        $crate::__asm_cfg_target_endian!($value $then $else)
+    };
+    (@eval [target_pointer_width = $value: tt] $then: tt $else: tt) => {
+        $crate::__asm_cfg_target_pointer_width!($value $then $else)
+    };
+    (@eval [target_os = $value: tt] $then: tt $else: tt) => {
+        $crate::__asm_cfg_target_os!($value $then $else)
+    };
+    (@eval [target_env = $value: tt] $then: tt $else: tt) => {
+        $crate::__asm_cfg_target_env!($value $then $else)
+    };
+    (@eval [target_family = $value: tt] $then: tt $else: tt) => {
+        $crate::__asm_cfg_target_family!($value $then $else)
+    };
+    (@eval [unix] $then: tt $else: tt) => { $crate::__asm_cfg_family_unix!($then $else) };
+    (@eval [windows] $then: tt $else: tt) => { $crate::__asm_cfg_family_windows!($then $else) };
+    (@eval [$($predicate: tt)*] $then: tt $else: tt) => {
+        compile_error!(concat!(
+            "unsupported predicate `",
+            stringify!($($predicate)*),
+            "`, expected `target_arch`, `target_feature`, `target_endian`, `target_pointer_width`, ",
+            "`target_os`, `target_env` or `target_family` with a value, `unix`, `windows`, ",
+            "`not(...)`, `all(...)` or `any(...)`"
+        ))
+    };
+}
+
+#[cfg(target_arch = "x86")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_arch {
+    ("x86" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_arch {
+    ("x86_64" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_arch {
+    ("arm" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_arch {
+    ("aarch64" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_arch = "riscv32")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_arch {
+    ("riscv32" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_arch = "riscv64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_arch {
+    ("riscv64" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_arch {
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_endian = "little")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_endian {
+    ("little" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_endian = "big")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_endian {
+    ("big" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_pointer_width = "16")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_pointer_width {
+    ("16" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_pointer_width = "32")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_pointer_width {
+    ("32" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_pointer_width = "64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_pointer_width {
+    ("64" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "linux")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("linux" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "android")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("android" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "macos")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("macos" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "ios")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("ios" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "tvos")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("tvos" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "watchos")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("watchos" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "visionos")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("visionos" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "windows")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("windows" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "freebsd")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("freebsd" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "netbsd")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("netbsd" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "openbsd")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("openbsd" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "dragonfly")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("dragonfly" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "solaris")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("solaris" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "illumos")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("illumos" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "fuchsia")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("fuchsia" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "redox")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("redox" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "haiku")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("haiku" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "hermit")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("hermit" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "uefi")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("uefi" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_os = "none")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ("none" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "tvos",
+    target_os = "watchos",
+    target_os = "visionos",
+    target_os = "windows",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly",
+    target_os = "solaris",
+    target_os = "illumos",
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "haiku",
+    target_os = "hermit",
+    target_os = "uefi",
+    target_os = "none"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_os {
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_env = "gnu")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ("gnu" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_env = "musl")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ("musl" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_env = "msvc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ("msvc" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_env = "uclibc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ("uclibc" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_env = "newlib")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ("newlib" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_env = "sgx")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ("sgx" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_env = "")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ("" $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(not(any(
+    target_env = "gnu",
+    target_env = "musl",
+    target_env = "msvc",
+    target_env = "uclibc",
+    target_env = "newlib",
+    target_env = "sgx",
+    target_env = ""
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_env {
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+// a target may belong to several families, so each has a pair of macros
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_family {
+    ("unix" $then: tt $else: tt) => { $crate::__asm_cfg_family_unix!($then $else) };
+    ("windows" $then: tt $else: tt) => { $crate::__asm_cfg_family_windows!($then $else) };
+    ("wasm" $then: tt $else: tt) => { $crate::__asm_cfg_family_wasm!($then $else) };
+    ($value: tt $then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_family = "unix")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_family_unix {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_family = "unix"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_family_unix {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_family = "windows")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_family_windows {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_family = "windows"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_family_windows {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_family = "wasm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_family_wasm {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_family_wasm {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+// only the features listed here can be tested, each by a pair of macros below
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_target_feature {
+    ("sse2" $then: tt $else: tt) => { $crate::__asm_cfg_sse2!($then $else) };
+    ("sse3" $then: tt $else: tt) => { $crate::__asm_cfg_sse3!($then $else) };
+    ("ssse3" $then: tt $else: tt) => { $crate::__asm_cfg_ssse3!($then $else) };
+    ("sse4.1" $then: tt $else: tt) => { $crate::__asm_cfg_sse4_1!($then $else) };
+    ("sse4.2" $then: tt $else: tt) => { $crate::__asm_cfg_sse4_2!($then $else) };
+    ("popcnt" $then: tt $else: tt) => { $crate::__asm_cfg_popcnt!($then $else) };
+    ("avx" $then: tt $else: tt) => { $crate::__asm_cfg_avx!($then $else) };
+    ("avx2" $then: tt $else: tt) => { $crate::__asm_cfg_avx2!($then $else) };
+    ("fma" $then: tt $else: tt) => { $crate::__asm_cfg_fma!($then $else) };
+    ("f16c" $then: tt $else: tt) => { $crate::__asm_cfg_f16c!($then $else) };
+    ("bmi1" $then: tt $else: tt) => { $crate::__asm_cfg_bmi1!($then $else) };
+    ("bmi2" $then: tt $else: tt) => { $crate::__asm_cfg_bmi2!($then $else) };
+    ("lzcnt" $then: tt $else: tt) => { $crate::__asm_cfg_lzcnt!($then $else) };
+    ("adx" $then: tt $else: tt) => { $crate::__asm_cfg_adx!($then $else) };
+    ("aes" $then: tt $else: tt) => { $crate::__asm_cfg_aes!($then $else) };
+    ("pclmulqdq" $then: tt $else: tt) => { $crate::__asm_cfg_pclmulqdq!($then $else) };
+    ("sha" $then: tt $else: tt) => { $crate::__asm_cfg_sha!($then $else) };
+    ("avx512f" $then: tt $else: tt) => { $crate::__asm_cfg_avx512f!($then $else) };
+    ("avx512bw" $then: tt $else: tt) => { $crate::__asm_cfg_avx512bw!($then $else) };
+    ("avx512vl" $then: tt $else: tt) => { $crate::__asm_cfg_avx512vl!($then $else) };
+    ("neon" $then: tt $else: tt) => { $crate::__asm_cfg_neon!($then $else) };
+    ("crc" $then: tt $else: tt) => { $crate::__asm_cfg_crc!($then $else) };
+    ("lse" $then: tt $else: tt) => { $crate::__asm_cfg_lse!($then $else) };
+    ("rdm" $then: tt $else: tt) => { $crate::__asm_cfg_rdm!($then $else) };
+    ("dotprod" $then: tt $else: tt) => { $crate::__asm_cfg_dotprod!($then $else) };
+    ("sha2" $then: tt $else: tt) => { $crate::__asm_cfg_sha2!($then $else) };
+    ("sha3" $then: tt $else: tt) => { $crate::__asm_cfg_sha3!($then $else) };
+    ("sve" $then: tt $else: tt) => { $crate::__asm_cfg_sve!($then $else) };
+    ("sve2" $then: tt $else: tt) => { $crate::__asm_cfg_sve2!($then $else) };
+    ("v7" $then: tt $else: tt) => { $crate::__asm_cfg_v7!($then $else) };
+    ("thumb2" $then: tt $else: tt) => { $crate::__asm_cfg_thumb2!($then $else) };
+    ("dsp" $then: tt $else: tt) => { $crate::__asm_cfg_dsp!($then $else) };
+    ("mclass" $then: tt $else: tt) => { $crate::__asm_cfg_mclass!($then $else) };
+    ("m" $then: tt $else: tt) => { $crate::__asm_cfg_m!($then $else) };
+    ("a" $then: tt $else: tt) => { $crate::__asm_cfg_a!($then $else) };
+    ("f" $then: tt $else: tt) => { $crate::__asm_cfg_f!($then $else) };
+    ("d" $then: tt $else: tt) => { $crate::__asm_cfg_d!($then $else) };
+    ("c" $then: tt $else: tt) => { $crate::__asm_cfg_c!($then $else) };
+    ("v" $then: tt $else: tt) => { $crate::__asm_cfg_v!($then $else) };
+    ("zba" $then: tt $else: tt) => { $crate::__asm_cfg_zba!($then $else) };
+    ("zbb" $then: tt $else: tt) => { $crate::__asm_cfg_zbb!($then $else) };
+    ("zbs" $then: tt $else: tt) => { $crate::__asm_cfg_zbs!($then $else) };
+    ($value: tt $then: tt $else: tt) => {
+        compile_error!(concat!("target feature ", stringify!($value), " cannot be tested by `asm_cfg!`"))
+    };
+}
+
+#[cfg(target_feature = "sse2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sse2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sse3")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse3 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sse3"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse3 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "ssse3")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_ssse3 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "ssse3"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_ssse3 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sse4.1")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse4_1 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sse4.1"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse4_1 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sse4.2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse4_2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sse4.2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sse4_2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "popcnt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_popcnt {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "popcnt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_popcnt {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "avx")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "avx"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "avx2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "avx2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "fma")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_fma {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "fma"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_fma {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "f16c")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_f16c {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "f16c"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_f16c {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "bmi1")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_bmi1 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "bmi1"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_bmi1 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "bmi2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_bmi2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "bmi2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_bmi2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "lzcnt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_lzcnt {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "lzcnt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_lzcnt {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "adx")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_adx {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "adx"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_adx {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "aes")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_aes {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "aes"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_aes {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "pclmulqdq")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_pclmulqdq {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "pclmulqdq"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_pclmulqdq {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sha")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sha {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sha"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sha {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "avx512f")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx512f {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "avx512f"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx512f {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "avx512bw")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx512bw {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "avx512bw"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx512bw {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "avx512vl")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx512vl {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "avx512vl"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_avx512vl {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "neon")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_neon {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "neon"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_neon {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "crc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_crc {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "crc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_crc {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "lse")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_lse {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "lse"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_lse {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "rdm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_rdm {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "rdm"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_rdm {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "dotprod")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_dotprod {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "dotprod"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_dotprod {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sha2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sha2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sha2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sha2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sha3")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sha3 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sha3"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sha3 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sve")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sve {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sve"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sve {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "sve2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sve2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "sve2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_sve2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "v7")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_v7 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "v7"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_v7 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "thumb2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_thumb2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "thumb2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_thumb2 {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "dsp")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_dsp {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "dsp"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_dsp {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "mclass")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_mclass {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "mclass"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_mclass {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "m")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_m {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "m"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_m {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "a")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_a {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "a"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_a {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "f")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_f {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "f"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_f {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "d")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_d {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "d"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_d {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "c")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_c {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "c"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_c {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "v")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_v {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "v"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_v {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "zba")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_zba {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "zba"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_zba {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "zbb")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_zbb {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "zbb"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_zbb {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(target_feature = "zbs")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_zbs {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $then) };
+}
+
+#[cfg(not(target_feature = "zbs"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cfg_zbs {
+    ($then: tt $else: tt) => { $crate::__asm_cfg!(@pick $else) };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_cfg)]
+mod tests {
+    #[test]
+    fn test_cfg() {
+        let arch = asm_block!(@cfg(target_arch = "x86_64") { x86_64; } else { other; });
+        assert_eq!(arch, if cfg!(target_arch = "x86_64") { "x86_64 \n" } else { "other \n" });
+        assert_eq!(asm_block!(nop; @cfg(not(target_arch = "x86_64")) { nop; } ret;), if cfg!(target_arch = "x86_64") {
+            "nop \nret \n"
+        } else {
+            "nop \nnop \nret \n"
+        });
+        assert_eq!(asm_block!(@cfg(all()) { a } else { b }), "a ");
+        assert_eq!(asm_block!(@cfg(any()) { a } else { b }), "b ");
+        assert_eq!(
+            asm_block!(@cfg(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv64",)) { a } else { b }),
+            if cfg!(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv64")) { "a " } else { "b " }
+        );
+        assert_eq!(
+            asm_block!(@cfg(all(not(target_feature = "bmi1"), not(target_feature = "zbb"))) { a } else { b }),
+            if cfg!(all(not(target_feature = "bmi1"), not(target_feature = "zbb"))) { "a " } else { "b " }
+        );
+        // the other branch is not expanded, so it may fail to compile
+        assert_eq!(
+            asm_block!(@cfg(target_arch = "mips") { @inline(compile_error!("unreachable")) } else { mov {x}, 1; }),
+            "mov {x}, 1 \n"
+        );
+        assert_eq!(asm_block!(push rax @cfg(all()) { pop rax }), "push rax pop rax ");
+    }
+
+    #[test]
+    fn test_asm_cfg() {
+        assert_eq!(asm_cfg!(), "");
+        assert_eq!(asm_cfg!(else => { nop; }), "nop \n");
+        assert_eq!(asm_cfg!(any() => { a }), "");
+        let code = asm_cfg! {
+            target_arch = "x86_64" => { x86_64 },
+            any(target_arch = "aarch64", target_arch = "arm") => { arm },
+            else => { other },
+        };
+        assert_eq!(code, if cfg!(target_arch = "x86_64") {
+            "x86_64 "
+        } else if cfg!(any(target_arch = "aarch64", target_arch = "arm")) {
+            "arm "
+        } else {
+            "other "
+        });
+        let code = asm_cfg! {
+            target_feature = "avx512f" => { zmm },
+            target_feature = "avx" => { ymm },
+            target_feature = "sse2" => { xmm },
+        };
+        assert_eq!(code, if cfg!(target_feature = "avx512f") {
+            "zmm "
+        } else if cfg!(target_feature = "avx") {
+            "ymm "
+        } else if cfg!(target_feature = "sse2") {
+            "xmm "
+        } else {
+            ""
+        });
+        assert_eq!(
+            asm_cfg!(all(target_feature = "neon", target_feature = "sve2") => { a }, else => { b }),
+            if cfg!(all(target_feature = "neon", target_feature = "sve2")) { "a " } else { "b " }
+        );
+    }
+
+    #[test]
+    fn test_cfg_layout() {
+        assert_eq!(
+            asm_cfg!(target_endian = "little" => { le }, target_endian = "big" => { be }),
+            if cfg!(target_endian = "little") { "le " } else { "be " }
+        );
+        let word = asm_cfg! {
+            target_pointer_width = "64" => { .quad },
+            target_pointer_width = "32" => { .long },
+            else => { .short },
+        };
+        assert_eq!(word, if cfg!(target_pointer_width = "64") {
+            ".quad "
+        } else if cfg!(target_pointer_width = "32") {
+            ".long "
+        } else {
+            ".short "
+        });
+        assert_eq!(
+            asm_block!(@cfg(all(target_endian = "little", not(target_pointer_width = "16"))) { a } else { b }),
+            if cfg!(all(target_endian = "little", not(target_pointer_width = "16"))) { "a " } else { "b " }
+        );
+    }
+
+    #[test]
+    fn test_cfg_os() {
+        let syscall = asm_cfg! {
+            all(target_os = "linux", target_arch = "x86_64") => { mov eax, 60; },
+            target_os = "freebsd" => { mov eax, 1; },
+            else => { ud2; },
+        };
+        assert_eq!(syscall, if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+            "mov eax , 60 \n"
+        } else if cfg!(target_os = "freebsd") {
+            "mov eax , 1 \n"
+        } else {
+            "ud2 \n"
+        });
+        assert_eq!(
+            asm_cfg!(target_env = "musl" => { musl }, target_env = "gnu" => { gnu }, target_env = "" => { none }),
+            if cfg!(target_env = "musl") {
+                "musl "
+            } else if cfg!(target_env = "gnu") {
+                "gnu "
+            } else if cfg!(target_env = "") {
+                "none "
+            } else {
+                ""
+            }
+        );
+        assert_eq!(
+            asm_block!(@cfg(any(unix, target_family = "wasm")) { a } else { b }),
+            if cfg!(any(unix, target_family = "wasm")) { "a " } else { "b " }
+        );
+        assert_eq!(
+            asm_block!(@cfg(not(windows)) { a } else { b }),
+            if cfg!(not(windows)) { "a " } else { "b " }
+        );
+        assert_eq!(asm_cfg!(target_os = "plan9" => { a }), "");
+    }
+}
@@ -0,0 +1,492 @@
+/// Compose fragments into an [`asm!`] call with the options they allow.
+///
+/// The options of [`asm!`] are promises about the whole block, such as
+/// `nostack` or `preserves_flags`, so they hold only if they hold for every
+/// fragment in the block, while every ABI clobbered by a fragment is
+/// clobbered by the block. Instead of reading the source of every fragment
+/// to find out, fragments can declare their options, and this macro invokes
+/// [`asm!`] with the concatenation of the fragments, followed by:
+/// - the options among `pure`, `nomem`, `readonly`, `preserves_flags` and
+///   `nostack` declared by all the fragments. `nomem` implies `readonly`, so
+///   composing a `nomem` fragment with a `readonly` one gives `readonly`.
+/// - `att_syntax` if the fragments declare it, or an error if only some do.
+/// - `clobber_abi("C")` and `clobber_abi("system")` if any fragment declares
+///   them.
+///
+/// When `preserves_flags` is declared by all the fragments, the block is
+/// checked against the mnemonics known to modify the flags with the tables of
+/// the `lint-x86_64` and `lint-aarch64` features, so that a wrong declaration
+/// fails to compile instead of corrupting the flags. Without the tables of the
+/// target architecture, the declarations are trusted.
+///
+/// The block is also checked for named labels defined more than once, e.g. by
+/// two fragments using the same name, or by a fragment given twice, which
+/// fails to compile with the fragments defining the label instead of an error
+/// of the assembler. Numeric labels are not checked, as they can be redefined,
+/// except that a fragment must not define one of the [reserved
+/// labels](index.html#reserved-labels) of the macros of this crate.
+/// Likewise, references to local labels must be defined in the block: `2f`
+/// after the reference and `2b` before it, and labels starting with `.L`
+/// anywhere. Labels defined outside of the block, e.g. by `global_asm!`, are
+/// listed in `allow(...)` right after the fragments, as string literals.
+///
+/// Other options, e.g. `noreturn`, and the operands are given after the list
+/// of fragments, or after `allow(...)`, and passed to [`asm!`] unchanged.
+/// They are checked against the placeholders of the fragments as well, so
+/// that a placeholder without a named operand is reported with the fragment
+/// using it, and a named operand which no fragment uses with its name, next
+/// to the error of [`asm!`] pointing at the whole template.
+///
+/// A fragment declares its options with an arm accepting `@options` which
+/// forwards the continuation to `@declare`. Every fragment in the list must
+/// have such an arm:
+/// ```
+/// # use asm_block::{asm_block, asm_compose};
+/// macro_rules! mad {
+///     (@options $($k: tt)*) => {
+///         asm_block::asm_compose!(@declare [pure, nomem, nostack] $($k)*)
+///     };
+///     ($x: tt, $y: tt) => {
+///         asm_block! {
+///             imul $x, $y;
+///             lea $x, [$x + $y];
+///         }
+///     };
+/// }
+/// macro_rules! push_pop {
+///     (@options $($k: tt)*) => {
+///         asm_block::asm_compose!(@declare [pure, readonly, preserves_flags] $($k)*)
+///     };
+///     ($x: tt) => {
+///         asm_block! {
+///             push $x;
+///             pop $x;
+///         }
+///     };
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// fn f(mut x: u64) -> u64 {
+///     unsafe {
+///         // options(pure, readonly)
+///         asm_compose!([mad!({x}, 5), push_pop!({x})], x = inout(reg) x);
+///     }
+///     x
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(f(1), 10);
+/// ```
+/// Fragments in different syntaxes are rejected:
+/// ```compile_fail
+/// # use asm_block::{asm_block, asm_compose};
+/// macro_rules! zero {
+///     (@options $($k: tt)*) => {
+///         asm_block::asm_compose!(@declare [att_syntax] $($k)*)
+///     };
+///     ($x: ident) => {
+///         asm_block! { xorl %$x, %$x; }
+///     };
+/// }
+/// macro_rules! nop {
+///     (@options $($k: tt)*) => {
+///         asm_block::asm_compose!(@declare [] $($k)*)
+///     };
+///     () => {
+///         asm_block! { nop; }
+///     };
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// unsafe { asm_compose!([zero!(eax), nop!()], out("eax") _) };
+/// # #[cfg(not(target_arch = "x86_64"))]
+/// # compile_error!("");
+/// ```
+///
+/// A label defined by two fragments is rejected:
+/// ```compile_fail
+/// # use asm_block::{asm_block, asm_compose};
+/// macro_rules! entry {
+///     (@options $($k: tt)*) => {
+///         asm_block::asm_compose!(@declare [nostack] $($k)*)
+///     };
+///     ($name: ident) => {
+///         asm_block! { .globl $name; $name:; }
+///     };
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// unsafe { asm_compose!([entry!(isr), entry!(isr)]) };
+/// # #[cfg(not(target_arch = "x86_64"))]
+/// # compile_error!("");
+/// ```
+///
+/// A placeholder without an operand is rejected:
+/// ```compile_fail
+/// # use asm_block::{asm_block, asm_compose};
+/// macro_rules! inc {
+///     (@options $($k: tt)*) => {
+///         asm_block::asm_compose!(@declare [pure, nomem, nostack] $($k)*)
+///     };
+///     ($x: tt) => {
+///         asm_block! { add $x, 1; }
+///     };
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// unsafe { asm_compose!([inc!({x}), inc!({y})], x = inout(reg) 1u64 => _) };
+/// # #[cfg(not(target_arch = "x86_64"))]
+/// # compile_error!("");
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+#[macro_export]
+macro_rules! asm_compose {
+    // ask the next fragment for its options
+    (@query [] $state: tt $template: tt $operands: tt) => {
+        $crate::asm_compose!(@emit $state $template $operands)
+    };
+    (@query [[$($fragment: ident)::+] $args: tt $($rest: tt)*] $state: tt $template: tt $operands: tt) => {
+        $($fragment)::+!(@options [$($rest)*] $state $template $operands)
+    };
+
+    // the options of a fragment are collected in slots, in order: `pure`,
+    // `nomem`, `readonly`, `preserves_flags`, `nostack`, `att_syntax`, Intel
+    // syntax, `clobber_abi("C")` and `clobber_abi("system")`, where an option
+    // allowed by the fragment is an empty slot and an ABI clobbered by the
+    // fragment is a non-empty slot, so that merging is concatenating
+    (@declare [$($declared: tt)*] $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] [x] [x] [x] [x] [x] [] [x] [] [] $($k)*)
+    };
+    (@set [, $($declared: tt)*] $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $($k)*)
+    };
+    (@set [pure $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] [] $m $r $f $s $a $i $c $y $($k)*)
+    };
+    (@set [nomem $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $p [] [] $f $s $a $i $c $y $($k)*)
+    };
+    (@set [readonly $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $p $m [] $f $s $a $i $c $y $($k)*)
+    };
+    (@set [preserves_flags $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $p $m $r [] $s $a $i $c $y $($k)*)
+    };
+    (@set [nostack $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $p $m $r $f [] $a $i $c $y $($k)*)
+    };
+    (@set [att_syntax $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $p $m $r $f $s [x] [] $c $y $($k)*)
+    };
+    (@set [clobber_abi("C") $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $p $m $r $f $s $a $i [x] $y $($k)*)
+    };
+    (@set [clobber_abi("system") $($declared: tt)*] $p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt $($k: tt)*) => {
+        $crate::asm_compose!(@set [$($declared)*] $p $m $r $f $s $a $i $c [x] $($k)*)
+    };
+    (@set [$other: tt $($declared: tt)*] $($k: tt)*) => {
+        compile_error!(concat!("unsupported option `", stringify!($other), "` declared by a fragment"))
+    };
+    (@set [] [$($p: tt)*] [$($m: tt)*] [$($r: tt)*] [$($f: tt)*] [$($s: tt)*] [$($a: tt)*] [$($i: tt)*] [$($c: tt)*] [$($y: tt)*] [$($rest: tt)*] {[$($gp: tt)*] [$($gm: tt)*] [$($gr: tt)*] [$($gf: tt)*] [$($gs: tt)*] [$($ga: tt)*] [$($gi: tt)*] [$($gc: tt)*] [$($gy: tt)*]} $template: tt $operands: tt) => {
+        $crate::asm_compose!(@query [$($rest)*] {[$($gp)* $($p)*] [$($gm)* $($m)*] [$($gr)* $($r)*] [$($gf)* $($f)*] [$($gs)* $($s)*] [$($ga)* $($a)*] [$($gi)* $($i)*] [$($gc)* $($c)*] [$($gy)* $($y)*]} $template $operands)
+    };
+
+    // turn the merged slots into options, checking `preserves_flags` first
+    (@emit {$p: tt $m: tt $r: tt [] $s: tt $a: tt $i: tt $c: tt $y: tt} [$([$($fragment: ident)::+] $args: tt)*] $operands: tt) => {{
+        const _: () = $crate::lint::check_preserves_flags(concat!($($($fragment)::+!$args),*));
+        $crate::asm_compose!(
+            @option [] [] pure $p nomem $m readonly $r preserves_flags [] nostack $s;
+            att_syntax $a $i "C" $c "system" $y; [$([$($fragment)::+] $args)*] $operands
+        )
+    }};
+    (@emit {$p: tt $m: tt $r: tt $f: tt $s: tt $a: tt $i: tt $c: tt $y: tt} $template: tt $operands: tt) => {
+        $crate::asm_compose!(
+            @option [] [] pure $p nomem $m readonly $r preserves_flags $f nostack $s;
+            att_syntax $a $i "C" $c "system" $y; $template $operands
+        )
+    };
+    (@option [$($option: tt)*] $abis: tt nomem [] readonly $r: tt $($rest: tt)*) => {
+        $crate::asm_compose!(@option [$($option)* nomem,] $abis $($rest)*)
+    };
+    (@option [$($option: tt)*] $abis: tt $name: ident [] $($rest: tt)*) => {
+        $crate::asm_compose!(@option [$($option)* $name,] $abis $($rest)*)
+    };
+    (@option $options: tt $abis: tt $name: ident [$($x: tt)+] $($rest: tt)*) => {
+        $crate::asm_compose!(@option $options $abis $($rest)*)
+    };
+    (@option $options: tt $abis: tt; att_syntax [$($a: tt)+] [$($i: tt)+] $($rest: tt)*) => {
+        compile_error!("cannot compose fragments in AT&T syntax with fragments in Intel syntax")
+    };
+    (@option [$($option: tt)*] $abis: tt; att_syntax [$($a: tt)+] [] $($rest: tt)*) => {
+        $crate::asm_compose!(@option [$($option)* att_syntax,] $abis $($rest)*)
+    };
+    (@option $options: tt $abis: tt; att_syntax [] $i: tt $($rest: tt)*) => {
+        $crate::asm_compose!(@option $options $abis $($rest)*)
+    };
+    (@option $options: tt [$($abi: tt)*] $name: literal [$($x: tt)+] $($rest: tt)*) => {
+        $crate::asm_compose!(@option $options [$($abi)* clobber_abi($name),] $($rest)*)
+    };
+    (@option $options: tt $abis: tt $name: literal [] $($rest: tt)*) => {
+        $crate::asm_compose!(@option $options $abis $($rest)*)
+    };
+    (@option [$($option: tt)*] [$($abi: tt)*]; [$([$($fragment: ident)::+] $args: tt)*] {[$($allow: literal),*] [$($operand: tt)*]}) => {{
+        const _: () = {
+            let fragments = &[$($($fragment)::+!$args),*];
+            let names = &[$(concat!(stringify!($($fragment)::+), "!", stringify!($args))),*];
+            $crate::lint::check_labels(fragments, names, &[$($allow),*]);
+            $crate::lint::check_operands(fragments, names, stringify!($($operand)*));
+        };
+        ::core::arch::asm!(
+            concat!($($($fragment)::+!$args),*),
+            options($($option)*),
+            $($abi)*
+            $($operand)*
+        )
+    }};
+
+    ([$($($fragment: ident)::+ ! $args: tt),+ $(,)?], allow($($allow: literal),* $(,)?) $(, $($operand: tt)*)?) => {
+        $crate::asm_compose!(
+            @query [$([$($fragment)::+] $args)+] {[] [] [] [] [] [] [] [] []}
+            [$([$($fragment)::+] $args)+] {[$($allow),*] [$($($operand)*)?]}
+        )
+    };
+    ([$($($fragment: ident)::+ ! $args: tt),+ $(,)?] $(, $($operand: tt)*)?) => {
+        $crate::asm_compose!(
+            @query [$([$($fragment)::+] $args)+] {[] [] [] [] [] [] [] [] []}
+            [$([$($fragment)::+] $args)+] {[] [$($($operand)*)?]}
+        )
+    };
+}
+
+/// Split a long kernel into consecutive [`asm!`] invocations.
+///
+/// A single [`asm!`] statement of tens of thousands of lines can make the
+/// compiler and LLVM very slow. This invokes [`asm!`] once per template in
+/// the list, in order, each with the same operands and options, so that every
+/// statement stays small. The state carried from one template to the next
+/// must live in the operands: registers which are not operands, the flags,
+/// and the labels are not preserved across templates, and a template must not
+/// jump into another one. Their inputs are evaluated again for every template
+/// and their outputs written after it, so state is carried by an `inout`
+/// operand reading and writing the same mutable place, e.g. `inout(reg) x`,
+/// while `inout(reg) a => b` starts every template from `a`.
+///
+/// Every template uses only some of the operands, which [`asm!`] rejects, so
+/// the named operands are referenced in a comment at the end of each template,
+/// e.g. `/* {x} {p} */`. Positional operands cannot be named that way, so
+/// operands must either be named, or bound to explicit registers, which need
+/// no reference. The options are given as `options(...)` among the operands,
+/// and must hold for every template, so `noreturn` cannot be used.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_split};
+/// macro_rules! round {
+///     ($x: tt, $k: tt) => {
+///         asm_block! {
+///             imul $x, $x, 3;
+///             add $x, $k;
+///         }
+///     };
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// let mut x = 1u64;
+/// unsafe {
+///     asm_split!(
+///         [
+///             concat!(round!({x}, 1), round!({x}, {k})),
+///             concat!(round!({x}, 3), round!({x}, {k})),
+///         ],
+///         x = inout(reg) x,
+///         k = in(reg) 5u64,
+///         options(pure, nomem, nostack),
+///     )
+/// };
+/// assert_eq!(x, (((1 * 3 + 1) * 3 + 5) * 3 + 3) * 3 + 5);
+/// # }
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+#[macro_export]
+macro_rules! asm_split {
+    // forward the operands one by one, collecting the names of the named ones
+    (@operand $templates: tt [$($name: ident)*] [$($done: tt)*] options($($option: tt)*) $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates [$($name)*] [$($done)* options($($option)*),] $($($rest)*)?)
+    };
+    (@operand $templates: tt [$($name: ident)*] [$($done: tt)*] clobber_abi($($abi: tt)*) $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates [$($name)*] [$($done)* clobber_abi($($abi)*),] $($($rest)*)?)
+    };
+    (@operand $templates: tt [$($name: ident)*] [$($done: tt)*] $operand: ident = const $value: expr $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates [$($name)* $operand] [$($done)* $operand = const $value,] $($($rest)*)?)
+    };
+    (@operand $templates: tt [$($name: ident)*] [$($done: tt)*] $operand: ident = sym $path: path $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates [$($name)* $operand] [$($done)* $operand = sym $path,] $($($rest)*)?)
+    };
+    (@operand $templates: tt [$($name: ident)*] $done: tt $operand: ident = $dir: ident ($($reg: tt)*) $($rest: tt)*) => {
+        $crate::asm_split!(@value $templates [$($name)* $operand] $done [$operand = $dir($($reg)*)] $($rest)*)
+    };
+    (@operand $templates: tt $names: tt $done: tt $dir: ident ($($reg: tt)*) $($rest: tt)*) => {
+        $crate::asm_split!(@value $templates $names $done [$dir($($reg)*)] $($rest)*)
+    };
+    (@operand $templates: tt $names: tt $done: tt) => {
+        $crate::asm_split!(@emit $templates $names $done)
+    };
+    (@operand $templates: tt $names: tt $done: tt $($rest: tt)*) => {
+        compile_error!(concat!("unsupported operand `", stringify!($($rest)*), "`"))
+    };
+
+    // the value of a register operand, where `_` is not an expression
+    (@value $templates: tt $names: tt [$($done: tt)*] [$($head: tt)*] _ $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates $names [$($done)* $($head)* _,] $($($rest)*)?)
+    };
+    (@value $templates: tt $names: tt [$($done: tt)*] [$($head: tt)*] $value: expr => _ $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates $names [$($done)* $($head)* $value => _,] $($($rest)*)?)
+    };
+    (@value $templates: tt $names: tt [$($done: tt)*] [$($head: tt)*] $value: expr => $out: expr $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates $names [$($done)* $($head)* $value => $out,] $($($rest)*)?)
+    };
+    (@value $templates: tt $names: tt [$($done: tt)*] [$($head: tt)*] $value: expr $(, $($rest: tt)*)?) => {
+        $crate::asm_split!(@operand $templates $names [$($done)* $($head)* $value,] $($($rest)*)?)
+    };
+
+    // the names and the operands are packed into single tokens so that they
+    // can be repeated alongside the templates
+    (@emit [$($template: expr),+] $names: tt $operands: tt) => {
+        { $($crate::asm_split!(@asm $template, $names $operands);)+ }
+    };
+    (@asm $template: expr, [] [$($operand: tt)*]) => {
+        ::core::arch::asm!($template, $($operand)*)
+    };
+    (@asm $template: expr, [$($name: ident)+] [$($operand: tt)*]) => {
+        ::core::arch::asm!(concat!($template, "/*", $(" {", stringify!($name), "}",)+ " */"), $($operand)*)
+    };
+
+    ([$($template: expr),+ $(,)?] $(, $($operand: tt)*)?) => {
+        $crate::asm_split!(@operand [$($template),+] [] [] $($($operand)*)?)
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block, asm_compose)]
+mod tests {
+    macro_rules! inc {
+        (@options $($k: tt)*) => {
+            asm_compose!(@declare [pure, nomem, nostack] $($k)*)
+        };
+        ($x: tt) => {
+            asm_block! { add $x, 1; }
+        };
+    }
+
+    macro_rules! load {
+        (@options $($k: tt)*) => {
+            asm_compose!(@declare [pure, readonly, nostack, preserves_flags] $($k)*)
+        };
+        ($x: tt, $p: tt) => {
+            asm_block! { mov $x, [$p]; }
+        };
+    }
+
+    macro_rules! call {
+        (@options $($k: tt)*) => {
+            asm_compose!(@declare [clobber_abi("C")] $($k)*)
+        };
+        ($f: tt) => {
+            asm_block! { call $f; }
+        };
+    }
+
+    extern "C" fn nothing() {}
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_compose() {
+        let mut x = 1u64;
+        unsafe { asm_compose!([inc!({x}), inc!({x})], x = inout(reg) x) };
+        assert_eq!(x, 3);
+        let y = 5u64;
+        unsafe { asm_compose!([load!({x}, {p}), inc!({x}),], x = out(reg) x, p = in(reg) &y,) };
+        assert_eq!(x, 6);
+        unsafe { asm_compose!([load!({x}, {p})], x = out(reg) x, p = in(reg) &y) };
+        assert_eq!(x, 5);
+        unsafe { asm_compose!([inc!(rdi), call!({f})], f = sym nothing, inout("rdi") x => _) };
+    }
+
+    macro_rules! countdown {
+        (@options $($k: tt)*) => {
+            asm_compose!(@declare [pure, nomem, nostack] $($k)*)
+        };
+        ($x: tt) => {
+            asm_block! {
+                2:;
+                sub $x, 1;
+                jnz 2b;
+            }
+        };
+    }
+
+    macro_rules! skip {
+        (@options $($k: tt)*) => {
+            asm_compose!(@declare [pure, nomem, nostack] $($k)*)
+        };
+        ($x: tt) => {
+            asm_block! {
+                test $x, $x;
+                jz 3f;
+            }
+        };
+    }
+
+    macro_rules! land {
+        (@options $($k: tt)*) => {
+            asm_compose!(@declare [pure, nomem, nostack] $($k)*)
+        };
+        () => {
+            asm_block! { 3:; }
+        };
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_compose_labels() {
+        let mut x = 3u64;
+        unsafe { asm_compose!([skip!({x}), countdown!({x}), land!()], x = inout(reg) x) };
+        assert_eq!(x, 0);
+        unsafe {
+            asm_compose!([skip!({x}), countdown!({x}), land!()], allow(".Lunused"), x = inout(reg) x)
+        };
+        assert_eq!(x, 0);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_split() {
+        extern "C" fn double(x: u64) -> u64 {
+            x * 2
+        }
+
+        let (mut x, mut y, mut z) = (1u64, 0u64, 1u64);
+        unsafe {
+            asm_split!(
+                [inc!({x}), "mov {y}, {x}", concat!(inc!({y}), "add rcx, {c}")],
+                x = inout(reg) x,
+                y = inout(reg) y => y,
+                out("rax") _,
+                inout("rcx") z,
+                c = const 3,
+                options(pure, nomem, nostack),
+            )
+        };
+        assert_eq!((x, y, z), (2, 3, 4));
+
+        // every template passes the result of the previous one through `x`
+        let mut x = 5u64;
+        unsafe {
+            asm_split!(
+                ["mov rdi, rax\ncall {f}", "mov rdi, rax\ncall {f}"],
+                f = sym double,
+                inout("rax") x,
+                clobber_abi("C"),
+            )
+        };
+        assert_eq!(x, 20);
+        unsafe { asm_split!(["nop", "nop"]) };
+    }
+}
@@ -0,0 +1,1609 @@
+/// Encrypt blocks with the AES instructions of the target.
+///
+/// This evaluates to a `&'static str` which encrypts the states with the
+/// round keys in order, i.e. 11, 13 or 15 keys for AES-128, AES-192 and
+/// AES-256, keeping everything in vector registers:
+/// - on x86-64, `pxor` with the first key, `aesenc` with the middle keys and
+///   `aesenclast` with the last key, which needs AES-NI.
+/// - on AArch64, `aese` and `aesmc` with all but the last key, and `eor` with
+///   the last key, which needs the `aes` feature.
+///
+/// The states and the keys are registers or placeholders of `xmm_reg` or
+/// `vreg` operands, which are written with the arrangement `.16b` on AArch64.
+/// On x86-64, the keys can also be memory operands aligned to 16 bytes.
+/// Several states can be given in brackets, which are encrypted with the same
+/// keys round by round, so that the latencies of the rounds overlap:
+///
+/// ```
+/// use asm_block::aes_encrypt;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     aes_encrypt!([xmm0, {b}], {k0}, {k1}, xmm15),
+///     concat!(
+///         "pxor xmm0 , {k0}\npxor {b}, {k0}\n",
+///         "aesenc xmm0 , {k1}\naesenc {b}, {k1}\n",
+///         "aesenclast xmm0 , xmm15 \naesenclast {b}, xmm15 \n",
+///     )
+/// );
+/// ```
+///
+/// The round keys come from [`aes_key_expand!`], or from any other key
+/// schedule, and the sequence can be interleaved with other code, e.g. the
+/// counter increments of CTR mode, by encrypting fewer keys at a time.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::{aes_encrypt, aes_key_expand};
+/// # #[cfg(target_arch = "x86_64")]
+/// # if std::is_x86_feature_detected!("aes") {
+/// let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+/// let mut block: [u8; 16] = core::array::from_fn(|i| i as u8 * 0x11);
+/// unsafe {
+///     asm!(
+///         "movdqu xmm0, [{k}]",
+///         "movdqu xmm13, [{b}]",
+///         "movdqa xmm1, xmm0",
+///         aes_key_expand!(xmm1, 0x01, xmm2, xmm3), "movdqa xmm4, xmm1",
+///         aes_key_expand!(xmm4, 0x02, xmm2, xmm3), "movdqa xmm5, xmm4",
+///         aes_key_expand!(xmm5, 0x04, xmm2, xmm3), "movdqa xmm6, xmm5",
+///         aes_key_expand!(xmm6, 0x08, xmm2, xmm3), "movdqa xmm7, xmm6",
+///         aes_key_expand!(xmm7, 0x10, xmm2, xmm3), "movdqa xmm8, xmm7",
+///         aes_key_expand!(xmm8, 0x20, xmm2, xmm3), "movdqa xmm9, xmm8",
+///         aes_key_expand!(xmm9, 0x40, xmm2, xmm3), "movdqa xmm10, xmm9",
+///         aes_key_expand!(xmm10, 0x80, xmm2, xmm3), "movdqa xmm11, xmm10",
+///         aes_key_expand!(xmm11, 0x1b, xmm2, xmm3), "movdqa xmm12, xmm11",
+///         aes_key_expand!(xmm12, 0x36, xmm2, xmm3),
+///         aes_encrypt!(xmm13, xmm0, xmm1, xmm4, xmm5, xmm6, xmm7, xmm8, xmm9, xmm10, xmm11, xmm12),
+///         "movdqu [{b}], xmm13",
+///         k = in(reg) key.as_ptr(),
+///         b = in(reg) block.as_mut_ptr(),
+///         out("xmm0") _, out("xmm1") _, out("xmm2") _, out("xmm3") _, out("xmm4") _,
+///         out("xmm5") _, out("xmm6") _, out("xmm7") _, out("xmm8") _, out("xmm9") _,
+///         out("xmm10") _, out("xmm11") _, out("xmm12") _, out("xmm13") _,
+///         options(nostack),
+///     );
+/// }
+/// assert_eq!(block, 0x69c4e0d8_6a7b0430_d8cdb780_70b4c55au128.to_be_bytes());
+/// # }
+/// ```
+///
+/// [`aes_key_expand!`]: macro.aes_key_expand.html
+#[macro_export]
+macro_rules! aes_encrypt {
+    ([$($state: tt),+ $(,)?], $($key: tt),+ $(,)?) => {
+        $crate::__asm_aes!(encrypt [$($state)+] $($key)+)
+    };
+    ($state: tt, $($key: tt),+ $(,)?) => {
+        $crate::__asm_aes!(encrypt [$state] $($key)+)
+    };
+}
+
+/// Decrypt blocks with the AES instructions of the target.
+///
+/// Like [`aes_encrypt!`], with `aesdec` and `aesdeclast` on x86-64, and
+/// `aesd` and `aesimc` on AArch64. The keys are those of the equivalent
+/// inverse cipher: the round keys of the encryption in reverse order, with
+/// `InvMixColumns` applied to all but the first and the last, e.g. with
+/// `aesimc`.
+///
+/// # Example
+/// ```
+/// use asm_block::aes_decrypt;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(
+///     aes_decrypt!({b}, v10, v9, {k}),
+///     concat!(
+///         "aesd {b}.16b, v10.16b\naesimc {b}.16b, {b}.16b\n",
+///         "aesd {b}.16b, v9.16b\n",
+///         "eor {b}.16b, {b}.16b, {k}.16b\n",
+///     )
+/// );
+/// ```
+///
+/// [`aes_encrypt!`]: macro.aes_encrypt.html
+#[macro_export]
+macro_rules! aes_decrypt {
+    ([$($state: tt),+ $(,)?], $($key: tt),+ $(,)?) => {
+        $crate::__asm_aes!(decrypt [$($state)+] $($key)+)
+    };
+    ($state: tt, $($key: tt),+ $(,)?) => {
+        $crate::__asm_aes!(decrypt [$state] $($key)+)
+    };
+}
+
+/// Compute the next round key of AES-128.
+///
+/// This evaluates to a `&'static str` which replaces the round key in `key`
+/// with the next one, using the round constant `rcon`, i.e. `0x01`, `0x02`,
+/// `0x04`, ..., `0x80`, `0x1b` and `0x36` for the ten round keys after the
+/// key itself. `tmp0` and `tmp1` are scratch vector registers:
+/// - on x86-64, `aeskeygenassist` computes the substituted and rotated word,
+///   which needs AES-NI.
+/// - on AArch64, `aese` with a zero key substitutes the bytes of the last
+///   word, which is then rotated with `ushr` and `sli`, and `rcon` is loaded
+///   with `movi`, which needs the `aes` feature.
+///
+/// The schedules of AES-192 and AES-256 mix words across round keys, and are
+/// not provided. The registers can be placeholders, like for
+/// [`aes_encrypt!`], whose example expands a whole key schedule.
+///
+/// [`aes_encrypt!`]: macro.aes_encrypt.html
+#[macro_export]
+macro_rules! aes_key_expand {
+    ($key: tt, $rcon: tt, $tmp0: tt, $tmp1: tt $(,)?) => {
+        $crate::__asm_aes_key_expand!($key, $rcon, $tmp0, $tmp1)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_aes {
+    (@each $instruction: ident [$($state: tt)+] $key: tt) => {
+        concat!($($crate::asm_block!($instruction $state, $key;)),+)
+    };
+    (@rounds $round: ident $last: ident [$($state: tt)+] $key: tt) => {
+        $crate::__asm_aes!(@each $last [$($state)+] $key)
+    };
+    (@rounds $round: ident $last: ident [$($state: tt)+] $key: tt $($rest: tt)+) => {
+        concat!(
+            $crate::__asm_aes!(@each $round [$($state)+] $key),
+            $crate::__asm_aes!(@rounds $round $last [$($state)+] $($rest)+)
+        )
+    };
+
+    (encrypt [$($state: tt)+] $first: tt $($key: tt)+) => {
+        concat!(
+            $crate::__asm_aes!(@each pxor [$($state)+] $first),
+            $crate::__asm_aes!(@rounds aesenc aesenclast [$($state)+] $($key)+)
+        )
+    };
+    (decrypt [$($state: tt)+] $first: tt $($key: tt)+) => {
+        concat!(
+            $crate::__asm_aes!(@each pxor [$($state)+] $first),
+            $crate::__asm_aes!(@rounds aesdec aesdeclast [$($state)+] $($key)+)
+        )
+    };
+    ($direction: ident [$($state: tt)+] $key: tt) => {
+        compile_error!("AES needs at least two round keys")
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_aes {
+    (@round $round: ident $mix: ident [$($state: tt)+] $key: tt) => {
+        concat!($(
+            stringify!($round), " ", $crate::__asm_neon!(@vector $state), ", ",
+            $crate::__asm_neon!(@vector $key), "\n",
+            stringify!($mix), " ", $crate::__asm_neon!(@vector $state), ", ",
+            $crate::__asm_neon!(@vector $state), "\n",
+        )+)
+    };
+    (@rounds $round: ident $mix: ident [$($state: tt)+] $key: tt $last: tt) => {
+        concat!(
+            $(
+                stringify!($round), " ", $crate::__asm_neon!(@vector $state), ", ",
+                $crate::__asm_neon!(@vector $key), "\n",
+            )+
+            $(
+                "eor ", $crate::__asm_neon!(@vector $state), ", ", $crate::__asm_neon!(@vector $state),
+                ", ", $crate::__asm_neon!(@vector $last), "\n",
+            )+
+        )
+    };
+    (@rounds $round: ident $mix: ident [$($state: tt)+] $key: tt $($rest: tt)+) => {
+        concat!(
+            $crate::__asm_aes!(@round $round $mix [$($state)+] $key),
+            $crate::__asm_aes!(@rounds $round $mix [$($state)+] $($rest)+)
+        )
+    };
+
+    (encrypt [$($state: tt)+] $first: tt $($key: tt)+) => {
+        $crate::__asm_aes!(@rounds aese aesmc [$($state)+] $first $($key)+)
+    };
+    (decrypt [$($state: tt)+] $first: tt $($key: tt)+) => {
+        $crate::__asm_aes!(@rounds aesd aesimc [$($state)+] $first $($key)+)
+    };
+    ($direction: ident [$($state: tt)+] $key: tt) => {
+        compile_error!("AES needs at least two round keys")
+    };
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_aes {
+    ($($token: tt)*) => {
+        compile_error!("AES rounds are only supported on x86_64 and AArch64")
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_aes_key_expand {
+    ($key: tt, $rcon: tt, $tmp0: tt, $tmp1: tt) => {
+        $crate::asm_block! {
+            aeskeygenassist $tmp0, $key, $rcon;
+            pshufd $tmp0, $tmp0, 0xff;
+            // xor each word of the key into the words above it
+            movdqa $tmp1, $key;
+            pslldq $tmp1, 4;
+            pxor $key, $tmp1;
+            movdqa $tmp1, $key;
+            pslldq $tmp1, 8;
+            pxor $key, $tmp1;
+            pxor $key, $tmp0;
+        }
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_aes_key_expand {
+    ($key: tt, $rcon: tt, $tmp0: tt, $tmp1: tt) => {
+        concat!(
+            "movi ", $crate::__asm_neon!(@vector $tmp1), ", #0\n",
+            "dup ", $crate::__asm_neon!(@vector $tmp0 "4s"), ", ", $crate::__asm_neon!(@vector $key "s[3]"), "\n",
+            "aese ", $crate::__asm_neon!(@vector $tmp0), ", ", $crate::__asm_neon!(@vector $tmp1), "\n",
+            // xor each word of the key into the words above it
+            "ext ", $crate::__asm_neon!(@vector $tmp1), ", ", $crate::__asm_neon!(@vector $tmp1), ", ",
+            $crate::__asm_neon!(@vector $key), ", #12\n",
+            "eor ", $crate::__asm_neon!(@vector $key), ", ", $crate::__asm_neon!(@vector $key), ", ",
+            $crate::__asm_neon!(@vector $tmp1), "\n",
+            "mov ", $crate::__asm_neon!(@vector $tmp1 "d[1]"), ", ", $crate::__asm_neon!(@vector $key "d[0]"), "\n",
+            "mov ", $crate::__asm_neon!(@vector $tmp1 "d[0]"), ", xzr\n",
+            "eor ", $crate::__asm_neon!(@vector $key), ", ", $crate::__asm_neon!(@vector $key), ", ",
+            $crate::__asm_neon!(@vector $tmp1), "\n",
+            // rotate the substituted word by a byte and add the round constant
+            "ushr ", $crate::__asm_neon!(@vector $tmp1 "4s"), ", ", $crate::__asm_neon!(@vector $tmp0 "4s"), ", #8\n",
+            "sli ", $crate::__asm_neon!(@vector $tmp1 "4s"), ", ", $crate::__asm_neon!(@vector $tmp0 "4s"), ", #24\n",
+            "movi ", $crate::__asm_neon!(@vector $tmp0 "4s"), ", #", stringify!($rcon), "\n",
+            "eor ", $crate::__asm_neon!(@vector $tmp1), ", ", $crate::__asm_neon!(@vector $tmp1), ", ",
+            $crate::__asm_neon!(@vector $tmp0), "\n",
+            "eor ", $crate::__asm_neon!(@vector $key), ", ", $crate::__asm_neon!(@vector $key), ", ",
+            $crate::__asm_neon!(@vector $tmp1), "\n"
+        )
+    };
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_aes_key_expand {
+    ($($token: tt)*) => {
+        compile_error!("AES key expansion is only supported on x86_64 and AArch64")
+    };
+}
+
+/// Compute a round of SHA-256 with general-purpose registers.
+///
+/// This evaluates to a `&'static str` which adds a round to the working
+/// variables `a` to `h`, given as 32-bit registers or placeholders, e.g.
+/// `{a:e}` on x86-64 or `{a:w}` on AArch64. Only `d` and `h` are written, so
+/// that the next round takes the variables rotated by one, i.e. `(h, a, b, c,
+/// d, e, f, g)`, and the original order comes back after eight rounds. `wk`
+/// is the word of the message schedule plus the round constant, which can be
+/// a memory operand on x86, and `t0` and `t1` are scratch registers. The
+/// flags are clobbered on x86.
+///
+/// The rotations use `ror` on x86 and x86-64, and the rotated operands of
+/// `eor` on AArch64, so that no extension is needed. [`sha256_rounds4!`]
+/// computes four rounds at a time with the SHA extensions.
+///
+/// # Example
+/// ```
+/// use asm_block::sha256_round;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert!(sha256_round!(w0, w1, w2, w3, w4, w5, w6, w7, w8, w9, w10).starts_with(concat!(
+///     "add w7 , w7 , w8 \n",
+///     "ror w9 , w4 , # 6 \n",
+///     "eor w9 , w9 , w4 , ror # 11 \n",
+/// )));
+/// # #[cfg(target_arch = "x86_64")]
+/// assert!(sha256_round!({a:e}, ebx, ecx, edx, {e:e}, {f:e}, {g:e}, {h:e}, [rsp], eax, esi)
+///     .starts_with("add {h:e}, [rsp ] \nmov eax , {e:e}\nror eax , 6 \n"));
+/// ```
+///
+/// [`sha256_rounds4!`]: macro.sha256_rounds4.html
+#[macro_export]
+macro_rules! sha256_round {
+    (
+        $a: tt, $b: tt, $c: tt, $d: tt, $e: tt, $f: tt, $g: tt, $h: tt,
+        $wk: tt, $t0: tt, $t1: tt $(,)?
+    ) => {
+        $crate::__asm_sha!(@sha256 $a $b $c $d $e $f $g $h $wk $t0 $t1)
+    };
+}
+
+/// Compute a round of SHA-1 with general-purpose registers.
+///
+/// Like [`sha256_round!`] for SHA-1: the round adds to `e`, and rotates `b`
+/// by 30 bits, so that the next round takes `(e, a, b, c, d)`. The quarter
+/// of the 80 rounds comes first, i.e. `0` to `3`, which selects the function
+/// of the round: `Ch`, `Parity`, `Maj` and `Parity`. `wk` is the word of the
+/// message schedule plus the constant of the quarter, and `t0` is a scratch
+/// register.
+///
+/// # Example
+/// ```
+/// use asm_block::sha1_round;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     sha1_round!(1, eax, ebx, ecx, edx, esi, {wk:e}, edi),
+///     concat!(
+///         "add esi , {wk:e}\n",
+///         "mov edi , ebx \nxor edi , ecx \nxor edi , edx \nadd esi , edi \n",
+///         "mov edi , eax \nrol edi , 5 \nadd esi , edi \n",
+///         "rol ebx , 30 \n",
+///     )
+/// );
+/// ```
+///
+/// [`sha256_round!`]: macro.sha256_round.html
+#[macro_export]
+macro_rules! sha1_round {
+    ($quarter: tt, $a: tt, $b: tt, $c: tt, $d: tt, $e: tt, $wk: tt, $t0: tt $(,)?) => {
+        concat!(
+            $crate::asm_block!(@inline($crate::__asm_sha!(@add $e $wk))),
+            $crate::__asm_sha!(@sha1 $quarter $b $c $d $e $t0),
+            $crate::__asm_sha!(@sha1_rotate $a $b $e $t0)
+        )
+    };
+}
+
+/// Compute four rounds of SHA-256 with the SHA extensions of the target.
+///
+/// This evaluates to a `&'static str` which adds four rounds to the state in
+/// two vector registers, with the four words of the message schedule plus
+/// the round constants in `wk`. The instructions split the state differently:
+///
+/// | Target  | Call                                    | Instructions          | Needs   |
+/// |---------|-----------------------------------------|-----------------------|---------|
+/// | x86-64  | `sha256_rounds4!(abef, cdgh, xmm0)`     | `sha256rnds2`         | SHA     |
+/// | AArch64 | `sha256_rounds4!(abcd, efgh, wk, tmp)`  | `sha256h`, `sha256h2` | `sha2`  |
+///
+/// `sha256rnds2` reads the words from `xmm0`, which must be passed, and is
+/// clobbered. AArch64 copies `abcd` into the scratch `tmp`. The registers are
+/// registers or placeholders of `xmm_reg` or `vreg` operands, and
+/// [`sha256_schedule!`] computes the next words.
+///
+/// # Example
+/// ```
+/// use asm_block::sha256_rounds4;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     sha256_rounds4!({abef}, {cdgh}, xmm0),
+///     concat!(
+///         "sha256rnds2 {cdgh}, {abef}, xmm0 \n",
+///         "pshufd xmm0 , xmm0 , 0x0e \n",
+///         "sha256rnds2 {abef}, {cdgh}, xmm0 \n",
+///     )
+/// );
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(
+///     sha256_rounds4!(v0, {efgh}, v16, v2),
+///     "mov v2.16b, v0.16b\nsha256h q0, {efgh:q}, v16.4s\nsha256h2 {efgh:q}, q2, v16.4s\n"
+/// );
+/// ```
+///
+/// [`sha256_schedule!`]: macro.sha256_schedule.html
+#[macro_export]
+macro_rules! sha256_rounds4 {
+    ($($argument: tt),+ $(,)?) => {
+        $crate::__asm_sha!(@sha256_rounds4 $($argument)+)
+    };
+}
+
+/// Compute four words of the message schedule of SHA-256 with the SHA
+/// extensions of the target.
+///
+/// This evaluates to a `&'static str` which replaces the words in `w0` with
+/// the words 16 positions after them, from the 16 words in `w0` to `w3`, with
+/// `sha256msg1` and `sha256msg2` on x86-64, or `sha256su0` and `sha256su1` on
+/// AArch64. x86-64 takes a scratch register `tmp` last, which AArch64 does
+/// not. The words are then in order in `w1`, `w2`, `w3` and `w0`.
+///
+/// # Example
+/// ```
+/// use asm_block::sha256_schedule;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(
+///     sha256_schedule!(v4, v5, v6, {w3}),
+///     "sha256su0 v4.4s, v5.4s\nsha256su1 v4.4s, v6.4s, {w3}.4s\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! sha256_schedule {
+    ($($argument: tt),+ $(,)?) => {
+        $crate::__asm_sha!(@sha256_schedule $($argument)+)
+    };
+}
+
+/// Compute four rounds of SHA-1 with the SHA extensions of the target.
+///
+/// This evaluates to a `&'static str` which adds four rounds of the given
+/// quarter, i.e. `0` to `3`, to the state `abcd`, and sets `next` to the
+/// value `e` of the next rounds. The value of `e` differs between the
+/// targets:
+/// - on x86-64, `e` holds the value `e` plus the words of these rounds, in
+///   its highest to lowest lanes, and `w` holds the words of the next
+///   rounds, which are added to `next` with `sha1nexte`. Before the first
+///   rounds, `e` is added to the words with `paddd`, and after the last
+///   rounds, passing the `e` of the previous block as `w` adds it to the new
+///   one. The constant of the quarter is implied by `sha1rnds4`.
+/// - on AArch64, `e` holds the value `e` in its lowest lane, and `w` holds the
+///   words of these rounds plus the constant of the quarter, as `sha1c`,
+///   `sha1p` and `sha1m` expect. `next` is computed with `sha1h`.
+///
+/// The registers are registers or placeholders of `xmm_reg` or `vreg`
+/// operands, and [`sha1_schedule!`] computes the next words.
+///
+/// # Example
+/// ```
+/// use asm_block::sha1_rounds4;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     sha1_rounds4!(2, {abcd}, xmm1, xmm2, {w}),
+///     "movdqa xmm2 , {abcd}\nsha1rnds4 {abcd}, xmm1 , 2 \nsha1nexte xmm2 , {w}\n"
+/// );
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(
+///     sha1_rounds4!(2, v0, v1, v2, {w}),
+///     "sha1h s2, s0\nsha1m q0, s1, {w}.4s\n"
+/// );
+/// ```
+///
+/// [`sha1_schedule!`]: macro.sha1_schedule.html
+#[macro_export]
+macro_rules! sha1_rounds4 {
+    ($quarter: tt, $abcd: tt, $e: tt, $next: tt, $w: tt $(,)?) => {
+        $crate::__asm_sha!(@sha1_rounds4 $quarter $abcd $e $next $w)
+    };
+}
+
+/// Compute four words of the message schedule of SHA-1 with the SHA
+/// extensions of the target.
+///
+/// Like [`sha256_schedule!`] for SHA-1, with `sha1msg1`, `pxor` and
+/// `sha1msg2` on x86-64, or `sha1su0` and `sha1su1` on AArch64, and without
+/// a scratch register.
+///
+/// [`sha256_schedule!`]: macro.sha256_schedule.html
+#[macro_export]
+macro_rules! sha1_schedule {
+    ($w0: tt, $w1: tt, $w2: tt, $w3: tt $(,)?) => {
+        $crate::__asm_sha!(@sha1_schedule $w0 $w1 $w2 $w3)
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_sha {
+    (@add $x: tt $y: tt) => { $crate::asm_block!(add $x, $y;) };
+    (@sha256 $a: tt $b: tt $c: tt $d: tt $e: tt $f: tt $g: tt $h: tt $wk: tt $t0: tt $t1: tt) => {
+        concat!(
+            $crate::asm_block!(add $h, $wk;),
+            // Σ1(e) = (e >>> 6) ^ (e >>> 11) ^ (e >>> 25)
+            $crate::asm_block! {
+                mov $t0, $e;
+                ror $t0, 6;
+                mov $t1, $e;
+                ror $t1, 11;
+                xor $t0, $t1;
+                ror $t1, 14;
+                xor $t0, $t1;
+                add $h, $t0;
+            },
+            // Ch(e, f, g) = ((f ^ g) & e) ^ g
+            $crate::asm_block! {
+                mov $t0, $f;
+                xor $t0, $g;
+                and $t0, $e;
+                xor $t0, $g;
+                add $h, $t0;
+                add $d, $h;
+            },
+            // Σ0(a) = (a >>> 2) ^ (a >>> 13) ^ (a >>> 22)
+            $crate::asm_block! {
+                mov $t0, $a;
+                ror $t0, 2;
+                mov $t1, $a;
+                ror $t1, 13;
+                xor $t0, $t1;
+                ror $t1, 9;
+                xor $t0, $t1;
+                add $h, $t0;
+            },
+            // Maj(a, b, c) = ((a | b) & c) | (a & b)
+            $crate::asm_block! {
+                mov $t0, $a;
+                or $t0, $b;
+                and $t0, $c;
+                mov $t1, $a;
+                and $t1, $b;
+                or $t0, $t1;
+                add $h, $t0;
+            }
+        )
+    };
+
+    (@sha1 0 $b: tt $c: tt $d: tt $e: tt $t0: tt) => {
+        $crate::asm_block! {
+            mov $t0, $c;
+            xor $t0, $d;
+            and $t0, $b;
+            xor $t0, $d;
+            add $e, $t0;
+        }
+    };
+    (@sha1 2 $b: tt $c: tt $d: tt $e: tt $t0: tt) => {
+        // the two halves of Maj(b, c, d) have no bit in common
+        $crate::asm_block! {
+            mov $t0, $b;
+            xor $t0, $c;
+            and $t0, $d;
+            add $e, $t0;
+            mov $t0, $b;
+            and $t0, $c;
+            add $e, $t0;
+        }
+    };
+    (@sha1 $quarter: tt $b: tt $c: tt $d: tt $e: tt $t0: tt) => {
+        $crate::asm_block! {
+            mov $t0, $b;
+            xor $t0, $c;
+            xor $t0, $d;
+            add $e, $t0;
+        }
+    };
+    (@sha1_rotate $a: tt $b: tt $e: tt $t0: tt) => {
+        $crate::asm_block! {
+            mov $t0, $a;
+            rol $t0, 5;
+            add $e, $t0;
+            rol $b, 30;
+        }
+    };
+
+    (@sha256_rounds4 $abef: tt $cdgh: tt xmm0) => {
+        $crate::asm_block! {
+            sha256rnds2 $cdgh, $abef, xmm0;
+            pshufd xmm0, xmm0, 0x0e;
+            sha256rnds2 $abef, $cdgh, xmm0;
+        }
+    };
+    (@sha256_rounds4 $($argument: tt)*) => {
+        compile_error!("`sha256rnds2` takes the state in two registers, and the words in `xmm0`")
+    };
+    (@sha256_schedule $w0: tt $w1: tt $w2: tt $w3: tt $tmp: tt) => {
+        $crate::asm_block! {
+            sha256msg1 $w0, $w1;
+            movdqa $tmp, $w3;
+            palignr $tmp, $w2, 4;
+            paddd $w0, $tmp;
+            sha256msg2 $w0, $w3;
+        }
+    };
+    (@sha256_schedule $($argument: tt)*) => {
+        compile_error!("the schedule of SHA-256 takes four registers of words and a scratch register on x86")
+    };
+    (@sha1_rounds4 $quarter: tt $abcd: tt $e: tt $next: tt $w: tt) => {
+        $crate::asm_block! {
+            movdqa $next, $abcd;
+            sha1rnds4 $abcd, $e, $quarter;
+            sha1nexte $next, $w;
+        }
+    };
+    (@sha1_schedule $w0: tt $w1: tt $w2: tt $w3: tt) => {
+        $crate::asm_block! {
+            sha1msg1 $w0, $w1;
+            pxor $w0, $w2;
+            sha1msg2 $w0, $w3;
+        }
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_sha {
+    (@add $x: tt $y: tt) => { $crate::asm_block!(add $x, $x, $y;) };
+    (@sha256 $a: tt $b: tt $c: tt $d: tt $e: tt $f: tt $g: tt $h: tt $wk: tt $t0: tt $t1: tt) => {
+        concat!(
+            $crate::asm_block!(add $h, $h, $wk;),
+            // Σ1(e) = (e >>> 6) ^ (e >>> 11) ^ (e >>> 25)
+            $crate::asm_block! {
+                ror $t0, $e, #6;
+                eor $t0, $t0, $e, ror #11;
+                eor $t0, $t0, $e, ror #25;
+                add $h, $h, $t0;
+            },
+            // Ch(e, f, g) = ((f ^ g) & e) ^ g
+            $crate::asm_block! {
+                eor $t0, $f, $g;
+                and $t0, $t0, $e;
+                eor $t0, $t0, $g;
+                add $h, $h, $t0;
+                add $d, $d, $h;
+            },
+            // Σ0(a) = (a >>> 2) ^ (a >>> 13) ^ (a >>> 22)
+            $crate::asm_block! {
+                ror $t0, $a, #2;
+                eor $t0, $t0, $a, ror #13;
+                eor $t0, $t0, $a, ror #22;
+                add $h, $h, $t0;
+            },
+            // Maj(a, b, c) = ((a | b) & c) | (a & b)
+            $crate::asm_block! {
+                orr $t0, $a, $b;
+                and $t0, $t0, $c;
+                and $t1, $a, $b;
+                orr $t0, $t0, $t1;
+                add $h, $h, $t0;
+            }
+        )
+    };
+
+    (@sha1 0 $b: tt $c: tt $d: tt $e: tt $t0: tt) => {
+        $crate::asm_block! {
+            eor $t0, $c, $d;
+            and $t0, $t0, $b;
+            eor $t0, $t0, $d;
+            add $e, $e, $t0;
+        }
+    };
+    (@sha1 2 $b: tt $c: tt $d: tt $e: tt $t0: tt) => {
+        // the two halves of Maj(b, c, d) have no bit in common
+        $crate::asm_block! {
+            eor $t0, $b, $c;
+            and $t0, $t0, $d;
+            add $e, $e, $t0;
+            and $t0, $b, $c;
+            add $e, $e, $t0;
+        }
+    };
+    (@sha1 $quarter: tt $b: tt $c: tt $d: tt $e: tt $t0: tt) => {
+        $crate::asm_block! {
+            eor $t0, $b, $c;
+            eor $t0, $t0, $d;
+            add $e, $e, $t0;
+        }
+    };
+    (@sha1_rotate $a: tt $b: tt $e: tt $t0: tt) => {
+        $crate::asm_block! {
+            ror $t0, $a, #27;
+            add $e, $e, $t0;
+            ror $b, $b, #2;
+        }
+    };
+
+    (@sha256_rounds4 $abcd: tt $efgh: tt $wk: tt $tmp: tt) => {
+        concat!(
+            "mov ", $crate::__asm_neon!(@vector $tmp), ", ", $crate::__asm_neon!(@vector $abcd), "\n",
+            "sha256h ", $crate::__asm_neon!(@scalar q $abcd), ", ", $crate::__asm_neon!(@scalar q $efgh),
+            ", ", $crate::__asm_neon!(@vector $wk "4s"), "\n",
+            "sha256h2 ", $crate::__asm_neon!(@scalar q $efgh), ", ", $crate::__asm_neon!(@scalar q $tmp),
+            ", ", $crate::__asm_neon!(@vector $wk "4s"), "\n"
+        )
+    };
+    (@sha256_rounds4 $($argument: tt)*) => {
+        compile_error!("`sha256h` takes the state in two registers, the words and a scratch register")
+    };
+    (@sha256_schedule $w0: tt $w1: tt $w2: tt $w3: tt) => {
+        concat!(
+            "sha256su0 ", $crate::__asm_neon!(@vector $w0 "4s"), ", ", $crate::__asm_neon!(@vector $w1 "4s"), "\n",
+            "sha256su1 ", $crate::__asm_neon!(@vector $w0 "4s"), ", ", $crate::__asm_neon!(@vector $w2 "4s"),
+            ", ", $crate::__asm_neon!(@vector $w3 "4s"), "\n"
+        )
+    };
+    (@sha256_schedule $($argument: tt)*) => {
+        compile_error!("the schedule of SHA-256 takes four registers of words on AArch64")
+    };
+    (@sha1_instruction 0) => { "sha1c " };
+    (@sha1_instruction 1) => { "sha1p " };
+    (@sha1_instruction 2) => { "sha1m " };
+    (@sha1_instruction 3) => { "sha1p " };
+    (@sha1_rounds4 $quarter: tt $abcd: tt $e: tt $next: tt $w: tt) => {
+        concat!(
+            "sha1h ", $crate::__asm_neon!(@scalar s $next), ", ", $crate::__asm_neon!(@scalar s $abcd), "\n",
+            $crate::__asm_sha!(@sha1_instruction $quarter), $crate::__asm_neon!(@scalar q $abcd), ", ",
+            $crate::__asm_neon!(@scalar s $e), ", ", $crate::__asm_neon!(@vector $w "4s"), "\n"
+        )
+    };
+    (@sha1_schedule $w0: tt $w1: tt $w2: tt $w3: tt) => {
+        concat!(
+            "sha1su0 ", $crate::__asm_neon!(@vector $w0 "4s"), ", ", $crate::__asm_neon!(@vector $w1 "4s"),
+            ", ", $crate::__asm_neon!(@vector $w2 "4s"), "\n",
+            "sha1su1 ", $crate::__asm_neon!(@vector $w0 "4s"), ", ", $crate::__asm_neon!(@vector $w3 "4s"), "\n"
+        )
+    };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_sha {
+    ($($token: tt)*) => {
+        compile_error!("SHA rounds are only supported on x86, x86_64 and AArch64")
+    };
+}
+
+/// Compute a quarter round of ChaCha.
+///
+/// This evaluates to a `&'static str` which mixes the words `a`, `b`, `c` and
+/// `d` with additions, xors and rotations by 16, 12, 8 and 7 bits. The
+/// instruction set comes first:
+///
+/// | Set      | Target      | Words                    | Needs       | Arguments         |
+/// |----------|-------------|--------------------------|-------------|-------------------|
+/// | `scalar` | x86, x86-64 | 32-bit registers         |             | `a, b, c, d`      |
+/// | `scalar` | AArch64     | `w` registers            |             | `a, b, c, d`      |
+/// | `sse`    | x86, x86-64 | `xmm` registers          | SSE2        | `a, b, c, d, tmp` |
+/// | `avx`    | x86, x86-64 | `xmm` or `ymm` registers | AVX or AVX2 | `a, b, c, d, tmp` |
+/// | `neon`   | AArch64     | vector registers         |             | `a, b, c, d, tmp` |
+///
+/// The vector sets compute four lanes at once, or eight with `ymm`
+/// registers, and take a scratch register `tmp` for the rotations by 12, 8
+/// and 7 bits, which are made of two shifts. The rotation by 16 bits swaps
+/// the halves of the words with `pshuflw` and `pshufhw` on x86-64, and
+/// `rev32` on AArch64. The registers can be placeholders, written with the
+/// arrangement `.4s` on AArch64.
+///
+/// The flags are clobbered on x86. For [`asm_foreach!`], the four words can
+/// also be given in brackets after the other arguments, e.g. `(scalar, [a,
+/// b, c, d])`.
+///
+/// # Example
+/// ```
+/// use asm_block::chacha_quarter_round;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     chacha_quarter_round!(scalar, eax, ebx, {c:e}, {d:e}),
+///     concat!(
+///         "add eax , ebx \nxor {d:e}, eax \nrol {d:e}, 16 \n",
+///         "add {c:e}, {d:e}\nxor ebx , {c:e}\nrol ebx , 12 \n",
+///         "add eax , ebx \nxor {d:e}, eax \nrol {d:e}, 8 \n",
+///         "add {c:e}, {d:e}\nxor ebx , {c:e}\nrol ebx , 7 \n",
+///     )
+/// );
+/// ```
+///
+/// [`asm_foreach!`]: macro.asm_foreach.html
+#[macro_export]
+macro_rules! chacha_quarter_round {
+    ($set: ident, [$a: tt, $b: tt, $c: tt, $d: tt] $(,)?) => {
+        $crate::__asm_chacha!($set $a $b $c $d)
+    };
+    ($set: ident, $tmp: tt, [$a: tt, $b: tt, $c: tt, $d: tt] $(,)?) => {
+        $crate::__asm_chacha!($set $a $b $c $d $tmp)
+    };
+    ($set: ident, $a: tt, $b: tt, $c: tt, $d: tt $(, $tmp: tt)? $(,)?) => {
+        $crate::__asm_chacha!($set $a $b $c $d $($tmp)?)
+    };
+}
+
+/// Compute a double round of ChaCha, i.e. a column round and a diagonal
+/// round.
+///
+/// This evaluates to a `&'static str` which applies [`chacha_quarter_round!`]
+/// to the state, ten times of which make ChaCha20. The state is laid out by
+/// the instruction set:
+/// - with `scalar`, the 16 words are given in order in brackets, e.g.
+///   `chacha_double_round!(scalar, [x0, x1, ..., x15])`, and the eight
+///   quarter rounds are unrolled with [`asm_foreach!`]. This needs 16
+///   registers, which AArch64 has, while x86-64 has one too few, so that a
+///   scalar state there is rather spilled around single quarter rounds.
+/// - with a vector set, each of the four registers holds a row of four
+///   words, e.g. `chacha_double_round!(sse, [a, b, c, d], tmp)`. The rows
+///   `b`, `c` and `d` are rotated by one, two and three lanes between the
+///   column round and the diagonal round, and back afterwards, with `pshufd`
+///   on x86-64 and `ext` on AArch64. With `ymm` registers, each 128-bit half
+///   holds the state of another block.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::{asm_rept, chacha_double_round};
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// // the block function of ChaCha20 with the test vector of RFC 8439
+/// let mut state: [u32; 16] = [
+///     0x61707865, 0x3320646e, 0x79622d32, 0x6b206574, 0x03020100, 0x07060504, 0x0b0a0908, 0x0f0e0d0c,
+///     0x13121110, 0x17161514, 0x1b1a1918, 0x1f1e1d1c, 0x00000001, 0x09000000, 0x4a000000, 0x00000000,
+/// ];
+/// unsafe {
+///     asm!(
+///         "movdqu {a}, [{s}]",
+///         "movdqu {b}, [{s} + 16]",
+///         "movdqu {c}, [{s} + 32]",
+///         "movdqu {d}, [{s} + 48]",
+///         "movdqa {a0}, {a}",
+///         asm_rept!(10, { @inline(chacha_double_round!(sse, [{a}, {b}, {c}, {d}], {t})) }),
+///         "paddd {a}, {a0}",
+///         "movdqu [{s}], {a}",
+///         s = in(reg) state.as_mut_ptr(),
+///         a = out(xmm_reg) _, b = out(xmm_reg) _, c = out(xmm_reg) _, d = out(xmm_reg) _,
+///         a0 = out(xmm_reg) _, t = out(xmm_reg) _,
+///         options(nostack),
+///     );
+/// }
+/// assert_eq!(state[..4], [0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3]);
+/// # }
+/// ```
+///
+/// [`chacha_quarter_round!`]: macro.chacha_quarter_round.html
+/// [`asm_foreach!`]: macro.asm_foreach.html
+#[macro_export]
+macro_rules! chacha_double_round {
+    (
+        scalar,
+        [
+            $x0: tt, $x1: tt, $x2: tt, $x3: tt, $x4: tt, $x5: tt, $x6: tt, $x7: tt,
+            $x8: tt, $x9: tt, $x10: tt, $x11: tt, $x12: tt, $x13: tt, $x14: tt, $x15: tt $(,)?
+        ] $(,)?
+    ) => {
+        $crate::asm_foreach!(
+            $crate::chacha_quarter_round(scalar),
+            [
+                [$x0, $x4, $x8, $x12], [$x1, $x5, $x9, $x13], [$x2, $x6, $x10, $x14], [$x3, $x7, $x11, $x15],
+                [$x0, $x5, $x10, $x15], [$x1, $x6, $x11, $x12], [$x2, $x7, $x8, $x13], [$x3, $x4, $x9, $x14],
+            ]
+        )
+    };
+    (scalar, $($argument: tt)*) => {
+        compile_error!("the scalar state is made of 16 words in brackets")
+    };
+    ($set: ident, [$a: tt, $b: tt, $c: tt, $d: tt $(,)?], $tmp: tt $(,)?) => {
+        concat!(
+            $crate::chacha_quarter_round!($set, $a, $b, $c, $d, $tmp),
+            $crate::__asm_chacha!(@rotate $set $b 1),
+            $crate::__asm_chacha!(@rotate $set $c 2),
+            $crate::__asm_chacha!(@rotate $set $d 3),
+            $crate::chacha_quarter_round!($set, $a, $b, $c, $d, $tmp),
+            $crate::__asm_chacha!(@rotate $set $b 3),
+            $crate::__asm_chacha!(@rotate $set $c 2),
+            $crate::__asm_chacha!(@rotate $set $d 1)
+        )
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_chacha {
+    (@step scalar $a: tt $b: tt $d: tt $n: tt) => {
+        $crate::asm_block!(add $a, $b; xor $d, $a; rol $d, $n;)
+    };
+    (scalar $a: tt $b: tt $c: tt $d: tt) => {
+        concat!(
+            $crate::__asm_chacha!(@step scalar $a $b $d 16),
+            $crate::__asm_chacha!(@step scalar $c $d $b 12),
+            $crate::__asm_chacha!(@step scalar $a $b $d 8),
+            $crate::__asm_chacha!(@step scalar $c $d $b 7)
+        )
+    };
+
+    // rotate the words of `x ^ y` into `x`
+    (@step sse $x: tt $y: tt $tmp: tt 16) => {
+        $crate::asm_block!(pxor $x, $y; pshuflw $x, $x, 0xb1; pshufhw $x, $x, 0xb1;)
+    };
+    (@step sse $x: tt $y: tt $tmp: tt $n: tt) => {
+        concat!(
+            $crate::asm_block!(pxor $x, $y; movdqa $tmp, $x;),
+            $crate::asm_block!(pslld $x, $n; psrld $tmp, 32 - $n; por $x, $tmp;)
+        )
+    };
+    (@step avx $x: tt $y: tt $tmp: tt 16) => {
+        $crate::asm_block!(vpxor $x, $x, $y; vpshuflw $x, $x, 0xb1; vpshufhw $x, $x, 0xb1;)
+    };
+    (@step avx $x: tt $y: tt $tmp: tt $n: tt) => {
+        concat!(
+            $crate::asm_block!(vpxor $x, $x, $y; vpsrld $tmp, $x, 32 - $n;),
+            $crate::asm_block!(vpslld $x, $x, $n; vpor $x, $x, $tmp;)
+        )
+    };
+    (@add sse $x: tt $y: tt) => { $crate::asm_block!(paddd $x, $y;) };
+    (@add avx $x: tt $y: tt) => { $crate::asm_block!(vpaddd $x, $x, $y;) };
+    (@rotate sse $x: tt 1) => { $crate::asm_block!(pshufd $x, $x, 0x39;) };
+    (@rotate sse $x: tt 2) => { $crate::asm_block!(pshufd $x, $x, 0x4e;) };
+    (@rotate sse $x: tt 3) => { $crate::asm_block!(pshufd $x, $x, 0x93;) };
+    (@rotate avx $x: tt 1) => { $crate::asm_block!(vpshufd $x, $x, 0x39;) };
+    (@rotate avx $x: tt 2) => { $crate::asm_block!(vpshufd $x, $x, 0x4e;) };
+    (@rotate avx $x: tt 3) => { $crate::asm_block!(vpshufd $x, $x, 0x93;) };
+    (@rotate $set: ident $x: tt $lanes: tt) => {
+        compile_error!(concat!("expected `sse` or `avx` on x86, found `", stringify!($set), "`"))
+    };
+
+    (sse $a: tt $b: tt $c: tt $d: tt $tmp: tt) => {
+        $crate::__asm_chacha!(@vector sse $a $b $c $d $tmp)
+    };
+    (avx $a: tt $b: tt $c: tt $d: tt $tmp: tt) => {
+        $crate::__asm_chacha!(@vector avx $a $b $c $d $tmp)
+    };
+    (@vector $set: ident $a: tt $b: tt $c: tt $d: tt $tmp: tt) => {
+        concat!(
+            $crate::__asm_chacha!(@add $set $a $b),
+            $crate::__asm_chacha!(@step $set $d $a $tmp 16),
+            $crate::__asm_chacha!(@add $set $c $d),
+            $crate::__asm_chacha!(@step $set $b $c $tmp 12),
+            $crate::__asm_chacha!(@add $set $a $b),
+            $crate::__asm_chacha!(@step $set $d $a $tmp 8),
+            $crate::__asm_chacha!(@add $set $c $d),
+            $crate::__asm_chacha!(@step $set $b $c $tmp 7)
+        )
+    };
+    ($set: ident $($argument: tt)*) => {
+        compile_error!("expected `scalar` with four words, or `sse` or `avx` with four rows and a scratch register")
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_chacha {
+    (@step scalar $a: tt $b: tt $d: tt $n: tt) => {
+        $crate::asm_block!(add $a, $a, $b; eor $d, $d, $a; ror $d, $d, #(32 - $n);)
+    };
+    (scalar $a: tt $b: tt $c: tt $d: tt) => {
+        concat!(
+            $crate::__asm_chacha!(@step scalar $a $b $d 16),
+            $crate::__asm_chacha!(@step scalar $c $d $b 12),
+            $crate::__asm_chacha!(@step scalar $a $b $d 8),
+            $crate::__asm_chacha!(@step scalar $c $d $b 7)
+        )
+    };
+
+    (@add $x: tt $y: tt) => {
+        concat!(
+            "add ", $crate::__asm_neon!(@vector $x "4s"), ", ", $crate::__asm_neon!(@vector $x "4s"), ", ",
+            $crate::__asm_neon!(@vector $y "4s"), "\n"
+        )
+    };
+    // rotate the words of `x ^ y` into `x`
+    (@step $x: tt $y: tt $tmp: tt 16) => {
+        concat!(
+            "eor ", $crate::__asm_neon!(@vector $x), ", ", $crate::__asm_neon!(@vector $x), ", ",
+            $crate::__asm_neon!(@vector $y), "\n",
+            "rev32 ", $crate::__asm_neon!(@vector $x "8h"), ", ", $crate::__asm_neon!(@vector $x "8h"), "\n"
+        )
+    };
+    (@step $x: tt $y: tt $tmp: tt $n: tt) => {
+        concat!(
+            "eor ", $crate::__asm_neon!(@vector $tmp), ", ", $crate::__asm_neon!(@vector $x), ", ",
+            $crate::__asm_neon!(@vector $y), "\n",
+            "ushr ", $crate::__asm_neon!(@vector $x "4s"), ", ", $crate::__asm_neon!(@vector $tmp "4s"),
+            ", #(32 - ", stringify!($n), ")\n",
+            "sli ", $crate::__asm_neon!(@vector $x "4s"), ", ", $crate::__asm_neon!(@vector $tmp "4s"),
+            ", #", stringify!($n), "\n"
+        )
+    };
+    (@rotate neon $x: tt $lanes: tt) => {
+        concat!(
+            "ext ", $crate::__asm_neon!(@vector $x), ", ", $crate::__asm_neon!(@vector $x), ", ",
+            $crate::__asm_neon!(@vector $x), ", #(4 * ", stringify!($lanes), ")\n"
+        )
+    };
+    (@rotate $set: ident $x: tt $lanes: tt) => {
+        compile_error!(concat!("expected `neon` on AArch64, found `", stringify!($set), "`"))
+    };
+
+    (neon $a: tt $b: tt $c: tt $d: tt $tmp: tt) => {
+        concat!(
+            $crate::__asm_chacha!(@add $a $b),
+            $crate::__asm_chacha!(@step $d $a $tmp 16),
+            $crate::__asm_chacha!(@add $c $d),
+            $crate::__asm_chacha!(@step $b $c $tmp 12),
+            $crate::__asm_chacha!(@add $a $b),
+            $crate::__asm_chacha!(@step $d $a $tmp 8),
+            $crate::__asm_chacha!(@add $c $d),
+            $crate::__asm_chacha!(@step $b $c $tmp 7)
+        )
+    };
+    ($set: ident $($argument: tt)*) => {
+        compile_error!("expected `scalar` with four words, or `neon` with four rows and a scratch register")
+    };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_chacha {
+    ($($token: tt)*) => {
+        compile_error!("ChaCha rounds are only supported on x86, x86_64 and AArch64")
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_neon {
+    (@vector $register: ident $arrangement: tt) => {
+        concat!(stringify!($register), ".", $arrangement)
+    };
+    (@vector $register: tt $arrangement: tt) => {
+        concat!($crate::asm_block!($register), ".", $arrangement)
+    };
+    (@vector $register: tt) => {
+        $crate::__asm_neon!(@vector $register "16b")
+    };
+    // the scalar of a vector register, e.g. `q0` for `v0`, or `{x:q}` for `{x}`
+    (@scalar $width: ident { $name: ident }) => {
+        concat!("{", stringify!($name), ":", stringify!($width), "}")
+    };
+    (@scalar $width: ident $register: ident) => {
+        concat!(stringify!($width), $crate::__asm_neon!(@number $register))
+    };
+    (@number v0) => { "0" };
+    (@number v1) => { "1" };
+    (@number v2) => { "2" };
+    (@number v3) => { "3" };
+    (@number v4) => { "4" };
+    (@number v5) => { "5" };
+    (@number v6) => { "6" };
+    (@number v7) => { "7" };
+    (@number v8) => { "8" };
+    (@number v9) => { "9" };
+    (@number v10) => { "10" };
+    (@number v11) => { "11" };
+    (@number v12) => { "12" };
+    (@number v13) => { "13" };
+    (@number v14) => { "14" };
+    (@number v15) => { "15" };
+    (@number v16) => { "16" };
+    (@number v17) => { "17" };
+    (@number v18) => { "18" };
+    (@number v19) => { "19" };
+    (@number v20) => { "20" };
+    (@number v21) => { "21" };
+    (@number v22) => { "22" };
+    (@number v23) => { "23" };
+    (@number v24) => { "24" };
+    (@number v25) => { "25" };
+    (@number v26) => { "26" };
+    (@number v27) => { "27" };
+    (@number v28) => { "28" };
+    (@number v29) => { "29" };
+    (@number v30) => { "30" };
+    (@number v31) => { "31" };
+    (@number $register: tt) => {
+        compile_error!(concat!("expected a vector register or a placeholder, found `", stringify!($register), "`"))
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(
+    aes_encrypt, aes_decrypt, aes_key_expand, sha256_round, sha1_round, chacha_quarter_round, chacha_double_round
+)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_aes() {
+        assert_eq!(aes_decrypt!({b}, {k0}, {k1}), "pxor {b}, {k0}\naesdeclast {b}, {k1}\n");
+        assert_eq!(
+            aes_key_expand!({k}, 0x01, xmm1, xmm2),
+            concat!(
+                "aeskeygenassist xmm1 , {k}, 0x01 \n",
+                "pshufd xmm1 , xmm1 , 0xff \n",
+                "movdqa xmm2 , {k}\n",
+                "pslldq xmm2 , 4 \n",
+                "pxor {k}, xmm2 \n",
+                "movdqa xmm2 , {k}\n",
+                "pslldq xmm2 , 8 \n",
+                "pxor {k}, xmm2 \n",
+                "pxor {k}, xmm1 \n",
+            )
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_aes_assemble() {
+        use crate::test::{assert_assembles, Arch};
+        #[cfg(target_arch = "x86_64")]
+        let (arch, code) = (
+            Arch::X86_64,
+            concat!(aes_key_expand!(xmm0, 0x36, xmm1, xmm2), aes_encrypt!([xmm3, xmm4], xmm0, xmm5)),
+        );
+        #[cfg(target_arch = "aarch64")]
+        let (arch, code) = (
+            Arch::AArch64,
+            concat!(
+                ".arch_extension aes\n",
+                aes_key_expand!(v0, 0x36, v1, v2),
+                aes_encrypt!([v3, v4], v0, v5, v6)
+            ),
+        );
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        assert_assembles(arch, code);
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_aes_rounds() {
+        if !std::is_x86_feature_detected!("aes") {
+            return;
+        }
+        // the round keys are memory operands of legacy SSE instructions
+        #[repr(align(16))]
+        #[derive(Clone, Copy)]
+        struct Keys([[u8; 16]; 11]);
+        let mut keys = Keys([[0u8; 16]; 11]);
+        keys.0[0] = core::array::from_fn(|i| i as u8);
+        let mut blocks: [[u8; 16]; 2] = [core::array::from_fn(|i| i as u8 * 0x11), [0x5a; 16]];
+        let plain = blocks;
+        macro_rules! expand {
+            ($($i: literal $rcon: literal),+) => {
+                concat!($(
+                    aes_key_expand!(xmm0, $rcon, xmm1, xmm2),
+                    "movdqu [{k} + 16 * ", $i, "], xmm0\n",
+                )+)
+            };
+        }
+        unsafe {
+            core::arch::asm!(
+                "movdqu xmm0, [{k}]",
+                expand!(1 0x01, 2 0x02, 3 0x04, 4 0x08, 5 0x10, 6 0x20, 7 0x40, 8 0x80, 9 0x1b, 10 0x36),
+                k = in(reg) keys.0.as_mut_ptr(),
+                out("xmm0") _, out("xmm1") _, out("xmm2") _,
+                options(nostack),
+            );
+            core::arch::asm!(
+                "movdqu {a}, [{b}]",
+                "movdqu {c}, [{b} + 16]",
+                aes_encrypt!([{a}, {c}], [{k}], [{k} + 16], [{k} + 32], [{k} + 48], [{k} + 64],
+                    [{k} + 80], [{k} + 96], [{k} + 112], [{k} + 128], [{k} + 144], [{k} + 160]),
+                "movdqu [{b}], {a}",
+                "movdqu [{b} + 16], {c}",
+                b = in(reg) blocks.as_mut_ptr(),
+                k = in(reg) keys.0.as_ptr(),
+                a = out(xmm_reg) _,
+                c = out(xmm_reg) _,
+                options(nostack),
+            );
+        }
+        assert_eq!(blocks[0], 0x69c4e0d8_6a7b0430_d8cdb780_70b4c55au128.to_be_bytes());
+
+        let mut inverse = keys;
+        inverse.0.reverse();
+        for key in &mut inverse.0[1..10] {
+            unsafe {
+                core::arch::asm!(
+                    "movdqu {x}, [{k}]",
+                    "aesimc {x}, {x}",
+                    "movdqu [{k}], {x}",
+                    k = in(reg) key.as_mut_ptr(),
+                    x = out(xmm_reg) _,
+                    options(nostack),
+                );
+            }
+        }
+        for block in &mut blocks {
+            unsafe {
+                core::arch::asm!(
+                    "movdqu {a}, [{b}]",
+                    aes_decrypt!({a}, [{k}], [{k} + 16], [{k} + 32], [{k} + 48], [{k} + 64],
+                        [{k} + 80], [{k} + 96], [{k} + 112], [{k} + 128], [{k} + 144], [{k} + 160]),
+                    "movdqu [{b}], {a}",
+                    b = in(reg) block.as_mut_ptr(),
+                    k = in(reg) inverse.0.as_ptr(),
+                    a = out(xmm_reg) _,
+                    options(nostack),
+                );
+            }
+        }
+        assert_eq!(blocks, plain);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sha_assemble() {
+        use crate::test::{assert_assembles, Arch};
+        #[cfg(target_arch = "x86_64")]
+        let (arch, code) = (
+            Arch::X86_64,
+            concat!(
+                sha256_round!(eax, ecx, edx, esi, edi, r8d, r9d, r10d, [rsp + 4], r11d, ebx),
+                sha1_round!(0, eax, ecx, edx, esi, edi, [rsp], r8d),
+                sha1_round!(2, eax, ecx, edx, esi, edi, r9d, r8d),
+                sha256_rounds4!(xmm1, xmm2, xmm0),
+                sha256_schedule!(xmm3, xmm4, xmm5, xmm6, xmm7),
+                sha1_rounds4!(3, xmm1, xmm2, xmm3, xmm4),
+                sha1_schedule!(xmm4, xmm5, xmm6, xmm7),
+            ),
+        );
+        #[cfg(target_arch = "aarch64")]
+        let (arch, code) = (
+            Arch::AArch64,
+            concat!(
+                ".arch_extension sha2\n",
+                sha256_round!(w0, w1, w2, w3, w4, w5, w6, w7, w8, w9, w10),
+                sha1_round!(2, w0, w1, w2, w3, w4, w5, w6),
+                sha256_rounds4!(v0, v1, v2, v3),
+                sha256_schedule!(v4, v5, v6, v7),
+                sha1_rounds4!(1, v0, v1, v2, v3),
+                sha1_schedule!(v4, v5, v6, v7),
+            ),
+        );
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        assert_assembles(arch, code);
+    }
+
+    // the padded block of "abc"
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    const ABC: [u32; 16] = [0x61626380, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 24];
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_sha256() {
+        const K: [u32; 64] = [
+            0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+            0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+            0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+            0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+            0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+            0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+            0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+            0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+        ];
+        const H: [u32; 8] = [
+            0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+        ];
+        const DIGEST: [u32; 8] = [
+            0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61, 0xf20015ad,
+        ];
+        let mut wk = [0u32; 64];
+        wk[..16].copy_from_slice(&ABC);
+        for i in 16..64 {
+            let (w2, w15) = (wk[i - 2], wk[i - 15]);
+            let s0 = w15.rotate_right(7) ^ w15.rotate_right(18) ^ (w15 >> 3);
+            let s1 = w2.rotate_right(17) ^ w2.rotate_right(19) ^ (w2 >> 10);
+            wk[i] = wk[i - 16].wrapping_add(s0).wrapping_add(wk[i - 7]).wrapping_add(s1);
+        }
+        for (w, k) in wk.iter_mut().zip(K) {
+            *w = w.wrapping_add(k);
+        }
+
+        macro_rules! rounds8 {
+            ($($i: literal)+) => {
+                concat!($(
+                    sha256_round!({a:e}, {b:e}, {c:e}, {d:e}, {e:e}, {f:e}, {g:e}, {h:e}, [{wk} + 32 * $i], {t0:e}, {t1:e}),
+                    sha256_round!({h:e}, {a:e}, {b:e}, {c:e}, {d:e}, {e:e}, {f:e}, {g:e}, [{wk} + 32 * $i + 4], {t0:e}, {t1:e}),
+                    sha256_round!({g:e}, {h:e}, {a:e}, {b:e}, {c:e}, {d:e}, {e:e}, {f:e}, [{wk} + 32 * $i + 8], {t0:e}, {t1:e}),
+                    sha256_round!({f:e}, {g:e}, {h:e}, {a:e}, {b:e}, {c:e}, {d:e}, {e:e}, [{wk} + 32 * $i + 12], {t0:e}, {t1:e}),
+                    sha256_round!({e:e}, {f:e}, {g:e}, {h:e}, {a:e}, {b:e}, {c:e}, {d:e}, [{wk} + 32 * $i + 16], {t0:e}, {t1:e}),
+                    sha256_round!({d:e}, {e:e}, {f:e}, {g:e}, {h:e}, {a:e}, {b:e}, {c:e}, [{wk} + 32 * $i + 20], {t0:e}, {t1:e}),
+                    sha256_round!({c:e}, {d:e}, {e:e}, {f:e}, {g:e}, {h:e}, {a:e}, {b:e}, [{wk} + 32 * $i + 24], {t0:e}, {t1:e}),
+                    sha256_round!({b:e}, {c:e}, {d:e}, {e:e}, {f:e}, {g:e}, {h:e}, {a:e}, [{wk} + 32 * $i + 28], {t0:e}, {t1:e}),
+                )+)
+            };
+        }
+        let mut state = H;
+        unsafe {
+            core::arch::asm!(
+                rounds8!(0 1 2 3 4 5 6 7),
+                a = inout(reg) state[0],
+                b = inout(reg) state[1],
+                c = inout(reg) state[2],
+                d = inout(reg) state[3],
+                e = inout(reg) state[4],
+                f = inout(reg) state[5],
+                g = inout(reg) state[6],
+                h = inout(reg) state[7],
+                wk = in(reg) wk.as_ptr(),
+                t0 = out(reg) _,
+                t1 = out(reg) _,
+                options(pure, readonly, nostack),
+            );
+        }
+        for (x, h) in state.iter_mut().zip(H) {
+            *x = x.wrapping_add(h);
+        }
+        assert_eq!(state, DIGEST);
+
+        if !std::is_x86_feature_detected!("sha") {
+            return;
+        }
+        macro_rules! rounds4 {
+            ($($i: literal $w0: tt $w1: tt $w2: tt $w3: tt)+) => {
+                concat!($(
+                    crate::asm_block!(movdqu xmm0, [{k} + 16 * $i]; paddd xmm0, $w0;),
+                    sha256_rounds4!({abef}, {cdgh}, xmm0),
+                    sha256_schedule!($w0, $w1, $w2, $w3, {tmp}),
+                )+)
+            };
+            (last $($i: literal $w0: tt)+) => {
+                concat!($(
+                    crate::asm_block!(movdqu xmm0, [{k} + 16 * $i]; paddd xmm0, $w0;),
+                    sha256_rounds4!({abef}, {cdgh}, xmm0),
+                )+)
+            };
+        }
+        // the lanes of `abef` and `cdgh`, from the lowest
+        let mut state = [[H[5], H[4], H[1], H[0]], [H[7], H[6], H[3], H[2]]];
+        unsafe {
+            core::arch::asm!(
+                "movdqu {abef}, [{s}]",
+                "movdqu {cdgh}, [{s} + 16]",
+                "movdqu {w0}, [{w}]",
+                "movdqu {w1}, [{w} + 16]",
+                "movdqu {w2}, [{w} + 32]",
+                "movdqu {w3}, [{w} + 48]",
+                rounds4!(
+                    0 {w0} {w1} {w2} {w3}
+                    1 {w1} {w2} {w3} {w0}
+                    2 {w2} {w3} {w0} {w1}
+                    3 {w3} {w0} {w1} {w2}
+                    4 {w0} {w1} {w2} {w3}
+                    5 {w1} {w2} {w3} {w0}
+                    6 {w2} {w3} {w0} {w1}
+                    7 {w3} {w0} {w1} {w2}
+                    8 {w0} {w1} {w2} {w3}
+                    9 {w1} {w2} {w3} {w0}
+                    10 {w2} {w3} {w0} {w1}
+                    11 {w3} {w0} {w1} {w2}
+                ),
+                rounds4!(last 12 {w0} 13 {w1} 14 {w2} 15 {w3}),
+                "movdqu [{s}], {abef}",
+                "movdqu [{s} + 16], {cdgh}",
+                s = in(reg) state.as_mut_ptr(),
+                w = in(reg) ABC.as_ptr(),
+                k = in(reg) K.as_ptr(),
+                abef = out(xmm_reg) _,
+                cdgh = out(xmm_reg) _,
+                w0 = out(xmm_reg) _,
+                w1 = out(xmm_reg) _,
+                w2 = out(xmm_reg) _,
+                w3 = out(xmm_reg) _,
+                tmp = out(xmm_reg) _,
+                out("xmm0") _,
+                options(nostack),
+            );
+        }
+        let [[f, e, b, a], [h, g, d, c]] = state;
+        let state = [a, b, c, d, e, f, g, h];
+        let digest = state.iter().zip(H).map(|(x, h)| x.wrapping_add(h)).collect::<Vec<_>>();
+        assert_eq!(digest, DIGEST);
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_sha1() {
+        const K: [u32; 4] = [0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xca62c1d6];
+        const H: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+        const DIGEST: [u32; 5] = [0xa9993e36, 0x4706816a, 0xba3e2571, 0x7850c26c, 0x9cd0d89d];
+        let mut wk = [0u32; 80];
+        wk[..16].copy_from_slice(&ABC);
+        for i in 16..80 {
+            wk[i] = (wk[i - 3] ^ wk[i - 8] ^ wk[i - 14] ^ wk[i - 16]).rotate_left(1);
+        }
+        for (i, w) in wk.iter_mut().enumerate() {
+            *w = w.wrapping_add(K[i / 20]);
+        }
+
+        macro_rules! rounds5 {
+            ($($q: tt $i: literal)+) => {
+                concat!($(
+                    sha1_round!($q, {a:e}, {b:e}, {c:e}, {d:e}, {e:e}, [{wk} + 20 * $i], {t:e}),
+                    sha1_round!($q, {e:e}, {a:e}, {b:e}, {c:e}, {d:e}, [{wk} + 20 * $i + 4], {t:e}),
+                    sha1_round!($q, {d:e}, {e:e}, {a:e}, {b:e}, {c:e}, [{wk} + 20 * $i + 8], {t:e}),
+                    sha1_round!($q, {c:e}, {d:e}, {e:e}, {a:e}, {b:e}, [{wk} + 20 * $i + 12], {t:e}),
+                    sha1_round!($q, {b:e}, {c:e}, {d:e}, {e:e}, {a:e}, [{wk} + 20 * $i + 16], {t:e}),
+                )+)
+            };
+        }
+        let mut state = H;
+        unsafe {
+            core::arch::asm!(
+                rounds5!(0 0 0 1 0 2 0 3 1 4 1 5 1 6 1 7 2 8 2 9 2 10 2 11 3 12 3 13 3 14 3 15),
+                a = inout(reg) state[0],
+                b = inout(reg) state[1],
+                c = inout(reg) state[2],
+                d = inout(reg) state[3],
+                e = inout(reg) state[4],
+                wk = in(reg) wk.as_ptr(),
+                t = out(reg) _,
+                options(pure, readonly, nostack),
+            );
+        }
+        for (x, h) in state.iter_mut().zip(H) {
+            *x = x.wrapping_add(h);
+        }
+        assert_eq!(state, DIGEST);
+
+        if !std::is_x86_feature_detected!("sha") {
+            return;
+        }
+        macro_rules! rounds4 {
+            ($($q: literal $e: tt $next: tt $w0: tt $w1: tt $w2: tt $w3: tt)+) => {
+                concat!($(
+                    sha1_rounds4!($q, {abcd}, $e, $next, $w1),
+                    sha1_schedule!($w0, $w1, $w2, $w3),
+                )+)
+            };
+            (last $($q: literal $e: tt $next: tt $w: tt)+) => {
+                concat!($(sha1_rounds4!($q, {abcd}, $e, $next, $w),)+)
+            };
+        }
+        // the words are in the lanes from the highest
+        let mut words = [[0u32; 4]; 4];
+        for (i, word) in ABC.iter().enumerate() {
+            words[i / 4][3 - i % 4] = *word;
+        }
+        let mut state = [[H[3], H[2], H[1], H[0]], [0, 0, 0, H[4]]];
+        unsafe {
+            core::arch::asm!(
+                "movdqu {abcd}, [{s}]",
+                "movdqu {e0}, [{s} + 16]",
+                "movdqa {e}, {e0}",
+                "movdqu {w0}, [{w}]",
+                "movdqu {w1}, [{w} + 16]",
+                "movdqu {w2}, [{w} + 32]",
+                "movdqu {w3}, [{w} + 48]",
+                "paddd {e0}, {w0}",
+                rounds4!(
+                    0 {e0} {e1} {w0} {w1} {w2} {w3}
+                    0 {e1} {e0} {w1} {w2} {w3} {w0}
+                    0 {e0} {e1} {w2} {w3} {w0} {w1}
+                    0 {e1} {e0} {w3} {w0} {w1} {w2}
+                    0 {e0} {e1} {w0} {w1} {w2} {w3}
+                    1 {e1} {e0} {w1} {w2} {w3} {w0}
+                    1 {e0} {e1} {w2} {w3} {w0} {w1}
+                    1 {e1} {e0} {w3} {w0} {w1} {w2}
+                    1 {e0} {e1} {w0} {w1} {w2} {w3}
+                    1 {e1} {e0} {w1} {w2} {w3} {w0}
+                    2 {e0} {e1} {w2} {w3} {w0} {w1}
+                    2 {e1} {e0} {w3} {w0} {w1} {w2}
+                    2 {e0} {e1} {w0} {w1} {w2} {w3}
+                    2 {e1} {e0} {w1} {w2} {w3} {w0}
+                    2 {e0} {e1} {w2} {w3} {w0} {w1}
+                    3 {e1} {e0} {w3} {w0} {w1} {w2}
+                ),
+                rounds4!(last 3 {e0} {e1} {w1} 3 {e1} {e0} {w2} 3 {e0} {e1} {w3} 3 {e1} {e0} {e}),
+                "movdqu [{s}], {abcd}",
+                "movdqu [{s} + 16], {e0}",
+                s = in(reg) state.as_mut_ptr(),
+                w = in(reg) words.as_ptr(),
+                abcd = out(xmm_reg) _,
+                e = out(xmm_reg) _,
+                e0 = out(xmm_reg) _,
+                e1 = out(xmm_reg) _,
+                w0 = out(xmm_reg) _,
+                w1 = out(xmm_reg) _,
+                w2 = out(xmm_reg) _,
+                w3 = out(xmm_reg) _,
+                options(nostack),
+            );
+        }
+        let [[d, c, b, a], [.., e]] = state;
+        let digest = [a, b, c, d].iter().zip(H).map(|(x, h)| x.wrapping_add(h)).collect::<Vec<_>>();
+        assert_eq!(digest, DIGEST[..4]);
+        assert_eq!(e, DIGEST[4]);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_chacha() {
+        assert_eq!(
+            chacha_quarter_round!(avx, {a}, {b}, {c}, {d}, {t}),
+            concat!(
+                "vpaddd {a}, {a}, {b}\n",
+                "vpxor {d}, {d}, {a}\n",
+                "vpshuflw {d}, {d}, 0xb1 \n",
+                "vpshufhw {d}, {d}, 0xb1 \n",
+                "vpaddd {c}, {c}, {d}\n",
+                "vpxor {b}, {b}, {c}\n",
+                "vpsrld {t}, {b}, 32 - 12 \n",
+                "vpslld {b}, {b}, 12 \n",
+                "vpor {b}, {b}, {t}\n",
+                "vpaddd {a}, {a}, {b}\n",
+                "vpxor {d}, {d}, {a}\n",
+                "vpsrld {t}, {d}, 32 - 8 \n",
+                "vpslld {d}, {d}, 8 \n",
+                "vpor {d}, {d}, {t}\n",
+                "vpaddd {c}, {c}, {d}\n",
+                "vpxor {b}, {b}, {c}\n",
+                "vpsrld {t}, {b}, 32 - 7 \n",
+                "vpslld {b}, {b}, 7 \n",
+                "vpor {b}, {b}, {t}\n",
+            )
+        );
+
+        // the test vector of section 2.1.1 of RFC 8439
+        let mut x: [u32; 4] = [0x11111111, 0x01020304, 0x9b8d6f43, 0x01234567];
+        unsafe {
+            core::arch::asm!(
+                chacha_quarter_round!(scalar, {a:e}, {b:e}, {c:e}, {d:e}),
+                a = inout(reg) x[0],
+                b = inout(reg) x[1],
+                c = inout(reg) x[2],
+                d = inout(reg) x[3],
+                options(pure, nomem, nostack),
+            );
+        }
+        assert_eq!(x, [0xea2a92f4, 0xcb1cf8ce, 0x4581472e, 0x5881c4bb]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_chacha_assemble() {
+        use crate::test::{assert_assembles, Arch};
+        #[cfg(target_arch = "x86_64")]
+        let (arch, code) = (
+            Arch::X86_64,
+            concat!(
+                chacha_quarter_round!(scalar, eax, ebx, ecx, edx),
+                chacha_double_round!(sse, [xmm0, xmm1, xmm2, xmm3], xmm4),
+                chacha_double_round!(avx, [ymm0, ymm1, ymm2, ymm3], ymm4),
+            ),
+        );
+        #[cfg(target_arch = "aarch64")]
+        let (arch, code) = (
+            Arch::AArch64,
+            concat!(
+                chacha_double_round!(
+                    scalar,
+                    [w0, w1, w2, w3, w4, w5, w6, w7, w8, w9, w10, w11, w12, w13, w14, w15]
+                ),
+                chacha_double_round!(neon, [v0, v1, v2, v3], v4),
+            ),
+        );
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        assert_assembles(arch, code);
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_chacha_rounds() {
+        fn double_round(x: &mut [u32; 16]) {
+            for [a, b, c, d] in [
+                [0, 4, 8, 12], [1, 5, 9, 13], [2, 6, 10, 14], [3, 7, 11, 15],
+                [0, 5, 10, 15], [1, 6, 11, 12], [2, 7, 8, 13], [3, 4, 9, 14],
+            ] {
+                for (p, q, r, n) in [(a, b, d, 16), (c, d, b, 12), (a, b, d, 8), (c, d, b, 7)] {
+                    x[p] = x[p].wrapping_add(x[q]);
+                    x[r] = (x[r] ^ x[p]).rotate_left(n);
+                }
+            }
+        }
+        let mut expected: [u32; 16] = core::array::from_fn(|i| (i as u32).wrapping_mul(0x9e3779b9));
+        let input = expected;
+        double_round(&mut expected);
+
+        macro_rules! rows {
+            ($set: ident, $class: ident) => {{
+                let mut x = input;
+                unsafe {
+                    core::arch::asm!(
+                        "movdqu {a}, [{s}]",
+                        "movdqu {b}, [{s} + 16]",
+                        "movdqu {c}, [{s} + 32]",
+                        "movdqu {d}, [{s} + 48]",
+                        chacha_double_round!($set, [{a}, {b}, {c}, {d}], {t}),
+                        "movdqu [{s}], {a}",
+                        "movdqu [{s} + 16], {b}",
+                        "movdqu [{s} + 32], {c}",
+                        "movdqu [{s} + 48], {d}",
+                        s = in(reg) x.as_mut_ptr(),
+                        a = out($class) _,
+                        b = out($class) _,
+                        c = out($class) _,
+                        d = out($class) _,
+                        t = out($class) _,
+                        options(nostack),
+                    );
+                }
+                x
+            }};
+        }
+        assert_eq!(rows!(sse, xmm_reg), expected);
+        if std::is_x86_feature_detected!("avx") {
+            assert_eq!(rows!(avx, xmm_reg), expected);
+        }
+    }
+}
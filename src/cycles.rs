@@ -0,0 +1,157 @@
+/// Read the cycle counter of the target into an operand.
+///
+/// This evaluates to a `&'static str` which reads the counter into the
+/// operand `name`, declared in the class `reg`, e.g. to time a kernel between
+/// two reads:
+///
+/// | Architecture | Counter             | Also clobbers                               |
+/// |--------------|---------------------|---------------------------------------------|
+/// | x86-64       | `rdtsc` or `rdtscp` | `rax`, `rdx`, flags, and `rcx` for `rdtscp` |
+/// | AArch64      | `cntvct_el0`        |                                             |
+/// | RISC-V       | `rdcycle`           |                                             |
+///
+/// The counters do not count the same thing: the time-stamp counter of x86-64
+/// and the virtual counter of AArch64 tick at a constant rate, which is far
+/// below the clock of the core on AArch64, e.g. 24 MHz on Apple M1, while
+/// `cycle` counts the cycles of the core, and may be disabled in user mode,
+/// e.g. by Linux since 6.6. The registers clobbered on x86-64 must be
+/// declared, e.g. with `out("rax") _, out("rdx") _`, and `rcx` for `rdtscp`,
+/// and since the halves of the counter are combined with `shl` and `or`, the
+/// block cannot use `options(preserves_flags)` there.
+///
+/// The counter can be read before earlier instructions complete or after
+/// later instructions start, which blurs short measurements. The order is
+/// chosen by the first argument:
+/// - without it, the counter is read alone.
+/// - with `fenced`, the read is fenced on both sides, with `lfence` on x86-64
+///   and `isb` on AArch64, so that it waits for the instructions before it,
+///   and the instructions after it wait for it.
+/// - with `rdtscp` on x86-64, `rdtscp` waits for the instructions before it,
+///   and a trailing `lfence` makes the instructions after it wait, which is
+///   the usual way to end a measurement. `rdtscp` also writes `ecx`.
+///
+/// RISC-V has no such fences, so only the first form is available there.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::read_cycles;
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// let (start, end): (u64, u64);
+/// unsafe {
+///     asm!(
+///         read_cycles!(fenced, start),
+///         "imul {x}, {x}",
+///         read_cycles!(fenced, end),
+///         start = out(reg) start,
+///         end = out(reg) end,
+///         x = inout(reg) 3u64 => _,
+///         out("rax") _,
+///         out("rdx") _,
+///         options(nomem, nostack),
+///     );
+/// }
+/// assert!(end >= start);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! read_cycles {
+    (fenced, $name: ident $(,)?) => {
+        $crate::__asm_cycles!(fenced, $name)
+    };
+    (rdtscp, $name: ident $(,)?) => {
+        $crate::__asm_cycles!(rdtscp, $name)
+    };
+    ($name: ident $(,)?) => {
+        $crate::__asm_cycles!(plain, $name)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cycles {
+    (@combine $name: ident) => {
+        concat!("shl rdx, 32\nor rdx, rax\nmov {", stringify!($name), "}, rdx\n")
+    };
+    (plain, $name: ident) => {
+        concat!("rdtsc\n", $crate::__asm_cycles!(@combine $name))
+    };
+    (fenced, $name: ident) => {
+        concat!("lfence\nrdtsc\nlfence\n", $crate::__asm_cycles!(@combine $name))
+    };
+    (rdtscp, $name: ident) => {
+        concat!("rdtscp\nlfence\n", $crate::__asm_cycles!(@combine $name))
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cycles {
+    (plain, $name: ident) => {
+        concat!("mrs {", stringify!($name), "}, cntvct_el0\n")
+    };
+    (fenced, $name: ident) => {
+        concat!("isb\nmrs {", stringify!($name), "}, cntvct_el0\nisb\n")
+    };
+    ($order: ident, $name: ident) => {
+        compile_error!(concat!("`", stringify!($order), "` is only available on x86_64"))
+    };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cycles {
+    (plain, $name: ident) => {
+        concat!("rdcycle {", stringify!($name), "}\n")
+    };
+    ($order: ident, $name: ident) => {
+        compile_error!(concat!("`", stringify!($order), "` is not available on RISC-V"))
+    };
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_cycles {
+    ($($token: tt)*) => {
+        compile_error!("cycle counters are only supported on x86_64, AArch64 and RISC-V")
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_cycles() {
+        assert_eq!(
+            read_cycles!(t),
+            "rdtsc\nshl rdx, 32\nor rdx, rax\nmov {t}, rdx\n"
+        );
+        assert!(read_cycles!(rdtscp, t).starts_with("rdtscp\nlfence\n"));
+
+        let (start, end): (u64, u64);
+        unsafe {
+            core::arch::asm!(
+                read_cycles!(start),
+                "pause",
+                read_cycles!(rdtscp, end),
+                start = out(reg) start,
+                end = out(reg) end,
+                out("rax") _,
+                out("rcx") _,
+                out("rdx") _,
+                options(nomem, nostack),
+            );
+        }
+        assert!(end >= start);
+    }
+}
@@ -0,0 +1,199 @@
+/// Emit integers as `.byte` sequences in a chosen byte order.
+///
+/// Directives like `.short`, `.long` and `.quad` use the byte order of the
+/// target, which is not what a firmware image or an on-disk structure for
+/// another machine needs. This evaluates to a `&'static str` with one `.byte`
+/// line per value, where each byte is extracted by the assembler with shifts
+/// and masks. The values can therefore be anything the assembler is able to
+/// evaluate, including placeholders of `const` operands and differences of
+/// labels in the same section.
+///
+/// The byte order is `le` or `be`, and the width is given by one of `u16`,
+/// `u32`, `u64` or their signed counterparts. Values in two's complement
+/// outside the range of the width are truncated. Values made of several
+/// tokens must be wrapped in parentheses.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_data;
+/// assert_eq!(
+///     asm_data!(be, u16, [0x1234, {n}]),
+///     ".byte ((0x1234 ) >> 8) & 0xff, ((0x1234 ) >> 0) & 0xff\n\
+///      .byte (({n}) >> 8) & 0xff, (({n}) >> 0) & 0xff\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! asm_data {
+    // extract one byte of the value
+    (@byte $value: tt, $shift: literal) => {
+        concat!("((", $crate::asm_block!($value), ") >> ", $shift, ") & 0xff")
+    };
+
+    (@bytes $value: tt, [$first: literal $(, $shift: literal)*]) => {
+        concat!(
+            ".byte ",
+            $crate::asm_data!(@byte $value, $first),
+            $(", ", $crate::asm_data!(@byte $value, $shift),)*
+            "\n"
+        )
+    };
+
+    // pass the shifts of the bytes in order to another fragment of the crate
+    (@shifts le, u16, $callback: ident!($($arg: tt)*)) => { $crate::$callback!($($arg)*, [0, 8]) };
+    (@shifts le, u32, $callback: ident!($($arg: tt)*)) => { $crate::$callback!($($arg)*, [0, 8, 16, 24]) };
+    (@shifts le, u64, $callback: ident!($($arg: tt)*)) => {
+        $crate::$callback!($($arg)*, [0, 8, 16, 24, 32, 40, 48, 56])
+    };
+    (@shifts be, u16, $callback: ident!($($arg: tt)*)) => { $crate::$callback!($($arg)*, [8, 0]) };
+    (@shifts be, u32, $callback: ident!($($arg: tt)*)) => { $crate::$callback!($($arg)*, [24, 16, 8, 0]) };
+    (@shifts be, u64, $callback: ident!($($arg: tt)*)) => {
+        $crate::$callback!($($arg)*, [56, 48, 40, 32, 24, 16, 8, 0])
+    };
+    (@shifts $order: ident, i16, $($callback: tt)*) => { $crate::asm_data!(@shifts $order, u16, $($callback)*) };
+    (@shifts $order: ident, i32, $($callback: tt)*) => { $crate::asm_data!(@shifts $order, u32, $($callback)*) };
+    (@shifts $order: ident, i64, $($callback: tt)*) => { $crate::asm_data!(@shifts $order, u64, $($callback)*) };
+    (@shifts $order: ident, $width: ident, $($callback: tt)*) => {
+        compile_error!(concat!(
+            "expected `le` or `be` followed by `u16`, `u32`, `u64`, `i16`, `i32` or `i64`, found `",
+            stringify!($order),
+            ", ",
+            stringify!($width),
+            "`"
+        ))
+    };
+
+    (@value $order: ident, $width: ident, $value: tt) => {
+        $crate::asm_data!(@shifts $order, $width, asm_data!(@bytes $value))
+    };
+
+    ($order: ident, $width: ident, [$($value: tt),* $(,)?]) => {
+        concat!($($crate::asm_data!(@value $order, $width, $value)),*)
+    };
+}
+
+/// Compute a checksum of integers at assembly time.
+///
+/// This evaluates to a `&'static str` which defines the symbol `name` as the
+/// checksum of the bytes that [`asm_data!`] emits for the same order, width
+/// and values, with one `.set` directive per byte, so that it can be spliced
+/// into a block with `@inline(...)` and emitted afterwards, e.g. with
+/// `asm_data!(le, u32, [name])`:
+///
+/// | Algorithm | Checksum                                                |
+/// |-----------|---------------------------------------------------------|
+/// | `sum8`    | sum of the bytes modulo 256                             |
+/// | `xor8`    | exclusive or of the bytes                               |
+/// | `crc32`   | CRC-32 of IEEE 802.3, as computed by zlib               |
+///
+/// A header whose bytes must sum to zero stores `(0x100 - name) & 0xff`. The
+/// values must be absolute when the directives are reached, so they can be
+/// placeholders of `const` operands or differences of labels defined before,
+/// but not forward references, and the symbol is local to the object, where
+/// it can be redefined by the next checksum.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_checksum;
+/// assert_eq!(
+///     asm_checksum!(sum8, total, be, u16, [0x1234]),
+///     ".set total , 0\n\
+///      .set total , (total + (((0x1234 ) >> 8) & 0xff)) & 0xff\n\
+///      .set total , (total + (((0x1234 ) >> 0) & 0xff)) & 0xff\n"
+/// );
+/// ```
+///
+/// [`asm_data!`]: macro.asm_data.html
+#[macro_export]
+macro_rules! asm_checksum {
+    (@init sum8 $name: tt) => { concat!(".set ", $crate::asm_block!($name), ", 0\n") };
+    (@init xor8 $name: tt) => { concat!(".set ", $crate::asm_block!($name), ", 0\n") };
+    (@init crc32 $name: tt) => { concat!(".set ", $crate::asm_block!($name), ", 0xffffffff\n") };
+    (@init $algorithm: ident $name: tt) => {
+        compile_error!(concat!("expected `sum8`, `xor8` or `crc32`, found `", stringify!($algorithm), "`"))
+    };
+
+    // fold one byte into the checksum
+    (@byte sum8 $name: tt $byte: expr) => {
+        concat!(".set ", $crate::asm_block!($name), ", (", $crate::asm_block!($name), "+ (", $byte, ")) & 0xff\n")
+    };
+    (@byte xor8 $name: tt $byte: expr) => {
+        concat!(".set ", $crate::asm_block!($name), ", ", $crate::asm_block!($name), "^ (", $byte, ")\n")
+    };
+    (@byte crc32 $name: tt $byte: expr) => {
+        concat!(
+            ".set ", $crate::asm_block!($name), ", ", $crate::asm_block!($name), "^ (", $byte, ")\n",
+            ".rept 8\n.set ", $crate::asm_block!($name), ", (", $crate::asm_block!($name), ">> 1) ^ (0xedb88320 & -(",
+            $crate::asm_block!($name), "& 1))\n.endr\n"
+        )
+    };
+    (@byte $algorithm: ident $name: tt $byte: expr) => { "" };
+
+    (@final crc32 $name: tt) => {
+        concat!(".set ", $crate::asm_block!($name), ", ", $crate::asm_block!($name), "^ 0xffffffff\n")
+    };
+    (@final $algorithm: ident $name: tt) => { "" };
+
+    (@value $algorithm: ident $name: tt $value: tt, [$($shift: literal),*]) => {
+        concat!($($crate::asm_checksum!(@byte $algorithm $name $crate::asm_data!(@byte $value, $shift)),)*)
+    };
+
+    ($algorithm: ident, $name: tt, $order: ident, $width: ident, [$($value: tt),* $(,)?]) => {
+        concat!(
+            $crate::asm_checksum!(@init $algorithm $name),
+            $($crate::asm_data!(@shifts $order, $width, asm_checksum!(@value $algorithm $name $value)),)*
+            $crate::asm_checksum!(@final $algorithm $name)
+        )
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_data, asm_checksum)]
+mod tests {
+    #[test]
+    fn test_data() {
+        assert_eq!(asm_data!(le, u32, []), "");
+        assert_eq!(
+            asm_data!(le, u16, [1]),
+            ".byte ((1 ) >> 0) & 0xff, ((1 ) >> 8) & 0xff\n"
+        );
+        assert_eq!(
+            asm_data!(be, i32, [(2f - 1f),]),
+            ".byte (((2f - 1f ) ) >> 24) & 0xff, (((2f - 1f ) ) >> 16) & 0xff, \
+             (((2f - 1f ) ) >> 8) & 0xff, (((2f - 1f ) ) >> 0) & 0xff\n"
+        );
+        assert_eq!(
+            asm_data!(le, u64, [{x}]),
+            ".byte (({x}) >> 0) & 0xff, (({x}) >> 8) & 0xff, (({x}) >> 16) & 0xff, \
+             (({x}) >> 24) & 0xff, (({x}) >> 32) & 0xff, (({x}) >> 40) & 0xff, \
+             (({x}) >> 48) & 0xff, (({x}) >> 56) & 0xff\n"
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_checksum() {
+        let (crc, sum, xor): (u32, u32, u32);
+        unsafe {
+            core::arch::asm!(
+                "jmp 3f",
+                "2:",
+                asm_checksum!(crc32, crc, be, u32, [0x31323334, {k}]),
+                asm_checksum!(sum8, sum, le, u16, [0xfffe, 4]),
+                asm_checksum!(xor8, xor, le, u64, [0x0f0ff0f0]),
+                asm_data!(le, u32, [crc, sum, xor]),
+                "3:",
+                "mov {c:e}, [rip + 2b]",
+                "mov {s:e}, [rip + 2b + 4]",
+                "mov {x:e}, [rip + 2b + 8]",
+                k = const 0x35363738,
+                c = out(reg) crc,
+                s = out(reg) sum,
+                x = out(reg) xor,
+                options(pure, readonly, nostack),
+            );
+        }
+        assert_eq!(crc, 0x9ae0_daaf);
+        assert_eq!(sum, 0x1);
+        assert_eq!(xor, 0);
+    }
+}
@@ -0,0 +1,548 @@
+/// Print values from the middle of a block with `printf`.
+///
+/// With the `printf-debug` feature in a build with debug assertions, this
+/// evaluates to a `&'static str` which saves the flags and every register the
+/// callee may clobber, calls `printf` with the format string and up to five
+/// integer arguments, or three on Windows, and restores everything, so that it
+/// can be dropped anywhere into a block and removed again without touching the
+/// surrounding code. Otherwise, it evaluates to an empty string, so the
+/// fragments can stay in release builds. The assertions are those of this
+/// crate, which follow the profile unless it is overridden for dependencies.
+///
+/// The call follows the convention of variadic functions of the target:
+/// - on x86-64 System V, the arguments go in `rsi`, `rdx`, `rcx`, `r8` and
+///   `r9`, and `al` holds the number of vector registers used, i.e. 0.
+/// - on x86-64 Windows, the arguments go in `rdx`, `r8` and `r9`, below 32
+///   bytes of shadow space.
+///
+/// With `hook <function>` first, the function is called instead of `printf`,
+/// e.g. a `sym` operand, for targets without `printf`, like Windows where it is
+/// only exported by `legacy_stdio_definitions.lib`, or to log somewhere else.
+///
+/// The format string is a string literal emitted as is into a read-only
+/// section, and any `{` or `}` in it must be escaped as `{{` or `}}` for
+/// [`asm!`]. The arguments are transcribed by [`asm_block!`], and must be
+/// 64-bit registers, placeholders of `reg` operands or immediates. All of them
+/// are read before any register is changed, so they can be in any register,
+/// but not relative to the stack pointer, which is moved before they are read.
+/// The stack pointer is realigned around the call like with [`call_sysv!`]
+/// with `align`, which pushes below it, so the block must not have the
+/// `nostack` option. The upper halves of the `ymm` and `zmm` registers, the x87
+/// state and `mxcsr` are not saved.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::asm_printf;
+/// # #[cfg(target_arch = "x86_64")]
+/// unsafe fn triple(mut x: u64) -> u64 {
+///     asm!(
+///         "lea {x}, [{x} + 2 * {x}]",
+///         asm_printf!("3x = %llu\n", {x}),
+///         x = inout(reg) x,
+///     );
+///     x
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(unsafe { triple(5) }, 15);
+/// # #[cfg(not(all(feature = "printf-debug", debug_assertions)))]
+/// assert_eq!(asm_printf!("x = %llu\n", {x}), "");
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+/// [`call_sysv!`]: macro.call_sysv.html
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+#[macro_export]
+macro_rules! asm_printf {
+    (hook $function: tt, $format: literal $(, $argument: tt)* $(,)?) => {
+        $crate::__asm_printf!($crate::asm_block!(call $function;), $format $(, $argument)*)
+    };
+    ($format: literal $(, $argument: tt)* $(,)?) => {
+        $crate::__asm_printf!($crate::__asm_printf_target!(@printf), $format $(, $argument)*)
+    };
+}
+
+#[cfg(all(feature = "printf-debug", debug_assertions))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf_arguments {
+    // push the arguments from the last one, so that they are popped in order
+    (@push) => { "" };
+    (@push $argument: tt $($arguments: tt)*) => {
+        concat!(
+            $crate::__asm_printf_arguments!(@push $($arguments)*),
+            $crate::asm_block!(push $argument;)
+        )
+    };
+
+    ([$($register: ident)*]) => { "" };
+    ([$register: ident $($rest: ident)*] $argument: tt $($arguments: tt)*) => {
+        concat!(
+            "pop ", stringify!($register), "\n",
+            $crate::__asm_printf_arguments!([$($rest)*] $($arguments)*)
+        )
+    };
+    ([] $($arguments: tt)+) => {
+        compile_error!("too many arguments, passing arguments on the stack is not supported")
+    };
+}
+
+#[cfg(all(
+    feature = "printf-debug",
+    debug_assertions,
+    not(any(target_vendor = "apple", windows))
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf_target {
+    (@printf) => { "call printf\n" };
+    (@format $format: literal) => {
+        concat!(
+            ".pushsection .rodata, \"a\"\n",
+            ".set ", $crate::__asm_local_prefix!(), "asm_block_printf, .\n",
+            ".asciz ", stringify!($format), "\n",
+            ".popsection\n"
+        )
+    };
+}
+
+#[cfg(all(feature = "printf-debug", debug_assertions, target_vendor = "apple"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf_target {
+    (@printf) => { "call _printf\n" };
+    (@format $format: literal) => {
+        concat!(
+            ".pushsection __TEXT,__const\n",
+            ".set ", $crate::__asm_local_prefix!(), "asm_block_printf, .\n",
+            ".asciz ", stringify!($format), "\n",
+            ".popsection\n"
+        )
+    };
+}
+
+#[cfg(all(feature = "printf-debug", debug_assertions, windows))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf_target {
+    (@printf) => { "call printf\n" };
+    (@format $format: literal) => {
+        concat!(
+            ".section .rdata, \"dr\"\n",
+            ".set ", $crate::__asm_local_prefix!(), "asm_block_printf, .\n",
+            ".asciz ", stringify!($format), "\n",
+            ".text\n"
+        )
+    };
+}
+
+#[cfg(all(
+    feature = "printf-debug",
+    debug_assertions,
+    target_arch = "x86_64",
+    not(windows)
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf {
+    ($call: expr, $format: literal $(, $argument: tt)*) => {
+        concat!(
+            "pushfq\n",
+            "push rax\npush rcx\npush rdx\npush rsi\npush rdi\n",
+            "push r8\npush r9\npush r10\npush r11\n",
+            $crate::__asm_printf_arguments!(@push $($argument)*),
+            $crate::__asm_printf_arguments!([rsi rdx rcx r8 r9] $($argument)*),
+            $crate::__asm_printf_target!(@format $format),
+            "lea rdi, [rip + ", $crate::__asm_local_prefix!(), "asm_block_printf]\n",
+            $crate::__asm_call_arguments!(@align),
+            "sub rsp, 256\n",
+            "movaps [rsp], xmm0\nmovaps [rsp + 16], xmm1\n",
+            "movaps [rsp + 32], xmm2\nmovaps [rsp + 48], xmm3\n",
+            "movaps [rsp + 64], xmm4\nmovaps [rsp + 80], xmm5\n",
+            "movaps [rsp + 96], xmm6\nmovaps [rsp + 112], xmm7\n",
+            "movaps [rsp + 128], xmm8\nmovaps [rsp + 144], xmm9\n",
+            "movaps [rsp + 160], xmm10\nmovaps [rsp + 176], xmm11\n",
+            "movaps [rsp + 192], xmm12\nmovaps [rsp + 208], xmm13\n",
+            "movaps [rsp + 224], xmm14\nmovaps [rsp + 240], xmm15\n",
+            "xor eax, eax\n",
+            $call,
+            "movaps xmm0, [rsp]\nmovaps xmm1, [rsp + 16]\n",
+            "movaps xmm2, [rsp + 32]\nmovaps xmm3, [rsp + 48]\n",
+            "movaps xmm4, [rsp + 64]\nmovaps xmm5, [rsp + 80]\n",
+            "movaps xmm6, [rsp + 96]\nmovaps xmm7, [rsp + 112]\n",
+            "movaps xmm8, [rsp + 128]\nmovaps xmm9, [rsp + 144]\n",
+            "movaps xmm10, [rsp + 160]\nmovaps xmm11, [rsp + 176]\n",
+            "movaps xmm12, [rsp + 192]\nmovaps xmm13, [rsp + 208]\n",
+            "movaps xmm14, [rsp + 224]\nmovaps xmm15, [rsp + 240]\n",
+            "add rsp, 256\n",
+            $crate::__asm_call_arguments!(@restore),
+            "pop r11\npop r10\npop r9\npop r8\n",
+            "pop rdi\npop rsi\npop rdx\npop rcx\npop rax\n",
+            "popfq\n"
+        )
+    };
+}
+
+#[cfg(all(
+    feature = "printf-debug",
+    debug_assertions,
+    target_arch = "x86_64",
+    windows
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf {
+    ($call: expr, $format: literal $(, $argument: tt)*) => {
+        concat!(
+            "pushfq\n",
+            "push rax\npush rcx\npush rdx\n",
+            "push r8\npush r9\npush r10\npush r11\n",
+            $crate::__asm_printf_arguments!(@push $($argument)*),
+            $crate::__asm_printf_arguments!([rdx r8 r9] $($argument)*),
+            $crate::__asm_printf_target!(@format $format),
+            "lea rcx, [rip + ", $crate::__asm_local_prefix!(), "asm_block_printf]\n",
+            $crate::__asm_call_arguments!(@align),
+            "sub rsp, 128\n",
+            "movaps [rsp + 32], xmm0\nmovaps [rsp + 48], xmm1\n",
+            "movaps [rsp + 64], xmm2\nmovaps [rsp + 80], xmm3\n",
+            "movaps [rsp + 96], xmm4\nmovaps [rsp + 112], xmm5\n",
+            $call,
+            "movaps xmm0, [rsp + 32]\nmovaps xmm1, [rsp + 48]\n",
+            "movaps xmm2, [rsp + 64]\nmovaps xmm3, [rsp + 80]\n",
+            "movaps xmm4, [rsp + 96]\nmovaps xmm5, [rsp + 112]\n",
+            "add rsp, 128\n",
+            $crate::__asm_call_arguments!(@restore),
+            "pop r11\npop r10\npop r9\npop r8\n",
+            "pop rdx\npop rcx\npop rax\n",
+            "popfq\n"
+        )
+    };
+}
+
+#[cfg(all(
+    feature = "printf-debug",
+    debug_assertions,
+    not(target_arch = "x86_64")
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf {
+    ($($token: tt)*) => {
+        compile_error!("`asm_printf!` is only supported on x86_64")
+    };
+}
+
+#[cfg(not(all(feature = "printf-debug", debug_assertions)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_printf {
+    ($($token: tt)*) => { "" };
+}
+
+/// Stop at a breakpoint.
+///
+/// This evaluates to a `&'static str` with the breakpoint instruction of the
+/// target, which raises `SIGTRAP` on Unix, or stops in an attached debugger:
+///
+/// | Architecture         | Instruction |
+/// |----------------------|-------------|
+/// | x86, x86-64          | `int3`      |
+/// | ARM                  | `bkpt #0`   |
+/// | AArch64              | `brk #0`    |
+/// | RISC-V               | `ebreak`    |
+///
+/// Debuggers skip `int3` when continuing, but stop on `bkpt`, `brk` and
+/// `ebreak` again, so the program counter must be moved past it by hand.
+///
+/// # Example
+/// ```
+/// use asm_block::debug_trap;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(debug_trap!(), "int3\n");
+/// ```
+#[macro_export]
+macro_rules! debug_trap {
+    () => {
+        $crate::__asm_trap!()
+    };
+}
+
+/// Stop at a breakpoint if a condition holds.
+///
+/// This evaluates to a `&'static str` which branches over a [`debug_trap!`]
+/// unless the condition holds. The condition is written like the condition
+/// of a branch of the target:
+/// - on x86 and x86-64, a condition code of `jcc`, e.g. `trap_if!(ne)`.
+/// - on ARM and AArch64, a condition code of `b`, e.g. `trap_if!(hs)`.
+/// - on RISC-V, a comparison of two registers, e.g. `trap_if!(ltu, {a}, {b})`,
+///   where the comparisons are those of `beq`, `bne`, `blt`, `bge`, `bltu`
+///   and `bgeu`.
+///
+/// The branch goes to the numeric label `42000`, so the check can be
+/// duplicated by the compiler, as long as no branch of the block jumps over it
+/// to that label.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::trap_if;
+/// # #[cfg(target_arch = "x86_64")]
+/// # unsafe {
+/// asm!(
+///     "cmp {x}, 8",
+///     trap_if!(ae),
+///     x = in(reg) 5,
+/// );
+/// # }
+/// ```
+///
+/// [`debug_trap!`]: macro.debug_trap.html
+#[macro_export]
+macro_rules! trap_if {
+    ($($condition: tt)+) => {
+        concat!(
+            $crate::__asm_trap!(unless $($condition)+), " 42000f\n",
+            $crate::__asm_trap!(),
+            "42000:\n"
+        )
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_trap {
+    () => { "int3\n" };
+    (unless o) => { "jno" };
+    (unless no) => { "jo" };
+    (unless b) => { "jae" };
+    (unless c) => { "jnc" };
+    (unless nae) => { "jae" };
+    (unless ae) => { "jb" };
+    (unless nb) => { "jb" };
+    (unless nc) => { "jc" };
+    (unless e) => { "jne" };
+    (unless z) => { "jnz" };
+    (unless ne) => { "je" };
+    (unless nz) => { "jz" };
+    (unless be) => { "ja" };
+    (unless na) => { "ja" };
+    (unless a) => { "jbe" };
+    (unless nbe) => { "jbe" };
+    (unless s) => { "jns" };
+    (unless ns) => { "js" };
+    (unless p) => { "jnp" };
+    (unless pe) => { "jpo" };
+    (unless np) => { "jp" };
+    (unless po) => { "jpe" };
+    (unless l) => { "jge" };
+    (unless nge) => { "jge" };
+    (unless ge) => { "jl" };
+    (unless nl) => { "jl" };
+    (unless le) => { "jg" };
+    (unless ng) => { "jg" };
+    (unless g) => { "jle" };
+    (unless nle) => { "jle" };
+    (unless $($condition: tt)*) => {
+        compile_error!("expected a condition code of `jcc`, e.g. `ne`")
+    };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_trap {
+    () => { "bkpt #0\n" };
+    (unless eq) => { "bne" };
+    (unless ne) => { "beq" };
+    (unless cs) => { "bcc" };
+    (unless hs) => { "blo" };
+    (unless cc) => { "bcs" };
+    (unless lo) => { "bhs" };
+    (unless mi) => { "bpl" };
+    (unless pl) => { "bmi" };
+    (unless vs) => { "bvc" };
+    (unless vc) => { "bvs" };
+    (unless hi) => { "bls" };
+    (unless ls) => { "bhi" };
+    (unless ge) => { "blt" };
+    (unless lt) => { "bge" };
+    (unless gt) => { "ble" };
+    (unless le) => { "bgt" };
+    (unless $($condition: tt)*) => {
+        compile_error!("expected a condition code of `b`, e.g. `ne`")
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_trap {
+    () => { "brk #0\n" };
+    (unless eq) => { "b.ne" };
+    (unless ne) => { "b.eq" };
+    (unless cs) => { "b.cc" };
+    (unless hs) => { "b.lo" };
+    (unless cc) => { "b.cs" };
+    (unless lo) => { "b.hs" };
+    (unless mi) => { "b.pl" };
+    (unless pl) => { "b.mi" };
+    (unless vs) => { "b.vc" };
+    (unless vc) => { "b.vs" };
+    (unless hi) => { "b.ls" };
+    (unless ls) => { "b.hi" };
+    (unless ge) => { "b.lt" };
+    (unless lt) => { "b.ge" };
+    (unless gt) => { "b.le" };
+    (unless le) => { "b.gt" };
+    (unless $($condition: tt)*) => {
+        compile_error!("expected a condition code of `b`, e.g. `ne`")
+    };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_trap {
+    () => { "ebreak\n" };
+    (unless eq, $a: tt, $b: tt $(,)?) => {
+        $crate::asm_block!(bne $a, $b,)
+    };
+    (unless ne, $a: tt, $b: tt $(,)?) => {
+        $crate::asm_block!(beq $a, $b,)
+    };
+    (unless lt, $a: tt, $b: tt $(,)?) => {
+        $crate::asm_block!(bge $a, $b,)
+    };
+    (unless ge, $a: tt, $b: tt $(,)?) => {
+        $crate::asm_block!(blt $a, $b,)
+    };
+    (unless ltu, $a: tt, $b: tt $(,)?) => {
+        $crate::asm_block!(bgeu $a, $b,)
+    };
+    (unless geu, $a: tt, $b: tt $(,)?) => {
+        $crate::asm_block!(bltu $a, $b,)
+    };
+    (unless $($condition: tt)*) => {
+        compile_error!("expected a comparison of `beq`, `bne`, `blt`, `bge`, `bltu` or `bgeu` and two registers, e.g. `ne, a0, a1`")
+    };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_trap {
+    ($($token: tt)*) => {
+        compile_error!("breakpoints are only supported on x86, x86_64, ARM, AArch64 and RISC-V")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_printf, trap_if)]
+mod tests {
+    #[cfg(not(all(feature = "printf-debug", debug_assertions)))]
+    #[test]
+    fn test_disabled() {
+        assert_eq!(asm_printf!("%d %d\n", {x}, 1), "");
+        assert_eq!(
+            asm_printf!(hook {f}, "", rax, rbx, rcx, rdx, rsi, rdi, r8),
+            ""
+        );
+    }
+
+    #[cfg(all(
+        feature = "printf-debug",
+        debug_assertions,
+        target_os = "linux",
+        target_arch = "x86_64"
+    ))]
+    #[test]
+    fn test_printf() {
+        let code = asm_printf!(hook {f}, "%d %d\n", {x}, 1);
+        let expected = concat!(
+            "pushfq\n",
+            "push rax\npush rcx\npush rdx\npush rsi\npush rdi\n",
+            "push r8\npush r9\npush r10\npush r11\n",
+            "push 1 \npush {x}\n",
+            "pop rsi\npop rdx\n",
+            ".pushsection .rodata, \"a\"\n",
+            ".set .Lasm_block_printf, .\n",
+            ".asciz \"%d %d\\n\"\n",
+            ".popsection\n",
+            "lea rdi, [rip + .Lasm_block_printf]\n",
+            "push rsp\npush qword ptr [rsp]\nand rsp, -16\n",
+            "sub rsp, 256\n",
+        );
+        assert!(code.starts_with(expected));
+        assert!(code.contains("xor eax, eax\ncall {f}\n"));
+        assert!(code.ends_with("pop rax\npopfq\n"));
+    }
+
+    #[cfg(all(
+        feature = "printf-debug",
+        debug_assertions,
+        target_arch = "x86_64",
+        not(windows)
+    ))]
+    #[test]
+    fn test_hook() {
+        use core::sync::atomic::{AtomicU64, Ordering};
+        static LOGGED: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+        extern "C" fn hook(format: *const u8, a: u64, b: u64) {
+            LOGGED[0].store(unsafe { *format } as u64, Ordering::Relaxed);
+            LOGGED[1].store(a, Ordering::Relaxed);
+            LOGGED[2].store(b, Ordering::Relaxed);
+        }
+        let (mut a, mut b, mut c): (u64, u64, u64) = (1, 2, 3);
+        unsafe {
+            core::arch::asm!(
+                "add {a}, 10",
+                asm_printf!(hook {f}, "x", rsi, {a}),
+                "cmp {a}, 11",
+                "jne 2f",
+                "add rsi, {a}",
+                "2:",
+                f = sym hook,
+                a = inout(reg) a,
+                inout("rsi") b,
+                inout("rdi") c,
+            );
+        }
+        assert_eq!((a, b, c), (11, 13, 3));
+        assert_eq!(LOGGED[0].load(Ordering::Relaxed), u64::from(b'x'));
+        assert_eq!(LOGGED[1].load(Ordering::Relaxed), 2);
+        assert_eq!(LOGGED[2].load(Ordering::Relaxed), 11);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_trap() {
+        assert_eq!(debug_trap!(), "int3\n");
+        let code = trap_if!(nae);
+        assert_eq!(code, "jae 42000f\nint3\n42000:\n");
+        #[cfg(feature = "std")]
+        if let Some(instructions) = crate::test::assert_disassembles(crate::test::Arch::X86_64, concat!(trap_if!(z), trap_if!(g))) {
+            let mnemonics = instructions.iter().map(|i| i.mnemonic()).collect::<Vec<_>>();
+            assert_eq!(mnemonics, ["jne", "int3", "jle", "int3"]);
+        }
+        let mut x: u64 = 5;
+        unsafe {
+            core::arch::asm!(
+                "cmp {x}, 8",
+                trap_if!(ae),
+                trap_if!(ae),
+                "inc {x}",
+                x = inout(reg) x,
+            );
+            core::arch::asm!("cmp {x}, 6", trap_if!(ne), x = in(reg) x);
+        }
+        assert_eq!(x, 6);
+    }
+}
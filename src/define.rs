@@ -0,0 +1,149 @@
+/// Define a fragment with named parameters and default arguments.
+///
+/// A fragment written with `macro_rules!` needs an arm for every combination
+/// of arguments its callers may leave out, e.g. a scratch register which is
+/// usually `rax` but sometimes has to be another one. This defines the macro
+/// `name` from a list of parameters, each optionally followed by `= default`,
+/// and a block transcribed by [`asm_block!`], in which the parameters are
+/// used like metavariables. The macro evaluates to a `&'static str` and takes
+/// the arguments by position, then by name as `param = value`, in any order,
+/// so that a caller overrides only the parameters it cares about:
+/// - arguments are single `tt`s, so values made of several tokens must be
+///   wrapped in parentheses or braces, as for any fragment.
+/// - a parameter without a default must be given, and a named argument
+///   overrides a positional one, but cannot be given twice.
+/// - unknown names and extra arguments fail to compile with the name of the
+///   fragment.
+///
+/// The macro invokes itself by name to bind the arguments, so it must be in
+/// scope by that name where it is used, e.g. imported with `use` from the
+/// crate exporting it.
+///
+/// # Example
+/// ```
+/// use asm_block::define_fragment;
+/// define_fragment! {
+///     /// Multiply `$x` by `$y` and add it, through a scratch register.
+///     macro mad($x, $y, $tmp = rax) {
+///         mov $tmp, $y;
+///         imul $tmp, $x;
+///         add $x, $tmp;
+///     }
+/// }
+/// assert_eq!(mad!({x}, 5), "mov rax , 5 \nimul rax , {x}\nadd {x}, rax \n");
+/// assert_eq!(mad!(tmp = {t}, x = rbx, y = 3), "mov {t}, 3 \nimul {t}, rbx \nadd rbx , {t}\n");
+/// ```
+///
+/// A named argument given twice is rejected:
+/// ```compile_fail
+/// use asm_block::define_fragment;
+/// define_fragment! {
+///     macro mad($x, $y, $tmp = rax) {
+///         mov $tmp, $y;
+///         imul $tmp, $x;
+///         add $x, $tmp;
+///     }
+/// }
+/// mad!({x}, 5, tmp = rbx, tmp = rcx);
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! define_fragment {
+    (@define ($d: tt) [$(#[$attr: meta])*] $mac: ident [$($param: ident [$($default: tt)?])*] { $($token: tt)* }) => {
+        $(#[$attr])*
+        macro_rules! $mac {
+            (@expand $($d $param: tt),*) => {
+                $crate::asm_block! { $($token)* }
+            };
+
+            // bind the positional arguments to the parameters in order, where
+            // every parameter is a slot `(param [value])`, empty if unbound
+            (@position [$d ($d done: tt)*] [$d ($d slot: tt)*] $d name: ident = $d ($d rest: tt)*) => {
+                $mac!(@name [] [$d ($d done)* $d ($d slot)*] $d name = $d ($d rest)*)
+            };
+            (@position [$d ($d done: tt)*] [($d p: ident $d old: tt) $d ($d slot: tt)*] $d value: tt $d (, $d ($d rest: tt)*)?) => {
+                $mac!(@position [$d ($d done)* ($d p [$d value])] [$d ($d slot)*] $d ($d ($d rest)*)?)
+            };
+            (@position [$d ($d done: tt)*] [$d ($d slot: tt)*]) => {
+                $mac!(@name [] [$d ($d done)* $d ($d slot)*])
+            };
+            (@position $d done: tt [] $d ($d rest: tt)+) => {
+                compile_error!(concat!("too many arguments to `", stringify!($mac), "!`"))
+            };
+
+            // find the slot of the next named argument, rotating the slots
+            // before it into the first list, and mark it with `=` once bound
+            $(
+                (@name $d done: tt [($param $d old: tt =) $d ($d slot: tt)*] $param = $d ($d rest: tt)*) => {
+                    compile_error!(concat!("argument `", stringify!($param), "` of `", stringify!($mac), "!` is given twice"))
+                };
+                (@name [$d ($d done: tt)*] [($param $d old: tt) $d ($d slot: tt)*] $param = $d value: tt $d (, $d ($d rest: tt)*)?) => {
+                    $mac!(@name [] [$d ($d done)* ($param [$d value] =) $d ($d slot)*] $d ($d ($d rest)*)?)
+                };
+            )*
+            (@name [$d ($d done: tt)*] [$d first: tt $d ($d slot: tt)*] $d name: ident = $d ($d rest: tt)*) => {
+                $mac!(@name [$d ($d done)* $d first] [$d ($d slot)*] $d name = $d ($d rest)*)
+            };
+            (@name $d done: tt [] $d name: ident = $d ($d rest: tt)*) => {
+                compile_error!(concat!("`", stringify!($mac), "!` has no parameter `", stringify!($d name), "`"))
+            };
+            (@name [] [$d (($d p: ident [$d value: tt] $d (=)?))*]) => {
+                $mac!(@expand $d ($d value),*)
+            };
+            (@name [] [$d ($d slot: tt)*]) => {
+                $mac!(@missing $d ($d slot)*)
+            };
+            (@name $d ($d rest: tt)*) => {
+                compile_error!(concat!("expected `name = value` in the arguments of `", stringify!($mac), "!`"))
+            };
+
+            (@missing ($d p: ident []) $d ($d rest: tt)*) => {
+                compile_error!(concat!("missing argument `", stringify!($d p), "` of `", stringify!($mac), "!`"))
+            };
+            (@missing $d first: tt $d ($d rest: tt)*) => {
+                $mac!(@missing $d ($d rest)*)
+            };
+
+            ($d ($d arg: tt)*) => {
+                $mac!(@position [] [$(($param [$($default)?]))*] $d ($d arg)*)
+            };
+        }
+    };
+
+    (
+        $(#[$attr: meta])*
+        macro $mac: ident($($dollar: tt $param: ident $(= $default: tt)?),* $(,)?) { $($token: tt)* }
+    ) => {
+        $crate::define_fragment!(@define ($) [$(#[$attr])*] $mac [$($param [$($default)?])*] { $($token)* });
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(define_fragment, load)]
+mod tests {
+    define_fragment! {
+        macro load($dst, $base = rsp, $offset = 0) {
+            mov $dst, [$base + $offset];
+        }
+    }
+
+    define_fragment! {
+        macro nop() {
+            nop;
+        }
+    }
+
+    #[test]
+    fn test_define_fragment() {
+        assert_eq!(load!(rax), "mov rax , [rsp + 0 ] \n");
+        assert_eq!(load!({x}, {p}), "mov {x}, [{p}+ 0 ] \n");
+        assert_eq!(load!(rax, offset = 8), "mov rax , [rsp + 8 ] \n");
+        assert_eq!(
+            load!(offset = 8, dst = rax, base = rbp,),
+            "mov rax , [rbp + 8 ] \n"
+        );
+        assert_eq!(load!(rax, rbx, dst = rcx), "mov rcx , [rbx + 0 ] \n");
+        assert_eq!(nop!(), "nop \n");
+    }
+}
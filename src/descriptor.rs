@@ -0,0 +1,219 @@
+/// Emit a segment descriptor of the global or a local descriptor table.
+///
+/// This evaluates to a `&'static str` with a `.quad` directive whose value is
+/// the descriptor made of the fields, which the assembler splits and shifts
+/// into place:
+///
+/// | Field    | Bits                          | Meaning                                          |
+/// |----------|-------------------------------|--------------------------------------------------|
+/// | `base`   | 16–39, 56–63                  | linear address of the segment                    |
+/// | `limit`  | 0–15, 48–51                   | last offset of the segment, 20 bits              |
+/// | `access` | 40–47                         | present, DPL, type, e.g. `0x9a` for kernel code  |
+/// | `flags`  | 52–55                         | granularity, size and long mode, e.g. `0xa`      |
+///
+/// In long mode, the base and the limit of code and data segments are
+/// ignored, e.g. `gdt_entry!(base = 0, limit = 0, access = 0x9a, flags = 0x2)`
+/// is the usual kernel code segment. System segments like the TSS are 16
+/// bytes there, with the upper half of the base in a second `.quad`, which is
+/// emitted when `system` is given first.
+///
+/// The fields must be absolute expressions, i.e. immediates, placeholders of
+/// `const` operands, or differences of labels in the same section, as no
+/// relocation can split a symbol into bitfields. Values made of several tokens
+/// must be wrapped in parentheses.
+///
+/// # Example
+/// ```
+/// use asm_block::gdt_entry;
+/// # #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// assert_eq!(
+///     gdt_entry!(base = 0, limit = 0xfffff, access = 0x92, flags = 0xc),
+///     ".quad ((((0xfffff ) >> 0) & 0xffff) << 0) | ((((0 ) >> 0) & 0xffffff) << 16) \
+///      | ((((0x92 ) >> 0) & 0xff) << 40) | ((((0xfffff ) >> 16) & 0xf) << 48) \
+///      | ((((0xc ) >> 0) & 0xf) << 52) | ((((0 ) >> 24) & 0xff) << 56)\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! gdt_entry {
+    (system, base = $base: tt, limit = $limit: tt, access = $access: tt, flags = $flags: tt $(,)?) => {
+        $crate::__asm_descriptor!(gdt $base $limit $access $flags true)
+    };
+    (base = $base: tt, limit = $limit: tt, access = $access: tt, flags = $flags: tt $(,)?) => {
+        $crate::__asm_descriptor!(gdt $base $limit $access $flags false)
+    };
+}
+
+/// Emit a gate descriptor of the interrupt descriptor table.
+///
+/// This evaluates to a `&'static str` with the gate made of the fields, in the
+/// format of the target: 16 bytes on x86-64, where `ist` selects the interrupt
+/// stack table entry, 0 if omitted, and 8 bytes on x86, where there is no such
+/// field, as for [`gdt_entry!`]:
+///
+/// | Field      | Bits on x86-64 | Bits on x86 | Meaning                                          |
+/// |------------|----------------|-------------|--------------------------------------------------|
+/// | `offset`   | 0–15, 48–95    | 0–15, 48–63 | address of the handler                           |
+/// | `selector` | 16–31          | 16–31       | code segment of the handler                      |
+/// | `ist`      | 32–34          |             | stack of the handler, from 1 to 7                |
+/// | `access`   | 40–47          | 40–47       | present, DPL, type, e.g. `0x8e` for an interrupt |
+///
+/// The same rules apply to the fields, so the offset is only a symbol if it
+/// is absolute, e.g. defined with `.set` for a kernel linked at a fixed
+/// address. Otherwise, the table has to be filled at run time.
+///
+/// # Example
+/// ```
+/// use asm_block::idt_entry;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     idt_entry!(offset = {handler}, selector = 0x08, access = 0x8e, ist = 1),
+///     ".quad (((({handler}) >> 0) & 0xffff) << 0) | ((((0x08 ) >> 0) & 0xffff) << 16) \
+///      | ((((1 ) >> 0) & 0x7) << 32) | ((((0x8e ) >> 0) & 0xff) << 40) \
+///      | (((({handler}) >> 16) & 0xffff) << 48)\n\
+///      .quad (((({handler}) >> 32) & 0xffffffff) << 0)\n"
+/// );
+/// ```
+///
+/// [`gdt_entry!`]: macro.gdt_entry.html
+#[macro_export]
+macro_rules! idt_entry {
+    (offset = $offset: tt, selector = $selector: tt, access = $access: tt $(, ist = $ist: tt)? $(,)?) => {
+        $crate::__asm_descriptor!(idt $offset $selector $access $($ist)?)
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_descriptor {
+    // take `mask` bits of the value starting at `from`, and move them to `to`
+    (@bits $value: tt >> $from: literal & $mask: literal << $to: literal) => {
+        concat!("((((", $crate::asm_block!($value), ") >> ", $from, ") & ", $mask, ") << ", $to, ")")
+    };
+
+    (gdt $base: tt $limit: tt $access: tt $flags: tt $system: tt) => {
+        concat!(
+            ".quad ",
+            $crate::__asm_descriptor!(@bits $limit >> 0 & "0xffff" << 0),
+            " | ",
+            $crate::__asm_descriptor!(@bits $base >> 0 & "0xffffff" << 16),
+            " | ",
+            $crate::__asm_descriptor!(@bits $access >> 0 & "0xff" << 40),
+            " | ",
+            $crate::__asm_descriptor!(@bits $limit >> 16 & "0xf" << 48),
+            " | ",
+            $crate::__asm_descriptor!(@bits $flags >> 0 & "0xf" << 52),
+            " | ",
+            $crate::__asm_descriptor!(@bits $base >> 24 & "0xff" << 56),
+            "\n",
+            $crate::__asm_descriptor!(@upper $system $base)
+        )
+    };
+    (@upper true $value: tt) => {
+        concat!(".quad ", $crate::__asm_descriptor!(@bits $value >> 32 & "0xffffffff" << 0), "\n")
+    };
+    (@upper false $value: tt) => { "" };
+
+    (idt $offset: tt $selector: tt $access: tt) => {
+        $crate::__asm_descriptor!(idt $offset $selector $access 0)
+    };
+    (idt $offset: tt $selector: tt $access: tt $ist: tt) => {
+        $crate::__asm_descriptor_gate!($offset $selector $access $ist)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_descriptor_gate {
+    ($offset: tt $selector: tt $access: tt $ist: tt) => {
+        concat!(
+            ".quad ",
+            $crate::__asm_descriptor!(@bits $offset >> 0 & "0xffff" << 0),
+            " | ",
+            $crate::__asm_descriptor!(@bits $selector >> 0 & "0xffff" << 16),
+            " | ",
+            $crate::__asm_descriptor!(@bits $ist >> 0 & "0x7" << 32),
+            " | ",
+            $crate::__asm_descriptor!(@bits $access >> 0 & "0xff" << 40),
+            " | ",
+            $crate::__asm_descriptor!(@bits $offset >> 16 & "0xffff" << 48),
+            "\n",
+            $crate::__asm_descriptor!(@upper true $offset)
+        )
+    };
+}
+
+#[cfg(target_arch = "x86")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_descriptor_gate {
+    ($offset: tt $selector: tt $access: tt 0) => {
+        concat!(
+            ".quad ",
+            $crate::__asm_descriptor!(@bits $offset >> 0 & "0xffff" << 0),
+            " | ",
+            $crate::__asm_descriptor!(@bits $selector >> 0 & "0xffff" << 16),
+            " | ",
+            $crate::__asm_descriptor!(@bits $access >> 0 & "0xff" << 40),
+            " | ",
+            $crate::__asm_descriptor!(@bits $offset >> 16 & "0xffff" << 48),
+            "\n"
+        )
+    };
+    ($offset: tt $selector: tt $access: tt $ist: tt) => {
+        compile_error!("gates of x86 have no interrupt stack table field")
+    };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_descriptor {
+    ($($token: tt)*) => {
+        compile_error!("descriptor tables are only supported on x86 and x86_64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(gdt_entry, idt_entry)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_descriptor() {
+        let (code, data, tss_low, tss_high, gate_low, gate_high): (u64, u64, u64, u64, u64, u64);
+        unsafe {
+            core::arch::asm!(
+                "jmp 3f",
+                "2:",
+                gdt_entry!(base = 0, limit = 0xfffff, access = 0x9a, flags = 0xa),
+                gdt_entry!(base = 0x12345678, limit = 0x67, access = 0x92, flags = 0x4),
+                gdt_entry!(system, base = {base}, limit = (0x2000 - 1), access = 0x89, flags = 0),
+                idt_entry!(offset = {base}, selector = 0x08, access = 0x8e, ist = 2),
+                "3:",
+                "lea {p}, [rip + 2b]",
+                "mov {a}, [{p}]",
+                "mov {b}, [{p} + 8]",
+                "mov {c}, [{p} + 16]",
+                "mov {d}, [{p} + 24]",
+                "mov {e}, [{p} + 32]",
+                "mov {f}, [{p} + 40]",
+                base = const 0xffff_8000_dead_beefu64,
+                p = out(reg) _,
+                a = out(reg) code,
+                b = out(reg) data,
+                c = out(reg) tss_low,
+                d = out(reg) tss_high,
+                e = out(reg) gate_low,
+                f = out(reg) gate_high,
+                options(pure, readonly, nostack),
+            );
+        }
+        assert_eq!(code, 0x00af_9a00_0000_ffff);
+        assert_eq!(data, 0x1240_9234_5678_0067);
+        assert_eq!(tss_low, 0xde00_89ad_beef_1fff);
+        assert_eq!(tss_high, 0xffff_8000);
+        assert_eq!(gate_low, 0xdead_8e02_0008_beef);
+        assert_eq!(gate_high, 0xffff_8000);
+    }
+}
@@ -0,0 +1,338 @@
+//! Fragments implemented for several architectures.
+//!
+//! A primitive like a spin-loop hint or a full barrier has a different
+//! implementation on every architecture. [`asm_fragment!`] declares a type
+//! implementing [`AsmFragment`] with one block per architecture, so that code
+//! generic over the primitive can pick the implementation of the target with
+//! [`AsmFragment::CODE`] instead of repeating the `cfg`s:
+//! ```
+//! use asm_block::asm_fragment;
+//! use asm_block::fragment::AsmFragment;
+//!
+//! asm_fragment! {
+//!     /// A hint for spin loops.
+//!     pub struct SpinHint;
+//!     x86 | x86_64 => { pause; }
+//!     aarch64 => { yield; }
+//! }
+//! asm_fragment! {
+//!     /// A full memory barrier.
+//!     pub struct Barrier;
+//!     x86 | x86_64 => { mfence; }
+//!     aarch64 => { dmb ish; }
+//!     riscv32 | riscv64 => { fence rw, rw; }
+//! }
+//!
+//! const fn len<F: AsmFragment>() -> usize {
+//!     match F::CODE {
+//!         Some(code) => code.len(),
+//!         None => 0,
+//!     }
+//! }
+//!
+//! const BARRIER: usize = len::<Barrier>();
+//! assert_eq!(SpinHint::X86_64, Some("pause \n"));
+//! assert_eq!(SpinHint::ARM, None);
+//! # #[cfg(target_arch = "x86_64")]
+//! assert_eq!(BARRIER, "mfence \n".len());
+//! ```
+//!
+//! The templates of [`asm!`] and [`global_asm!`] must be made of literals and
+//! macros, so the code of a fragment selected through generics cannot be
+//! passed to them. The trait is meant for `const` contexts, e.g. to compare or
+//! audit implementations, to register them in a [`Fragment`] or to assemble
+//! them in tests.
+//!
+//! [`Unroll`] repeats a fragment a number of times given by a const generic,
+//! e.g. the unroll factor of a generic SIMD library, where [`asm_foreach!`]
+//! would need the indices written as tokens:
+//! ```
+//! use asm_block::asm_fragment;
+//! use asm_block::fragment::{AsmFragment, Unroll};
+//!
+//! asm_fragment! {
+//!     /// Add the lane `{index}` to the accumulator.
+//!     pub struct AddLane;
+//!     x86_64 => { paddd xmm0, [rsi + 16 * {index}]; }
+//! }
+//!
+//! assert_eq!(Unroll::<AddLane, 0>::X86_64, Some(""));
+//! assert_eq!(
+//!     Unroll::<AddLane, 2>::X86_64,
+//!     Some("paddd xmm0 , [rsi + 16 * 0] \npaddd xmm0 , [rsi + 16 * 1] \n")
+//! );
+//! ```
+//!
+//! [`asm_fragment!`]: ../macro.asm_fragment.html
+//! [`asm_foreach!`]: ../macro.asm_foreach.html
+//! [`Fragment`]: ../registry/struct.Fragment.html
+//! [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+//! [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+
+use core::marker::PhantomData;
+
+/// A fragment with an implementation per architecture.
+///
+/// Every architecture without an implementation is `None`. Implement it with
+/// [`asm_fragment!`].
+///
+/// [`asm_fragment!`]: ../macro.asm_fragment.html
+pub trait AsmFragment {
+    /// The name of the fragment.
+    const NAME: &'static str;
+    /// The implementation for x86.
+    const X86: Option<&'static str> = None;
+    /// The implementation for x86-64.
+    const X86_64: Option<&'static str> = None;
+    /// The implementation for ARM.
+    const ARM: Option<&'static str> = None;
+    /// The implementation for AArch64.
+    const AARCH64: Option<&'static str> = None;
+    /// The implementation for 32-bit RISC-V.
+    const RISCV32: Option<&'static str> = None;
+    /// The implementation for 64-bit RISC-V.
+    const RISCV64: Option<&'static str> = None;
+
+    /// The implementation for the target architecture.
+    const CODE: Option<&'static str> = if cfg!(target_arch = "x86") {
+        Self::X86
+    } else if cfg!(target_arch = "x86_64") {
+        Self::X86_64
+    } else if cfg!(target_arch = "arm") {
+        Self::ARM
+    } else if cfg!(target_arch = "aarch64") {
+        Self::AARCH64
+    } else if cfg!(target_arch = "riscv32") {
+        Self::RISCV32
+    } else if cfg!(target_arch = "riscv64") {
+        Self::RISCV64
+    } else {
+        None
+    };
+}
+
+/// A fragment repeated `N` times.
+///
+/// Every implementation of `F` is concatenated `N` times, each copy with the
+/// index of the copy, from 0, in place of every `{index}` in the code, so
+/// that the copies can address their own data or define their own labels.
+/// The architectures without an implementation of `F` have none either. The
+/// code is built at compile time in a buffer of `CAP` bytes, and a fragment
+/// unrolled to a longer code fails to compile.
+///
+/// See the [module documentation](index.html) for an example.
+pub struct Unroll<F, const N: usize, const CAP: usize = 4096>(PhantomData<F>);
+
+/// The code of a fragment built at compile time.
+struct Code<const CAP: usize> {
+    buf: [u8; CAP],
+    len: usize,
+}
+
+impl<const CAP: usize> Code<CAP> {
+    const fn unroll(code: Option<&str>, n: usize) -> Option<Self> {
+        let code = match code {
+            Some(code) => code.as_bytes(),
+            None => return None,
+        };
+        let mut unrolled = Code {
+            buf: [0; CAP],
+            len: 0,
+        };
+        let mut index = 0;
+        while index < n {
+            let mut i = 0;
+            while i < code.len() {
+                if starts_with(code, i, INDEX) {
+                    unrolled = unrolled.push_number(index);
+                    i += INDEX.len();
+                } else {
+                    unrolled = unrolled.push(code[i]);
+                    i += 1;
+                }
+            }
+            index += 1;
+        }
+        Some(unrolled)
+    }
+
+    const fn push(mut self, byte: u8) -> Self {
+        if self.len == CAP {
+            panic!("the unrolled fragment does not fit in the capacity of `Unroll`");
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+        self
+    }
+
+    const fn push_number(mut self, n: usize) -> Self {
+        if n >= 10 {
+            self = self.push_number(n / 10);
+        }
+        self.push(b'0' + (n % 10) as u8)
+    }
+
+    const fn as_str(code: &'static Option<Self>) -> Option<&'static str> {
+        match code {
+            // the code is copied from strings, and split at character boundaries
+            Some(code) => match core::str::from_utf8(code.buf.split_at(code.len).0) {
+                Ok(code) => Some(code),
+                Err(_) => unreachable!(),
+            },
+            None => None,
+        }
+    }
+}
+
+const INDEX: &[u8] = b"{index}";
+
+const fn starts_with(code: &[u8], at: usize, prefix: &[u8]) -> bool {
+    if code.len() - at < prefix.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < prefix.len() {
+        if code[at + i] != prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+impl<F: AsmFragment, const N: usize, const CAP: usize> Unroll<F, N, CAP> {
+    const UNROLLED: [Option<Code<CAP>>; 6] = [
+        Code::unroll(F::X86, N),
+        Code::unroll(F::X86_64, N),
+        Code::unroll(F::ARM, N),
+        Code::unroll(F::AARCH64, N),
+        Code::unroll(F::RISCV32, N),
+        Code::unroll(F::RISCV64, N),
+    ];
+}
+
+impl<F: AsmFragment, const N: usize, const CAP: usize> AsmFragment for Unroll<F, N, CAP> {
+    const NAME: &'static str = F::NAME;
+    const X86: Option<&'static str> = Code::as_str(&Self::UNROLLED[0]);
+    const X86_64: Option<&'static str> = Code::as_str(&Self::UNROLLED[1]);
+    const ARM: Option<&'static str> = Code::as_str(&Self::UNROLLED[2]);
+    const AARCH64: Option<&'static str> = Code::as_str(&Self::UNROLLED[3]);
+    const RISCV32: Option<&'static str> = Code::as_str(&Self::UNROLLED[4]);
+    const RISCV64: Option<&'static str> = Code::as_str(&Self::UNROLLED[5]);
+}
+
+/// Declare a fragment implemented for several architectures.
+///
+/// This declares a unit struct implementing [`AsmFragment`], followed by the
+/// architectures and the blocks implementing the fragment on them, which are
+/// translated by [`asm_block!`]. The architectures are spelled like
+/// `target_arch`, and several architectures sharing a block are separated by
+/// `|`. See the [module documentation](fragment/index.html) for an example.
+///
+/// [`AsmFragment`]: fragment/trait.AsmFragment.html
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! asm_fragment {
+    // define the constant of every architecture sharing a block
+    (@impl [] $block: tt) => {};
+    (@impl [x86 $(| $rest: ident)*] { $($token: tt)* }) => {
+        const X86: Option<&'static str> = Some($crate::asm_block!($($token)*));
+        $crate::asm_fragment!(@impl [$($rest)|*] { $($token)* });
+    };
+    (@impl [x86_64 $(| $rest: ident)*] { $($token: tt)* }) => {
+        const X86_64: Option<&'static str> = Some($crate::asm_block!($($token)*));
+        $crate::asm_fragment!(@impl [$($rest)|*] { $($token)* });
+    };
+    (@impl [arm $(| $rest: ident)*] { $($token: tt)* }) => {
+        const ARM: Option<&'static str> = Some($crate::asm_block!($($token)*));
+        $crate::asm_fragment!(@impl [$($rest)|*] { $($token)* });
+    };
+    (@impl [aarch64 $(| $rest: ident)*] { $($token: tt)* }) => {
+        const AARCH64: Option<&'static str> = Some($crate::asm_block!($($token)*));
+        $crate::asm_fragment!(@impl [$($rest)|*] { $($token)* });
+    };
+    (@impl [riscv32 $(| $rest: ident)*] { $($token: tt)* }) => {
+        const RISCV32: Option<&'static str> = Some($crate::asm_block!($($token)*));
+        $crate::asm_fragment!(@impl [$($rest)|*] { $($token)* });
+    };
+    (@impl [riscv64 $(| $rest: ident)*] { $($token: tt)* }) => {
+        const RISCV64: Option<&'static str> = Some($crate::asm_block!($($token)*));
+        $crate::asm_fragment!(@impl [$($rest)|*] { $($token)* });
+    };
+    (@impl [$other: ident $(| $rest: ident)*] $block: tt) => {
+        compile_error!(concat!("unsupported architecture `", stringify!($other), "`"));
+    };
+
+    (
+        $(#[$attr: meta])*
+        $vis: vis struct $name: ident;
+        $($($arch: ident)|+ => { $($token: tt)* })*
+    ) => {
+        $(#[$attr])*
+        $vis struct $name;
+
+        impl $crate::fragment::AsmFragment for $name {
+            const NAME: &'static str = stringify!($name);
+            $($crate::asm_fragment!(@impl [$($arch)|+] { $($token)* });)*
+        }
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_fragment)]
+mod tests {
+    use super::*;
+
+    asm_fragment! {
+        struct Nop;
+        x86 | x86_64 | arm | aarch64 | riscv32 | riscv64 => { nop; }
+    }
+
+    asm_fragment! {
+        struct Trap;
+        x86_64 => { ud2; }
+        aarch64 => { brk 0; }
+    }
+
+    asm_fragment! {
+        struct Empty;
+    }
+
+    #[test]
+    fn test_fragment() {
+        assert_eq!(Nop::NAME, "Nop");
+        assert_eq!(Nop::CODE, Some("nop \n"));
+        assert_eq!(Nop::RISCV32, Some("nop \n"));
+        assert_eq!(Trap::X86_64, Some("ud2 \n"));
+        assert_eq!(Trap::AARCH64, Some("brk 0 \n"));
+        assert_eq!(Trap::X86, None);
+        assert_eq!(
+            Trap::CODE.is_some(),
+            cfg!(any(target_arch = "x86_64", target_arch = "aarch64"))
+        );
+        assert_eq!(Empty::CODE, None);
+    }
+
+    asm_fragment! {
+        struct Byte;
+        x86_64 | aarch64 => { .byte {index}; }
+    }
+
+    fn unrolled<F: AsmFragment, const N: usize>() -> Option<&'static str> {
+        Unroll::<F, N>::X86_64
+    }
+
+    #[test]
+    fn test_unroll() {
+        assert_eq!(Unroll::<Nop, 3>::NAME, "Nop");
+        assert_eq!(Unroll::<Nop, 3>::ARM, Some("nop \nnop \nnop \n"));
+        assert_eq!(Unroll::<Trap, 2>::X86, None);
+        assert_eq!(Unroll::<Empty, 2>::CODE, None);
+        assert_eq!(unrolled::<Byte, 0>(), Some(""));
+        assert_eq!(
+            unrolled::<Byte, 12>().map(|code| code.lines().nth(11)),
+            Some(Some(".byte 11"))
+        );
+        assert_eq!(Unroll::<Byte, 2, 16>::AARCH64, Some(".byte 0\n.byte 1\n"));
+    }
+}
@@ -0,0 +1,258 @@
+/// Reserve named slots on the stack around a block.
+///
+/// This evaluates to a `&'static str` which lays out the slots in order, each
+/// aligned to its `align` or to 8 bytes, moves the stack pointer down by the
+/// size of the frame rounded up to 16 bytes, translates the block, and moves
+/// the stack pointer back. Inside the block, [`slot!`] refers to a slot as a
+/// memory operand relative to the stack pointer:
+/// - on x86 and x86-64, `[rsp + offset]` or `[esp + offset]`, in Intel syntax.
+/// - on AArch64, `[sp, #offset]`.
+///
+/// The offsets are computed by the assembler in local symbols defined with
+/// `.set`, so the block can be duplicated by the compiler, and several frames
+/// can follow each other in the same block. Frames cannot be nested, and the
+/// block must not move the stack pointer itself, e.g. with `push`, unless it
+/// moves it back before using a slot.
+///
+/// The alignment of a slot is relative to the stack pointer, which [`asm!`]
+/// aligns for a function call at the start of a block without `nostack`, i.e.
+/// to 16 bytes on x86-64 and AArch64, so slots can be aligned up to 16 bytes
+/// there. Since the frame uses the stack, the block must not have the
+/// `nostack` option anyway.
+///
+/// With `red_zone` first, the slots are placed in the red zone below the stack
+/// pointer of the System V ABI on x86-64, without moving the stack pointer,
+/// which fails to assemble if they take more than its 128 bytes. Targets
+/// without a red zone, i.e. Windows, UEFI, bare metal and other architectures,
+/// move the stack pointer as usual. Note that the red zone is only free for
+/// blocks *without* the `nostack` option: `nostack` lets the compiler keep its
+/// own data in the red zone around the block, which the slots would then
+/// silently overwrite, while without it the compiler keeps the red zone free
+/// because the block may push. Targets whose red zone is disabled in a custom
+/// target specification, like kernels, cannot be detected and must not use
+/// `red_zone`.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::{frame, slot};
+/// # #[cfg(target_arch = "x86_64")]
+/// fn swap(x: &mut u64, y: &mut u64) {
+///     unsafe {
+///         asm!(
+///             frame!([a: 8, b: 8], {
+///                 mov @inline(slot!(a)), {x};
+///                 mov @inline(slot!(b)), {y};
+///                 mov {x}, @inline(slot!(b));
+///                 mov {y}, @inline(slot!(a));
+///             }),
+///             x = inout(reg) *x,
+///             y = inout(reg) *y,
+///         );
+///     }
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// let (mut x, mut y) = (1, 2);
+/// swap(&mut x, &mut y);
+/// assert_eq!((x, y), (2, 1));
+/// # }
+/// ```
+///
+/// [`slot!`]: macro.slot.html
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+#[macro_export]
+macro_rules! frame {
+    (@size) => {
+        concat!($crate::__asm_local_prefix!(), "asm_block_frame_size")
+    };
+    (@slot $name: ident $size: tt []) => {
+        $crate::frame!(@slot $name $size [8])
+    };
+    (@slot $name: ident $size: tt [$align: tt]) => {
+        concat!(
+            ".set ", $crate::slot!(@symbol $name), ", (", $crate::frame!(@size), " + ",
+            $align, " - 1) & -", $align, "\n",
+            ".set ", $crate::frame!(@size), ", ", $crate::slot!(@symbol $name), " + ", $size, "\n"
+        )
+    };
+
+    (@layout [$($name: ident: $size: literal $(align $align: literal)?),* $(,)?]) => {
+        concat!(
+            ".set ", $crate::frame!(@size), ", 0\n",
+            $($crate::frame!(@slot $name $size [$($align)?]),)*
+            ".set ", $crate::frame!(@size), ", (", $crate::frame!(@size), " + 15) & -16\n"
+        )
+    };
+
+    (red_zone, [$($slot: tt)*], { $($token: tt)* } $(,)?) => {
+        $crate::__asm_red_zone!([$($slot)*], { $($token)* })
+    };
+    ([$($slot: tt)*], { $($token: tt)* } $(,)?) => {
+        concat!(
+            $crate::frame!(@layout [$($slot)*]),
+            $crate::__asm_frame!(sub, $crate::frame!(@size)),
+            $crate::asm_block!($($token)*),
+            $crate::__asm_frame!(add, $crate::frame!(@size))
+        )
+    };
+}
+
+/// Refer to a slot of the enclosing [`frame!`].
+///
+/// This evaluates to a `&'static str` with the memory operand of the slot,
+/// which can be spliced into a block with `@inline(...)`.
+///
+/// [`frame!`]: macro.frame.html
+#[macro_export]
+macro_rules! slot {
+    (@symbol $name: ident) => {
+        concat!($crate::__asm_local_prefix!(), "asm_block_slot_", stringify!($name))
+    };
+    ($name: ident) => {
+        $crate::__asm_frame!(slot, $crate::slot!(@symbol $name))
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_frame {
+    (sub, $size: expr) => { concat!("sub rsp, ", $size, "\n") };
+    (add, $size: expr) => { concat!("add rsp, ", $size, "\n") };
+    (slot, $offset: expr) => { concat!("[rsp + ", $offset, "]") };
+}
+
+#[cfg(target_arch = "x86")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_frame {
+    (sub, $size: expr) => { concat!("sub esp, ", $size, "\n") };
+    (add, $size: expr) => { concat!("add esp, ", $size, "\n") };
+    (slot, $offset: expr) => { concat!("[esp + ", $offset, "]") };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_frame {
+    (sub, $size: expr) => { concat!("sub sp, sp, ", $size, "\n") };
+    (add, $size: expr) => { concat!("add sp, sp, ", $size, "\n") };
+    (slot, $offset: expr) => { concat!("[sp, #", $offset, "]") };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_frame {
+    ($($token: tt)*) => {
+        compile_error!("stack frames are only supported on x86, x86_64 and AArch64")
+    };
+}
+
+#[cfg(all(
+    target_arch = "x86_64",
+    not(any(windows, target_os = "uefi", target_os = "none"))
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_red_zone {
+    ([$($name: ident: $size: literal $(align $align: literal)?),* $(,)?], { $($token: tt)* }) => {
+        concat!(
+            $crate::frame!(@layout [$($name: $size $(align $align)?),*]),
+            ".if ", $crate::frame!(@size), " > 128\n",
+            ".error \"the slots do not fit in the red zone\"\n",
+            ".endif\n",
+            $(
+                ".set ", $crate::slot!(@symbol $name), ", ", $crate::slot!(@symbol $name), " - ",
+                $crate::frame!(@size), "\n",
+            )*
+            $crate::asm_block!($($token)*)
+        )
+    };
+}
+
+#[cfg(not(all(
+    target_arch = "x86_64",
+    not(any(windows, target_os = "uefi", target_os = "none"))
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_red_zone {
+    ($($token: tt)*) => {
+        $crate::frame!($($token)*)
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(frame)]
+mod tests {
+    #[cfg(all(target_arch = "x86_64", not(target_vendor = "apple")))]
+    #[test]
+    fn test_frame() {
+        let code = frame!([a: 8, v: 16 align 16,], { mov @inline(slot!(a)), rax; });
+        let expected = concat!(
+            ".set .Lasm_block_frame_size, 0\n",
+            ".set .Lasm_block_slot_a, (.Lasm_block_frame_size + 8 - 1) & -8\n",
+            ".set .Lasm_block_frame_size, .Lasm_block_slot_a + 8\n",
+            ".set .Lasm_block_slot_v, (.Lasm_block_frame_size + 16 - 1) & -16\n",
+            ".set .Lasm_block_frame_size, .Lasm_block_slot_v + 16\n",
+            ".set .Lasm_block_frame_size, (.Lasm_block_frame_size + 15) & -16\n",
+            "sub rsp, .Lasm_block_frame_size\n",
+            "mov [rsp + .Lasm_block_slot_a], rax \n",
+            "add rsp, .Lasm_block_frame_size\n",
+        );
+        assert_eq!(code, expected);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_red_zone() {
+        let code = frame!(red_zone, [a: 8], { mov rax, @inline(slot!(a)); });
+        let expected = concat!(
+            ".set .Lasm_block_frame_size, 0\n",
+            ".set .Lasm_block_slot_a, (.Lasm_block_frame_size + 8 - 1) & -8\n",
+            ".set .Lasm_block_frame_size, .Lasm_block_slot_a + 8\n",
+            ".set .Lasm_block_frame_size, (.Lasm_block_frame_size + 15) & -16\n",
+            ".if .Lasm_block_frame_size > 128\n",
+            ".error \"the slots do not fit in the red zone\"\n",
+            ".endif\n",
+            ".set .Lasm_block_slot_a, .Lasm_block_slot_a - .Lasm_block_frame_size\n",
+            "mov rax , [rsp + .Lasm_block_slot_a]\n",
+        );
+        assert_eq!(code, expected);
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_frame_assemble() {
+        use crate::test::{assert_disassembles, disassemble, Arch, Error};
+        let code = frame!([a: 4, v: 16 align 16, b: 1], {
+            mov @inline(slot!(a)), eax;
+            movups @inline(slot!(v)), xmm0;
+            mov @inline(slot!(b)), al;
+        });
+        if let Some(instructions) = assert_disassembles(Arch::X86_64, code) {
+            let text = instructions.iter().map(|i| i.text.as_str()).collect::<Vec<_>>();
+            assert_eq!(
+                text,
+                [
+                    "sub rsp, 48",
+                    "mov dword ptr [rsp], eax",
+                    "movups xmmword ptr [rsp + 16], xmm0",
+                    "mov byte ptr [rsp + 32], al",
+                    "add rsp, 48",
+                ]
+            );
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(instructions) = assert_disassembles(Arch::X86_64, frame!(red_zone, [a: 8, b: 8], { mov @inline(slot!(b)), rax; })) {
+            assert_eq!(instructions[0].text, "mov qword ptr [rsp - 8], rax");
+        }
+        #[cfg(target_os = "linux")]
+        assert!(matches!(
+            disassemble(Arch::X86_64, frame!(red_zone, [a: 64, b: 72], {})),
+            Err(Error::NotFound) | Err(Error::Rejected(_))
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 //! This crate provides the [`asm_block!`] macro for allowing composition
 //! through Rust macro when writing inline assembly.
@@ -149,6 +149,305 @@
 //! Now we are able to make calls like `mad!({x}, rbx)`, `mad!([rax], rbp)`, and
 //! `mad!({x:e}, [rsp - 4])`.
 //!
+//! # Variadic Fragments
+//! Metavariables are substituted before [`asm_block!`] sees any token, so the
+//! repetitions of `macro_rules!` work inside the block as well. This makes it
+//! possible to write fragments accepting a variable number of operands:
+//! ```
+//! use asm_block::asm_block;
+//! macro_rules! push_all {
+//!     ($($r: tt),+ $(,)?) => {
+//!         asm_block! {
+//!             $(push $r;)+
+//!         }
+//!     };
+//! }
+//! assert_eq!(
+//!     push_all!(rax, {x}, [rsp + 8]),
+//!     "push rax \npush {x}\npush [rsp + 8 ] \n"
+//! );
+//! ```
+//! Several operands can be iterated in lockstep by capturing them in the same
+//! repetition, e.g. a message word together with its round constant:
+//! ```
+//! use asm_block::asm_block;
+//! macro_rules! add_rounds {
+//!     ($acc: tt; $($msg: tt + $k: literal),+) => {
+//!         asm_block! {
+//!             $(
+//!                 add $acc, $msg;
+//!                 add $acc, $k;
+//!             )+
+//!         }
+//!     };
+//! }
+//! assert_eq!(
+//!     add_rounds!({a}; {m0} + 0x428a2f98, {m1} + 0x71374491),
+//!     "add {a}, {m0}\nadd {a}, 0x428a2f98 \nadd {a}, {m1}\nadd {a}, 0x71374491 \n"
+//! );
+//! ```
+//! When the list is written out at the call site instead, [`asm_foreach!`]
+//! transcribes a body, or invokes a fragment, once per element.
+//!
+//! Optional operands, such as a scratch register which callers rarely need
+//! to change, would take one arm per combination of the operands given.
+//! [`define_fragment!`] defines a fragment whose parameters have defaults
+//! instead, which callers override by name, e.g. `mad!({x}, 5, tmp = rbx)`.
+//!
+//! # Nested Fragments
+//! Another fragment can be spliced in the middle of a block with
+//! `@inline(<fragment>!(<args>))`, as long as the fragment evaluates to a
+//! `&'static str`:
+//! ```
+//! use asm_block::asm_block;
+//! macro_rules! mad {
+//!     ($x: tt, $y: tt) => {
+//!         asm_block! {
+//!             imul $x, $y;
+//!             lea $x, [$x + $y];
+//!         }
+//!     };
+//! }
+//! macro_rules! mad_twice {
+//!     ($x: tt, $y: tt, $z: tt) => {
+//!         asm_block! {
+//!             @inline(mad!($x, $y))
+//!             @inline(mad!($x, $z))
+//!             ret;
+//!         }
+//!     };
+//! }
+//! assert_eq!(
+//!     mad_twice!(rax, 3, 5),
+//!     "imul rax , 3 \nlea rax , [rax + 3 ] \nimul rax , 5 \nlea rax , [rax + 5 ] \nret \n"
+//! );
+//! ```
+//!
+//! Composed code can be hard to navigate in `--emit asm`. A fragment can
+//! start with `@origin(<name>)`, which becomes a `/* <name> */` comment when
+//! the `origin-comments` feature is enabled, and nothing otherwise:
+//! ```
+//! use asm_block::asm_block;
+//! macro_rules! zero {
+//!     ($x: tt) => {
+//!         asm_block! {
+//!             @origin(my_crate::zero)
+//!             xor $x, $x;
+//!         }
+//!     };
+//! }
+//! # #[cfg(not(feature = "origin-comments"))]
+//! assert_eq!(zero!(eax), "xor eax , eax \n");
+//! # #[cfg(all(feature = "origin-comments", not(feature = "origin-markers")))]
+//! # assert_eq!(zero!(eax), "/* my_crate::zero */\nxor eax , eax \n");
+//! # #[cfg(feature = "origin-markers")]
+//! # assert_eq!(zero!(eax), "/* my_crate::zero */\nxor eax , eax \n/* end my_crate::zero */\n");
+//! ```
+//!
+//! The `origin-markers` feature also closes the rest of the block with a
+//! `/* end <name> */` comment, so that fragments nested with `@inline(...)`
+//! show up as nested pairs of markers. The lines of a nested fragment cannot
+//! be indented, though, since its output is an opaque string by the time it
+//! is spliced.
+//!
+//! # Conditional Fragments
+//! A part of a block can depend on the target with
+//! `@cfg(<predicate>) { ... } else { ... }`, where the `else` block is
+//! optional. Only the selected block is expanded, so the template carries no
+//! dead code, and the other block may even use fragments which fail to
+//! compile for the target:
+//! ```
+//! use asm_block::asm_block;
+//! macro_rules! andn {
+//!     ($dst: tt, $a: tt, $b: tt) => {
+//!         asm_block! {
+//!             @cfg(target_feature = "bmi1") {
+//!                 andn $dst, $a, $b;
+//!             } else {
+//!                 mov $dst, $a;
+//!                 not $dst;
+//!                 and $dst, $b;
+//!             }
+//!         }
+//!     };
+//! }
+//! # #[cfg(not(target_feature = "bmi1"))]
+//! assert_eq!(andn!(eax, ecx, edx), "mov eax , ecx \nnot eax \nand eax , edx \n");
+//! ```
+//! Predicates are written as for `cfg`, with `not(...)`, `all(...)` and
+//! `any(...)` around `unix`, `windows`, `target_arch = "..."`,
+//! `target_os = "..."` and the other keys of the target, or
+//! `target_feature = "..."` for the features listed in the documentation of
+//! [`asm_cfg!`], e.g. to pick the numbers of system calls or the prefix of
+//! symbols. They are
+//! evaluated when this crate is compiled, which is for the same target as the
+//! crate using it, with the same target features. [`asm_cfg!`] chains several
+//! conditions, as `else if` would, and evaluates to the string of the
+//! selected block, to be spliced into a block with `@inline(...)`.
+//!
+//! # Symbol Arithmetic
+//! Expressions on symbols, such as `end - start` or `{table} + 8`, are
+//! transcribed like any other operand, and so are the relocation specifiers
+//! of each target, e.g. `sym@GOTPCREL` on x86, `:lo12:sym` on AArch64,
+//! `:lower16:sym` on ARM and `%pcrel_lo(1b)` on RISC-V. Symbols defined in
+//! Rust should be `sym` operands, and constants `const` operands:
+//! ```
+//! use asm_block::asm_block;
+//! macro_rules! load_field {
+//!     ($dst: tt, $table: tt, $offset: tt) => {
+//!         asm_block! {
+//!             adrp $dst, $table;
+//!             add $dst, $dst, :lo12:$table;
+//!             ldr $dst, [$dst, $offset];
+//!         }
+//!     };
+//! }
+//! assert_eq!(
+//!     load_field!(x0, {table}, {offset}),
+//!     "adrp x0 , {table}\nadd x0 , x0 , :lo12:{table}\nldr x0 , [x0 , {offset}] \n"
+//! );
+//! assert_eq!(
+//!     asm_block!(.quad end - start, {table} + 8),
+//!     ".quad end - start , {table}+ 8 "
+//! );
+//! ```
+//!
+//! # Aligning Loops
+//! A loop is only a label and a branch, which fragments write with numeric
+//! labels, so the head of a hot loop is aligned by `.p2align` before its label.
+//! The argument is the log2 of the alignment, e.g. `5` for 32 bytes, and the
+//! padding in code sections is made of `nop`s, which run once if the code
+//! before falls through into the loop. A third argument caps the padding, so
+//! that the loop is only aligned if it takes at most that many bytes:
+//! ```
+//! use asm_block::asm_block;
+//! macro_rules! count_down {
+//!     ($n: tt) => {
+//!         asm_block! {
+//!             .p2align 5, , 15;
+//!             2:
+//!             dec $n;
+//!             jnz 2b;
+//!         }
+//!     };
+//! }
+//! assert_eq!(count_down!({n}), ".p2align 5 , , 15 \n2 :dec {n}\njnz 2b \n");
+//! ```
+//! The assembler raises the alignment of the section to match, so the
+//! alignment holds wherever the function is placed.
+//!
+//! # Assembler Macros
+//! Assembler macros can still be written with [`asm_block!`] when they are
+//! preferable, e.g. to keep the emitted code short. Arguments are referenced
+//! with `@arg(<arg>)`, which becomes `\<arg>`, since `\` is not a valid Rust
+//! token. Similarly, `@arg()` becomes the separator `\()` and `@arg(@)`
+//! becomes the counter `\@`. Purging the macro at the end of the block keeps
+//! the block free of name collisions even if it is duplicated by the compiler.
+//! ```no_run
+//! # use std::arch::asm;
+//! use asm_block::asm_block;
+//! # #[cfg(target_arch = "x86_64")]
+//! unsafe fn f() -> u64 {
+//!     let mut x = 20;
+//!     asm!(
+//!         asm_block! {
+//!             .macro mad x, y;
+//!             imul @arg(x), @arg(y);
+//!             lea @arg(x), [@arg(x) + @arg(y)];
+//!             .endm;
+//!             mad {x}, 5;
+//!             mad {x}, 8;
+//!             .purgem mad;
+//!         },
+//!
+//!         x = inout(reg) x
+//!     );
+//!     x
+//! }
+//! # unsafe { println!("{}", f()) };
+//! ```
+//! Libraries relying on `.altmacro` to reference arguments without `\` do not
+//! work with the LLVM assembler used by Rust, and [`asm_block!`] cannot
+//! substitute them either, since `macro_rules!` is unable to compare
+//! identifiers. Such macros are ported by turning every parameter into a
+//! metavariable of a Rust macro, i.e. `.macro mad x, y` becomes
+//! `macro_rules! mad { ($x: tt, $y: tt) => { ... } }` with `x` replaced by `$x`
+//! in the body, after which `%expr` can usually be replaced by the expression
+//! itself and `LOCAL` labels by numeric local labels.
+//!
+//! # Reserved Labels
+//! The macros of this crate which branch or refer to their own data use
+//! numeric labels from `42000` to `42119`, so that their code can be
+//! duplicated by the compiler or appear several times in a block. Blocks must
+//! not define these labels, which may capture a branch of a macro around them:
+//!
+//! | Labels                          | Macros                                |
+//! |---------------------------------|---------------------------------------|
+//! | `42000`                         | [`trap_if!`]                          |
+//! | `42010` to `42013`              | [`memcpy_loop!`], [`memset_loop!`]    |
+//! | `42020` to `42022`, `42024<i>`  | [`asm_switch!`], [`asm_specialize!`]  |
+//! | `42030` to `42032`              | [`byte_find!`]                        |
+//! | `42040` to `42044`              | [`crc32_loop!`]                       |
+//! | `42050`                         | [`swapgs_if_user!`]                   |
+//! | `42060`, `42061`                | [`zero_bss!`], [`copy_data!`]         |
+//! | `42070` to `42073`              | [`multiboot2_header!`]                |
+//! | `42080`, `42082`                | [`pool_const!`], [`load_string!`]     |
+//! | `42090`                         | [`pad_to!`]                           |
+//! | `42100`, `42101`                | [`outline!`]                          |
+//! | `42110`                         | [`asm_veneer!`]                       |
+//!
+//! The case `i` of a switch is labelled `42024` followed by `i`, e.g. `420247`
+//! or `4202412`. [`asm_compose!`] rejects a fragment defining one of these
+//! labels with [`asm_block!`]. The macros themselves define them as `42000:`,
+//! which [`asm_block!`] transcribes as `42000 :`, so that they can be told
+//! apart.
+//!
+//! # Linting
+//! Since every fragment is a plain `&'static str`, mistakes which are valid to
+//! [`asm_block!`] but garbage to the assembler only show up when compiling the
+//! `asm!` call. [`asm_lint!`] opts into checking an expansion at compile time
+//! instead, e.g. for a forgotten `;`:
+//! ```
+//! use asm_block::{asm_block, asm_lint};
+//! macro_rules! mad {
+//!     ($x: tt, $y: tt) => {
+//!         asm_block! {
+//!             imul $x, $y;
+//!             lea $x, [$x + $y];
+//!         }
+//!     };
+//! }
+//! asm_lint!(mad!({x}, 5));
+//! ```
+//! With the `lint-x86_64` or the `lint-aarch64` feature, unknown mnemonics like
+//! `mvo` or `lrd`, as well as mistakes like `mov rax` or `stp x0, [sp]`, are
+//! rejected on the corresponding targets. Template modifiers which do not
+//! exist on the target, like `{x:e}` on AArch64, are rejected on x86,
+//! AArch64, ARM and RISC-V without any feature, instead of failing in the
+//! lowering of `asm!` without naming the fragment. A named label defined
+//! twice is rejected as well, and [`asm_compose!`] always checks the labels
+//! of the fragments it composes, naming the two fragments defining one, or
+//! the fragment referencing a local label like `2f` which is not defined.
+//!
+//! With the same features, [`asm_assert!`] checks properties which fragments
+//! promise, e.g. that constant-time code has no branches:
+//! ```
+//! use asm_block::{asm_assert, asm_block};
+//! asm_assert!(no_branches, asm_block!(cmp {a}, {b}; cmovb {a}, {b};));
+//! ```
+//!
+//! These are special cases of a [`Policy`], which a crate can define
+//! once to deny groups of instructions, like privileged or x87 ones, or
+//! mnemonics of its choice in all its fragments. [`asm_size!`] bounds the
+//! size in bytes of the code, e.g. to assert that a patch sequence fits in 5
+//! bytes, which is exact on fixed-width architectures.
+//!
+//! For fragment libraries, the `std` feature provides [`test::assemble`] to
+//! check in unit tests that an expansion is accepted by the assembler, and
+//! [`transcribe::asm_block`] for build scripts and code generators, which
+//! translates the source of a block at runtime with the rules of
+//! [`asm_block!`], e.g. to write `.s` files spaced like the fragments.
+//!
 //! # Limitations
 //! - Due to the tokenization rule of Rust macro, strings enclosed by `'` are
 //!   not supported.
@@ -162,7 +461,18 @@
 //!   interior of `mad!` calls.
 //! - Some assemblers use `;` as the comment starter, but we are using it as
 //!   instruction delimeter, so assembly comments may not work properly. Users
-//!   are strongly suggested to stick to Rust comments.
+//!   are strongly suggested to stick to Rust comments, other than doc comments.
+//!   Rust comments never reach the template, so there is nothing to strip. On
+//!   the other hand, the output of a macro cannot be post-processed, since
+//!   `asm!` only accepts literals, so comments and blank lines spliced through
+//!   `@raw(...)` or `@inline(...)`, e.g. `@inline(include_str!("kernel.s"))`,
+//!   are kept verbatim.
+//! - Statements must be separated by `;`, even in blocks pasted from an
+//!   assembly file. `macro_rules!` cannot see on which line a token is, and
+//!   the crate has no procedural macro which could read it from the spans, so
+//!   newlines are whitespace like any other. Existing assembly which does not
+//!   need metavariables can be spliced verbatim instead, with
+//!   `@inline(include_str!("kernel.s"))` or `@raw("...")`.
 //! - `tt` cannot capture multiple tokens, so to make `mad!(dword ptr [rax],
 //!   ebp)` possible, calling convention of `mad!` needs to be changed. For
 //!   example
@@ -188,13 +498,96 @@
 //!   # }
 //!   ```
 //!   But `mad!` must be called with `mad!([{ dword ptr [rax] }], ebp)` instead.
-//! - Currently we don't have an escape hatch to manually inject assembly if the
-//!   macro is not able to emit the correct assembly code.
+//! - Characters that never appear in Rust tokens, such as `\`, can only be
+//!   emitted through `@arg(...)` or the `@raw("...")` escape hatch, which is
+//!   also useful when the macro is not able to emit the correct assembly code.
+//! - String literals are transcribed as written, which works for directives like
+//!   `.ascii "\n"` since the escapes of Rust and the assembler mostly agree.
+//!   `\n`, `\t`, `\"`, `\\`, `\0` and `\x41` have the same meaning, and
+//!   non-ASCII characters are emitted as their UTF-8 bytes. However, `\'` and
+//!   `\u{...}` are rejected by the assembler, while `\0` followed by an octal digit and
+//!   `\x` followed by more than two hexadecimal digits are read as longer
+//!   escapes, which [`asm_lint!`] reports. The literal cannot be rewritten into
+//!   escaped bytes, since `macro_rules!` cannot look inside a literal, so such
+//!   strings have to be written with the assembler's escapes in the first place,
+//!   e.g. `"\303\251"` for `é` or `"\x41", "42"` for `A42`.
+//!   `macro_rules!` cannot tell raw string literals apart, so `r"..."` is emitted
+//!   with its prefix, which is invalid assembly. Raw string literals are still
+//!   useful inside `@raw(...)` to splice text full of `\`, e.g. `@raw(r"\x\()")`.
+//! - The value of a `const` item cannot be spliced into a block, since
+//!   `concat!` only accepts literals. Symbol names defined in Rust should be
+//!   passed as `sym` operands of [`asm!`] instead. Other strings, such as
+//!   section names, have to be literals, possibly produced by a macro.
+//! - Likewise, integer constants should be passed as `const` operands, e.g.
+//!   `{n}` with `n = const TABLE_SIZE`. These are always rendered in decimal,
+//!   which makes no difference to the assembler, so there is no radix control.
+//!   Helpers like `hex!(TABLE_SIZE)` cannot exist either, since the value of
+//!   a `const` is only known after `concat!` has run, but integer literals
+//!   are transcribed as written, so masks can be written as `0xff00` or
+//!   `0b1010` directly and read the same in the emitted code.
+//! - Float literals can be written in data directives like `.float -1.5, 2.5e-3`
+//!   or `.double 1E5`, but without a suffix like `f32`, which the assembler
+//!   does not understand. `const` operands only accept integers, so a float
+//!   constant has to be emitted as its bit pattern, e.g. `.long {c}` with
+//!   `c = const COEFFICIENT.to_bits()`, which is exact by construction.
+//! - Fragments should capture operands with `tt`. A metavariable captured as
+//!   `expr` reaches [`asm_block!`] as a single opaque token, which is
+//!   stringified as a whole without applying the rules above, e.g. `[rbx]` stays
+//!   `[rbx]`. `macro_rules!` cannot look into such a token, so there is no way
+//!   to re-tokenize it. Moreover, `{x:e}` is not an expression at all.
+//!   On the other hand, `tt`, `ident`, `literal` and `lifetime` captures are
+//!   transparent, so forwarding them through nested fragments does not change
+//!   the emitted code.
+//! - There is no generator of unique names, e.g. an `asm_gensym!()` giving a
+//!   fresh suffix at every expansion, since `macro_rules!` has no state shared
+//!   between expansions, and `asm!` does not expose the unique identifier of
+//!   LLVM to templates. Names which must be unique at assembly time can end
+//!   with `@arg(@)` inside an assembler macro, which the assembler replaces
+//!   with the number of macros executed so far, and numeric labels can be
+//!   redefined anyway. Otherwise, [`asm_paste!`] builds names from the
+//!   parameters of a fragment.
+//! - Fragments cannot register requirements, such as labels, constants or
+//!   scratch registers, to be resolved by the block in a second pass. The
+//!   crate is made of `macro_rules!` only, without a procedural macro, and a
+//!   wrapper would not be able to rewrite the template anyway, since `asm!`
+//!   only accepts literals. Fragments coordinate through the `@options` query
+//!   of [`asm_compose!`], which also checks the labels of the whole block,
+//!   through numeric labels, through sections merged by the linker, as with
+//!   [`pool_const!`] and [`asm_outline!`], and through operands named by the
+//!   caller, which lets the register allocator pick the scratch registers.
 //!
 //! # License
 //! Dual licensed under the Apache 2.0 license and the MIT license.
 //!
+//! [`asm_assert!`]: macro.asm_assert.html
+//! [`Policy`]: struct.Policy.html
+//! [`asm_size!`]: macro.asm_size.html
 //! [`asm_block!`]: macro.asm_block.html
+//! [`asm_cfg!`]: macro.asm_cfg.html
+//! [`asm_compose!`]: macro.asm_compose.html
+//! [`define_fragment!`]: macro.define_fragment.html
+//! [`asm_outline!`]: macro.asm_outline.html
+//! [`asm_paste!`]: macro.asm_paste.html
+//! [`pool_const!`]: macro.pool_const.html
+//! [`asm_foreach!`]: macro.asm_foreach.html
+//! [`trap_if!`]: macro.trap_if.html
+//! [`memcpy_loop!`]: macro.memcpy_loop.html
+//! [`memset_loop!`]: macro.memset_loop.html
+//! [`asm_switch!`]: macro.asm_switch.html
+//! [`asm_specialize!`]: macro.asm_specialize.html
+//! [`byte_find!`]: macro.byte_find.html
+//! [`crc32_loop!`]: macro.crc32_loop.html
+//! [`swapgs_if_user!`]: macro.swapgs_if_user.html
+//! [`zero_bss!`]: macro.zero_bss.html
+//! [`copy_data!`]: macro.copy_data.html
+//! [`multiboot2_header!`]: macro.multiboot2_header.html
+//! [`load_string!`]: macro.load_string.html
+//! [`pad_to!`]: macro.pad_to.html
+//! [`outline!`]: macro.outline.html
+//! [`asm_veneer!`]: macro.asm_veneer.html
+//! [`asm_lint!`]: macro.asm_lint.html
+//! [`test::assemble`]: test/fn.assemble.html
+//! [`transcribe::asm_block`]: transcribe/fn.asm_block.html
 //! [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
 
 /// Translate tokens to a string containing assembly.
@@ -210,8 +603,23 @@
 /// - Convert `;` to `\n`.
 /// - No space before and after `@`, `:`.
 /// - Must have a space after `.<ident>`.
+/// - Treat `.` followed by `+`, `-`, `=`, `,` or `;` as the location counter.
 /// - Not violating the previous rule, no space before `.`.
 /// - Concatenate everything inside a pair of `{` and `}` without any space.
+/// - Convert `@arg(<arg>)` to `\<arg>`, `@arg()` to `\()` and `@arg(@)` to `\@`
+///   for assembler macros, without any space after. No space before `@arg(@)`.
+/// - Splice the string literals inside `@raw(...)` verbatim, without any space.
+/// - Splice the output of the macro invocation inside `@inline(...)` verbatim.
+/// - Convert `@origin(<name>)` to a `/* <name> */` line if the `origin-comments`
+///   feature is enabled, or nothing otherwise. The `origin-markers` feature
+///   also ends the rest of the block with a `/* end <name> */` line.
+/// - Expand `@cfg(<predicate>) { ... } else { ... }` to the first block if the
+///   predicate holds for the target, or to the block after `else`, if any,
+///   otherwise. The other block is not expanded at all. The predicate is made
+///   of `not(...)`, `all(...)`, `any(...)`, `unix`, `windows`, and
+///   `target_arch`, `target_endian`, `target_pointer_width`, `target_os`,
+///   `target_env`, `target_family` and `target_feature` with a value, where
+///   the features are those listed for [`asm_cfg!`].
 /// - Transcribe all the other tokens as-is (by `stringify!`), and add a space afterwards.
 /// 
 /// This should work for most assembly code.
@@ -252,6 +660,8 @@
 /// );
 /// # }
 /// ```
+///
+/// [`asm_cfg!`]: macro.asm_cfg.html
 #[allow(clippy::deprecated_cfg_attr)]
 #[cfg_attr(rustfmt, rustfmt::skip)]
 #[macro_export]
@@ -259,6 +669,11 @@ macro_rules! asm_block {
     // base case
     () => { "" };
 
+    // doc comments would be emitted as attributes
+    (# [doc = $doc: literal] $($token: tt)*) => {
+        compile_error!("doc comments are not supported inside `asm_block!`, use `//` instead")
+    };
+
     // convert `;` to newline
     (; $($token: tt)*) => {
         concat!("\n", $crate::asm_block!($($token)*))
@@ -269,6 +684,24 @@ macro_rules! asm_block {
         concat!(stringify!($first), $crate::asm_block!(: $($token)*))
     };
 
+    // `\@` is the suffix of a symbol, but `@raw` and other `@arg`s are not, so
+    // keep the space before them
+    ($first: ident @ arg (@) $($token: tt)*) => {
+        concat!(stringify!($first), "\\@", $crate::asm_block!($($token)*))
+    };
+    ($first: ident @ arg ($($arg: tt)*) $($token: tt)*) => {
+        concat!(stringify!($first), " ", $crate::asm_block!(@ arg ($($arg)*) $($token)*))
+    };
+    ($first: ident @ raw ($($raw: tt)*) $($token: tt)*) => {
+        concat!(stringify!($first), " ", $crate::asm_block!(@ raw ($($raw)*) $($token)*))
+    };
+    ($first: ident @ inline ($($inline: tt)*) $($token: tt)*) => {
+        concat!(stringify!($first), " ", $crate::asm_block!(@ inline ($($inline)*) $($token)*))
+    };
+    ($first: ident @ cfg ($($predicate: tt)*) $($token: tt)*) => {
+        concat!(stringify!($first), " ", $crate::asm_block!(@ cfg ($($predicate)*) $($token)*))
+    };
+
     // no space between an `ident` and a `@`
     ($first: ident @ $($token: tt)*) => {
         concat!(stringify!($first), $crate::asm_block!(@ $($token)*))
@@ -279,6 +712,59 @@ macro_rules! asm_block {
         concat!(stringify!($first), $crate::asm_block!(. $($token)*))
     };
 
+    // reference an assembler macro argument `\<arg>`, `\()` or `\@`
+    (@ arg () $($token: tt)*) => {
+        concat!("\\()", $crate::asm_block!($($token)*))
+    };
+    (@ arg ($arg: tt) $($token: tt)*) => {
+        concat!("\\", stringify!($arg), $crate::asm_block!($($token)*))
+    };
+    (@ arg ($($arg: tt)*) $($token: tt)*) => {
+        compile_error!("`@arg(...)` accepts a single argument name, or `@` for `\\@`")
+    };
+
+    // splice string literals verbatim, e.g. for `\` which Rust cannot lex
+    (@ raw ($($raw: literal),* $(,)?) $($token: tt)*) => {
+        concat!($($raw,)* $crate::asm_block!($($token)*))
+    };
+    (@ raw ($($raw: tt)*) $($token: tt)*) => {
+        compile_error!("`@raw(...)` only accepts string literals, e.g. `@raw(\"\\\\reg\")`")
+    };
+
+    // splice the output of another fragment
+    (@ inline ($($fragment: ident)::+ ! $args: tt) $($token: tt)*) => {
+        concat!($($fragment)::+ ! $args, $crate::asm_block!($($token)*))
+    };
+    (@ inline ($($inline: tt)*) $($token: tt)*) => {
+        compile_error!("`@inline(...)` only accepts a macro invocation, e.g. `@inline(mad!(rax, 5))`")
+    };
+
+    // record the fragment in a comment if enabled, and its end if marked
+    (@ origin ($($origin: tt)*) $($token: tt)*) => {
+        concat!(
+            $crate::__asm_block_origin!($($origin)*),
+            $crate::asm_block!($($token)*),
+            $crate::__asm_block_origin_end!($($origin)*)
+        )
+    };
+
+    // expand only the branch selected by the predicate
+    (@ cfg ($($predicate: tt)*) {$($then: tt)*} else {$($else: tt)*} $($token: tt)*) => {
+        concat!(
+            $crate::__asm_cfg!(@eval [$($predicate)*] [$crate::asm_block!($($then)*)] [$crate::asm_block!($($else)*)]),
+            $crate::asm_block!($($token)*)
+        )
+    };
+    (@ cfg ($($predicate: tt)*) {$($then: tt)*} $($token: tt)*) => {
+        concat!(
+            $crate::__asm_cfg!(@eval [$($predicate)*] [$crate::asm_block!($($then)*)] [""]),
+            $crate::asm_block!($($token)*)
+        )
+    };
+    (@ cfg $($token: tt)*) => {
+        compile_error!("`@cfg(...)` must be followed by a block, e.g. `@cfg(target_arch = \"x86_64\") { ... } else { ... }`")
+    };
+
     // no space after `:`, `@`
     (: $($token: tt)*) => {
         concat!(":", $crate::asm_block!($($token)*))
@@ -287,6 +773,23 @@ macro_rules! asm_block {
         concat!("@", $crate::asm_block!($($token)*))
     };
 
+    // `.` followed by an operator or a separator is the location counter
+    (. + $($token: tt)*) => {
+        concat!(". ", $crate::asm_block!(+ $($token)*))
+    };
+    (. - $($token: tt)*) => {
+        concat!(". ", $crate::asm_block!(- $($token)*))
+    };
+    (. ; $($token: tt)*) => {
+        concat!(". ", $crate::asm_block!(; $($token)*))
+    };
+    (. , $($token: tt)*) => {
+        concat!(". ", $crate::asm_block!(, $($token)*))
+    };
+    (. = $($token: tt)*) => {
+        concat!(". ", $crate::asm_block!(= $($token)*))
+    };
+
     // must have a space after `.<tt>`
     (. $first: tt $($token: tt)*) => {
         concat!(".", stringify!($first), " ", $crate::asm_block!($($token)*))
@@ -311,6 +814,82 @@ macro_rules! asm_block {
     };
 }
 
+#[cfg(feature = "origin-comments")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_block_origin {
+    ($first: ident $(:: $rest: ident)*) => {
+        concat!("/* ", stringify!($first), $("::", stringify!($rest),)* " */\n")
+    };
+}
+
+#[cfg(not(feature = "origin-comments"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_block_origin {
+    ($first: ident $(:: $rest: ident)*) => { "" };
+}
+
+#[cfg(feature = "origin-markers")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_block_origin_end {
+    ($first: ident $(:: $rest: ident)*) => {
+        concat!("/* end ", stringify!($first), $("::", stringify!($rest),)* " */\n")
+    };
+}
+
+#[cfg(not(feature = "origin-markers"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_block_origin_end {
+    ($first: ident $(:: $rest: ident)*) => { "" };
+}
+
+mod bitfield;
+mod boot;
+mod branch;
+mod call;
+mod cfg;
+mod compose;
+#[cfg(feature = "crypto")]
+mod crypto;
+mod cycles;
+mod data;
+mod debug;
+mod define;
+mod descriptor;
+mod frame;
+mod load;
+mod mmio;
+mod outline;
+mod pad;
+mod pattern;
+mod perf;
+mod pool;
+mod privilege;
+mod register;
+mod repeat;
+mod rotate;
+mod section;
+mod startup;
+mod string;
+mod switch;
+mod symbol;
+mod sysreg;
+mod vector;
+pub mod fragment;
+pub mod operand;
+pub mod registry;
+#[doc(hidden)]
+pub mod lint;
+#[cfg(feature = "std")]
+pub mod test;
+#[cfg(feature = "std")]
+pub mod transcribe;
+
+pub use lint::{Group, Policy, Size};
+
 #[cfg(test)]
 #[rustfmt::skip::macros(asm_block)]
 mod tests {
@@ -348,6 +927,16 @@ mod tests {
             "gs:[eax + 4 * {b:e}- 0x30 ] "
         );
         assert_eq!(asm_block!(%gs:4(,%eax,8)), "% gs:4 (, % eax , 8 ) ");
+        assert_eq!(asm_block!(@raw()), "");
+        assert_eq!(asm_block!(@raw("\\x")), "\\x");
+        assert_eq!(asm_block!(@raw("\\", "()",)), "\\()");
+        assert_eq!(asm_block!(@raw(r"\@")), "\\@");
+        assert_eq!(asm_block!(@raw(r"\x\()\y")), "\\x\\()\\y");
+        assert_eq!(asm_block!(@arg(x)), "\\x");
+        assert_eq!(asm_block!(@arg(@)), "\\@");
+        assert_eq!(asm_block!(@arg()), "\\()");
+        assert_eq!(asm_block!(loop@arg(@)), "loop\\@");
+        assert_eq!(asm_block!(@arg(name)@arg()_done), "\\name\\()_done ");
     }
 
     #[test]
@@ -370,12 +959,36 @@ mod tests {
             asm_block!(.ascii  "Hello, world\n"),
             r#".ascii "Hello, world\n" "#
         );
+        assert_eq!(
+            asm_block!(.ascii "\t\"\\\0\x41", "héllo"),
+            r#".ascii "\t\"\\\0\x41" , "héllo" "#
+        );
+        assert_eq!(asm_block!(db "é", 0), "db \"é\" , 0 ");
+        assert_eq!(
+            asm_block!(.float -1.5, 2.5e-3, 1.0e+10),
+            ".float - 1.5 , 2.5e-3 , 1.0e+10 "
+        );
+        assert_eq!(asm_block!(.double 0.1, 1E5), ".double 0.1 , 1E5 ");
+        assert_eq!(asm_block!(.long 1f - .), ".long 1f - . ");
+        assert_eq!(asm_block!(.quad ., . + 8), ".quad . , . + 8 ");
+        assert_eq!(asm_block!(.skip 64 - (. - start)), ".skip 64 - (. - start ) ");
+        assert_eq!(asm_block!(. = . + 4), ". = . + 4 ");
+        assert_eq!(asm_block!(.long 1f - .; nop), ".long 1f - . \nnop ");
         assert_eq!(
             asm_block!(call    _WriteConsoleA@20),
             "call _WriteConsoleA@20 "
         );
         assert_eq!(asm_block!(str  fp, [sp, -4]!), "str fp , [sp , - 4 ] ! ");
         assert_eq!(asm_block!(ldr fp, [{x}], 4), "ldr fp , [{x}] , 4 ");
+        assert_eq!(asm_block!(mul @raw("\\x"), @raw("\\y")), "mul \\x, \\y");
+        assert_eq!(asm_block!(lea rax, [rax + @raw("\\y")]), "lea rax , [rax + \\y] ");
+        assert_eq!(asm_block!(mul @arg(x), @arg(y)), "mul \\x, \\y");
+        assert_eq!(
+            asm_block!(lea @arg(x), [@arg(x) + @arg(y)*4]),
+            "lea \\x, [\\x+ \\y* 4 ] "
+        );
+        assert_eq!(asm_block!(loop@arg(@): dec rcx), "loop\\@:dec rcx ");
+        assert_eq!(asm_block!(.macro mad x:req, y=4), ".macro mad x:req , y = 4 ");
         assert_eq!(
             asm_block!(add   v19.4s, v2.4s, v4.4s),
             "add v19.4s , v2.4s , v4.4s "
@@ -486,4 +1099,152 @@ add eax , ebx
 "
         );
     }
+
+    #[test]
+    #[rustfmt::skip::macros(push_all, store_limbs)]
+    fn test_variadic() {
+        macro_rules! push_all {
+            ($($r: tt),*) => {
+                asm_block! {
+                    $(push $r;)*
+                }
+            };
+        }
+        macro_rules! store_limbs {
+            ($base: tt; $($limb: tt => $offset: literal),+) => {
+                asm_block! {
+                    $(mov [$base + $offset], $limb;)+
+                }
+            };
+        }
+
+        assert_eq!(push_all!(), "");
+        assert_eq!(push_all!(rax), "push rax \n");
+        assert_eq!(
+            push_all!(rax, {x}, [rsp + 8]),
+            "\
+push rax 
+push {x}
+push [rsp + 8 ] 
+"
+        );
+        assert_eq!(
+            store_limbs!({p}; {l0} => 0, {l1} => 8, {l2} => 16),
+            "\
+mov [{p}+ 0 ] , {l0}
+mov [{p}+ 8 ] , {l1}
+mov [{p}+ 16 ] , {l2}
+"
+        );
+    }
+
+    #[test]
+    fn test_relocation() {
+        assert_eq!(
+            asm_block!(mov rax, [rip + sym@GOTPCREL]; call {f}@PLT),
+            "mov rax , [rip + sym@GOTPCREL ] \ncall {f}@PLT "
+        );
+        assert_eq!(
+            asm_block!(adrp x0, :got:sym; ldr x0, [x0, :got_lo12:sym]),
+            "adrp x0 , :got:sym \nldr x0 , [x0 , :got_lo12:sym ] "
+        );
+        assert_eq!(
+            asm_block!(movw r0, :lower16:{s}; movt r0, :upper16:{s}),
+            "movw r0 , :lower16:{s}\nmovt r0 , :upper16:{s}"
+        );
+        assert_eq!(
+            asm_block!(1: auipc a0, %pcrel_hi(sym); addi a0, a0, %pcrel_lo(1b)),
+            "1 :auipc a0 , % pcrel_hi (sym ) \naddi a0 , a0 , % pcrel_lo (1b ) "
+        );
+        assert_eq!(
+            asm_block!(.quad (end - start) / 8, {s} + {off}),
+            ".quad (end - start ) / 8 , {s}+ {off}"
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip::macros(load, load_pair)]
+    fn test_inline() {
+        macro_rules! load {
+            ($dst: tt, $src: tt) => {
+                asm_block! { mov $dst, $src; }
+            };
+        }
+        macro_rules! load_pair {
+            ($a: tt, $b: tt, $base: tt) => {
+                asm_block! {
+                    @inline(load!($a, [$base]))
+                    @inline(load! { $b, [$base + 8] });
+                    ret
+                }
+            };
+        }
+
+        assert_eq!(asm_block!(@inline(load!(rax, rbx))), "mov rax , rbx \n");
+        assert_eq!(asm_block!(@inline(crate::asm_block!(nop))), "nop ");
+        assert_eq!(asm_block!(nop @inline(load!({x}, 1))), "nop mov {x}, 1 \n");
+        assert_eq!(
+            load_pair!({a}, {b}, {p}),
+            "mov {a}, [{p}] \nmov {b}, [{p}+ 8 ] \n\nret "
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip::macros(inner, middle, outer)]
+    fn test_forwarded() {
+        macro_rules! inner {
+            ($l: ident, $d: ident, $v: literal, $raw: literal, $x: tt, $y: tt) => {
+                asm_block! {
+                    $l: .$d $v;
+                    @raw($raw) $x, $y;
+                }
+            };
+        }
+        macro_rules! middle {
+            ($l: ident, $d: ident, $v: literal, $raw: literal, $x: tt, $y: tt) => {
+                inner!($l, $d, $v, $raw, $x, $y)
+            };
+        }
+        macro_rules! outer {
+            ($l: ident, $d: ident, $v: literal, $raw: literal, $x: tt, $y: tt) => {
+                middle!($l, $d, $v, $raw, $x, $y)
+            };
+        }
+
+        let expected = "table:.byte 5 \n\\op{x}, [rsp + 8 ] \n";
+        assert_eq!(inner!(table, byte, 5, "\\op", {x}, [rsp + 8]), expected);
+        assert_eq!(outer!(table, byte, 5, "\\op", {x}, [rsp + 8]), expected);
+    }
+
+    #[test]
+    #[rustfmt::skip::macros(zero)]
+    fn test_origin() {
+        macro_rules! zero {
+            ($x: tt) => {
+                asm_block! {
+                    @origin(crate::zero)
+                    xor $x, $x;
+                }
+            };
+        }
+
+        #[cfg(not(feature = "origin-comments"))]
+        {
+            assert_eq!(asm_block!(@origin(zero)), "");
+            assert_eq!(zero!({x}), "xor {x}, {x}\n");
+        }
+        #[cfg(all(feature = "origin-comments", not(feature = "origin-markers")))]
+        {
+            assert_eq!(asm_block!(@origin(zero)), "/* zero */\n");
+            assert_eq!(zero!({x}), "/* crate::zero */\nxor {x}, {x}\n");
+        }
+        #[cfg(feature = "origin-markers")]
+        {
+            assert_eq!(asm_block!(@origin(zero)), "/* zero */\n/* end zero */\n");
+            assert_eq!(
+                asm_block!(@origin(outer) @inline(zero!({x})) ret;),
+                "/* outer */\n/* crate::zero */\nxor {x}, {x}\n/* end crate::zero */\nret \n/* end outer */\n"
+            );
+        }
+    }
 }
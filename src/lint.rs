@@ -0,0 +1,3088 @@
+//! Compile-time checks on translated assembly.
+//!
+//! Everything here is a `const fn`, so that `asm_lint!` can run the checks
+//! while evaluating a `const` item. The scanner works on the output of
+//! `asm_block!` rather than on tokens, which also makes it applicable to
+//! handwritten templates.
+
+use core::cmp::Ordering;
+
+/// Check assembly produced by fragments at compile time.
+///
+/// This expands to a `const` item, so it can be placed wherever an item or a
+/// statement is allowed. The template is scanned line by line, and compilation
+/// fails with a message pointing at the offending line if
+/// - an operand is directly followed by another operand or a mnemonic, which
+///   is most likely a forgotten `;`.
+/// - a string literal contains an escape which is valid in Rust but read
+///   differently by the assembler, i.e. `\'`, `\u{...}`, `\0` followed by an
+///   octal digit, or `\x` followed by more than two hexadecimal digits.
+/// - the mnemonic is unknown to the target architecture. This requires the
+///   `lint-x86_64` feature on x86 targets, or the `lint-aarch64` feature on
+///   AArch64 targets. Assembler macros defined in the same template and the
+///   mnemonics listed in `allow(...)` are accepted.
+/// - a common instruction like `mov` or `stp` has a wrong number of operands,
+///   with the same features.
+/// - a named label is defined twice. Numeric labels can be redefined, and so
+///   they are not checked.
+/// - a placeholder has a template modifier which no register class of the
+///   target architecture accepts, e.g. `{x:e}` on AArch64 or `{x:w}` on x86.
+///   Only x86, AArch64, ARM and RISC-V are known.
+///
+/// The checks are heuristics, and apart from string literals only look at
+/// instructions, so directives and labels are ignored. Operand keywords like `dword ptr` or `lsl` are
+/// recognized.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_lint};
+/// macro_rules! mad {
+///     ($x: tt, $y: tt) => {
+///         asm_block! {
+///             imul $x, $y;
+///             lea $x, [$x + $y];
+///         }
+///     };
+/// }
+/// asm_lint!(mad!({x}, 5));
+/// asm_lint!(mad!([rax], ebx));
+/// asm_lint!(asm_block!(mad {x}, 5), allow(mad));
+/// ```
+/// A Rust escape the assembler reads differently is rejected:
+/// ```compile_fail
+/// use asm_block::{asm_block, asm_lint};
+/// asm_lint!(asm_block!(.ascii "caf\u{e9}"));
+/// ```
+/// A missing `;` is rejected:
+/// ```compile_fail
+/// use asm_block::{asm_block, asm_lint};
+/// asm_lint!(asm_block! {
+///     mov rax, 1
+///     add rax, 2;
+/// });
+/// ```
+#[macro_export]
+macro_rules! asm_lint {
+    ($template: expr $(,)?) => {
+        const _: () = $crate::lint::check($template, &[]);
+    };
+    ($template: expr, allow($($allow: ident),* $(,)?) $(,)?) => {
+        const _: () = $crate::lint::check($template, &[$(stringify!($allow)),*]);
+    };
+}
+
+/// Assert a property of assembly produced by fragments at compile time.
+///
+/// This expands to a `const` item, as [`asm_lint!`] does, which fails to
+/// compile with a message pointing at the offending line if the template
+/// breaks the property:
+/// - `no_branches`: no instruction transfers control, i.e. there is no jump,
+///   conditional branch, call, return, loop instruction, system call or trap.
+///   Fragments of constant-time code can thus be checked not to branch, e.g.
+///   on secret data, when only `cmov` or `csel` are intended.
+/// - `no_memory_ops`: no instruction accesses memory, i.e. there is no memory
+///   operand, in brackets or in parentheses in AT&T syntax, apart from the
+///   address computed by `lea`, and no instruction accessing memory
+///   implicitly, like `movsb`, `push` or `ldr` from a label. Such fragments
+///   can be used with `options(nomem)`.
+/// - `no_stack_ops`: no instruction uses the stack, i.e. there is no `push`,
+///   `pop`, call or return, and the stack pointer is not an operand. Such
+///   fragments can be used with `options(nostack)`.
+/// - `policy(expr)`: no instruction is denied by the constant [`Policy`],
+///   which can deny groups of instructions, like privileged or x87 ones, or
+///   restrict the mnemonics to a list. A crate can thus define its policy once
+///   and apply it to all its fragments.
+/// - `max_size(expr)`: the code takes at most the given number of bytes, as
+///   bounded by [`asm_size!`], e.g. for a patch sequence replacing a 5-byte
+///   `call`, or the entry of a vector table.
+///
+/// The instructions are recognized with the tables of the `lint-x86_64`
+/// feature on x86 targets, or the `lint-aarch64` feature on AArch64 targets.
+/// Without the tables of the target architecture, nothing is checked, so a
+/// crate relying on an assertion should enable the feature of its targets.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_assert, asm_block};
+/// macro_rules! select {
+///     ($dst: tt, $src: tt, $mask: tt) => {
+///         asm_block! {
+///             test $mask, $mask;
+///             cmovnz $dst, $src;
+///         }
+///     };
+/// }
+/// asm_assert!(no_branches, select!({x}, {y}, {m}));
+/// asm_assert!(no_memory_ops, select!({x}, {y}, {m}));
+/// asm_assert!(no_stack_ops, select!({x}, {y}, {m}));
+///
+/// use asm_block::{Group, Policy};
+/// const SANDBOX: Policy = Policy::new()
+///     .deny(Group::Privileged)
+///     .deny(Group::X87)
+///     .deny_mnemonics(&["rdtsc", "rdtscp"]);
+/// asm_assert!(policy(SANDBOX), concat!(select!({x}, {y}, {m}), select!({y}, {x}, {m})));
+/// ```
+///
+/// ```compile_fail
+/// use asm_block::{asm_assert, asm_block, Policy};
+/// const POLICY: Policy = Policy::new().allow_only(&["mov", "add"]);
+/// asm_assert!(policy(POLICY), asm_block!(mov {x}, 1; shl {x}, 2;));
+/// ```
+///
+/// [`asm_lint!`]: macro.asm_lint.html
+/// [`Policy`]: struct.Policy.html
+/// [`asm_size!`]: macro.asm_size.html
+#[macro_export]
+macro_rules! asm_assert {
+    (no_branches, $template: expr $(,)?) => {
+        $crate::asm_assert!(
+            policy($crate::lint::Policy::new().deny($crate::lint::Group::Branches)),
+            $template
+        );
+    };
+    (no_memory_ops, $template: expr $(,)?) => {
+        $crate::asm_assert!(
+            policy($crate::lint::Policy::new().deny($crate::lint::Group::MemoryOps)),
+            $template
+        );
+    };
+    (no_stack_ops, $template: expr $(,)?) => {
+        $crate::asm_assert!(
+            policy($crate::lint::Policy::new().deny($crate::lint::Group::StackOps)),
+            $template
+        );
+    };
+    (policy($policy: expr), $template: expr $(,)?) => {
+        const _: () = $crate::lint::check_policy(&$policy, $template);
+    };
+    (max_size($bytes: expr), $template: expr $(,)?) => {
+        const _: () = $crate::lint::check_size($bytes, $template);
+    };
+    ($property: ident, $template: expr $(,)?) => {
+        compile_error!(concat!(
+            "unknown property `",
+            stringify!($property),
+            "`, expected `no_branches`, `no_memory_ops`, `no_stack_ops`, `policy(...)` or `max_size(...)`"
+        ));
+    };
+}
+
+/// Compute bounds of the size in bytes of the code assembled from fragments.
+///
+/// This evaluates to a constant [`Size`], with the fewest and the most bytes
+/// which the template can assemble to in the current section. The size is
+/// exact on AArch64, on ARM outside of Thumb and on RISC-V without compressed
+/// instructions, apart from pseudo-instructions like `li` on RISC-V, whose
+/// expansion depends on their operands. On x86, it is exact for instructions
+/// without operands and bounded for branches to a local label or to a
+/// placeholder with the `lint-x86_64` feature, and other instructions take
+/// from 1 to 15 bytes.
+///
+/// Data directives like `.byte`, `.ascii` or `.skip` with a number count their
+/// bytes, alignment directives from none to all of their padding, and a block
+/// padded by [`pad_to!`] counts as its size. The bytes of sections entered
+/// with `.pushsection` are not counted. Any other directive, macro or
+/// repetition leaves the size unbounded, as does any instruction on other
+/// architectures. [`asm_assert!`] with `max_size` asserts a bound.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_assert, asm_block, asm_size};
+/// macro_rules! patch {
+///     ($target: tt) => {
+///         asm_block! {
+///             jmp $target;
+///         }
+///     };
+/// }
+/// assert_eq!(asm_size!(asm_block!(.byte 0x0f, 0x0b; .ascii "ok";)).exact(), Some(4));
+/// # #[cfg(all(feature = "lint-x86_64", target_arch = "x86_64"))]
+/// asm_assert!(max_size(5), patch!({target}));
+/// ```
+///
+/// [`Size`]: struct.Size.html
+/// [`asm_assert!`]: macro.asm_assert.html
+/// [`pad_to!`]: macro.pad_to.html
+#[macro_export]
+macro_rules! asm_size {
+    ($template: expr $(,)?) => {
+        $crate::lint::code_size($template)
+    };
+}
+
+#[cfg(all(feature = "lint-aarch64", any(target_arch = "aarch64", test)))]
+mod aarch64;
+#[cfg(all(
+    feature = "lint-x86_64",
+    any(target_arch = "x86", target_arch = "x86_64", test)
+))]
+mod x86_64;
+
+/// Tables describing the instructions of an architecture.
+#[derive(Clone, Copy)]
+pub struct Arch {
+    /// All mnemonics, sorted.
+    pub mnemonics: &'static [&'static str],
+    /// Minimum and maximum operand counts of common mnemonics, sorted.
+    pub operands: &'static [(&'static str, usize, usize)],
+    /// Mnemonics modifying the flags, sorted.
+    pub flags: &'static [&'static str],
+    /// Mnemonics transferring control, sorted.
+    pub branches: &'static [&'static str],
+    /// Mnemonics accessing memory without a memory operand, sorted.
+    pub memory: &'static [&'static str],
+    /// Mnemonics with a memory operand which is not accessed, sorted.
+    pub addresses: &'static [&'static str],
+    /// Mnemonics using the stack implicitly, sorted.
+    pub stack: &'static [&'static str],
+    /// Names of the stack pointer.
+    pub stack_pointers: &'static [&'static str],
+    /// Mnemonics faulting outside of the kernel, sorted.
+    pub privileged: &'static [&'static str],
+    /// Mnemonics of the x87 floating point unit, sorted.
+    pub x87: &'static [&'static str],
+    /// Sizes in bytes of instructions without operands, sorted.
+    pub sizes: &'static [(&'static str, usize, usize)],
+    /// Bounds of the sizes of branches to a local label or a placeholder,
+    /// sorted.
+    pub relative: &'static [(&'static str, usize, usize)],
+}
+
+/// Tables of the target architecture, if enabled.
+#[cfg(all(
+    feature = "lint-x86_64",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+const ARCH: Option<Arch> = Some(x86_64::ARCH);
+#[cfg(all(feature = "lint-aarch64", target_arch = "aarch64"))]
+const ARCH: Option<Arch> = Some(aarch64::ARCH);
+#[cfg(not(any(
+    all(
+        feature = "lint-x86_64",
+        any(target_arch = "x86", target_arch = "x86_64")
+    ),
+    all(feature = "lint-aarch64", target_arch = "aarch64")
+)))]
+const ARCH: Option<Arch> = None;
+
+/// Template modifiers accepted by some register class of the target
+/// architecture, or `None` if the architecture is not known.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const MODIFIERS: Option<&[u8]> = Some(b"ehlrxyz");
+#[cfg(target_arch = "aarch64")]
+const MODIFIERS: Option<&[u8]> = Some(b"bdhqsvwx");
+#[cfg(target_arch = "arm")]
+const MODIFIERS: Option<&[u8]> = Some(b"ef");
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+const MODIFIERS: Option<&[u8]> = Some(b"");
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "arm",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+const MODIFIERS: Option<&[u8]> = None;
+
+/// Fail the evaluation of the enclosing constant if any check fails.
+pub const fn check(template: &str, allow: &[&str]) {
+    let bytes = template.as_bytes();
+    if let Some((line, first, second)) = missing_separator(bytes) {
+        panic!(
+            "{}",
+            Message::new()
+                .push(b"missing `;` or `,` between `")
+                .push(slice(bytes, first))
+                .push(b"` and `")
+                .push(slice(bytes, second))
+                .push(b"` in `")
+                .push(slice(bytes, line))
+                .push(b"`")
+                .as_str()
+        );
+    }
+    if let Some((line, escape)) = misread_escape(bytes) {
+        panic!(
+            "{}",
+            Message::new()
+                .push(b"escape `")
+                .push(slice(bytes, escape))
+                .push(b"` is read differently by the assembler in `")
+                .push(slice(bytes, line))
+                .push(b"`")
+                .as_str()
+        );
+    }
+    if let Some((_, label, _, _)) = duplicate_label(&[template]) {
+        panic!(
+            "{}",
+            Message::new()
+                .push(b"label `")
+                .push(slice(bytes, label))
+                .push(b"` is defined twice")
+                .as_str()
+        );
+    }
+    if let Some(modifiers) = MODIFIERS {
+        if let Some((line, placeholder)) = unknown_modifier(bytes, modifiers) {
+            panic!(
+                "{}",
+                Message::new()
+                    .push(b"unknown modifier in `")
+                    .push(slice(bytes, placeholder))
+                    .push(b"` for the target architecture in `")
+                    .push(slice(bytes, line))
+                    .push(b"`")
+                    .as_str()
+            );
+        }
+    }
+    if let Some(arch) = ARCH {
+        if let Some((line, mnemonic)) = unknown_mnemonic(bytes, arch.mnemonics, allow) {
+            panic!(
+                "{}",
+                Message::new()
+                    .push(b"unknown mnemonic `")
+                    .push(slice(bytes, mnemonic))
+                    .push(b"` in `")
+                    .push(slice(bytes, line))
+                    .push(b"`")
+                    .as_str()
+            );
+        }
+        if let Some((line, mnemonic, count)) = wrong_operand_count(bytes, arch.operands) {
+            panic!(
+                "{}",
+                Message::new()
+                    .push(b"wrong number of operands for `")
+                    .push(slice(bytes, mnemonic))
+                    .push(b"`, found ")
+                    .push_number(count)
+                    .push(b" in `")
+                    .push(slice(bytes, line))
+                    .push(b"`")
+                    .as_str()
+            );
+        }
+    }
+}
+
+/// Fail the evaluation of the enclosing constant if a named label is defined
+/// twice in the fragments, naming the fragments defining it, if a local label
+/// is referenced but not defined, naming the fragment referencing it, or if a
+/// fragment defines one of the [reserved labels](../index.html#reserved-labels).
+pub const fn check_labels(fragments: &[&str], names: &[&str], allow: &[&str]) {
+    if let Some((fragment, label)) = reserved_label(fragments) {
+        panic!(
+            "{}",
+            Message::new()
+                .push(b"label `")
+                .push(slice(fragments[fragment].as_bytes(), label))
+                .push(b"` defined by `")
+                .push(names[fragment].as_bytes())
+                .push(b"` is reserved by the macros of `asm_block`")
+                .as_str()
+        );
+    }
+    if let Some((first, label, second, _)) = duplicate_label(fragments) {
+        let message = Message::new()
+            .push(b"label `")
+            .push(slice(fragments[first].as_bytes(), label));
+        let message = if first == second {
+            message
+                .push(b"` is defined twice by `")
+                .push(names[first].as_bytes())
+                .push(b"`")
+        } else {
+            message
+                .push(b"` is defined by both `")
+                .push(names[first].as_bytes())
+                .push(b"` and `")
+                .push(names[second].as_bytes())
+                .push(b"`")
+        };
+        panic!("{}", message.as_str());
+    }
+    if let Some((fragment, reference)) = undefined_label(fragments, allow) {
+        let bytes = fragments[fragment].as_bytes();
+        let word = slice(bytes, reference);
+        let message = Message::new().push(b"label `");
+        let message = if word[0] == b'.' {
+            message.push(word).push(b"` referenced by `")
+        } else {
+            message
+                .push(slice(bytes, (reference.0, reference.1 - 1)))
+                .push(b"` referenced as `")
+                .push(word)
+                .push(b"` by `")
+        };
+        let message = message
+            .push(names[fragment].as_bytes())
+            .push(b"` is not defined");
+        let message = match word[word.len() - 1] {
+            b'f' if word[0] != b'.' => message.push(b" after it"),
+            b'b' if word[0] != b'.' => message.push(b" before it"),
+            _ => message,
+        };
+        panic!("{}", message.as_str());
+    }
+}
+
+/// Fail the evaluation of the enclosing constant if a placeholder of the
+/// fragments has no operand of that name, naming the fragment using it, or if
+/// a named operand is used by none of the fragments. The operands are given
+/// as written in [`asm!`], e.g. `x = inout(reg) x, out("rax") _`, of which
+/// only the named ones are checked, and positional placeholders like `{}` or
+/// `{0}` are ignored.
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+pub const fn check_operands(fragments: &[&str], names: &[&str], operands: &str) {
+    if let Some((fragment, placeholder, name)) = undeclared_placeholder(fragments, operands) {
+        let bytes = fragments[fragment].as_bytes();
+        panic!(
+            "{}",
+            Message::new()
+                .push(b"`")
+                .push(slice(bytes, placeholder))
+                .push(b"` is used by `")
+                .push(names[fragment].as_bytes())
+                .push(b"`, but no operand is named `")
+                .push(slice(bytes, name))
+                .push(b"`")
+                .as_str()
+        );
+    }
+    if let Some(name) = unused_operand(fragments, operands) {
+        panic!(
+            "{}",
+            Message::new()
+                .push(b"operand `")
+                .push(slice(operands.as_bytes(), name))
+                .push(b"` is not used by the fragments")
+                .as_str()
+        );
+    }
+}
+
+/// Find a named placeholder of the fragments without an operand of that name.
+///
+/// Return the index of the fragment, and the spans of the placeholder and of
+/// its name.
+pub const fn undeclared_placeholder(
+    fragments: &[&str],
+    operands: &str,
+) -> Option<(usize, Span, Span)> {
+    let mut fragment = 0;
+    while fragment < fragments.len() {
+        let template = fragments[fragment].as_bytes();
+        let mut pos = 0;
+        while let Some((placeholder, name)) = next_placeholder(template, pos) {
+            if !is_positional(slice(template, name))
+                && !declares(operands.as_bytes(), slice(template, name))
+            {
+                return Some((fragment, placeholder, name));
+            }
+            pos = placeholder.1;
+        }
+        fragment += 1;
+    }
+    None
+}
+
+/// Find a named operand which no placeholder of the fragments uses.
+///
+/// Return the span of its name in the operands.
+pub const fn unused_operand(fragments: &[&str], operands: &str) -> Option<Span> {
+    let bytes = operands.as_bytes();
+    let mut pos = 0;
+    while let Some((name, next)) = next_operand(bytes, pos) {
+        let mut used = false;
+        let mut fragment = 0;
+        while fragment < fragments.len() && !used {
+            let template = fragments[fragment].as_bytes();
+            let mut from = 0;
+            while let Some((placeholder, found)) = next_placeholder(template, from) {
+                if eq(slice(template, found), slice(bytes, name)) {
+                    used = true;
+                    break;
+                }
+                from = placeholder.1;
+            }
+            fragment += 1;
+        }
+        if !used {
+            return Some(name);
+        }
+        pos = next;
+    }
+    None
+}
+
+/// Whether the operands have one with the given name.
+const fn declares(operands: &[u8], name: &[u8]) -> bool {
+    let mut pos = 0;
+    while let Some((found, next)) = next_operand(operands, pos) {
+        if eq(slice(operands, found), name) {
+            return true;
+        }
+        pos = next;
+    }
+    false
+}
+
+const fn is_positional(name: &[u8]) -> bool {
+    name.is_empty() || name[0].is_ascii_digit()
+}
+
+/// Find the next placeholder, skipping escaped braces.
+///
+/// Return the spans of the placeholder and of its name, i.e. the part before
+/// the modifier.
+const fn next_placeholder(template: &[u8], mut pos: usize) -> Option<(Span, Span)> {
+    while pos < template.len() {
+        if template[pos] == b'{' && pos + 1 < template.len() && template[pos + 1] == b'{' {
+            pos += 2;
+        } else if template[pos] == b'{' {
+            let start = pos;
+            let mut name = start + 1;
+            while name < template.len() && template[name] != b'}' && template[name] != b':' {
+                name += 1;
+            }
+            let mut close = name;
+            while close < template.len() && template[close] != b'}' {
+                close += 1;
+            }
+            let end = if close < template.len() {
+                close + 1
+            } else {
+                close
+            };
+            return Some(((start, end), (start + 1, name)));
+        } else {
+            pos += 1;
+        }
+    }
+    None
+}
+
+/// Find the name of the next named operand, i.e. an operand starting with an
+/// identifier followed by `=`, among operands separated by commas.
+///
+/// Return the span of the name and the position after the operand.
+const fn next_operand(operands: &[u8], mut pos: usize) -> Option<(Span, usize)> {
+    while pos < operands.len() {
+        // find the end of the operand, outside of groups and strings
+        let start = pos;
+        let mut depth = 0;
+        let mut in_string = false;
+        while pos < operands.len() && (in_string || depth > 0 || operands[pos] != b',') {
+            match operands[pos] {
+                b'"' => in_string = !in_string,
+                b'\\' if in_string => pos += 1,
+                b'(' | b'[' | b'{' if !in_string => depth += 1,
+                b')' | b']' | b'}' if !in_string => depth -= 1,
+                _ => {}
+            }
+            pos += 1;
+        }
+        let (start, end) = trim(operands, (start, pos));
+        pos += 1;
+        let mut name = start;
+        while name < end && (operands[name].is_ascii_alphanumeric() || operands[name] == b'_') {
+            name += 1;
+        }
+        let (equals, _) = trim(operands, (name, end));
+        if name > start
+            && equals < end
+            && operands[equals] == b'='
+            && (equals + 1 == end || !matches!(operands[equals + 1], b'=' | b'>'))
+        {
+            return Some(((start, name), pos));
+        }
+    }
+    None
+}
+
+/// Fail the evaluation of the enclosing constant if an instruction modifies
+/// the flags, for blocks promising `preserves_flags`. Nothing is checked if
+/// the tables of the target architecture are not enabled.
+pub const fn check_preserves_flags(template: &str) {
+    let bytes = template.as_bytes();
+    if let Some(arch) = ARCH {
+        if let Some((line, mnemonic)) = modifies_flags(bytes, arch.flags) {
+            panic!(
+                "{}",
+                Message::new()
+                    .push(b"`")
+                    .push(slice(bytes, mnemonic))
+                    .push(b"` modifies the flags in `")
+                    .push(slice(bytes, line))
+                    .push(b"`, but the fragments declare `preserves_flags`")
+                    .as_str()
+            );
+        }
+    }
+}
+
+/// Find a named label defined twice in the fragments, which are assembled
+/// one after another.
+///
+/// Return the indices of the fragments and the spans of the definitions.
+pub const fn duplicate_label(fragments: &[&str]) -> Option<(usize, Span, usize, Span)> {
+    let mut first = 0;
+    while first < fragments.len() {
+        let template = fragments[first].as_bytes();
+        let mut pos = 0;
+        while let Some((label, next)) = next_label(template, pos) {
+            // look for the same label after this one
+            let mut second = first;
+            let mut from = next;
+            while second < fragments.len() {
+                let other = fragments[second].as_bytes();
+                while let Some((found, next)) = next_label(other, from) {
+                    if eq(slice(template, label), slice(other, found)) {
+                        return Some((first, label, second, found));
+                    }
+                    from = next;
+                }
+                second += 1;
+                from = 0;
+            }
+            pos = next;
+        }
+        first += 1;
+    }
+    None
+}
+
+/// Find a definition of a reserved label, from `42000` to `42119` or starting
+/// with `42024`, as [`asm_block!`] transcribes it, i.e. with a space before
+/// the `:`. The macros of this crate define them without the space, and are
+/// not reported.
+///
+/// Return the index of the fragment and the span of the label.
+///
+/// [`asm_block!`]: ../macro.asm_block.html
+pub const fn reserved_label(fragments: &[&str]) -> Option<(usize, Span)> {
+    let mut fragment = 0;
+    while fragment < fragments.len() {
+        let template = fragments[fragment].as_bytes();
+        let mut pos = 0;
+        while let Some((label, next)) = next_definition(template, pos) {
+            let word = slice(template, label);
+            let reserved = is_numeric(word)
+                && match parse_number(word) {
+                    Some(number) if word.len() == 5 => number >= 42000 && number < 42120,
+                    _ => word.len() > 5 && starts_with(word, b"42024"),
+                };
+            if reserved && template[label.1] != b':' {
+                return Some((fragment, label));
+            }
+            pos = next;
+        }
+        fragment += 1;
+    }
+    None
+}
+
+/// Find a reference to a local label without a definition in the fragments,
+/// i.e. `Nf` without a later `N:`, `Nb` without an earlier `N:`, or a label
+/// starting with `.L` which is neither defined nor allowed.
+///
+/// Return the index of the fragment and the span of the reference.
+pub const fn undefined_label(fragments: &[&str], allow: &[&str]) -> Option<(usize, Span)> {
+    let mut fragment = 0;
+    while fragment < fragments.len() {
+        let template = fragments[fragment].as_bytes();
+        let mut pos = 0;
+        while let Some(reference) = next_reference(template, pos) {
+            let word = slice(template, reference);
+            let defined = if word[0] == b'.' {
+                str_contains(allow, word)
+                    || is_defined(fragments, word, (0, 0), (fragments.len(), 0))
+            } else {
+                let number = slice(template, (reference.0, reference.1 - 1));
+                if word[word.len() - 1] == b'f' {
+                    let next = line_end(template, reference.1) + 1;
+                    is_defined(fragments, number, (fragment, next), (fragments.len(), 0))
+                } else {
+                    is_defined(fragments, number, (0, 0), (fragment, reference.0))
+                }
+            };
+            if !defined {
+                return Some((fragment, reference));
+            }
+            pos = reference.1;
+        }
+        fragment += 1;
+    }
+    None
+}
+
+/// Whether a label is defined between two positions, each made of the index
+/// of a fragment and an offset in it.
+const fn is_defined(
+    fragments: &[&str],
+    name: &[u8],
+    from: (usize, usize),
+    to: (usize, usize),
+) -> bool {
+    let mut fragment = from.0;
+    let mut pos = from.1;
+    while fragment < fragments.len() && fragment <= to.0 {
+        let template = fragments[fragment].as_bytes();
+        while let Some((label, next)) = next_definition(template, pos) {
+            if fragment == to.0 && label.0 >= to.1 {
+                return false;
+            }
+            if eq(slice(template, label), name) {
+                return true;
+            }
+            pos = next;
+        }
+        fragment += 1;
+        pos = 0;
+    }
+    false
+}
+
+/// Find the next reference to a local label, i.e. `Nf`, `Nb` or a word
+/// starting with `.L`, outside of definitions, string literals and
+/// placeholders.
+const fn next_reference(template: &[u8], mut pos: usize) -> Option<Span> {
+    let mut in_string = false;
+    while pos < template.len() {
+        let c = template[pos];
+        if c == b'\n' {
+            in_string = false;
+            pos += 1;
+        } else if c == b'"' {
+            in_string = !in_string;
+            pos += 1;
+        } else if in_string {
+            pos += if c == b'\\' { 2 } else { 1 };
+        } else if c == b'{' {
+            while pos < template.len() && template[pos] != b'}' && template[pos] != b'\n' {
+                pos += 1;
+            }
+        } else if is_word(c) {
+            let start = pos;
+            while pos < template.len() && is_word(template[pos]) {
+                pos += 1;
+            }
+            let word = slice(template, (start, pos));
+            let defined = pos < template.len() && template[pos] == b':';
+            if !defined && !has_macro_argument(word) && is_local_reference(word) {
+                return Some((start, pos));
+            }
+        } else {
+            pos += 1;
+        }
+    }
+    None
+}
+
+const fn is_local_reference(word: &[u8]) -> bool {
+    if word.len() > 2 && word[0] == b'.' && word[1] == b'L' {
+        return true;
+    }
+    word.len() > 1
+        && matches!(word[word.len() - 1], b'f' | b'b')
+        && is_numeric(word.split_at(word.len() - 1).0)
+}
+
+/// Find the next definition of a named label, from the start of a line or
+/// the end of a previous label.
+///
+/// Return the span of the label and the position after its `:`.
+const fn next_label(template: &[u8], mut pos: usize) -> Option<(Span, usize)> {
+    while let Some((label, next)) = next_definition(template, pos) {
+        let word = slice(template, label);
+        if !is_numeric(word) && !has_macro_argument(word) {
+            return Some((label, next));
+        }
+        pos = next;
+    }
+    None
+}
+
+/// Find the next definition of a label, numeric or not, from the start of a
+/// line or the end of a previous label.
+///
+/// Return the span of the label and the position after its `:`.
+const fn next_definition(template: &[u8], mut pos: usize) -> Option<(Span, usize)> {
+    while pos < template.len() {
+        let end = line_end(template, pos);
+        let token = next_token(template, pos, end);
+        let after = next_token(template, token.span.1, end);
+        if matches!(token.kind, Kind::Word) && matches!(after.kind, Kind::Colon) {
+            return Some((token.span, after.span.1));
+        }
+        pos = end + 1;
+    }
+    None
+}
+
+/// A group of instructions recognized with the tables of the target
+/// architecture, which a [`Policy`] can deny.
+///
+/// [`Policy`]: struct.Policy.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+    /// Jumps, conditional branches, calls, returns, loop instructions, system
+    /// calls and traps.
+    Branches,
+    /// Memory operands, apart from the address computed by `lea`, and
+    /// instructions accessing memory implicitly, like `movsb`, `push` or `ldr`
+    /// from a label.
+    MemoryOps,
+    /// `push`, `pop`, calls, returns, and the stack pointer as an operand.
+    StackOps,
+    /// Instructions modifying the flags.
+    Flags,
+    /// Instructions faulting outside of the kernel, like `cli`, `wrmsr`, port
+    /// I/O, or `eret` and `tlbi` on AArch64.
+    Privileged,
+    /// Instructions of the x87 floating point unit.
+    X87,
+}
+
+impl Group {
+    const ALL: [Group; 6] = [
+        Group::Branches,
+        Group::MemoryOps,
+        Group::StackOps,
+        Group::Flags,
+        Group::Privileged,
+        Group::X87,
+    ];
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A set of instructions which fragments are asserted not to use, checked by
+/// [`asm_assert!`] with `policy(...)`.
+///
+/// A policy is built in a `const` item, so that a crate can define it once,
+/// e.g. "no privileged instructions" for code running in a sandbox, and apply
+/// it to all its fragments. The groups are recognized with the tables of the
+/// target architecture, and are not checked without them. The mnemonics of
+/// [`deny_mnemonics`] and [`allow_only`] are compared without the tables, case
+/// insensitively, so they apply on every target. Directives like `.byte` are
+/// never checked, since anything can be emitted with them.
+///
+/// [`asm_assert!`]: macro.asm_assert.html
+/// [`deny_mnemonics`]: Policy::deny_mnemonics
+/// [`allow_only`]: Policy::allow_only
+#[derive(Clone, Copy, Debug)]
+pub struct Policy {
+    groups: u8,
+    denied: &'static [&'static str],
+    allowed: Option<&'static [&'static str]>,
+}
+
+impl Policy {
+    /// Create a policy allowing every instruction.
+    pub const fn new() -> Self {
+        Policy {
+            groups: 0,
+            denied: &[],
+            allowed: None,
+        }
+    }
+
+    /// Deny the instructions of a group.
+    pub const fn deny(mut self, group: Group) -> Self {
+        self.groups |= group.bit();
+        self
+    }
+
+    /// Deny the mnemonics of the list, replacing any list denied before.
+    pub const fn deny_mnemonics(mut self, mnemonics: &'static [&'static str]) -> Self {
+        self.denied = mnemonics;
+        self
+    }
+
+    /// Deny every mnemonic which is not in the list, replacing any list
+    /// allowed before. The groups and the denied mnemonics are still denied.
+    pub const fn allow_only(mut self, mnemonics: &'static [&'static str]) -> Self {
+        self.allowed = Some(mnemonics);
+        self
+    }
+
+    const fn denies(&self, group: Group) -> bool {
+        self.groups & group.bit() != 0
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::new()
+    }
+}
+
+/// Fail the evaluation of the enclosing constant if an instruction is denied
+/// by the policy, for [`asm_assert!`].
+///
+/// [`asm_assert!`]: ../macro.asm_assert.html
+pub const fn check_policy(policy: &Policy, template: &str) {
+    let bytes = template.as_bytes();
+    if let Some((line, mnemonic)) = find_listed(bytes, policy.denied) {
+        panic!(
+            "{}",
+            Message::new()
+                .push(b"`")
+                .push(slice(bytes, mnemonic))
+                .push(b"` is denied by the policy in `")
+                .push(slice(bytes, line))
+                .push(b"`")
+                .as_str()
+        );
+    }
+    if let Some(allowed) = policy.allowed {
+        if let Some((line, mnemonic)) = find_unlisted(bytes, allowed) {
+            panic!(
+                "{}",
+                Message::new()
+                    .push(b"`")
+                    .push(slice(bytes, mnemonic))
+                    .push(b"` is not allowed by the policy in `")
+                    .push(slice(bytes, line))
+                    .push(b"`")
+                    .as_str()
+            );
+        }
+    }
+    if let Some(arch) = ARCH {
+        let mut i = 0;
+        while i < Group::ALL.len() {
+            let group = Group::ALL[i];
+            if policy.denies(group) {
+                if let Some((line, found)) = find_group(bytes, &arch, group) {
+                    let (action, property): (&[u8], &[u8]) = match group {
+                        Group::Branches => (b"transfers control", b"have no branches"),
+                        Group::MemoryOps => (b"accesses memory", b"have no memory operations"),
+                        Group::StackOps => (b"uses the stack", b"have no stack operations"),
+                        Group::Flags => (b"modifies the flags", b"preserve the flags"),
+                        Group::Privileged => (b"is privileged", b"have no privileged instructions"),
+                        Group::X87 => (b"is an x87 instruction", b"have no x87 instructions"),
+                    };
+                    panic!(
+                        "{}",
+                        Message::new()
+                            .push(b"`")
+                            .push(slice(bytes, found))
+                            .push(b"` ")
+                            .push(action)
+                            .push(b" in `")
+                            .push(slice(bytes, line))
+                            .push(b"`, but the fragments are asserted to ")
+                            .push(property)
+                            .as_str()
+                    );
+                }
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Find an instruction of the group with the tables of the architecture.
+///
+/// Return the spans of the line and of the mnemonic or operand.
+const fn find_group(template: &[u8], arch: &Arch, group: Group) -> Option<(Span, Span)> {
+    match group {
+        Group::Branches => find_mnemonic(template, arch.branches),
+        Group::MemoryOps => match find_mnemonic(template, arch.memory) {
+            Some(found) => Some(found),
+            None => match find_mnemonic(template, arch.stack) {
+                Some(found) => Some(found),
+                None => memory_operand(template, arch.addresses),
+            },
+        },
+        Group::StackOps => match find_mnemonic(template, arch.stack) {
+            Some(found) => Some(found),
+            None => find_operand(template, arch.stack_pointers),
+        },
+        Group::Flags => find_mnemonic(template, arch.flags),
+        Group::Privileged => find_mnemonic(template, arch.privileged),
+        Group::X87 => find_mnemonic(template, arch.x87),
+    }
+}
+
+/// Find an instruction whose mnemonic is in the list, which need not be
+/// sorted.
+///
+/// Return the spans of the line and the mnemonic.
+pub const fn find_listed(template: &[u8], list: &[&str]) -> Option<(Span, Span)> {
+    find_instruction(template, list, true)
+}
+
+/// Find an instruction whose mnemonic is not in the list, which need not be
+/// sorted.
+///
+/// Return the spans of the line and the mnemonic.
+pub const fn find_unlisted(template: &[u8], list: &[&str]) -> Option<(Span, Span)> {
+    find_instruction(template, list, false)
+}
+
+const fn find_instruction(template: &[u8], list: &[&str], listed: bool) -> Option<(Span, Span)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        if let Some((mnemonic, _)) = instruction(template, line) {
+            let word = slice(template, mnemonic);
+            if !has_macro_argument(word) && str_contains(list, word) == listed {
+                return Some((trim(template, line), mnemonic));
+            }
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Bounds of the size in bytes of the code assembled from a template, as
+/// computed by [`asm_size!`].
+///
+/// [`asm_size!`]: macro.asm_size.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Size {
+    /// The fewest bytes the code can take.
+    pub min: usize,
+    /// The most bytes the code can take, or `None` if the size is not bounded,
+    /// e.g. by a directive whose size is not known.
+    pub max: Option<usize>,
+}
+
+impl Size {
+    /// The size, if it is exactly known.
+    pub const fn exact(self) -> Option<usize> {
+        match self.max {
+            Some(max) if max == self.min => Some(max),
+            _ => None,
+        }
+    }
+
+    /// Whether the code is known to take at most `bytes` bytes.
+    pub const fn fits(self, bytes: usize) -> bool {
+        matches!(self.max, Some(max) if max <= bytes)
+    }
+}
+
+/// Bounds of the size of an instruction of the target architecture, or `None`
+/// if the architecture is not known.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const INSTRUCTION_SIZE: Option<(usize, usize)> = Some((1, 15));
+#[cfg(any(
+    target_arch = "aarch64",
+    all(target_arch = "arm", not(target_feature = "thumb-mode")),
+    all(
+        any(target_arch = "riscv32", target_arch = "riscv64"),
+        not(target_feature = "c")
+    )
+))]
+const INSTRUCTION_SIZE: Option<(usize, usize)> = Some((4, 4));
+#[cfg(any(
+    all(target_arch = "arm", target_feature = "thumb-mode"),
+    all(
+        any(target_arch = "riscv32", target_arch = "riscv64"),
+        target_feature = "c"
+    )
+))]
+const INSTRUCTION_SIZE: Option<(usize, usize)> = Some((2, 4));
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "arm",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+const INSTRUCTION_SIZE: Option<(usize, usize)> = None;
+
+/// Most instructions which pseudo-instructions expand to, when their operands
+/// have no parentheses, i.e. for a symbol rather than an offset from a
+/// register.
+#[cfg(target_arch = "riscv64")]
+const EXPANSIONS: &[(&str, usize, usize)] = &[
+    ("call", 1, 2),
+    ("fld", 1, 2),
+    ("flw", 1, 2),
+    ("fsd", 1, 2),
+    ("fsw", 1, 2),
+    ("la", 1, 2),
+    ("lb", 1, 2),
+    ("lbu", 1, 2),
+    ("ld", 1, 2),
+    ("lh", 1, 2),
+    ("lhu", 1, 2),
+    ("li", 1, 8),
+    ("lla", 1, 2),
+    ("lw", 1, 2),
+    ("lwu", 1, 2),
+    ("sb", 1, 2),
+    ("sd", 1, 2),
+    ("sh", 1, 2),
+    ("sw", 1, 2),
+    ("tail", 1, 2),
+];
+#[cfg(target_arch = "riscv32")]
+const EXPANSIONS: &[(&str, usize, usize)] = &[
+    ("call", 1, 2),
+    ("fld", 1, 2),
+    ("flw", 1, 2),
+    ("fsd", 1, 2),
+    ("fsw", 1, 2),
+    ("la", 1, 2),
+    ("lb", 1, 2),
+    ("lbu", 1, 2),
+    ("lh", 1, 2),
+    ("lhu", 1, 2),
+    ("li", 1, 2),
+    ("lla", 1, 2),
+    ("lw", 1, 2),
+    ("sb", 1, 2),
+    ("sh", 1, 2),
+    ("sw", 1, 2),
+    ("tail", 1, 2),
+];
+#[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+const EXPANSIONS: &[(&str, usize, usize)] = &[];
+
+/// Directives which emit nothing in the current section.
+const SILENT: &[&[u8]] = &[
+    b".altmacro",
+    b".arch",
+    b".arch_extension",
+    b".att_syntax",
+    b".code16",
+    b".code32",
+    b".code64",
+    b".cpu",
+    b".endfunc",
+    b".equ",
+    b".equiv",
+    b".eqv",
+    b".file",
+    b".fpu",
+    b".func",
+    b".global",
+    b".globl",
+    b".hidden",
+    b".ident",
+    b".intel_syntax",
+    b".internal",
+    b".loc",
+    b".local",
+    b".noaltmacro",
+    b".option",
+    b".private_extern",
+    b".protected",
+    b".purgem",
+    b".set",
+    b".size",
+    b".syntax",
+    b".thumb_func",
+    b".type",
+    b".weak",
+    b".weakref",
+];
+
+/// Compute bounds of the size of the code assembled from the template in the
+/// current section.
+///
+/// Instructions take the sizes of the target architecture, which are exact on
+/// AArch64, on ARM outside of Thumb and on RISC-V without compressed
+/// instructions, up to the pseudo-instructions of RISC-V like `li`. On x86,
+/// the sizes of instructions without operands and of branches to a local label
+/// or a placeholder are known with the tables of the `lint-x86_64` feature,
+/// and other instructions take from 1 to 15 bytes. Data directives take their
+/// exact size, alignment directives from none to all of their padding, and
+/// the bytes emitted in another section, e.g. between `.pushsection` and
+/// `.popsection`, are not counted. Any other directive, macro or repetition
+/// leaves the size unbounded.
+pub const fn code_size(template: &str) -> Size {
+    match measure(template.as_bytes()) {
+        (min, Ok(max)) => Size {
+            min,
+            max: Some(max),
+        },
+        (min, Err(_)) => Size { min, max: None },
+    }
+}
+
+/// Fail the evaluation of the enclosing constant if the code may take more
+/// than `bytes` bytes, for [`asm_assert!`] with `max_size`.
+///
+/// [`asm_assert!`]: ../macro.asm_assert.html
+pub const fn check_size(bytes: usize, template: &str) {
+    match measure(template.as_bytes()).1 {
+        Ok(max) if max <= bytes => {}
+        Ok(max) => panic!(
+            "{}",
+            Message::new()
+                .push(b"the fragments take up to ")
+                .push_number(max)
+                .push(b" bytes, but are asserted to fit in ")
+                .push_number(bytes)
+                .push(b" bytes")
+                .as_str()
+        ),
+        Err(line) => panic!(
+            "{}",
+            Message::new()
+                .push(b"the size of `")
+                .push(slice(template.as_bytes(), line))
+                .push(b"` is not known, but the fragments are asserted to fit in ")
+                .push_number(bytes)
+                .push(b" bytes")
+                .as_str()
+        ),
+    }
+}
+
+/// The line emitted by [`pad_to!`] before the block it pads.
+///
+/// [`pad_to!`]: ../macro.pad_to.html
+const PAD_MARKER: &[u8] = b"/* pad_to! */";
+
+/// Compute bounds of the size of the template.
+///
+/// Return the fewest bytes, and the most bytes or the span of the first line
+/// whose size is not bounded.
+const fn measure(template: &[u8]) -> (usize, Result<usize, Span>) {
+    let mut min = 0;
+    let mut max = Ok(0);
+    // nesting of `.pushsection`, and whether `.section` switched away
+    let mut depth = 0;
+    let mut away = false;
+    // size before the block padded by `pad_to!`, which starts at its marker
+    let mut region = None;
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        let counted = depth == 0 && !away;
+        if counted && eq(slice(template, trim(template, line)), PAD_MARKER) {
+            region = Some((min, max));
+        }
+        let bounds = if let Some((mnemonic, pos)) = instruction(template, line) {
+            let word = slice(template, mnemonic);
+            if !counted {
+                Some((0, 0))
+            } else if has_macro_argument(word) || defines_macro(template, word) {
+                None
+            } else {
+                instruction_size(template, line, mnemonic, pos)
+            }
+        } else if let Some((name, pos)) = directive(template, line) {
+            let word = slice(template, name);
+            if eq_ignore_case(word, b".pushsection") {
+                depth += 1;
+                Some((0, 0))
+            } else if eq_ignore_case(word, b".popsection") {
+                depth -= if depth > 0 { 1 } else { 0 };
+                Some((0, 0))
+            } else if eq_ignore_case(word, b".text") || eq_ignore_case(word, b".previous") {
+                away = false;
+                Some((0, 0))
+            } else if eq_ignore_case(word, b".section")
+                || eq_ignore_case(word, b".data")
+                || eq_ignore_case(word, b".bss")
+            {
+                away = depth == 0 || away;
+                Some((0, 0))
+            } else if eq_ignore_case(word, b".macro") {
+                start = block_end(template, end, b".endm");
+                continue;
+            } else if !counted {
+                Some((0, 0))
+            } else if region.is_some() && mentions(template, (pos, end), b"42090b") {
+                // the padding of `pad_to!` completes the block to its size
+                let first = next_token(template, pos, end);
+                match (region, parse_number(slice(template, first.span))) {
+                    (Some((low, high)), Some(size)) if eq_ignore_case(word, b".skip") => {
+                        min = if low + size > min { low + size } else { min };
+                        max = match high {
+                            Ok(high) => Ok(high + size),
+                            Err(line) => Err(line),
+                        };
+                        region = None;
+                        Some((0, 0))
+                    }
+                    _ if eq_ignore_case(word, b".fill") => Some((0, 0)),
+                    _ => None,
+                }
+            } else if eq_ignore_case(word, b".rept")
+                || eq_ignore_case(word, b".irp")
+                || eq_ignore_case(word, b".irpc")
+            {
+                if max.is_ok() {
+                    max = Err(trim(template, line));
+                }
+                start = block_end(template, end, b".endr");
+                continue;
+            } else {
+                directive_size(template, word, (pos, end))
+            }
+        } else {
+            Some((0, 0))
+        };
+        match (bounds, max) {
+            (Some((low, high)), Ok(total)) => {
+                min += low;
+                max = Ok(total + high);
+            }
+            (Some((low, _)), Err(_)) => min += low,
+            (None, Ok(_)) => max = Err(trim(template, line)),
+            (None, Err(_)) => {}
+        }
+        start = end + 1;
+    }
+    (min, max)
+}
+
+/// Bounds of the size of an instruction, with its prefixes.
+const fn instruction_size(
+    template: &[u8],
+    (start, end): Span,
+    mnemonic: Span,
+    pos: usize,
+) -> Option<(usize, usize)> {
+    let (min, max) = match INSTRUCTION_SIZE {
+        Some(bounds) => bounds,
+        None => return None,
+    };
+    let word = slice(template, mnemonic);
+    let count = operand_count(template, pos, end);
+    if let Some(arch) = ARCH {
+        let prefixes = prefix_count(template, start, mnemonic.0);
+        let operand = next_token(template, pos, end);
+        let local = match operand.kind {
+            Kind::Placeholder => true,
+            Kind::Word => is_local_reference(slice(template, operand.span)),
+            _ => false,
+        };
+        let known = if count == 0 {
+            arity(arch.sizes, word)
+        } else if count == 1 && local {
+            arity(arch.relative, word)
+        } else {
+            None
+        };
+        if let Some((min, max)) = known {
+            return Some((min + prefixes, max + prefixes));
+        }
+    }
+    if !has_parenthesis(template, pos, end) {
+        if let Some((low, high)) = arity(EXPANSIONS, word) {
+            return Some((min * low, max * high));
+        }
+    }
+    Some((min, max))
+}
+
+/// Count the prefixes before the mnemonic.
+const fn prefix_count(template: &[u8], mut pos: usize, mnemonic: usize) -> usize {
+    let mut count = 0;
+    while pos < mnemonic {
+        let token = next_token(template, pos, mnemonic);
+        if contains(PREFIXES, slice(template, token.span)) {
+            count += 1;
+        }
+        pos = token.span.1;
+    }
+    count
+}
+
+const fn has_parenthesis(template: &[u8], mut pos: usize, end: usize) -> bool {
+    while pos < end {
+        if template[pos] == b'(' {
+            return true;
+        }
+        pos += 1;
+    }
+    false
+}
+
+/// Bounds of the size of a directive emitting into the current section, or
+/// `None` if its size is not known.
+const fn directive_size(template: &[u8], name: &[u8], arguments: Span) -> Option<(usize, usize)> {
+    let width = if eq_ignore_case(name, b".byte") {
+        1
+    } else if eq_ignore_case(name, b".2byte")
+        || eq_ignore_case(name, b".short")
+        || eq_ignore_case(name, b".hword")
+        || eq_ignore_case(name, b".value")
+    {
+        2
+    } else if eq_ignore_case(name, b".4byte")
+        || eq_ignore_case(name, b".long")
+        || eq_ignore_case(name, b".int")
+        || eq_ignore_case(name, b".inst")
+    {
+        4
+    } else if eq_ignore_case(name, b".8byte")
+        || eq_ignore_case(name, b".quad")
+        || eq_ignore_case(name, b".xword")
+        || eq_ignore_case(name, b".dword")
+    {
+        8
+    } else if eq_ignore_case(name, b".word") {
+        // 2 bytes on x86 only
+        if matches!(INSTRUCTION_SIZE, Some((1, _))) {
+            2
+        } else {
+            4
+        }
+    } else {
+        0
+    };
+    if width != 0 {
+        let count = operand_count(template, arguments.0, arguments.1);
+        return Some((width * count, width * count));
+    }
+    let first = next_token(template, arguments.0, arguments.1);
+    let number = parse_number(slice(template, first.span));
+    if eq_ignore_case(name, b".ascii") {
+        string_size(template, arguments, 0)
+    } else if eq_ignore_case(name, b".asciz") || eq_ignore_case(name, b".string") {
+        string_size(template, arguments, 1)
+    } else if eq_ignore_case(name, b".zero")
+        || eq_ignore_case(name, b".skip")
+        || eq_ignore_case(name, b".space")
+    {
+        match number {
+            Some(count) => Some((count, count)),
+            None => None,
+        }
+    } else if eq_ignore_case(name, b".balign") {
+        match number {
+            Some(align) if align > 0 => Some((0, align - 1)),
+            _ => None,
+        }
+    } else if eq_ignore_case(name, b".p2align") {
+        match number {
+            Some(shift) if shift < usize::BITS as usize => Some((0, (1 << shift) - 1)),
+            _ => None,
+        }
+    } else if eq_ignore_case(name, b".align") {
+        // in bytes on some targets, and as a power of two on others
+        match number {
+            Some(0) => Some((0, 0)),
+            Some(align) if align < usize::BITS as usize => {
+                let power = (1 << align) - 1;
+                Some((0, if power > align { power } else { align - 1 }))
+            }
+            Some(align) => Some((0, align - 1)),
+            None => None,
+        }
+    } else if starts_with(name, b".cfi_") || contains(SILENT, name) {
+        Some((0, 0))
+    } else {
+        None
+    }
+}
+
+/// Count the bytes of the strings of `.ascii`, each followed by `terminator`
+/// bytes.
+const fn string_size(
+    template: &[u8],
+    (mut pos, end): Span,
+    terminator: usize,
+) -> Option<(usize, usize)> {
+    let mut size = 0;
+    let mut strings = 0;
+    while pos < end {
+        match template[pos] {
+            b'"' => {
+                pos += 1;
+                while pos < end && template[pos] != b'"' {
+                    if template[pos] == b'\\' && pos + 1 < end {
+                        pos += 1;
+                        if template[pos] == b'x' {
+                            while pos + 1 < end && template[pos + 1].is_ascii_hexdigit() {
+                                pos += 1;
+                            }
+                        } else {
+                            let mut digits = 0;
+                            while digits < 2
+                                && pos + 1 < end
+                                && matches!(template[pos], b'0'..=b'7')
+                                && matches!(template[pos + 1], b'0'..=b'7')
+                            {
+                                pos += 1;
+                                digits += 1;
+                            }
+                        }
+                    }
+                    size += 1;
+                    pos += 1;
+                }
+                strings += 1;
+            }
+            b',' => {}
+            c if c.is_ascii_whitespace() => {}
+            _ => return None,
+        }
+        pos += 1;
+    }
+    let size = size + strings * terminator;
+    Some((size, size))
+}
+
+/// Parse a decimal or hexadecimal integer.
+const fn parse_number(word: &[u8]) -> Option<usize> {
+    let (digits, radix) = if word.len() > 2 && word[0] == b'0' && matches!(word[1], b'x' | b'X') {
+        (word.split_at(2).1, 16)
+    } else {
+        (word, 10)
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    let mut value: usize = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        let digit = match digits[i] {
+            c @ b'0'..=b'9' => (c - b'0') as usize,
+            c @ b'a'..=b'f' if radix == 16 => (c - b'a') as usize + 10,
+            c @ b'A'..=b'F' if radix == 16 => (c - b'A') as usize + 10,
+            _ => return None,
+        };
+        value = match value.checked_mul(radix) {
+            Some(value) => match value.checked_add(digit) {
+                Some(value) => value,
+                None => return None,
+            },
+            None => return None,
+        };
+        i += 1;
+    }
+    Some(value)
+}
+
+/// Locate the directive on a line, skipping labels. Return the span of its
+/// name and the start of its arguments.
+const fn directive(template: &[u8], (start, end): Span) -> Option<(Span, usize)> {
+    let mut pos = start;
+    loop {
+        let token = next_token(template, pos, end);
+        if !matches!(token.kind, Kind::Word) {
+            return None;
+        }
+        let after = next_token(template, token.span.1, end);
+        if matches!(after.kind, Kind::Colon) {
+            pos = after.span.1;
+        } else if template[token.span.0] == b'.' {
+            return Some((token.span, token.span.1));
+        } else {
+            return None;
+        }
+    }
+}
+
+/// Find the start of the line after the end of the block opened on the line
+/// ending at `end`, e.g. by `.macro` and closed by `.endm`.
+const fn block_end(template: &[u8], end: usize, close: &[u8]) -> usize {
+    let mut start = end + 1;
+    while start < template.len() {
+        let end = line_end(template, start);
+        if let Some((name, _)) = directive(template, (start, end)) {
+            if eq_ignore_case(slice(template, name), close) {
+                return end + 1;
+            }
+        }
+        start = end + 1;
+    }
+    start
+}
+
+/// Whether the bytes of the span contain `needle`.
+const fn mentions(template: &[u8], (start, end): Span, needle: &[u8]) -> bool {
+    let mut pos = start;
+    while pos + needle.len() <= end {
+        if eq(slice(template, (pos, pos + needle.len())), needle) {
+            return true;
+        }
+        pos += 1;
+    }
+    false
+}
+
+const fn starts_with(word: &[u8], prefix: &[u8]) -> bool {
+    word.len() >= prefix.len() && eq_ignore_case(word.split_at(prefix.len()).0, prefix)
+}
+
+/// Find a memory operand, i.e. `[...]`, or `(...)` with a register in AT&T
+/// syntax, of an instruction whose mnemonic is not in the sorted table of
+/// mnemonics only computing the address.
+///
+/// Return the spans of the line and the operand.
+pub const fn memory_operand(template: &[u8], addresses: &[&str]) -> Option<(Span, Span)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        if let Some((mnemonic, mut pos)) = instruction(template, line) {
+            if !sorted_contains(addresses, slice(template, mnemonic)) {
+                while pos < end {
+                    let token = next_token(template, pos, end);
+                    if token.span.0 == token.span.1 {
+                        break;
+                    }
+                    let memory = match token.kind {
+                        Kind::Memory => true,
+                        Kind::Transparent => {
+                            template[token.span.0] == b'('
+                                && token.span.0 + 1 < token.span.1
+                                && template[token.span.0 + 1] == b'%'
+                        }
+                        _ => false,
+                    };
+                    if memory {
+                        return Some((trim(template, line), token.span));
+                    }
+                    pos = token.span.1;
+                }
+            }
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Find one of the words as an operand of an instruction, or a part of one,
+/// e.g. a register used as the base of a memory operand.
+///
+/// Return the spans of the line and the word.
+pub const fn find_operand(template: &[u8], words: &[&str]) -> Option<(Span, Span)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        if let Some((_, mut pos)) = instruction(template, line) {
+            while pos < end {
+                let c = template[pos];
+                if c == b'{' {
+                    // placeholders are named by Rust, not by the assembler
+                    while pos < end && template[pos] != b'}' {
+                        pos += 1;
+                    }
+                } else if is_word(c) {
+                    let word = pos;
+                    while pos < end && is_word(template[pos]) {
+                        pos += 1;
+                    }
+                    if str_contains(words, slice(template, (word, pos))) {
+                        return Some((trim(template, line), (word, pos)));
+                    }
+                } else {
+                    pos += 1;
+                }
+            }
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Find an escape in a string literal which the assembler does not read the
+/// same way as Rust.
+///
+/// Return the spans of the line and the escape.
+pub const fn misread_escape(template: &[u8]) -> Option<(Span, Span)> {
+    let mut line = 0;
+    let mut in_string = false;
+    let mut pos = 0;
+    while pos < template.len() {
+        let c = template[pos];
+        if c == b'\n' {
+            line = pos + 1;
+            in_string = false;
+        } else if c == b'"' {
+            in_string = !in_string;
+        } else if c == b'\\' && in_string && pos + 1 < template.len() {
+            let end = match template[pos + 1] {
+                b'\'' => pos + 2,
+                b'u' => {
+                    let mut end = pos + 2;
+                    while end < template.len() && template[end - 1] != b'}' {
+                        end += 1;
+                    }
+                    end
+                }
+                b'0' if pos + 2 < template.len() && matches!(template[pos + 2], b'0'..=b'7') => {
+                    pos + 3
+                }
+                b'x' if pos + 4 < template.len() && template[pos + 4].is_ascii_hexdigit() => {
+                    pos + 5
+                }
+                _ => {
+                    pos += 2;
+                    continue;
+                }
+            };
+            return Some((
+                trim(template, (line, line_break(template, pos))),
+                (pos, end),
+            ));
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Find a placeholder whose template modifier, i.e. the part after `:`, is not
+/// one of the given letters. Placeholders without a modifier are not checked.
+///
+/// Return the spans of the line and the placeholder.
+pub const fn unknown_modifier(template: &[u8], modifiers: &[u8]) -> Option<(Span, Span)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let mut pos = start;
+        while pos < end {
+            if template[pos] == b'{' && pos + 1 < end && template[pos + 1] == b'{' {
+                // escaped brace
+                pos += 2;
+                continue;
+            }
+            if template[pos] != b'{' {
+                pos += 1;
+                continue;
+            }
+            let mut close = pos;
+            let mut colon = None;
+            while close < end && template[close] != b'}' {
+                if template[close] == b':' && colon.is_none() {
+                    colon = Some(close);
+                }
+                close += 1;
+            }
+            if let Some(colon) = colon {
+                // `{x:e}` has the modifier between the colon and the brace
+                if close != colon + 2 || !byte_contains(modifiers, template[colon + 1]) {
+                    let close = if close < end { close + 1 } else { end };
+                    return Some((trim(template, (start, end)), (pos, close)));
+                }
+            }
+            pos = close;
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Find an instruction whose number of operands is out of the range given in
+/// the sorted table. Mnemonics missing from the table are not checked.
+///
+/// Return the spans of the line and the mnemonic, and the number of operands.
+pub const fn wrong_operand_count(
+    template: &[u8],
+    operands: &[(&str, usize, usize)],
+) -> Option<(Span, Span, usize)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        if let Some((mnemonic, pos)) = instruction(template, line) {
+            if let Some((min, max)) = arity(operands, slice(template, mnemonic)) {
+                let count = operand_count(template, pos, end);
+                if count < min || count > max {
+                    return Some((trim(template, line), mnemonic, count));
+                }
+            }
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Count the operands separated by `,` outside of any group.
+const fn operand_count(template: &[u8], mut pos: usize, end: usize) -> usize {
+    let mut count = 0;
+    let mut empty = true;
+    while pos < end {
+        let token = next_token(template, pos, end);
+        if token.span.0 == token.span.1 {
+            break;
+        }
+        let comma = token.span.1 - token.span.0 == 1 && template[token.span.0] == b',';
+        if comma {
+            count += 1;
+        }
+        empty = false;
+        pos = token.span.1;
+    }
+    if empty { 0 } else { count + 1 }
+}
+
+/// Binary search in the table of operand counts.
+const fn arity(operands: &[(&str, usize, usize)], word: &[u8]) -> Option<(usize, usize)> {
+    let (mut low, mut high) = (0, operands.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        let (mnemonic, min, max) = operands[mid];
+        match compare_ignore_case(mnemonic.as_bytes(), word) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return Some((min, max)),
+        }
+    }
+    None
+}
+
+/// Find a mnemonic which is neither in the sorted table, nor allowed, nor an
+/// assembler macro defined in the template.
+///
+/// Return the spans of the line and the mnemonic.
+pub const fn unknown_mnemonic(
+    template: &[u8],
+    mnemonics: &[&str],
+    allow: &[&str],
+) -> Option<(Span, Span)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        if let Some((mnemonic, _)) = instruction(template, line) {
+            let word = slice(template, mnemonic);
+            if !has_macro_argument(word)
+                && !sorted_contains(mnemonics, word)
+                && !str_contains(allow, word)
+                && !defines_macro(template, word)
+            {
+                return Some((trim(template, line), mnemonic));
+            }
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Find an instruction whose mnemonic is in the sorted table of mnemonics
+/// modifying the flags.
+///
+/// Return the spans of the line and the mnemonic.
+pub const fn modifies_flags(template: &[u8], flags: &[&str]) -> Option<(Span, Span)> {
+    find_mnemonic(template, flags)
+}
+
+/// Find an instruction whose mnemonic is in the sorted table.
+///
+/// Return the spans of the line and the mnemonic.
+pub const fn find_mnemonic(template: &[u8], table: &[&str]) -> Option<(Span, Span)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        if let Some((mnemonic, _)) = instruction(template, line) {
+            if sorted_contains(table, slice(template, mnemonic)) {
+                return Some((trim(template, line), mnemonic));
+            }
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Find an operand or mnemonic directly following another operand.
+///
+/// Return the spans of the line and the two operands.
+pub const fn missing_separator(template: &[u8]) -> Option<(Span, Span, Span)> {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let line = (start, end);
+        if let Some((_, operands)) = instruction(template, line) {
+            let mut prev = Token {
+                span: (0, 0),
+                kind: Kind::Other,
+            };
+            let mut pos = operands;
+            while pos < end {
+                let token = next_token(template, pos, end);
+                if token.kind.is_operand()
+                    && prev.kind.is_operand()
+                    && (token.span.0 > prev.span.1 || glued_to_placeholder(template, prev, token))
+                    && !is_keyword(slice(template, prev.span))
+                    && !is_keyword(slice(template, token.span))
+                {
+                    return Some((trim(template, line), prev.span, token.span));
+                }
+                if !matches!(token.kind, Kind::Transparent) {
+                    prev = token;
+                }
+                pos = token.span.1;
+            }
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// A range of bytes in the template.
+pub type Span = (usize, usize);
+
+#[derive(Clone, Copy)]
+enum Kind {
+    /// a register, a number, a symbol or a mnemonic
+    Word,
+    /// `{...}`
+    Placeholder,
+    /// `[...]`
+    Memory,
+    /// `(...)` or `{{...}}`, which belongs to the surrounding operand
+    Transparent,
+    Colon,
+    Other,
+}
+
+impl Kind {
+    const fn is_operand(self) -> bool {
+        matches!(self, Kind::Word | Kind::Placeholder | Kind::Memory)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Token {
+    span: Span,
+    kind: Kind,
+}
+
+/// Instruction prefixes, which are followed by the actual mnemonic.
+const PREFIXES: &[&[u8]] = &[
+    b"lock",
+    b"rep",
+    b"repe",
+    b"repz",
+    b"repne",
+    b"repnz",
+    b"notrack",
+    b"bnd",
+    b"xacquire",
+    b"xrelease",
+    b"data16",
+    b"data32",
+    b"addr16",
+    b"addr32",
+    b"rex",
+    b"rex64",
+];
+
+/// Words that can be followed by another part of the same operand.
+const KEYWORDS: &[&[u8]] = &[
+    // x86
+    b"byte", b"word", b"dword", b"fword", b"qword", b"tbyte", b"tword", b"oword", b"mmword",
+    b"xmmword", b"ymmword", b"zmmword", b"ptr", b"offset", b"short", b"near", b"far", b"rel",
+    // AArch64
+    b"lsl", b"lsr", b"asr", b"ror", b"msl", b"uxtb", b"uxth", b"uxtw", b"uxtx", b"sxtb", b"sxth",
+    b"sxtw", b"sxtx",
+];
+
+/// Locate the mnemonic and the start of the operands of the instruction on a
+/// line, skipping labels and prefixes. Return `None` for directives and empty
+/// lines.
+const fn instruction(template: &[u8], (start, end): Span) -> Option<(Span, usize)> {
+    let mut pos = start;
+    loop {
+        let token = next_token(template, pos, end);
+        if !matches!(token.kind, Kind::Word) {
+            return None;
+        }
+        let after = next_token(template, token.span.1, end);
+        let word = slice(template, token.span);
+        if matches!(after.kind, Kind::Colon) {
+            // label
+            pos = after.span.1;
+        } else if word[0] == b'.' {
+            // directive
+            return None;
+        } else if contains(PREFIXES, word) {
+            pos = token.span.1;
+        } else {
+            return Some((token.span, token.span.1));
+        }
+    }
+}
+
+/// Read the next token on the line, skipping whitespaces. Returns an empty
+/// [`Kind::Other`] token at the end of the line.
+const fn next_token(template: &[u8], pos: usize, end: usize) -> Token {
+    let mut start = pos;
+    while start < end && template[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    if start == end {
+        return Token {
+            span: (end, end),
+            kind: Kind::Other,
+        };
+    }
+    let (close, kind) = match template[start] {
+        b'{' if start + 1 < end && template[start + 1] == b'{' => (b'}', Kind::Transparent),
+        b'{' => (b'}', Kind::Placeholder),
+        b'[' => (b']', Kind::Memory),
+        b'(' => (b')', Kind::Transparent),
+        b':' => {
+            return Token {
+                span: (start, start + 1),
+                kind: Kind::Colon,
+            };
+        }
+        // `asm_block!` emits `$1`, `%rax` and `#1` as two tokens, where the
+        // prefix belongs to the operand after it
+        b'$' | b'%' | b'#' if start + 1 == end || !is_word(template[start + 1]) => {
+            let operand = next_token(template, start + 1, end);
+            if operand.kind.is_operand() {
+                return Token {
+                    span: (start, operand.span.1),
+                    kind: operand.kind,
+                };
+            }
+            return Token {
+                span: (start, start + 1),
+                kind: Kind::Other,
+            };
+        }
+        c if is_word(c) => {
+            let mut pos = start + 1;
+            while pos < end && is_word(template[pos]) {
+                pos += 1;
+            }
+            return Token {
+                span: (start, pos),
+                kind: Kind::Word,
+            };
+        }
+        _ => {
+            return Token {
+                span: (start, start + 1),
+                kind: Kind::Other,
+            };
+        }
+    };
+    let open = template[start];
+    let mut depth = 0;
+    let mut pos = start;
+    while pos < end {
+        if template[pos] == open {
+            depth += 1;
+        } else if template[pos] == close {
+            depth -= 1;
+            if depth == 0 {
+                break;
+            }
+        }
+        pos += 1;
+    }
+    let pos = if pos < end { pos + 1 } else { end };
+    Token {
+        span: (start, pos),
+        kind,
+    }
+}
+
+/// [`asm_block!`] emits no space after a placeholder, so a word can only
+/// directly follow one if it is a suffix like `@PLT`.
+///
+/// [`asm_block!`]: ../macro.asm_block.html
+const fn glued_to_placeholder(template: &[u8], prev: Token, token: Token) -> bool {
+    let c = template[token.span.0];
+    matches!(prev.kind, Kind::Placeholder) && (c.is_ascii_alphanumeric() || c == b'_')
+}
+
+/// Whether the template contains `.macro <name>`.
+const fn defines_macro(template: &[u8], name: &[u8]) -> bool {
+    let mut start = 0;
+    while start < template.len() {
+        let end = line_end(template, start);
+        let directive = next_token(template, start, end);
+        let defined = next_token(template, directive.span.1, end);
+        if eq_ignore_case(slice(template, directive.span), b".macro")
+            && eq_ignore_case(slice(template, defined.span), name)
+        {
+            return true;
+        }
+        start = end + 1;
+    }
+    false
+}
+
+/// A word containing `\` is substituted by the assembler, e.g. inside `.irp`.
+const fn has_macro_argument(word: &[u8]) -> bool {
+    let mut i = 0;
+    while i < word.len() {
+        if word[i] == b'\\' {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn is_numeric(word: &[u8]) -> bool {
+    let mut i = 0;
+    while i < word.len() {
+        if !word[i].is_ascii_digit() {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn is_word(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'$' | b'%' | b'#' | b'@' | b'\\')
+}
+
+const fn is_keyword(word: &[u8]) -> bool {
+    contains(KEYWORDS, word)
+}
+
+const fn line_end(template: &[u8], start: usize) -> usize {
+    let mut pos = start;
+    while pos < template.len() && template[pos] != b'\n' && template[pos] != b';' {
+        pos += 1;
+    }
+    pos
+}
+
+const fn line_break(template: &[u8], start: usize) -> usize {
+    let mut pos = start;
+    while pos < template.len() && template[pos] != b'\n' {
+        pos += 1;
+    }
+    pos
+}
+
+const fn trim(template: &[u8], (mut start, mut end): Span) -> Span {
+    while start < end && template[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    while start < end && template[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    (start, end)
+}
+
+/// Binary search in a list of lowercase words.
+const fn sorted_contains(list: &[&str], word: &[u8]) -> bool {
+    let (mut low, mut high) = (0, list.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        match compare_ignore_case(list[mid].as_bytes(), word) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return true,
+        }
+    }
+    false
+}
+
+const fn byte_contains(list: &[u8], byte: u8) -> bool {
+    let mut i = 0;
+    while i < list.len() {
+        if list[i] == byte {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn str_contains(list: &[&str], word: &[u8]) -> bool {
+    let mut i = 0;
+    while i < list.len() {
+        if eq_ignore_case(list[i].as_bytes(), word) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn contains(list: &[&[u8]], word: &[u8]) -> bool {
+    let mut i = 0;
+    while i < list.len() {
+        if eq_ignore_case(list[i], word) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn eq_ignore_case(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if !a[i].eq_ignore_ascii_case(&b[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn compare_ignore_case(a: &[u8], b: &[u8]) -> Ordering {
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        let (x, y) = (a[i].to_ascii_lowercase(), b[i].to_ascii_lowercase());
+        if x < y {
+            return Ordering::Less;
+        } else if x > y {
+            return Ordering::Greater;
+        }
+        i += 1;
+    }
+    if a.len() < b.len() {
+        Ordering::Less
+    } else if a.len() > b.len() {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+const fn slice(template: &[u8], (start, end): Span) -> &[u8] {
+    template.split_at(end).0.split_at(start).1
+}
+
+/// A fixed-size buffer to compose panic messages in `const` context.
+struct Message {
+    buf: [u8; 256],
+    len: usize,
+}
+
+impl Message {
+    const fn new() -> Self {
+        Message {
+            buf: [0; 256],
+            len: 0,
+        }
+    }
+
+    /// Append bytes, truncating at the capacity.
+    const fn push(mut self, bytes: &[u8]) -> Self {
+        let mut i = 0;
+        while i < bytes.len() && self.len < self.buf.len() {
+            self.buf[self.len] = bytes[i];
+            self.len += 1;
+            i += 1;
+        }
+        self
+    }
+
+    /// Append a number in decimal.
+    const fn push_number(self, mut n: usize) -> Self {
+        let mut digits = [0; 20];
+        let mut len = 0;
+        loop {
+            digits[digits.len() - 1 - len] = b'0' + (n % 10) as u8;
+            len += 1;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        self.push(digits.split_at(digits.len() - len).1)
+    }
+
+    const fn as_str(&self) -> &str {
+        let mut len = self.len;
+        loop {
+            // truncation might split a character
+            match core::str::from_utf8(self.buf.split_at(len).0) {
+                Ok(s) => return s,
+                Err(_) => len -= 1,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block)]
+mod tests {
+    use super::*;
+
+    // AT&T operands and `#` immediates as emitted by `asm_block!`
+    asm_lint!(asm_block!(mov $1, %rax; movq 8(%rsp), %rax; addq ${x}, (%rdi);));
+    #[cfg(not(all(feature = "lint-x86_64", target_arch = "x86_64")))]
+    asm_lint!(asm_block!(add x0, x0, #1; add x0, x1, x2, lsl #2;));
+
+    fn find(template: &str) -> Option<(&str, &str, &str)> {
+        let slice = |(start, end): Span| &template[start..end];
+        missing_separator(template.as_bytes())
+            .map(|(line, first, second)| (slice(line), slice(first), slice(second)))
+    }
+
+    #[test]
+    fn test_missing_separator() {
+        assert_eq!(find(""), None);
+        assert_eq!(find("mov rax , 1 \nadd rax , 2 \n"), None);
+        assert_eq!(
+            find("mov rax , 1 add rax , 2 \n"),
+            Some(("mov rax , 1 add rax , 2", "1", "add"))
+        );
+        assert_eq!(
+            find("nop \ncpuid mov rax , rbx \n"),
+            Some(("cpuid mov rax , rbx", "mov", "rax"))
+        );
+        assert_eq!(
+            find("mov {x}, {y}add {x}, 1 \n"),
+            Some(("mov {x}, {y}add {x}, 1", "{y}", "add"))
+        );
+        assert_eq!(
+            find("push [rax] pop rbx \n"),
+            Some(("push [rax] pop rbx", "[rax]", "pop"))
+        );
+        assert_eq!(
+            find("movl %eax %ebx"),
+            Some(("movl %eax %ebx", "%eax", "%ebx"))
+        );
+    }
+
+    #[test]
+    fn test_no_false_positive() {
+        assert_eq!(find("mov dword ptr [{x}+ 4 ] , ebx \n"), None);
+        assert_eq!(find("lock cmpxchg [rdx] , rcx \nrep movsb \n"), None);
+        assert_eq!(find("2:\nloop:dec rcx \njnz 2b \n"), None);
+        assert_eq!(find(".section .text.hot , \"ax\" \n.byte 1 2 \n"), None);
+        assert_eq!(
+            find(asm_block!(add x0, x1, x2, lsl #2; ldr x0, [sp, 16];)),
+            None
+        );
+        assert_eq!(
+            find(asm_block!(add x0, x0, #1; mov w1, #{x}; movz x2, #0xbeef, lsl #16;)),
+            None
+        );
+        assert_eq!(
+            find(asm_block!(mov $1, %rax; addq ${x}, %rax; leaq -8(%rsp, %rdi, 4), %rax;)),
+            None
+        );
+        assert_eq!(
+            find(asm_block!(movq 8(%rsp), %rax; movl $-1, (%rdi); call {f}@PLT;)),
+            None
+        );
+        assert_eq!(find("vaddps zmm0 {{k1}}, zmm1 , zmm2 \n"), None);
+        assert_eq!(find("mov {x:e}, fs:[0 ] \n"), None);
+    }
+
+    #[test]
+    fn test_prefixed_operand() {
+        assert_eq!(
+            find(asm_block!(mov $1 %rax;)),
+            Some(("mov $ 1 % rax", "$ 1", "% rax"))
+        );
+        assert_eq!(
+            find(asm_block!(add x0, x1 #1;)),
+            Some(("add x0 , x1 # 1", "x1", "# 1"))
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "missing `;` or `,` between `1` and `add` in `mov rax , 1 add rax , 2`"
+    )]
+    fn test_check() {
+        check(asm_block!(mov rax, 1 add rax, 2;), &[]);
+    }
+
+    #[test]
+    fn test_memory_operand() {
+        let find = |template: &'static str| {
+            let slice = |(start, end): Span| &template[start..end];
+            memory_operand(template.as_bytes(), &["lea", "nop"])
+                .map(|(line, operand)| (slice(line), slice(operand)))
+        };
+        assert_eq!(
+            find("lea rax , [rbx + 8 ] \nnop dword ptr [rax] \n.byte 1 \n"),
+            None
+        );
+        assert_eq!(
+            find("mov rax , ({x} + 1 ) \nvaddps zmm0 {{k1}}, zmm1 , zmm2 \n"),
+            None
+        );
+        assert_eq!(
+            find("add rax , qword ptr [{p}+ 8 ] \n"),
+            Some(("add rax , qword ptr [{p}+ 8 ]", "[{p}+ 8 ]"))
+        );
+        assert_eq!(
+            find("movq 8(%rsp), %rax \n"),
+            Some(("movq 8(%rsp), %rax", "(%rsp)"))
+        );
+        assert_eq!(
+            find("2:ldr x0 , [x1] , 8 \n"),
+            Some(("2:ldr x0 , [x1] , 8", "[x1]"))
+        );
+    }
+
+    #[test]
+    fn test_find_operand() {
+        let find = |template: &'static str| {
+            let slice = |(start, end): Span| &template[start..end];
+            find_operand(template.as_bytes(), &["rsp", "%rsp", "sp"])
+                .map(|(line, word)| (slice(line), slice(word)))
+        };
+        assert_eq!(find("mov {sp}, rbp \n.quad sp \nmov rax , rspx \n"), None);
+        assert_eq!(find("sub rsp , 8 \n"), Some(("sub rsp , 8", "rsp")));
+        assert_eq!(
+            find("mov rax , [rsp + 8 ] \n"),
+            Some(("mov rax , [rsp + 8 ]", "rsp"))
+        );
+        assert_eq!(
+            find("movq 8(%rsp), %rax \n"),
+            Some(("movq 8(%rsp), %rax", "%rsp"))
+        );
+        assert_eq!(
+            find("ldp x0 , x1 , [SP] \n"),
+            Some(("ldp x0 , x1 , [SP]", "SP"))
+        );
+    }
+
+    #[test]
+    fn test_duplicate_label() {
+        let find = |fragments: &[&'static str]| {
+            duplicate_label(fragments).map(|(first, label, second, found)| {
+                (
+                    first,
+                    &fragments[first][label.0..label.1],
+                    second,
+                    &fragments[second][found.0..found.1],
+                )
+            })
+        };
+        assert_eq!(find(&[]), None);
+        assert_eq!(find(&["2:\njmp 2b \n", "2:\njmp 2b \n"]), None);
+        assert_eq!(
+            find(&["start:\nmov rax , [rip + 8 ] \n", "Start:end:nop \n"]),
+            None
+        );
+        assert_eq!(
+            find(&[".ascii \"a:b\" \nmov rax , fs:[0 ] \n", "a:b:nop \n"]),
+            None
+        );
+        assert_eq!(
+            find(&[
+                "nop \n",
+                "head:\nloop:dec rcx \njnz loop \n",
+                "next:loop:nop \n"
+            ]),
+            Some((1, "loop", 2, "loop"))
+        );
+        assert_eq!(find(&["x:\n.balign 8 \nx:\n"]), Some((0, "x", 0, "x")));
+        assert_eq!(find(&[".macro m \n\\name:\n.endm \n", "\\name:\n"]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "label `done` is defined by both `a!()` and `c!()`")]
+    fn test_check_labels() {
+        check_labels(
+            &["done:\n", "2:\n", "2:\ndone:\n"],
+            &["a!()", "b!()", "c!()"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_reserved_label() {
+        let find = |fragments: &[&'static str]| {
+            reserved_label(fragments)
+                .map(|(fragment, label)| (fragment, &fragments[fragment][label.0..label.1]))
+        };
+        assert_eq!(find(&[asm_block!(41999: 42120: 4202: 420250: 1:)]), None);
+        // the definitions of the macros
+        assert_eq!(
+            find(&[
+                "42000:\n",
+                crate::pad_to!(4, { nop; }),
+                crate::asm_veneer!(aarch64, v, f),
+            ]),
+            None
+        );
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(
+            find(&[crate::asm_switch!(ladder, {x}, [0 => {}, default => {}])]),
+            None
+        );
+        assert_eq!(find(&["nop \n", asm_block!(42000: nop;)]), Some((1, "42000")));
+        assert_eq!(find(&[asm_block!(x: 42119 :)]), Some((0, "42119")));
+        assert_eq!(find(&[asm_block!(4202417:)]), Some((0, "4202417")));
+    }
+
+    #[test]
+    #[should_panic(expected = "label `42090` defined by `b!()` is reserved by the macros of `asm_block`")]
+    fn test_check_reserved_label() {
+        check_labels(&["42090:\n", "42090 :\n"], &["a!()", "b!()"], &[]);
+    }
+
+    #[test]
+    fn test_undefined_label() {
+        let find = |fragments: &[&'static str], allow: &[&str]| {
+            undefined_label(fragments, allow).map(|(fragment, reference)| {
+                (fragment, &fragments[fragment][reference.0..reference.1])
+            })
+        };
+        assert_eq!(
+            find(&["2:\ndec rcx \njnz 2b \n", "jmp 3f \n", "3:\n"], &[]),
+            None
+        );
+        assert_eq!(
+            find(&["2:jmp 2b \n.long 42073f - 2b \n42073:\n"], &[]),
+            None
+        );
+        assert_eq!(
+            find(
+                &[".Lhead:\nmov rax , [rip + .Lconst ] \n", ".Lconst:\n"],
+                &[]
+            ),
+            None
+        );
+        assert_eq!(
+            find(
+                &[".ascii \"1f\" \nmov {x:e}, 0x2f \n.byte 0b1 , $1f \n"],
+                &[]
+            ),
+            None
+        );
+        assert_eq!(find(&["jmp 2b \n", "2:\n"], &[]), Some((0, "2b")));
+        assert_eq!(find(&["2:\n", "jz 3f \njmp 2b \n", "3:\n"], &[]), None);
+        assert_eq!(find(&["nop \n", "3:jmp 3f \n"], &[]), Some((1, "3f")));
+        assert_eq!(
+            find(&["call .Lhelper \njmp .Lshared \n"], &[".Lhelper"]),
+            Some((0, ".Lshared"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "label `2` referenced as `2f` by `b!()` is not defined after it")]
+    fn test_check_undefined_label() {
+        check_labels(&["2:\n", "jmp 2f \n"], &["a!()", "b!()"], &[]);
+    }
+
+    #[test]
+    fn test_undeclared_placeholder() {
+        let find = |fragments: &[&'static str], operands| {
+            undeclared_placeholder(fragments, operands).map(|(fragment, placeholder, name)| {
+                let template = fragments[fragment];
+                (
+                    fragment,
+                    &template[placeholder.0..placeholder.1],
+                    &template[name.0..name.1],
+                )
+            })
+        };
+        let operands = "x = inout(reg) x, p = in(reg) & y, out(\"rax\") _";
+        assert_eq!(
+            find(&["mov {x}, [{p}+ 8 ] \n", "add {x:e}, 1 \n"], operands),
+            None
+        );
+        assert_eq!(find(&["mov {0}, {} \n.byte 1 {{y}} \n"], ""), None);
+        assert_eq!(
+            find(&["nop \n", "mov {y:e}, {x:e}\n"], operands),
+            Some((1, "{y:e}", "y"))
+        );
+        assert_eq!(find(&["push {rax}\n"], "rax = in(\"rcx\") 1"), None);
+        assert_eq!(
+            find(&["mov {x}, {y}\n"], "x = in(reg) a == b, y => c"),
+            Some((0, "{y}", "y"))
+        );
+        assert_eq!(
+            find(&["mov {x}, {y}\n"], "a = in(reg) f(x = 1, \"y = 2\"),"),
+            Some((0, "{x}", "x"))
+        );
+        assert_eq!(find(&["mov {p"], ""), Some((0, "{p", "p")));
+    }
+
+    #[test]
+    fn test_unused_operand() {
+        let find = |fragments: &[&str], operands: &'static str| {
+            unused_operand(fragments, operands).map(|(start, end)| &operands[start..end])
+        };
+        assert_eq!(
+            find(&["mov {x}, {p}\n"], "x = out(reg) x, p = in(reg) &y,"),
+            None
+        );
+        assert_eq!(
+            find(
+                &["call {f}\n"],
+                "f = sym g, inout(\"rdi\") x => _, options(nostack)"
+            ),
+            None
+        );
+        assert_eq!(
+            find(
+                &["mov {x}, 1 \n", ".long {{n}} \n"],
+                "x = out(reg) x, n = const 4"
+            ),
+            Some("n")
+        );
+        assert_eq!(find(&[], "first = const 1"), Some("first"));
+    }
+
+    #[test]
+    #[should_panic(expected = "`{y}` is used by `b!({y})`, but no operand is named `y`")]
+    fn test_check_operands() {
+        check_operands(
+            &["mov {x}, 1 \n", "inc {y}\n"],
+            &["a!({x})", "b!({y})"],
+            "x = out(reg) _",
+        );
+    }
+
+    #[test]
+    fn test_unknown_modifier() {
+        let find = |template: &'static str, modifiers: &[u8]| {
+            let slice = |(start, end): Span| &template[start..end];
+            unknown_modifier(template.as_bytes(), modifiers)
+                .map(|(line, placeholder)| (slice(line), slice(placeholder)))
+        };
+        assert_eq!(
+            find(
+                "mov {x:e}, [{p}+ 8 ] 
+shl {x:r}, {c:l}
+",
+                b"ehlrxyz"
+            ),
+            None
+        );
+        assert_eq!(
+            find(
+                "vaddps {v:y}, {v:y}, [{p}] 
+.byte 1 {{2}}
+",
+                b"xyz"
+            ),
+            None
+        );
+        assert_eq!(
+            find(
+                "ldr {x}, [{p}] 
+add {x:w}, {y:e}, 1 
+",
+                b"bdhqsvwx"
+            ),
+            Some(("add {x:w}, {y:e}, 1", "{y:e}"))
+        );
+        assert_eq!(
+            find(
+                "mov {x:ee}, 1 
+",
+                b"e"
+            ),
+            Some(("mov {x:ee}, 1", "{x:ee}"))
+        );
+        assert_eq!(
+            find(
+                "add {0:x}, {1:}
+",
+                b"x"
+            ),
+            Some(("add {0:x}, {1:}", "{1:}"))
+        );
+        assert_eq!(
+            find(
+                "li {x}, 1 ; mv {y:w}, {x}
+",
+                b""
+            ),
+            Some(("mv {y:w}, {x}", "{y:w}"))
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    #[should_panic(
+        expected = "unknown modifier in `{x:w}` for the target architecture in `mov {x:w}, 1`"
+    )]
+    fn test_check_modifier() {
+        check(asm_block!(mov {x:w}, 1;), &[]);
+    }
+
+    #[test]
+    fn test_misread_escape() {
+        let find = |template: &'static str| {
+            let slice = |(start, end): Span| &template[start..end];
+            misread_escape(template.as_bytes()).map(|(line, escape)| (slice(line), slice(escape)))
+        };
+        assert_eq!(find(r#".ascii "\t\"\n\\\0;\x41" "#), None);
+        assert_eq!(find(r#".ascii "\x41\x42" , "\0" , "é" "#), None);
+        assert_eq!(find("mov \\x0123 , 1 \n"), None);
+        assert_eq!(
+            find(r#".ascii "it\'s" "#),
+            Some((r#".ascii "it\'s""#, r"\'"))
+        );
+        assert_eq!(
+            find(".byte 1 \n.ascii \"\\u{e9}\" \n"),
+            Some((".ascii \"\\u{e9}\"", r"\u{e9}"))
+        );
+        assert_eq!(
+            find(r#".ascii "\0123" "#),
+            Some((r#".ascii "\0123""#, r"\01"))
+        );
+        assert_eq!(
+            find(r#".ascii "\x4142" "#),
+            Some((r#".ascii "\x4142""#, r"\x414"))
+        );
+    }
+
+    #[test]
+    fn test_unknown_mnemonic() {
+        const TABLE: &[&str] = &["add", "mov", "pop", "push"];
+        let find = |template: &'static str, allow: &[&str]| {
+            let slice = |(start, end): Span| &template[start..end];
+            unknown_mnemonic(template.as_bytes(), TABLE, allow)
+                .map(|(line, mnemonic)| (slice(line), slice(mnemonic)))
+        };
+        assert_eq!(
+            find("mov rax , 1 \nADD rax , 2 \nloop:pop rbx \n", &[]),
+            None
+        );
+        assert_eq!(
+            find("push rax \nmvo rax , 1 \n", &[]),
+            Some(("mvo rax , 1", "mvo"))
+        );
+        assert_eq!(find("mad rax , 1 \n", &["mad"]), None);
+        assert_eq!(
+            find(
+                ".macro mad x , y \nadd \\x, \\y\n.endm \nmad rax , 1 \n",
+                &[]
+            ),
+            None
+        );
+        assert_eq!(
+            find(".irp op , add , mov \n\\op rax , 1 \n.endr \n", &[]),
+            None
+        );
+        assert_eq!(find(".rept 3 \nnop \n.endr \n", &[]), Some(("nop", "nop")));
+    }
+
+    #[test]
+    fn test_operand_count() {
+        const TABLE: &[(&str, usize, usize)] = &[("mov", 2, 2), ("nop", 0, 1), ("ret", 0, 1)];
+        let find = |template: &'static str| {
+            let slice = |(start, end): Span| &template[start..end];
+            wrong_operand_count(template.as_bytes(), TABLE)
+                .map(|(line, mnemonic, count)| (slice(line), slice(mnemonic), count))
+        };
+        assert_eq!(
+            find("mov [rax + 8 ] , {x}\nnop \nret 8 \npush rax \n"),
+            None
+        );
+        assert_eq!(find("mov {{v0.4s , v1.4s}}, [x0 , 16 ] \n"), None);
+        assert_eq!(find("mov rax \n"), Some(("mov rax", "mov", 1)));
+        assert_eq!(
+            find("MOV rax , rbx , rcx \n"),
+            Some(("MOV rax , rbx , rcx", "MOV", 3))
+        );
+        assert_eq!(find("nop 1 , 2 \n"), Some(("nop 1 , 2", "nop", 2)));
+        assert_eq!(find("loop:mov \n"), Some(("loop:mov", "mov", 0)));
+    }
+
+    #[test]
+    fn test_modifies_flags() {
+        const TABLE: &[&str] = &["add", "cmp", "inc"];
+        let find = |template: &'static str| {
+            let slice = |(start, end): Span| &template[start..end];
+            modifies_flags(template.as_bytes(), TABLE)
+                .map(|(line, mnemonic)| (slice(line), slice(mnemonic)))
+        };
+        assert_eq!(
+            find("mov rax , 1 \nlea rax , [rax + 1 ] \n.byte 0x48 \n"),
+            None
+        );
+        assert_eq!(
+            find("push rax \nloop:INC rax \n"),
+            Some(("loop:INC rax", "INC"))
+        );
+        assert_eq!(
+            find("lock add [rdi] , 1 \n"),
+            Some(("lock add [rdi] , 1", "add"))
+        );
+    }
+
+    #[test]
+    fn test_find_listed() {
+        let find = |template: &'static str, list: &[&str], listed: bool| {
+            let slice = |(start, end): Span| &template[start..end];
+            if listed {
+                find_listed(template.as_bytes(), list)
+            } else {
+                find_unlisted(template.as_bytes(), list)
+            }
+            .map(|(line, mnemonic)| (slice(line), slice(mnemonic)))
+        };
+        let template = "mov rax , 1 \n.byte 0x0f , 0x31 \nlock ADD [rdi] , rax \n";
+        assert_eq!(find(template, &["rdtsc", "cli"], true), None);
+        assert_eq!(
+            find(template, &["cli", "add"], true),
+            Some(("lock ADD [rdi] , rax", "ADD"))
+        );
+        assert_eq!(find(template, &["add", "mov"], false), None);
+        assert_eq!(
+            find(template, &["add"], false),
+            Some(("mov rax , 1", "mov"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`cli` is denied by the policy in `cli`")]
+    fn test_check_policy_denied() {
+        check_policy(
+            &Policy::new().deny_mnemonics(&["sti", "cli"]),
+            "mov rax , 1 \ncli \n",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`shl` is not allowed by the policy in `shl {x}, 2`")]
+    fn test_check_policy_allowed() {
+        let policy = Policy::new().allow_only(&["mov", "add"]);
+        check_policy(&policy, "mov {x}, 1 \nadd {x}, {x}\n");
+        check_policy(&policy, "mov {x}, 1 \nshl {x}, 2 \n");
+    }
+
+    #[cfg(all(feature = "lint-x86_64", target_arch = "x86_64"))]
+    #[test]
+    #[should_panic(expected = "`fld` is an x87 instruction in `fld qword ptr [rdi]`, \
+                               but the fragments are asserted to have no x87 instructions")]
+    fn test_check_policy_group() {
+        let policy = Policy::new().deny(Group::Privileged).deny(Group::X87);
+        check_policy(&policy, "movsd xmm0 , qword ptr [rdi] \nrdtsc \n");
+        check_policy(&policy, "fld qword ptr [rdi] \n");
+    }
+
+    #[test]
+    fn test_code_size() {
+        let size = |min, max| Size { min, max };
+        assert_eq!(code_size(""), size(0, Some(0)));
+        assert_eq!(
+            code_size(".byte 1 , 2 \n.short {x}\n2:.long 2f - 1b \n.quad 0 \n"),
+            size(16, Some(16))
+        );
+        assert_eq!(
+            code_size(".ascii \"a\\n\\x41\\101\" \n.asciz \"\" , \"b\" \n"),
+            size(7, Some(7))
+        );
+        assert_eq!(
+            code_size(".globl f \n.cfi_startproc \n.skip 0x10 \n.balign 8 \n.p2align 2 \n"),
+            size(16, Some(26))
+        );
+        assert_eq!(
+            code_size(".pushsection .rodata \n.quad 1 \n.popsection \n.byte 1 \n"),
+            size(1, Some(1))
+        );
+        assert_eq!(
+            code_size(".macro m \n.byte 1 \n.endm \n.byte 2 \n"),
+            size(1, Some(1))
+        );
+        assert_eq!(code_size(".byte 1 \n.skip {n}\n"), size(1, None));
+        assert_eq!(
+            code_size(
+                ".byte 1 \n/* pad_to! */\n42090:\n.skip {n}\n\
+                       .fill (8 - (. - 42090b)) / 4, 4, 0 \n.skip 8 - (. - 42090b)\n"
+            ),
+            size(9, Some(9))
+        );
+        // the label alone is ordinary code
+        assert_eq!(
+            code_size(".byte 1 \n42090:\n.byte 2 \n.fill (8 - (. - 42090b)) / 4, 4, 0 \n"),
+            size(2, None)
+        );
+        assert_eq!(
+            code_size(".rept 4 \n.byte 1 \n.endr \n.byte 2 \n"),
+            size(1, None)
+        );
+        assert_eq!(size(2, Some(2)).exact(), Some(2));
+        assert_eq!(size(2, Some(5)).exact(), None);
+        assert!(size(2, Some(5)).fits(5));
+        assert!(!size(2, None).fits(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "the size of `.skip {n}` is not known, \
+                               but the fragments are asserted to fit in 8 bytes")]
+    fn test_check_size() {
+        check_size(8, ".quad 0 \n");
+        check_size(8, ".byte 0 \n.skip {n}\n");
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_x86_64_code_size() {
+        let size = |template| {
+            let size = code_size(template);
+            (size.min, size.max)
+        };
+        if ARCH.is_some() {
+            assert_eq!(size("rep movsb \nret \n2:jz 2b \n"), (5, Some(9)));
+            assert_eq!(size("call {f}\n.byte 0x90 \n"), (3, Some(6)));
+        } else {
+            assert_eq!(size("rep movsb \nret \n2:jz 2b \n"), (3, Some(45)));
+        }
+        assert_eq!(size(".word 1 \nmov rax , rbx \n"), (3, Some(17)));
+    }
+
+    #[test]
+    fn test_push_number() {
+        assert_eq!(Message::new().push_number(0).as_str(), "0");
+        assert_eq!(
+            Message::new().push(b"n=").push_number(1024).as_str(),
+            "n=1024"
+        );
+    }
+
+    #[cfg(feature = "lint-x86_64")]
+    #[test]
+    fn test_x86_64_mnemonics() {
+        let find =
+            |template: &str| unknown_mnemonic(template.as_bytes(), x86_64::ARCH.mnemonics, &[]);
+        assert!(
+            find("lock cmpxchg16b [rdi] \nrep movsb \njz 2f \nvpdpbusd zmm0 , zmm1 , zmm2 \n")
+                .is_none()
+        );
+        assert!(find("movq %rax, %rbx \npushfq \nsyscall \nud2 \n").is_none());
+        assert!(find("mvo rax , rbx \n").is_some());
+        assert!(find("xror rax , rax \n").is_some());
+        let find = |template: &str| modifies_flags(template.as_bytes(), x86_64::ARCH.flags);
+        assert!(find("mov rax , rbx \nlea rax , [rax + 1 ] \nshlx rax , rbx , rcx \n").is_none());
+        assert!(find("xorl %eax, %eax \n").is_some());
+        assert!(find("rep cmpsb \n").is_some());
+        let find = |template: &str| find_mnemonic(template.as_bytes(), x86_64::ARCH.branches);
+        assert!(
+            find("test rax , rax \ncmovnz rax , rbx \nsetc al \nlea rax , [rip + 2f] \n").is_none()
+        );
+        assert!(find("2:jz 2b \n").is_some());
+        assert!(find("notrack jmp rax \n").is_some());
+        assert!(find("callq *%rax \n").is_some());
+        let find = |template: &str| find_mnemonic(template.as_bytes(), x86_64::ARCH.memory);
+        assert!(find("movsd xmm0 , xmm1 \ncmpsd xmm0 , xmm1 , 1 \n").is_none());
+        assert!(find("rep movsb \n").is_some());
+        let find = |template: &str| find_mnemonic(template.as_bytes(), x86_64::ARCH.stack);
+        assert!(find("popcnt rax , rbx \nmov rax , rbx \n").is_none());
+        assert!(find("pushfq \n").is_some());
+        let find = |template: &str| find_mnemonic(template.as_bytes(), x86_64::ARCH.privileged);
+        assert!(find("rdtsc \nsyscall \ncpuid \ninc rax \n").is_none());
+        assert!(find("rep outsb \n").is_some());
+        assert!(find("wrmsr \n").is_some());
+        let find = |template: &str| find_mnemonic(template.as_bytes(), x86_64::ARCH.x87);
+        assert!(find("fxsave [rdi] \nmovss xmm0 , xmm1 \n").is_none());
+        assert!(find("fstpt (%rdi) \n").is_some());
+    }
+
+    #[cfg(any(feature = "lint-x86_64", feature = "lint-aarch64"))]
+    fn assert_tables(arch: Arch) {
+        assert!(arch.mnemonics.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.operands.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(arch.flags.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.branches.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.memory.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.addresses.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.stack.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.privileged.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.x87.windows(2).all(|w| w[0] < w[1]));
+        assert!(arch.sizes.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(arch.relative.windows(2).all(|w| w[0].0 < w[1].0));
+        let tables = [
+            arch.flags,
+            arch.branches,
+            arch.memory,
+            arch.addresses,
+            arch.stack,
+            arch.privileged,
+            arch.x87,
+        ];
+        for &mnemonic in tables.iter().flat_map(|table| table.iter()) {
+            assert!(
+                sorted_contains(arch.mnemonics, mnemonic.as_bytes()),
+                "{}",
+                mnemonic
+            );
+        }
+        let sizes = [arch.operands, arch.sizes, arch.relative];
+        for &(mnemonic, min, max) in sizes.iter().flat_map(|table| table.iter()) {
+            assert!(
+                sorted_contains(arch.mnemonics, mnemonic.as_bytes()),
+                "{}",
+                mnemonic
+            );
+            assert!(min <= max, "{}", mnemonic);
+        }
+    }
+
+    #[cfg(feature = "lint-x86_64")]
+    #[test]
+    fn test_x86_64_operands() {
+        assert_tables(x86_64::ARCH);
+        let find = |template: &str| wrong_operand_count(template.as_bytes(), x86_64::ARCH.operands);
+        assert!(find("mov rax , [rbx + 8 ] \nimul rax , rbx , 3 \nshl rax \nret \n").is_none());
+        assert!(find("lock xadd [rdi] , rax \nnop dword ptr [rax] \n").is_none());
+        assert!(find("mov rax \n").is_some());
+        assert!(find("push rax , rbx \n").is_some());
+    }
+
+    #[cfg(feature = "lint-aarch64")]
+    #[test]
+    fn test_aarch64_mnemonics() {
+        let find =
+            |template: &str| unknown_mnemonic(template.as_bytes(), aarch64::ARCH.mnemonics, &[]);
+        assert!(
+            find("stp x29 , x30 , [sp , - 16 ] ! \nb.ne 2f \nbeq 3b \ncbz x0 , 4f \n").is_none()
+        );
+        assert!(find("ld1 {{v0.4s}}, [x0] \nfmla v0.4s , v1.4s , v2.4s \n").is_none());
+        assert!(find("whilelo p0.s , x0 , x1 \nptrue p1.b \nsmstart \n").is_none());
+        assert!(find("lrd x0 , [x1] \n").is_some());
+        assert!(find("b.nq 2f \n").is_some());
+        let find = |template: &str| modifies_flags(template.as_bytes(), aarch64::ARCH.flags);
+        assert!(find("add x0 , x1 , x2 \ncsel x0 , x1 , x2 , eq \nb.ne 2f \n").is_none());
+        assert!(find("subs x0 , x0 , 1 \n").is_some());
+        assert!(find("whilelo p0.s , x0 , x1 \n").is_some());
+        let find = |template: &str| find_mnemonic(template.as_bytes(), aarch64::ARCH.branches);
+        assert!(
+            find("cmp x0 , x1 \ncsel x0 , x1 , x2 , eq \ntbl v0.16b , {{v1.16b}}, v2.16b \n")
+                .is_none()
+        );
+        assert!(find("b.ne 2f \n").is_some());
+        assert!(find("cbz x0 , 2f \n").is_some());
+        assert!(find("blr x16 \n").is_some());
+        let find = |template: &str| find_mnemonic(template.as_bytes(), aarch64::ARCH.privileged);
+        assert!(find("mrs x0 , tpidr_el0 \nsvc 0 \n").is_none());
+        assert!(find("tlbi vmalle1 \n").is_some());
+        assert!(find("eret \n").is_some());
+    }
+
+    #[cfg(feature = "lint-aarch64")]
+    #[test]
+    fn test_aarch64_operands() {
+        assert_tables(aarch64::ARCH);
+        let find =
+            |template: &str| wrong_operand_count(template.as_bytes(), aarch64::ARCH.operands);
+        assert!(find("ldr x0 , [x1] , 8 \nstp x29 , x30 , [sp , - 16 ] ! \n").is_none());
+        assert!(find("add x0 , x1 , x2 , lsl 2 \nmov z0.d , p0/m , z1.d \nret \n").is_none());
+        assert!(find("stp x0 , [sp] \n").is_some());
+        assert!(find("add x0 , x1 \n").is_some());
+    }
+}
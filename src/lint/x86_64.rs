@@ -0,0 +1,608 @@
+//! Instruction tables of x86 and x86-64.
+//!
+//! The mnemonics are the ones accepted by the LLVM assembler, in both Intel and
+//! AT&T syntax. The list is extracted from the mnemonic table of the assembly
+//! matcher of LLVM 14, together with the aliases it accepts.
+
+use super::Arch;
+
+pub(super) const ARCH: Arch = Arch {
+    mnemonics: MNEMONICS,
+    operands: OPERANDS,
+    flags: FLAGS,
+    branches: BRANCHES,
+    memory: MEMORY,
+    addresses: ADDRESSES,
+    stack: STACK,
+    stack_pointers: &["rsp", "esp", "sp", "spl", "%rsp", "%esp", "%sp", "%spl"],
+    privileged: PRIVILEGED,
+    x87: X87,
+    sizes: SIZES,
+    relative: RELATIVE,
+};
+
+/// Operand counts of common instructions.
+const OPERANDS: &[(&str, usize, usize)] = &[
+    ("adc", 2, 2),
+    ("adcx", 2, 2),
+    ("add", 2, 2),
+    ("adox", 2, 2),
+    ("and", 2, 2),
+    ("andn", 3, 3),
+    ("bsf", 2, 2),
+    ("bsr", 2, 2),
+    ("bswap", 1, 1),
+    ("bt", 2, 2),
+    ("btc", 2, 2),
+    ("btr", 2, 2),
+    ("bts", 2, 2),
+    ("call", 1, 1),
+    ("cdq", 0, 0),
+    ("cmp", 2, 2),
+    ("cmpxchg", 2, 2),
+    ("cpuid", 0, 0),
+    ("cqo", 0, 0),
+    ("dec", 1, 1),
+    ("div", 1, 1),
+    ("hlt", 0, 0),
+    ("idiv", 1, 1),
+    ("imul", 1, 3),
+    ("inc", 1, 1),
+    ("int", 1, 1),
+    ("int3", 0, 0),
+    ("jmp", 1, 1),
+    ("lea", 2, 2),
+    ("leave", 0, 0),
+    ("lfence", 0, 0),
+    ("lzcnt", 2, 2),
+    ("mfence", 0, 0),
+    ("mov", 2, 2),
+    ("movabs", 2, 2),
+    ("movsx", 2, 2),
+    ("movsxd", 2, 2),
+    ("movzx", 2, 2),
+    ("mul", 1, 1),
+    ("mulx", 3, 3),
+    ("neg", 1, 1),
+    ("nop", 0, 1),
+    ("not", 1, 1),
+    ("or", 2, 2),
+    ("pause", 0, 0),
+    ("pop", 1, 1),
+    ("popcnt", 2, 2),
+    ("push", 1, 1),
+    ("rcl", 1, 2),
+    ("rcr", 1, 2),
+    ("rdtsc", 0, 0),
+    ("ret", 0, 1),
+    ("rol", 1, 2),
+    ("ror", 1, 2),
+    ("rorx", 3, 3),
+    ("sal", 1, 2),
+    ("sar", 1, 2),
+    ("sarx", 3, 3),
+    ("sbb", 2, 2),
+    ("sfence", 0, 0),
+    ("shl", 1, 2),
+    ("shld", 2, 3),
+    ("shlx", 3, 3),
+    ("shr", 1, 2),
+    ("shrd", 2, 3),
+    ("shrx", 3, 3),
+    ("sub", 2, 2),
+    ("syscall", 0, 0),
+    ("test", 2, 2),
+    ("tzcnt", 2, 2),
+    ("ud2", 0, 0),
+    ("xadd", 2, 2),
+    ("xchg", 2, 2),
+    ("xor", 2, 2),
+];
+
+/// Mnemonics writing to `EFLAGS`, including the direction and interrupt flags,
+/// with their AT&T suffixes. `cmpsd` is left out, since it is also an SSE
+/// comparison which does not touch `EFLAGS`.
+#[rustfmt::skip]
+const FLAGS: &[&str] = &[
+    "aaa", "aad", "aam", "aas", "adc", "adcb", "adcl", "adcq", "adcw", "adcx", "adcxl", "adcxq",
+    "add", "addb", "addl", "addq", "addw", "adox", "adoxl", "adoxq", "and", "andb", "andl", "andn",
+    "andnl", "andnq", "andq", "andw", "bextr", "bextrl", "bextrq", "blcfill", "blcfilll",
+    "blcfillq", "blci", "blcic", "blcicl", "blcicq", "blcil", "blciq", "blcmsk", "blcmskl",
+    "blcmskq", "blcs", "blcsl", "blcsq", "blsfill", "blsfilll", "blsfillq", "blsi", "blsic",
+    "blsicl", "blsicq", "blsil", "blsiq", "blsmsk", "blsmskl", "blsmskq", "blsr", "blsrl", "blsrq",
+    "bsf", "bsfl", "bsfq", "bsfw", "bsr", "bsrl", "bsrq", "bsrw", "bt", "btc", "btcl", "btcq",
+    "btcw", "btl", "btq", "btr", "btrl", "btrq", "btrw", "bts", "btsl", "btsq", "btsw", "btw",
+    "bzhi", "bzhil", "bzhiq", "clac", "clc", "cld", "cli", "cmc", "cmp", "cmpb", "cmpl", "cmpq",
+    "cmpsb", "cmpsq", "cmpsw", "cmpw", "cmpxchg", "cmpxchg16b", "cmpxchg8b", "cmpxchgb", "cmpxchgl",
+    "cmpxchgq", "cmpxchgw", "comisd", "comiss", "daa", "das", "dec", "decb", "decl", "decq", "decw",
+    "div", "divb", "divl", "divq", "divw", "fcomi", "fcomip", "fucomi", "fucomip", "idiv", "idivb",
+    "idivl", "idivq", "idivw", "imul", "imulb", "imull", "imulq", "imulw", "inc", "incb", "incl",
+    "incq", "incw", "kortestb", "kortestd", "kortestq", "kortestw", "ktestb", "ktestd", "ktestq",
+    "ktestw", "lar", "larl", "larq", "larw", "lsl", "lsll", "lslq", "lslw", "lzcnt", "lzcntl",
+    "lzcntq", "lzcntw", "mul", "mulb", "mull", "mulq", "mulw", "neg", "negb", "negl", "negq",
+    "negw", "or", "orb", "orl", "orq", "orw", "popcnt", "popcntl", "popcntq", "popcntw", "popf",
+    "popfd", "popfl", "popfq", "popfw", "ptest", "rcl", "rclb", "rcll", "rclq", "rclw", "rcr",
+    "rcrb", "rcrl", "rcrq", "rcrw", "rdrand", "rdrandl", "rdrandq", "rdrandw", "rdseed", "rdseedl",
+    "rdseedq", "rdseedw", "rol", "rolb", "roll", "rolq", "rolw", "ror", "rorb", "rorl", "rorq",
+    "rorw", "sahf", "sal", "salb", "sall", "salq", "salw", "sar", "sarb", "sarl", "sarq", "sarw",
+    "sbb", "sbbb", "sbbl", "sbbq", "sbbw", "scasb", "scasd", "scasq", "scasw", "shl", "shlb",
+    "shld", "shldl", "shldq", "shldw", "shll", "shlq", "shlw", "shr", "shrb", "shrd", "shrdl",
+    "shrdq", "shrdw", "shrl", "shrq", "shrw", "stac", "stc", "std", "sti", "sub", "subb", "subl",
+    "subq", "subw", "test", "testb", "testl", "testq", "testw", "tzcnt", "tzcntl", "tzcntq",
+    "tzcntw", "ucomisd", "ucomiss", "vcomisd", "vcomiss", "verr", "verw", "vptest", "vtestpd",
+    "vtestps", "vucomisd", "vucomiss", "xadd", "xaddb", "xaddl", "xaddq", "xaddw", "xor", "xorb",
+    "xorl", "xorq", "xorw", "xtest",
+];
+
+/// Mnemonics transferring control, i.e. jumps, calls, returns, loops and
+/// transitions to and from other privilege levels, including the transaction
+/// instructions aborting to another address.
+#[rustfmt::skip]
+const BRANCHES: &[&str] = &[
+    "call", "calll", "callq", "callw", "int", "int3", "into", "iret", "iretd", "iretl", "iretq",
+    "iretw", "ja", "jae", "jb", "jbe", "jc", "jcxz", "je", "jecxz", "jg", "jge", "jl", "jle", "jmp",
+    "jmpl", "jmpq", "jmpw", "jna", "jnae", "jnb", "jnbe", "jnc", "jne", "jng", "jnge", "jnl",
+    "jnle", "jno", "jnp", "jns", "jnz", "jo", "jp", "jpe", "jpo", "jrcxz", "js", "jz", "lcall",
+    "lcalll", "lcallq", "lcallw", "ljmp", "ljmpl", "ljmpq", "ljmpw", "loop", "loope", "loopne",
+    "lretl", "lretq", "lretw", "ret", "retf", "retfq", "retl", "retq", "retw", "syscall",
+    "sysenter", "sysexit", "sysexitl", "sysexitq", "sysret", "sysretl", "sysretq", "uiret",
+    "vmcall", "vmlaunch", "vmmcall", "vmresume", "vmrun", "xabort", "xbegin",
+];
+
+/// Mnemonics accessing memory without a memory operand, i.e. the string
+/// instructions, apart from `cmpsd` and `movsd` which are also SSE
+/// instructions on registers, and `xlat`. The stack is listed separately.
+#[rustfmt::skip]
+const MEMORY: &[&str] = &[
+    "cmpsb", "cmpsq", "cmpsw", "ins", "insb", "insd", "insl", "insw", "lods", "lodsb", "lodsd",
+    "lodsl", "lodsq", "lodsw", "movs", "movsb", "movsl", "movsq", "movsw", "outs", "outsb", "outsd",
+    "outsl", "outsw", "scas", "scasb", "scasd", "scasl", "scasq", "scasw", "stos", "stosb", "stosd",
+    "stosl", "stosq", "stosw", "xlat", "xlatb",
+];
+
+/// Mnemonics whose memory operand is an address which is not accessed.
+const ADDRESSES: &[&str] = &["lea", "leal", "leaq", "leaw", "nop", "nopl", "nopq", "nopw"];
+
+/// Mnemonics pushing to or popping from the stack, including calls, returns
+/// and software interrupts.
+#[rustfmt::skip]
+const STACK: &[&str] = &[
+    "call", "calll", "callq", "callw", "enter", "int", "int3", "into", "iret", "iretd", "iretl",
+    "iretq", "iretw", "lcall", "lcalll", "lcallq", "lcallw", "leave", "lretl", "lretq", "lretw",
+    "pop", "popal", "popaw", "popf", "popfd", "popfl", "popfq", "popfw", "popl", "popq", "popw",
+    "push", "pushal", "pushaw", "pushf", "pushfd", "pushfl", "pushfq", "pushfw", "pushl", "pushq",
+    "pushw", "ret", "retf", "retfq", "retl", "retq", "retw",
+];
+
+/// Mnemonics which fault outside of ring 0 by default, including the port
+/// I/O instructions, which depend on the I/O privilege level, and the
+/// instructions of virtualization.
+#[rustfmt::skip]
+const PRIVILEGED: &[&str] = &[
+    "clac", "clgi", "cli", "clts", "encls", "hlt", "hreset", "in", "inb", "inl", "ins", "insb",
+    "insd", "insl", "insw", "invd", "invept", "invlpg", "invlpga", "invlpgb", "invpcid", "invvpid",
+    "inw", "lgdt", "lgdtd", "lgdtl", "lgdtq", "lgdtw", "lidt", "lidtd", "lidtl", "lidtq", "lidtw",
+    "lldt", "lldtw", "lmsw", "lmsww", "ltr", "ltrw", "monitor", "mwait", "out", "outb", "outl",
+    "outs", "outsb", "outsd", "outsl", "outsw", "outw", "pconfig", "rdmsr", "seamcall", "seamops",
+    "seamret", "skinit", "stac", "stgi", "sti", "swapgs", "sysexit", "sysexitl", "sysexitq",
+    "sysret", "sysretl", "sysretq", "tdcall", "vmclear", "vmlaunch", "vmload", "vmptrld", "vmptrst",
+    "vmread", "vmreadl", "vmreadq", "vmresume", "vmrun", "vmsave", "vmwrite", "vmwritel",
+    "vmwriteq", "vmxoff", "vmxon", "wbinvd", "wbnoinvd", "wrmsr", "xrstors", "xrstors64", "xsaves",
+    "xsaves64", "xsetbv",
+];
+
+/// Mnemonics of the x87 floating point unit. `fxsave` and `fxrstor` are left
+/// out, since they also save the state of SSE.
+#[rustfmt::skip]
+const X87: &[&str] = &[
+    "f2xm1", "fabs", "fadd", "faddl", "faddp", "fadds", "fbld", "fbstp", "fchs", "fcmovb",
+    "fcmovbe", "fcmove", "fcmovnb", "fcmovnbe", "fcmovne", "fcmovnu", "fcmovu", "fcom", "fcomi",
+    "fcomip", "fcoml", "fcomp", "fcompi", "fcompl", "fcompp", "fcomps", "fcoms", "fcos", "fdecstp",
+    "fdiv", "fdivl", "fdivp", "fdivr", "fdivrl", "fdivrp", "fdivrs", "fdivs", "ffree", "ffreep",
+    "fiadd", "fiaddl", "fiadds", "ficom", "ficoml", "ficomp", "ficompl", "ficomps", "ficoms",
+    "fidiv", "fidivl", "fidivr", "fidivrl", "fidivrs", "fidivs", "fild", "fildl", "fildll", "filds",
+    "fimul", "fimull", "fimuls", "fincstp", "fist", "fistl", "fistp", "fistpl", "fistpll", "fistps",
+    "fists", "fisttp", "fisttpl", "fisttpll", "fisttps", "fisub", "fisubl", "fisubr", "fisubrl",
+    "fisubrs", "fisubs", "fld", "fld1", "fldcw", "fldenv", "fldl", "fldl2e", "fldl2t", "fldlg2",
+    "fldln2", "fldpi", "flds", "fldt", "fldz", "fmul", "fmull", "fmulp", "fmuls", "fnclex",
+    "fninit", "fnop", "fnsave", "fnstcw", "fnstenv", "fnstsw", "fpatan", "fprem", "fprem1", "fptan",
+    "frndint", "frstor", "fscale", "fsin", "fsincos", "fsqrt", "fst", "fstl", "fstp", "fstpl",
+    "fstps", "fstpt", "fsts", "fsub", "fsubl", "fsubp", "fsubr", "fsubrl", "fsubrp", "fsubrs",
+    "fsubs", "ftst", "fucom", "fucomi", "fucomip", "fucomp", "fucompi", "fucompp", "fwait", "fxam",
+    "fxch", "fxtract", "fyl2x", "fyl2xp1",
+];
+
+/// Sizes of instructions without operands, in 64-bit mode, which are the
+/// same in 32-bit mode where they exist.
+const SIZES: &[(&str, usize, usize)] = &[
+    ("cbw", 2, 2),
+    ("cdq", 1, 1),
+    ("cdqe", 2, 2),
+    ("clac", 3, 3),
+    ("clc", 1, 1),
+    ("cld", 1, 1),
+    ("cli", 1, 1),
+    ("cmc", 1, 1),
+    ("cmpsb", 1, 1),
+    ("cmpsq", 2, 2),
+    ("cpuid", 2, 2),
+    ("cqo", 2, 2),
+    ("cwd", 2, 2),
+    ("cwde", 1, 1),
+    ("endbr32", 4, 4),
+    ("endbr64", 4, 4),
+    ("hlt", 1, 1),
+    ("int3", 1, 1),
+    ("iretq", 2, 2),
+    ("lahf", 1, 1),
+    ("leave", 1, 1),
+    ("lfence", 3, 3),
+    ("lodsb", 1, 1),
+    ("lodsq", 2, 2),
+    ("mfence", 3, 3),
+    ("movsb", 1, 1),
+    ("movsq", 2, 2),
+    ("nop", 1, 1),
+    ("pause", 2, 2),
+    ("popfq", 1, 1),
+    ("pushfq", 1, 1),
+    ("rdmsr", 2, 2),
+    ("rdpmc", 2, 2),
+    ("rdtsc", 2, 2),
+    ("rdtscp", 3, 3),
+    ("ret", 1, 1),
+    ("retq", 1, 1),
+    ("sahf", 1, 1),
+    ("scasb", 1, 1),
+    ("scasq", 2, 2),
+    ("sfence", 3, 3),
+    ("stac", 3, 3),
+    ("stc", 1, 1),
+    ("std", 1, 1),
+    ("sti", 1, 1),
+    ("stosb", 1, 1),
+    ("stosq", 2, 2),
+    ("swapgs", 3, 3),
+    ("syscall", 2, 2),
+    ("ud2", 2, 2),
+    ("vzeroall", 3, 3),
+    ("vzeroupper", 3, 3),
+    ("wbinvd", 2, 2),
+    ("wrmsr", 2, 2),
+    ("xgetbv", 3, 3),
+    ("xsetbv", 3, 3),
+];
+
+/// Sizes of branches to a label, from the short to the near form, or to a
+/// placeholder, which may also be a register for `jmp` and `call`.
+const RELATIVE: &[(&str, usize, usize)] = &[
+    ("call", 2, 5),
+    ("callq", 2, 5),
+    ("ja", 2, 6),
+    ("jae", 2, 6),
+    ("jb", 2, 6),
+    ("jbe", 2, 6),
+    ("jc", 2, 6),
+    ("je", 2, 6),
+    ("jecxz", 2, 3),
+    ("jg", 2, 6),
+    ("jge", 2, 6),
+    ("jl", 2, 6),
+    ("jle", 2, 6),
+    ("jmp", 2, 5),
+    ("jmpq", 2, 5),
+    ("jna", 2, 6),
+    ("jnae", 2, 6),
+    ("jnb", 2, 6),
+    ("jnbe", 2, 6),
+    ("jnc", 2, 6),
+    ("jne", 2, 6),
+    ("jng", 2, 6),
+    ("jnge", 2, 6),
+    ("jnl", 2, 6),
+    ("jnle", 2, 6),
+    ("jno", 2, 6),
+    ("jnp", 2, 6),
+    ("jns", 2, 6),
+    ("jnz", 2, 6),
+    ("jo", 2, 6),
+    ("jp", 2, 6),
+    ("jpe", 2, 6),
+    ("jpo", 2, 6),
+    ("jrcxz", 2, 2),
+    ("js", 2, 6),
+    ("jz", 2, 6),
+    ("loop", 2, 2),
+    ("loope", 2, 2),
+    ("loopne", 2, 2),
+];
+
+#[rustfmt::skip]
+const MNEMONICS: &[&str] = &[
+    "aaa", "aad", "aam", "aas", "adc", "adcb", "adcl", "adcq", "adcw", "adcx", "adcxl", "adcxq",
+    "add", "addb", "addl", "addpd", "addps", "addq", "addr16", "addr32", "addsd", "addss",
+    "addsubpd", "addsubps", "addw", "adox", "adoxl", "adoxq", "aesdec", "aesdec128kl",
+    "aesdec256kl", "aesdeclast", "aesdecwide128kl", "aesdecwide256kl", "aesenc", "aesenc128kl",
+    "aesenc256kl", "aesenclast", "aesencwide128kl", "aesencwide256kl", "aesimc", "aeskeygenassist",
+    "and", "andb", "andl", "andn", "andnl", "andnpd", "andnps", "andnq", "andpd", "andps", "andq",
+    "andw", "arpl", "bextr", "bextrl", "bextrq", "blcfill", "blcfilll", "blcfillq", "blci", "blcic",
+    "blcicl", "blcicq", "blcil", "blciq", "blcmsk", "blcmskl", "blcmskq", "blcs", "blcsl", "blcsq",
+    "blendpd", "blendps", "blendvpd", "blendvps", "blsfill", "blsfilll", "blsfillq", "blsi",
+    "blsic", "blsicl", "blsicq", "blsil", "blsiq", "blsmsk", "blsmskl", "blsmskq", "blsr", "blsrl",
+    "blsrq", "bound", "bsf", "bsfl", "bsfq", "bsfw", "bsr", "bsrl", "bsrq", "bsrw", "bswap",
+    "bswapl", "bswapq", "bt", "btc", "btcl", "btcq", "btcw", "btl", "btq", "btr", "btrl", "btrq",
+    "btrw", "bts", "btsl", "btsq", "btsw", "btw", "bzhi", "bzhil", "bzhiq", "call", "calll",
+    "callq", "callw", "cbtw", "cbw", "cdq", "cdqe", "clac", "clc", "cld", "cldemote", "clflush",
+    "clflushopt", "clgi", "cli", "clr", "clrb", "clrl", "clrq", "clrssbsy", "clrw", "cltd", "cltq",
+    "clts", "clui", "clwb", "clzero", "cmc", "cmova", "cmovae", "cmovael", "cmovaeq", "cmovaew",
+    "cmoval", "cmovaq", "cmovaw", "cmovb", "cmovbe", "cmovbel", "cmovbeq", "cmovbew", "cmovbl",
+    "cmovbq", "cmovbw", "cmovc", "cmovcl", "cmovcq", "cmovcw", "cmove", "cmovel", "cmoveq",
+    "cmovew", "cmovg", "cmovge", "cmovgel", "cmovgeq", "cmovgew", "cmovgl", "cmovgq", "cmovgw",
+    "cmovl", "cmovle", "cmovlel", "cmovleq", "cmovlew", "cmovll", "cmovlq", "cmovlw", "cmovna",
+    "cmovnae", "cmovnael", "cmovnaeq", "cmovnaew", "cmovnal", "cmovnaq", "cmovnaw", "cmovnb",
+    "cmovnbe", "cmovnbel", "cmovnbeq", "cmovnbew", "cmovnbl", "cmovnbq", "cmovnbw", "cmovnc",
+    "cmovncl", "cmovncq", "cmovncw", "cmovne", "cmovnel", "cmovneq", "cmovnew", "cmovng", "cmovnge",
+    "cmovngel", "cmovngeq", "cmovngew", "cmovngl", "cmovngq", "cmovngw", "cmovnl", "cmovnle",
+    "cmovnlel", "cmovnleq", "cmovnlew", "cmovnll", "cmovnlq", "cmovnlw", "cmovno", "cmovnol",
+    "cmovnoq", "cmovnow", "cmovnp", "cmovnpl", "cmovnpq", "cmovnpw", "cmovns", "cmovnsl", "cmovnsq",
+    "cmovnsw", "cmovnz", "cmovnzl", "cmovnzq", "cmovnzw", "cmovo", "cmovol", "cmovoq", "cmovow",
+    "cmovp", "cmovpe", "cmovpel", "cmovpeq", "cmovpew", "cmovpl", "cmovpo", "cmovpol", "cmovpoq",
+    "cmovpow", "cmovpq", "cmovpw", "cmovs", "cmovsl", "cmovsq", "cmovsw", "cmovz", "cmovzl",
+    "cmovzq", "cmovzw", "cmp", "cmpb", "cmpl", "cmppd", "cmpps", "cmpq", "cmps", "cmpsb", "cmpsd",
+    "cmpsl", "cmpsq", "cmpss", "cmpsw", "cmpw", "cmpxchg", "cmpxchg16b", "cmpxchg8b", "cmpxchgb",
+    "cmpxchgl", "cmpxchgq", "cmpxchgw", "comisd", "comiss", "cpuid", "cqo", "cqto", "crc32",
+    "crc32b", "crc32l", "crc32q", "crc32w", "cs", "cvtdq2pd", "cvtdq2ps", "cvtpd2dq", "cvtpd2pi",
+    "cvtpd2ps", "cvtpi2pd", "cvtpi2ps", "cvtps2dq", "cvtps2pd", "cvtps2pi", "cvtsd2si", "cvtsd2sil",
+    "cvtsd2siq", "cvtsd2ss", "cvtsi2sd", "cvtsi2sdl", "cvtsi2sdq", "cvtsi2ss", "cvtsi2ssl",
+    "cvtsi2ssq", "cvtss2sd", "cvtss2si", "cvtss2sil", "cvtss2siq", "cvttpd2dq", "cvttpd2pi",
+    "cvttps2dq", "cvttps2pi", "cvttsd2si", "cvttsd2sil", "cvttsd2siq", "cvttss2si", "cvttss2sil",
+    "cvttss2siq", "cwd", "cwde", "cwtd", "cwtl", "daa", "das", "data16", "data32", "dec", "decb",
+    "decl", "decq", "decw", "div", "divb", "divl", "divpd", "divps", "divq", "divsd", "divss",
+    "divw", "dppd", "dpps", "ds", "emms", "encls", "enclu", "enclv", "encodekey128", "encodekey256",
+    "endbr32", "endbr64", "enqcmd", "enqcmds", "enter", "es", "extractps", "extrq", "f2xm1", "fabs",
+    "fadd", "faddl", "faddp", "fadds", "fbld", "fbstp", "fchs", "fcmovb", "fcmovbe", "fcmove",
+    "fcmovnb", "fcmovnbe", "fcmovne", "fcmovnu", "fcmovu", "fcom", "fcomi", "fcomip", "fcoml",
+    "fcomp", "fcompi", "fcompl", "fcompp", "fcomps", "fcoms", "fcos", "fdecstp", "fdiv", "fdivl",
+    "fdivp", "fdivr", "fdivrl", "fdivrp", "fdivrs", "fdivs", "femms", "ffree", "ffreep", "fiadd",
+    "fiaddl", "fiadds", "ficom", "ficoml", "ficomp", "ficompl", "ficomps", "ficoms", "fidiv",
+    "fidivl", "fidivr", "fidivrl", "fidivrs", "fidivs", "fild", "fildl", "fildll", "filds", "fimul",
+    "fimull", "fimuls", "fincstp", "fist", "fistl", "fistp", "fistpl", "fistpll", "fistps", "fists",
+    "fisttp", "fisttpl", "fisttpll", "fisttps", "fisub", "fisubl", "fisubr", "fisubrl", "fisubrs",
+    "fisubs", "fld", "fld1", "fldcw", "fldenv", "fldl", "fldl2e", "fldl2t", "fldlg2", "fldln2",
+    "fldpi", "flds", "fldt", "fldz", "fmul", "fmull", "fmulp", "fmuls", "fnclex", "fninit", "fnop",
+    "fnsave", "fnstcw", "fnstenv", "fnstsw", "fpatan", "fprem", "fprem1", "fptan", "frndint",
+    "frstor", "fs", "fscale", "fsin", "fsincos", "fsqrt", "fst", "fstl", "fstp", "fstpl", "fstps",
+    "fstpt", "fsts", "fsub", "fsubl", "fsubp", "fsubr", "fsubrl", "fsubrp", "fsubrs", "fsubs",
+    "ftst", "fucom", "fucomi", "fucomip", "fucomp", "fucompi", "fucompp", "fwait", "fxam", "fxch",
+    "fxrstor", "fxrstor64", "fxsave", "fxsave64", "fxtract", "fyl2x", "fyl2xp1", "getsec",
+    "gf2p8affineinvqb", "gf2p8affineqb", "gf2p8mulb", "gs", "haddpd", "haddps", "hlt", "hreset",
+    "hsubpd", "hsubps", "idiv", "idivb", "idivl", "idivq", "idivw", "imul", "imulb", "imull",
+    "imulq", "imulw", "in", "inb", "inc", "incb", "incl", "incq", "incsspd", "incsspq", "incw",
+    "inl", "ins", "insb", "insd", "insertps", "insertq", "insl", "insw", "int", "int3", "into",
+    "invd", "invept", "invlpg", "invlpga", "invlpgb", "invlpgb}", "invpcid", "invvpid", "inw",
+    "iret", "iretd", "iretl", "iretq", "iretw", "ja", "jae", "jb", "jbe", "jc", "jcxz", "je",
+    "jecxz", "jg", "jge", "jl", "jle", "jmp", "jmpl", "jmpq", "jmpw", "jna", "jnae", "jnb", "jnbe",
+    "jnc", "jne", "jng", "jnge", "jnl", "jnle", "jno", "jnp", "jns", "jnz", "jo", "jp", "jpe",
+    "jpo", "jrcxz", "js", "jz", "kaddb", "kaddd", "kaddq", "kaddw", "kandb", "kandd", "kandnb",
+    "kandnd", "kandnq", "kandnw", "kandq", "kandw", "kmovb", "kmovd", "kmovq", "kmovw", "knotb",
+    "knotd", "knotq", "knotw", "korb", "kord", "korq", "kortestb", "kortestd", "kortestq",
+    "kortestw", "korw", "kshiftlb", "kshiftld", "kshiftlq", "kshiftlw", "kshiftrb", "kshiftrd",
+    "kshiftrq", "kshiftrw", "ktestb", "ktestd", "ktestq", "ktestw", "kunpckbw", "kunpckdq",
+    "kunpckwd", "kxnorb", "kxnord", "kxnorq", "kxnorw", "kxorb", "kxord", "kxorq", "kxorw", "lahf",
+    "lar", "larl", "larq", "larw", "lcall", "lcalll", "lcallq", "lcallw", "lddqu", "ldmxcsr", "lds",
+    "ldsl", "ldsw", "ldtilecfg", "lea", "leal", "leaq", "leave", "leaw", "les", "lesl", "lesw",
+    "lfence", "lfs", "lfsl", "lfsq", "lfsw", "lgdt", "lgdtd", "lgdtl", "lgdtq", "lgdtw", "lgs",
+    "lgsl", "lgsq", "lgsw", "lidt", "lidtd", "lidtl", "lidtq", "lidtw", "ljmp", "ljmpl", "ljmpq",
+    "ljmpw", "lldt", "lldtw", "llwpcb", "lmsw", "lmsww", "loadiwkey", "lock", "lods", "lodsb",
+    "lodsd", "lodsl", "lodsq", "lodsw", "loop", "loope", "loopne", "lretl", "lretq", "lretw", "lsl",
+    "lsll", "lslq", "lslw", "lss", "lssl", "lssq", "lssw", "ltr", "ltrw", "lwpins", "lwpval",
+    "lzcnt", "lzcntl", "lzcntq", "lzcntw", "maskmovdqu", "maskmovq", "maxpd", "maxps", "maxsd",
+    "maxss", "mfence", "minpd", "minps", "minsd", "minss", "monitor", "monitorx", "montmul", "mov",
+    "mov.s", "movabs", "movabsb", "movabsl", "movabsq", "movabsw", "movapd", "movapd.s", "movaps",
+    "movaps.s", "movb", "movb.s", "movbe", "movbel", "movbeq", "movbew", "movd", "movddup",
+    "movdir64b", "movdiri", "movdq2q", "movdqa", "movdqa.s", "movdqu", "movdqu.s", "movhlps",
+    "movhpd", "movhps", "movl", "movl.s", "movlhps", "movlpd", "movlps", "movmskpd", "movmskps",
+    "movntdq", "movntdqa", "movnti", "movntil", "movntiq", "movntpd", "movntps", "movntq",
+    "movntsd", "movntss", "movq", "movq.s", "movq2dq", "movs", "movsb", "movsbl", "movsbq",
+    "movsbw", "movsd", "movsd.s", "movshdup", "movsl", "movsldup", "movslq", "movsq", "movss",
+    "movss.s", "movsw", "movswl", "movswq", "movsx", "movsxd", "movupd", "movupd.s", "movups",
+    "movups.s", "movw", "movw.s", "movzbl", "movzbq", "movzbw", "movzwl", "movzwq", "movzx",
+    "mpsadbw", "mul", "mulb", "mull", "mulpd", "mulps", "mulq", "mulsd", "mulss", "mulw", "mulx",
+    "mulxl", "mulxq", "mwait", "mwaitx", "neg", "negb", "negl", "negq", "negw", "nop", "nopl",
+    "nopq", "nopw", "not", "notb", "notl", "notq", "notw", "or", "orb", "orl", "orpd", "orps",
+    "orq", "orw", "out", "outb", "outl", "outs", "outsb", "outsd", "outsl", "outsw", "outw",
+    "pabsb", "pabsd", "pabsw", "packssdw", "packsswb", "packusdw", "packuswb", "paddb", "paddd",
+    "paddq", "paddsb", "paddsw", "paddusb", "paddusw", "paddw", "palignr", "pand", "pandn", "pause",
+    "pavgb", "pavgusb", "pavgw", "pblendvb", "pblendw", "pclmulhqhqdq", "pclmulhqlqdq",
+    "pclmullqhqdq", "pclmullqlqdq", "pclmulqdq", "pcmpeqb", "pcmpeqd", "pcmpeqq", "pcmpeqw",
+    "pcmpestri", "pcmpestrm", "pcmpgtb", "pcmpgtd", "pcmpgtq", "pcmpgtw", "pcmpistri", "pcmpistrm",
+    "pconfig", "pdep", "pdepl", "pdepq", "pext", "pextl", "pextq", "pextrb", "pextrd", "pextrq",
+    "pextrw", "pf2id", "pf2iw", "pfacc", "pfadd", "pfcmpeq", "pfcmpge", "pfcmpgt", "pfmax", "pfmin",
+    "pfmul", "pfnacc", "pfpnacc", "pfrcp", "pfrcpit1", "pfrcpit2", "pfrsqit1", "pfrsqrt", "pfsub",
+    "pfsubr", "phaddd", "phaddsw", "phaddw", "phminposuw", "phsubd", "phsubsw", "phsubw", "pi2fd",
+    "pi2fw", "pinsrb", "pinsrd", "pinsrq", "pinsrw", "pmaddubsw", "pmaddwd", "pmaxsb", "pmaxsd",
+    "pmaxsw", "pmaxub", "pmaxud", "pmaxuw", "pminsb", "pminsd", "pminsw", "pminub", "pminud",
+    "pminuw", "pmovmskb", "pmovsxbd", "pmovsxbq", "pmovsxbw", "pmovsxdq", "pmovsxwd", "pmovsxwq",
+    "pmovzxbd", "pmovzxbq", "pmovzxbw", "pmovzxdq", "pmovzxwd", "pmovzxwq", "pmuldq", "pmulhrsw",
+    "pmulhrw", "pmulhuw", "pmulhw", "pmulld", "pmullw", "pmuludq", "pop", "popal", "popaw",
+    "popcnt", "popcntl", "popcntq", "popcntw", "popf", "popfd", "popfl", "popfq", "popfw", "popl",
+    "popq", "popw", "por", "prefetch", "prefetchnta", "prefetcht0", "prefetcht1", "prefetcht2",
+    "prefetchw", "prefetchwt1", "psadbw", "pshufb", "pshufd", "pshufhw", "pshuflw", "pshufw",
+    "psignb", "psignd", "psignw", "pslld", "pslldq", "psllq", "psllw", "psmash", "psrad", "psraw",
+    "psrld", "psrldq", "psrlq", "psrlw", "psubb", "psubd", "psubq", "psubsb", "psubsw", "psubusb",
+    "psubusw", "psubw", "pswapd", "ptest", "ptwrite", "ptwritel", "ptwriteq", "punpckhbw",
+    "punpckhdq", "punpckhqdq", "punpckhwd", "punpcklbw", "punpckldq", "punpcklqdq", "punpcklwd",
+    "push", "pushal", "pushaw", "pushf", "pushfd", "pushfl", "pushfq", "pushfw", "pushl", "pushq",
+    "pushw", "pvalidate", "pxor", "rcl", "rclb", "rcll", "rclq", "rclw", "rcpps", "rcpss", "rcr",
+    "rcrb", "rcrl", "rcrq", "rcrw", "rdfsbase", "rdfsbasel", "rdfsbaseq", "rdgsbase", "rdgsbasel",
+    "rdgsbaseq", "rdmsr", "rdpid", "rdpkru", "rdpmc", "rdrand", "rdrandl", "rdrandq", "rdrandw",
+    "rdseed", "rdseedl", "rdseedq", "rdseedw", "rdsspd", "rdsspq", "rdtsc", "rdtscp", "rep",
+    "repne", "repnz", "repz", "ret", "retf", "retfq", "retl", "retq", "retw", "rex64", "rmpadjust",
+    "rmpupdate", "rol", "rolb", "roll", "rolq", "rolw", "ror", "rorb", "rorl", "rorq", "rorw",
+    "rorx", "rorxl", "rorxq", "roundpd", "roundps", "roundsd", "roundss", "rsm", "rsqrtps",
+    "rsqrtss", "rstorssp", "sahf", "sal", "salb", "salc", "sall", "salq", "salw", "sar", "sarb",
+    "sarl", "sarq", "sarw", "sarx", "sarxl", "sarxq", "saveprevssp", "sbb", "sbbb", "sbbl", "sbbq",
+    "sbbw", "scas", "scasb", "scasd", "scasl", "scasq", "scasw", "seamcall", "seamops", "seamret",
+    "senduipi", "serialize", "seta", "setab", "setae", "setaeb", "setb", "setbb", "setbe", "setbeb",
+    "setc", "setcb", "sete", "seteb", "setg", "setgb", "setge", "setgeb", "setl", "setlb", "setle",
+    "setleb", "setna", "setnab", "setnae", "setnaeb", "setnb", "setnbb", "setnbe", "setnbeb",
+    "setnc", "setncb", "setne", "setneb", "setng", "setngb", "setnge", "setngeb", "setnl", "setnlb",
+    "setnle", "setnleb", "setno", "setnob", "setnp", "setnpb", "setns", "setnsb", "setnz", "setnzb",
+    "seto", "setob", "setp", "setpb", "setpe", "setpeb", "setpo", "setpob", "sets", "setsb",
+    "setssbsy", "setz", "setzb", "sfence", "sgdt", "sgdtd", "sgdtl", "sgdtq", "sgdtw", "sha1msg1",
+    "sha1msg2", "sha1nexte", "sha1rnds4", "sha256msg1", "sha256msg2", "sha256rnds2", "shl", "shlb",
+    "shld", "shldl", "shldq", "shldw", "shll", "shlq", "shlw", "shlx", "shlxl", "shlxq", "shr",
+    "shrb", "shrd", "shrdl", "shrdq", "shrdw", "shrl", "shrq", "shrw", "shrx", "shrxl", "shrxq",
+    "shufpd", "shufps", "sidt", "sidtd", "sidtl", "sidtq", "sidtw", "skinit", "sldt", "sldtl",
+    "sldtq", "sldtw", "slwpcb", "smsw", "smswl", "smswq", "smsww", "sqrtpd", "sqrtps", "sqrtsd",
+    "sqrtss", "ss", "stac", "stc", "std", "stgi", "sti", "stmxcsr", "stos", "stosb", "stosd",
+    "stosl", "stosq", "stosw", "str", "strl", "strq", "strw", "sttilecfg", "stui", "sub", "subb",
+    "subl", "subpd", "subps", "subq", "subsd", "subss", "subw", "swapgs", "syscall", "sysenter",
+    "sysexit", "sysexitl", "sysexitq", "sysret", "sysretl", "sysretq", "t1mskc", "t1mskcl",
+    "t1mskcq", "tdcall", "tdpbf16ps", "tdpbssd", "tdpbsud", "tdpbusd", "tdpbuud", "test", "testb",
+    "testl", "testq", "testui", "testw", "tileloadd", "tileloaddt1", "tilerelease", "tilestored",
+    "tilezero", "tlbsync", "tpause", "tzcnt", "tzcntl", "tzcntq", "tzcntw", "tzmsk", "tzmskl",
+    "tzmskq", "ucomisd", "ucomiss", "ud1", "ud1l", "ud1q", "ud1w", "ud2", "uiret", "umonitor",
+    "umwait", "unpckhpd", "unpckhps", "unpcklpd", "unpcklps", "v4fmaddps", "v4fmaddss",
+    "v4fnmaddps", "v4fnmaddss", "vaddpd", "vaddph", "vaddps", "vaddsd", "vaddsh", "vaddss",
+    "vaddsubpd", "vaddsubps", "vaesdec", "vaesdeclast", "vaesenc", "vaesenclast", "vaesimc",
+    "vaeskeygenassist", "valignd", "valignq", "vandnpd", "vandnps", "vandpd", "vandps", "vblendmpd",
+    "vblendmps", "vblendpd", "vblendps", "vblendvpd", "vblendvps", "vbroadcastf128",
+    "vbroadcastf32x2", "vbroadcastf32x4", "vbroadcastf32x8", "vbroadcastf64x2", "vbroadcastf64x4",
+    "vbroadcasti128", "vbroadcasti32x2", "vbroadcasti32x4", "vbroadcasti32x8", "vbroadcasti64x2",
+    "vbroadcasti64x4", "vbroadcastsd", "vbroadcastss", "vcmppd", "vcmpph", "vcmpps", "vcmpsd",
+    "vcmpsh", "vcmpss", "vcomisd", "vcomish", "vcomiss", "vcompresspd", "vcompressps", "vcvtdq2pd",
+    "vcvtdq2ph", "vcvtdq2phx", "vcvtdq2phy", "vcvtdq2ps", "vcvtne2ps2bf16", "vcvtneps2bf16",
+    "vcvtneps2bf16x", "vcvtneps2bf16y", "vcvtpd2dq", "vcvtpd2dqx", "vcvtpd2dqy", "vcvtpd2ph",
+    "vcvtpd2phx", "vcvtpd2phy", "vcvtpd2phz", "vcvtpd2ps", "vcvtpd2psx", "vcvtpd2psy", "vcvtpd2qq",
+    "vcvtpd2udq", "vcvtpd2udqx", "vcvtpd2udqy", "vcvtpd2uqq", "vcvtph2dq", "vcvtph2pd", "vcvtph2ps",
+    "vcvtph2psx", "vcvtph2qq", "vcvtph2udq", "vcvtph2uqq", "vcvtph2uw", "vcvtph2w", "vcvtps2dq",
+    "vcvtps2pd", "vcvtps2ph", "vcvtps2phx", "vcvtps2phxx", "vcvtps2phxy", "vcvtps2qq", "vcvtps2udq",
+    "vcvtps2uqq", "vcvtqq2pd", "vcvtqq2ph", "vcvtqq2phx", "vcvtqq2phy", "vcvtqq2phz", "vcvtqq2ps",
+    "vcvtqq2psx", "vcvtqq2psy", "vcvtsd2sh", "vcvtsd2si", "vcvtsd2sil", "vcvtsd2siq", "vcvtsd2ss",
+    "vcvtsd2usi", "vcvtsd2usil", "vcvtsd2usiq", "vcvtsh2sd", "vcvtsh2si", "vcvtsh2sil",
+    "vcvtsh2siq", "vcvtsh2ss", "vcvtsh2usi", "vcvtsh2usil", "vcvtsh2usiq", "vcvtsi2sd",
+    "vcvtsi2sdl", "vcvtsi2sdq", "vcvtsi2sh", "vcvtsi2shl", "vcvtsi2shq", "vcvtsi2ss", "vcvtsi2ssl",
+    "vcvtsi2ssq", "vcvtss2sd", "vcvtss2sh", "vcvtss2si", "vcvtss2sil", "vcvtss2siq", "vcvtss2usi",
+    "vcvtss2usil", "vcvtss2usiq", "vcvttpd2dq", "vcvttpd2dqx", "vcvttpd2dqy", "vcvttpd2qq",
+    "vcvttpd2udq", "vcvttpd2udqx", "vcvttpd2udqy", "vcvttpd2uqq", "vcvttph2dq", "vcvttph2qq",
+    "vcvttph2udq", "vcvttph2uqq", "vcvttph2uw", "vcvttph2w", "vcvttps2dq", "vcvttps2qq",
+    "vcvttps2udq", "vcvttps2uqq", "vcvttsd2si", "vcvttsd2sil", "vcvttsd2siq", "vcvttsd2usi",
+    "vcvttsd2usil", "vcvttsd2usiq", "vcvttsh2si", "vcvttsh2sil", "vcvttsh2siq", "vcvttsh2usi",
+    "vcvttsh2usil", "vcvttsh2usiq", "vcvttss2si", "vcvttss2sil", "vcvttss2siq", "vcvttss2usi",
+    "vcvttss2usil", "vcvttss2usiq", "vcvtudq2pd", "vcvtudq2ph", "vcvtudq2phx", "vcvtudq2phy",
+    "vcvtudq2ps", "vcvtuqq2pd", "vcvtuqq2ph", "vcvtuqq2phx", "vcvtuqq2phy", "vcvtuqq2phz",
+    "vcvtuqq2ps", "vcvtuqq2psx", "vcvtuqq2psy", "vcvtusi2sd", "vcvtusi2sdl", "vcvtusi2sdq",
+    "vcvtusi2sh", "vcvtusi2shl", "vcvtusi2shq", "vcvtusi2ss", "vcvtusi2ssl", "vcvtusi2ssq",
+    "vcvtuw2ph", "vcvtw2ph", "vdbpsadbw", "vdivpd", "vdivph", "vdivps", "vdivsd", "vdivsh",
+    "vdivss", "vdpbf16ps", "vdppd", "vdpps", "verr", "verw", "vexp2pd", "vexp2ps", "vexpandpd",
+    "vexpandps", "vextractf128", "vextractf32x4", "vextractf32x8", "vextractf64x2", "vextractf64x4",
+    "vextracti128", "vextracti32x4", "vextracti32x8", "vextracti64x2", "vextracti64x4",
+    "vextractps", "vfcmaddcph", "vfcmaddcsh", "vfcmulcph", "vfcmulcsh", "vfixupimmpd",
+    "vfixupimmps", "vfixupimmsd", "vfixupimmss", "vfmadd132pd", "vfmadd132ph", "vfmadd132ps",
+    "vfmadd132sd", "vfmadd132sh", "vfmadd132ss", "vfmadd213pd", "vfmadd213ph", "vfmadd213ps",
+    "vfmadd213sd", "vfmadd213sh", "vfmadd213ss", "vfmadd231pd", "vfmadd231ph", "vfmadd231ps",
+    "vfmadd231sd", "vfmadd231sh", "vfmadd231ss", "vfmaddcph", "vfmaddcsh", "vfmaddpd", "vfmaddps",
+    "vfmaddsd", "vfmaddss", "vfmaddsub132pd", "vfmaddsub132ph", "vfmaddsub132ps", "vfmaddsub213pd",
+    "vfmaddsub213ph", "vfmaddsub213ps", "vfmaddsub231pd", "vfmaddsub231ph", "vfmaddsub231ps",
+    "vfmaddsubpd", "vfmaddsubps", "vfmsub132pd", "vfmsub132ph", "vfmsub132ps", "vfmsub132sd",
+    "vfmsub132sh", "vfmsub132ss", "vfmsub213pd", "vfmsub213ph", "vfmsub213ps", "vfmsub213sd",
+    "vfmsub213sh", "vfmsub213ss", "vfmsub231pd", "vfmsub231ph", "vfmsub231ps", "vfmsub231sd",
+    "vfmsub231sh", "vfmsub231ss", "vfmsubadd132pd", "vfmsubadd132ph", "vfmsubadd132ps",
+    "vfmsubadd213pd", "vfmsubadd213ph", "vfmsubadd213ps", "vfmsubadd231pd", "vfmsubadd231ph",
+    "vfmsubadd231ps", "vfmsubaddpd", "vfmsubaddps", "vfmsubpd", "vfmsubps", "vfmsubsd", "vfmsubss",
+    "vfmulcph", "vfmulcsh", "vfnmadd132pd", "vfnmadd132ph", "vfnmadd132ps", "vfnmadd132sd",
+    "vfnmadd132sh", "vfnmadd132ss", "vfnmadd213pd", "vfnmadd213ph", "vfnmadd213ps", "vfnmadd213sd",
+    "vfnmadd213sh", "vfnmadd213ss", "vfnmadd231pd", "vfnmadd231ph", "vfnmadd231ps", "vfnmadd231sd",
+    "vfnmadd231sh", "vfnmadd231ss", "vfnmaddpd", "vfnmaddps", "vfnmaddsd", "vfnmaddss",
+    "vfnmsub132pd", "vfnmsub132ph", "vfnmsub132ps", "vfnmsub132sd", "vfnmsub132sh", "vfnmsub132ss",
+    "vfnmsub213pd", "vfnmsub213ph", "vfnmsub213ps", "vfnmsub213sd", "vfnmsub213sh", "vfnmsub213ss",
+    "vfnmsub231pd", "vfnmsub231ph", "vfnmsub231ps", "vfnmsub231sd", "vfnmsub231sh", "vfnmsub231ss",
+    "vfnmsubpd", "vfnmsubps", "vfnmsubsd", "vfnmsubss", "vfpclasspd", "vfpclasspdx", "vfpclasspdy",
+    "vfpclasspdz", "vfpclassph", "vfpclassphx", "vfpclassphy", "vfpclassphz", "vfpclassps",
+    "vfpclasspsx", "vfpclasspsy", "vfpclasspsz", "vfpclasssd", "vfpclasssh", "vfpclassss",
+    "vfrczpd", "vfrczps", "vfrczsd", "vfrczss", "vgatherdpd", "vgatherdps", "vgatherpf0dpd",
+    "vgatherpf0dps", "vgatherpf0qpd", "vgatherpf0qps", "vgatherpf1dpd", "vgatherpf1dps",
+    "vgatherpf1qpd", "vgatherpf1qps", "vgatherqpd", "vgatherqps", "vgetexppd", "vgetexpph",
+    "vgetexpps", "vgetexpsd", "vgetexpsh", "vgetexpss", "vgetmantpd", "vgetmantph", "vgetmantps",
+    "vgetmantsd", "vgetmantsh", "vgetmantss", "vgf2p8affineinvqb", "vgf2p8affineqb", "vgf2p8mulb",
+    "vhaddpd", "vhaddps", "vhsubpd", "vhsubps", "vinsertf128", "vinsertf32x4", "vinsertf32x8",
+    "vinsertf64x2", "vinsertf64x4", "vinserti128", "vinserti32x4", "vinserti32x8", "vinserti64x2",
+    "vinserti64x4", "vinsertps", "vlddqu", "vldmxcsr", "vmaskmovdqu", "vmaskmovpd", "vmaskmovps",
+    "vmaxpd", "vmaxph", "vmaxps", "vmaxsd", "vmaxsh", "vmaxss", "vmcall", "vmclear", "vmfunc",
+    "vminpd", "vminph", "vminps", "vminsd", "vminsh", "vminss", "vmlaunch", "vmload", "vmmcall",
+    "vmovapd", "vmovapd.s", "vmovaps", "vmovaps.s", "vmovd", "vmovddup", "vmovdqa", "vmovdqa.s",
+    "vmovdqa32", "vmovdqa32.s", "vmovdqa64", "vmovdqa64.s", "vmovdqu", "vmovdqu.s", "vmovdqu16",
+    "vmovdqu16.s", "vmovdqu32", "vmovdqu32.s", "vmovdqu64", "vmovdqu64.s", "vmovdqu8", "vmovdqu8.s",
+    "vmovhlps", "vmovhpd", "vmovhps", "vmovlhps", "vmovlpd", "vmovlps", "vmovmskpd", "vmovmskps",
+    "vmovntdq", "vmovntdqa", "vmovntpd", "vmovntps", "vmovq", "vmovq.s", "vmovsd", "vmovsd.s",
+    "vmovsh", "vmovsh.s", "vmovshdup", "vmovsldup", "vmovss", "vmovss.s", "vmovupd", "vmovupd.s",
+    "vmovups", "vmovups.s", "vmovw", "vmpsadbw", "vmptrld", "vmptrst", "vmread", "vmreadl",
+    "vmreadq", "vmresume", "vmrun", "vmsave", "vmulpd", "vmulph", "vmulps", "vmulsd", "vmulsh",
+    "vmulss", "vmwrite", "vmwritel", "vmwriteq", "vmxoff", "vmxon", "vorpd", "vorps",
+    "vp2intersectd", "vp2intersectq", "vp4dpwssd", "vp4dpwssds", "vpabsb", "vpabsd", "vpabsq",
+    "vpabsw", "vpackssdw", "vpacksswb", "vpackusdw", "vpackuswb", "vpaddb", "vpaddd", "vpaddq",
+    "vpaddsb", "vpaddsw", "vpaddusb", "vpaddusw", "vpaddw", "vpalignr", "vpand", "vpandd", "vpandn",
+    "vpandnd", "vpandnq", "vpandq", "vpavgb", "vpavgw", "vpblendd", "vpblendmb", "vpblendmd",
+    "vpblendmq", "vpblendmw", "vpblendvb", "vpblendw", "vpbroadcastb", "vpbroadcastd",
+    "vpbroadcastmb2q", "vpbroadcastmw2d", "vpbroadcastq", "vpbroadcastw", "vpclmulhqhqdq",
+    "vpclmulhqlqdq", "vpclmullqhqdq", "vpclmullqlqdq", "vpclmulqdq", "vpcmov", "vpcmpb", "vpcmpd",
+    "vpcmpeqb", "vpcmpeqd", "vpcmpeqq", "vpcmpeqw", "vpcmpestri", "vpcmpestrm", "vpcmpgtb",
+    "vpcmpgtd", "vpcmpgtq", "vpcmpgtw", "vpcmpistri", "vpcmpistrm", "vpcmpq", "vpcmpub", "vpcmpud",
+    "vpcmpuq", "vpcmpuw", "vpcmpw", "vpcomb", "vpcomd", "vpcompressb", "vpcompressd", "vpcompressq",
+    "vpcompressw", "vpcomq", "vpcomub", "vpcomud", "vpcomuq", "vpcomuw", "vpcomw", "vpconflictd",
+    "vpconflictq", "vpdpbusd", "vpdpbusds", "vpdpwssd", "vpdpwssds", "vperm2f128", "vperm2i128",
+    "vpermb", "vpermd", "vpermi2b", "vpermi2d", "vpermi2pd", "vpermi2ps", "vpermi2q", "vpermi2w",
+    "vpermil2pd", "vpermil2ps", "vpermilpd", "vpermilps", "vpermpd", "vpermps", "vpermq",
+    "vpermt2b", "vpermt2d", "vpermt2pd", "vpermt2ps", "vpermt2q", "vpermt2w", "vpermw", "vpexpandb",
+    "vpexpandd", "vpexpandq", "vpexpandw", "vpextrb", "vpextrd", "vpextrq", "vpextrw", "vpgatherdd",
+    "vpgatherdq", "vpgatherqd", "vpgatherqq", "vphaddbd", "vphaddbq", "vphaddbw", "vphaddd",
+    "vphadddq", "vphaddsw", "vphaddubd", "vphaddubq", "vphaddubw", "vphaddudq", "vphadduwd",
+    "vphadduwq", "vphaddw", "vphaddwd", "vphaddwq", "vphminposuw", "vphsubbw", "vphsubd",
+    "vphsubdq", "vphsubsw", "vphsubw", "vphsubwd", "vpinsrb", "vpinsrd", "vpinsrq", "vpinsrw",
+    "vplzcntd", "vplzcntq", "vpmacsdd", "vpmacsdqh", "vpmacsdql", "vpmacssdd", "vpmacssdqh",
+    "vpmacssdql", "vpmacsswd", "vpmacssww", "vpmacswd", "vpmacsww", "vpmadcsswd", "vpmadcswd",
+    "vpmadd52huq", "vpmadd52luq", "vpmaddubsw", "vpmaddwd", "vpmaskmovd", "vpmaskmovq", "vpmaxsb",
+    "vpmaxsd", "vpmaxsq", "vpmaxsw", "vpmaxub", "vpmaxud", "vpmaxuq", "vpmaxuw", "vpminsb",
+    "vpminsd", "vpminsq", "vpminsw", "vpminub", "vpminud", "vpminuq", "vpminuw", "vpmovb2m",
+    "vpmovd2m", "vpmovdb", "vpmovdw", "vpmovm2b", "vpmovm2d", "vpmovm2q", "vpmovm2w", "vpmovmskb",
+    "vpmovq2m", "vpmovqb", "vpmovqd", "vpmovqw", "vpmovsdb", "vpmovsdw", "vpmovsqb", "vpmovsqd",
+    "vpmovsqw", "vpmovswb", "vpmovsxbd", "vpmovsxbq", "vpmovsxbw", "vpmovsxdq", "vpmovsxwd",
+    "vpmovsxwq", "vpmovusdb", "vpmovusdw", "vpmovusqb", "vpmovusqd", "vpmovusqw", "vpmovuswb",
+    "vpmovw2m", "vpmovwb", "vpmovzxbd", "vpmovzxbq", "vpmovzxbw", "vpmovzxdq", "vpmovzxwd",
+    "vpmovzxwq", "vpmuldq", "vpmulhrsw", "vpmulhuw", "vpmulhw", "vpmulld", "vpmullq", "vpmullw",
+    "vpmultishiftqb", "vpmuludq", "vpopcntb", "vpopcntd", "vpopcntq", "vpopcntw", "vpor", "vpord",
+    "vporq", "vpperm", "vprold", "vprolq", "vprolvd", "vprolvq", "vprord", "vprorq", "vprorvd",
+    "vprorvq", "vprotb", "vprotd", "vprotq", "vprotw", "vpsadbw", "vpscatterdd", "vpscatterdq",
+    "vpscatterqd", "vpscatterqq", "vpshab", "vpshad", "vpshaq", "vpshaw", "vpshlb", "vpshld",
+    "vpshldd", "vpshldq", "vpshldvd", "vpshldvq", "vpshldvw", "vpshldw", "vpshlq", "vpshlw",
+    "vpshrdd", "vpshrdq", "vpshrdvd", "vpshrdvq", "vpshrdvw", "vpshrdw", "vpshufb", "vpshufbitqmb",
+    "vpshufd", "vpshufhw", "vpshuflw", "vpsignb", "vpsignd", "vpsignw", "vpslld", "vpslldq",
+    "vpsllq", "vpsllvd", "vpsllvq", "vpsllvw", "vpsllw", "vpsrad", "vpsraq", "vpsravd", "vpsravq",
+    "vpsravw", "vpsraw", "vpsrld", "vpsrldq", "vpsrlq", "vpsrlvd", "vpsrlvq", "vpsrlvw", "vpsrlw",
+    "vpsubb", "vpsubd", "vpsubq", "vpsubsb", "vpsubsw", "vpsubusb", "vpsubusw", "vpsubw",
+    "vpternlogd", "vpternlogq", "vptest", "vptestmb", "vptestmd", "vptestmq", "vptestmw",
+    "vptestnmb", "vptestnmd", "vptestnmq", "vptestnmw", "vpunpckhbw", "vpunpckhdq", "vpunpckhqdq",
+    "vpunpckhwd", "vpunpcklbw", "vpunpckldq", "vpunpcklqdq", "vpunpcklwd", "vpxor", "vpxord",
+    "vpxorq", "vrangepd", "vrangeps", "vrangesd", "vrangess", "vrcp14pd", "vrcp14ps", "vrcp14sd",
+    "vrcp14ss", "vrcp28pd", "vrcp28ps", "vrcp28sd", "vrcp28ss", "vrcpph", "vrcpps", "vrcpsh",
+    "vrcpss", "vreducepd", "vreduceph", "vreduceps", "vreducesd", "vreducesh", "vreducess",
+    "vrndscalepd", "vrndscaleph", "vrndscaleps", "vrndscalesd", "vrndscalesh", "vrndscaless",
+    "vroundpd", "vroundps", "vroundsd", "vroundss", "vrsqrt14pd", "vrsqrt14ps", "vrsqrt14sd",
+    "vrsqrt14ss", "vrsqrt28pd", "vrsqrt28ps", "vrsqrt28sd", "vrsqrt28ss", "vrsqrtph", "vrsqrtps",
+    "vrsqrtsh", "vrsqrtss", "vscalefpd", "vscalefph", "vscalefps", "vscalefsd", "vscalefsh",
+    "vscalefss", "vscatterdpd", "vscatterdps", "vscatterpf0dpd", "vscatterpf0dps", "vscatterpf0qpd",
+    "vscatterpf0qps", "vscatterpf1dpd", "vscatterpf1dps", "vscatterpf1qpd", "vscatterpf1qps",
+    "vscatterqpd", "vscatterqps", "vshuff32x4", "vshuff64x2", "vshufi32x4", "vshufi64x2", "vshufpd",
+    "vshufps", "vsqrtpd", "vsqrtph", "vsqrtps", "vsqrtsd", "vsqrtsh", "vsqrtss", "vstmxcsr",
+    "vsubpd", "vsubph", "vsubps", "vsubsd", "vsubsh", "vsubss", "vtestpd", "vtestps", "vucomisd",
+    "vucomish", "vucomiss", "vunpckhpd", "vunpckhps", "vunpcklpd", "vunpcklps", "vxorpd", "vxorps",
+    "vzeroall", "vzeroupper", "wait", "wbinvd", "wbnoinvd", "wrfsbase", "wrfsbasel", "wrfsbaseq",
+    "wrgsbase", "wrgsbasel", "wrgsbaseq", "wrmsr", "wrpkru", "wrssd", "wrssq", "wrussd", "wrussq",
+    "xabort", "xacquire", "xadd", "xaddb", "xaddl", "xaddq", "xaddw", "xbegin", "xchg", "xchgb",
+    "xchgl", "xchgq", "xchgw", "xcryptcbc", "xcryptcfb", "xcryptctr", "xcryptecb", "xcryptofb",
+    "xend", "xgetbv", "xlat", "xlatb", "xor", "xorb", "xorl", "xorpd", "xorps", "xorq", "xorw",
+    "xrelease", "xresldtrk", "xrstor", "xrstor64", "xrstors", "xrstors64", "xsave", "xsave64",
+    "xsavec", "xsavec64", "xsaveopt", "xsaveopt64", "xsaves", "xsaves64", "xsetbv", "xsha1",
+    "xsha256", "xstore", "xstorerng", "xsusldtrk", "xtest",
+];
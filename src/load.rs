@@ -0,0 +1,287 @@
+/// Load a constant into a register on ARM or AArch64.
+///
+/// This evaluates to a `&'static str` with one of the following sequences,
+/// picked per call:
+/// - `pool` emits `ldr <reg>, =<value>`, which places the constant in the
+///   next literal pool and loads it from there. The pool is flushed at the
+///   end of the section, or earlier by [`asm_ltorg!`].
+/// - `arm` emits a `movw` and `movt` pair, which needs no pool.
+/// - `aarch64` emits a `movz` followed by three `movk`, which needs no pool.
+///   The value is split into 16-bit chunks by the assembler.
+///
+/// The value can be anything the assembler is able to evaluate, including a
+/// placeholder of a `const` operand. Values made of several tokens must be
+/// wrapped in parentheses.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_load_const;
+/// assert_eq!(asm_load_const!(pool, r0, 0xdeadbeef), "ldr r0 , =0xdeadbeef \n");
+/// assert_eq!(
+///     asm_load_const!(arm, r0, {c}),
+///     "movw r0 , #:lower16:({c})\nmovt r0 , #:upper16:({c})\n"
+/// );
+/// ```
+///
+/// [`asm_ltorg!`]: macro.asm_ltorg.html
+#[macro_export]
+macro_rules! asm_load_const {
+    (@chunk $reg: tt, $value: tt, $shift: literal) => {
+        concat!(
+            "movk ", $crate::asm_block!($reg), ", #((", $crate::asm_block!($value), ") >> ", $shift,
+            ") & 0xffff, lsl #", $shift, "\n"
+        )
+    };
+
+    (pool, $reg: tt, $value: tt $(,)?) => {
+        concat!("ldr ", $crate::asm_block!($reg), ", =", $crate::asm_block!($value), "\n")
+    };
+    (arm, $reg: tt, $value: tt $(,)?) => {
+        concat!(
+            "movw ", $crate::asm_block!($reg), ", #:lower16:(", $crate::asm_block!($value), ")\n",
+            "movt ", $crate::asm_block!($reg), ", #:upper16:(", $crate::asm_block!($value), ")\n"
+        )
+    };
+    (aarch64, $reg: tt, $value: tt $(,)?) => {
+        concat!(
+            "movz ", $crate::asm_block!($reg), ", #(", $crate::asm_block!($value), ") & 0xffff\n",
+            $crate::asm_load_const!(@chunk $reg, $value, 16),
+            $crate::asm_load_const!(@chunk $reg, $value, 32),
+            $crate::asm_load_const!(@chunk $reg, $value, 48)
+        )
+    };
+    ($($token: tt)*) => {
+        compile_error!("expected `pool`, `arm` or `aarch64`, a register and a value")
+    };
+}
+
+/// Materialize a 64-bit immediate in a register with the fewest instructions.
+///
+/// This evaluates to a `&'static str` in which the assembler picks the
+/// sequence with assembler conditionals, so the value can be anything the
+/// assembler is able to evaluate, including a placeholder of a `const`
+/// operand. Values made of several tokens must be wrapped in parentheses.
+/// - `aarch64` emits a `movz` for the lowest non-zero 16-bit chunk and a
+///   `movk` for every other non-zero chunk, or a `movn` and `movk` for the
+///   chunks other than `0xffff` if there are more of those. Values encodable
+///   as a logical immediate are not recognized, so `orr` is never used.
+/// - `x86_64` emits a `mov` to the 32-bit register if the value fits in 32
+///   bits unsigned, which is zero-extended, a `mov` with a sign-extended
+///   32-bit immediate if it fits in 32 bits signed, and a `movabs` otherwise.
+///   The flags are preserved, so zero is not materialized with `xor`.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_mov_imm;
+/// let code = asm_mov_imm!(x86_64, rax, 0x12345678);
+/// assert!(code.contains("mov eax , (0x12345678 )\n"));
+/// let code = asm_mov_imm!(aarch64, {x}, {c});
+/// assert!(code.contains("movk {x}, #((({c}) >> 16) & 0xffff), lsl #16\n"));
+/// ```
+#[macro_export]
+macro_rules! asm_mov_imm {
+    // the 32-bit register of a 64-bit one
+    (@e rax) => { "eax" };
+    (@e rbx) => { "ebx" };
+    (@e rcx) => { "ecx" };
+    (@e rdx) => { "edx" };
+    (@e rsi) => { "esi" };
+    (@e rdi) => { "edi" };
+    (@e rbp) => { "ebp" };
+    (@e rsp) => { "esp" };
+    (@e r8) => { "r8d" };
+    (@e r9) => { "r9d" };
+    (@e r10) => { "r10d" };
+    (@e r11) => { "r11d" };
+    (@e r12) => { "r12d" };
+    (@e r13) => { "r13d" };
+    (@e r14) => { "r14d" };
+    (@e r15) => { "r15d" };
+    (@e {$name: ident}) => { concat!("{", stringify!($name), ":e}") };
+    (@e $($token: tt)*) => {
+        compile_error!("expected a 64-bit general purpose register or a placeholder")
+    };
+
+    (@chunk $value: tt, $shift: literal) => {
+        concat!("(((", $crate::asm_block!($value), ") >> ", $shift, ") & 0xffff)")
+    };
+    // whether the chunks below the shift are all zeros or all ones
+    (@zeros $value: tt, $shift: literal) => {
+        concat!("(((", $crate::asm_block!($value), ") & ((1 << ", $shift, ") - 1)) == 0)")
+    };
+    (@ones $value: tt, $shift: literal) => {
+        concat!("(((~(", $crate::asm_block!($value), ")) & ((1 << ", $shift, ") - 1)) == 0)")
+    };
+    (@movz $reg: tt, $value: tt, $shift: literal) => {
+        concat!(
+            ".if ", $crate::asm_mov_imm!(@chunk $value, $shift), " != 0\n",
+            ".if ", $crate::asm_mov_imm!(@zeros $value, $shift), "\n",
+            "movz ", $crate::asm_block!($reg), ", #", $crate::asm_mov_imm!(@chunk $value, $shift),
+            ", lsl #", $shift, "\n",
+            ".else\n",
+            "movk ", $crate::asm_block!($reg), ", #", $crate::asm_mov_imm!(@chunk $value, $shift),
+            ", lsl #", $shift, "\n",
+            ".endif\n",
+            ".endif\n"
+        )
+    };
+    (@movn $reg: tt, $value: tt, $shift: literal) => {
+        concat!(
+            ".if ", $crate::asm_mov_imm!(@chunk $value, $shift), " != 0xffff\n",
+            ".if ", $crate::asm_mov_imm!(@ones $value, $shift), "\n",
+            "movn ", $crate::asm_block!($reg), ", #((~", $crate::asm_mov_imm!(@chunk $value, $shift),
+            ") & 0xffff), lsl #", $shift, "\n",
+            ".else\n",
+            "movk ", $crate::asm_block!($reg), ", #", $crate::asm_mov_imm!(@chunk $value, $shift),
+            ", lsl #", $shift, "\n",
+            ".endif\n",
+            ".endif\n"
+        )
+    };
+    // a comparison is -1 if true, so the sums are the negated counts
+    (@count $value: tt, $chunk: literal) => {
+        concat!(
+            "((", $crate::asm_mov_imm!(@chunk $value, 0), " == ", $chunk, ") + (",
+            $crate::asm_mov_imm!(@chunk $value, 16), " == ", $chunk, ") + (",
+            $crate::asm_mov_imm!(@chunk $value, 32), " == ", $chunk, ") + (",
+            $crate::asm_mov_imm!(@chunk $value, 48), " == ", $chunk, "))"
+        )
+    };
+
+    (aarch64, $reg: tt, $value: tt $(,)?) => {
+        concat!(
+            ".if ", $crate::asm_mov_imm!(@count $value, "0xffff"), " < ",
+            $crate::asm_mov_imm!(@count $value, "0"), "\n",
+            ".if (", $crate::asm_block!($value), ") == -1\n",
+            "movn ", $crate::asm_block!($reg), ", #0\n",
+            ".endif\n",
+            $crate::asm_mov_imm!(@movn $reg, $value, 0),
+            $crate::asm_mov_imm!(@movn $reg, $value, 16),
+            $crate::asm_mov_imm!(@movn $reg, $value, 32),
+            $crate::asm_mov_imm!(@movn $reg, $value, 48),
+            ".else\n",
+            ".if (", $crate::asm_block!($value), ") == 0\n",
+            "movz ", $crate::asm_block!($reg), ", #0\n",
+            ".endif\n",
+            $crate::asm_mov_imm!(@movz $reg, $value, 0),
+            $crate::asm_mov_imm!(@movz $reg, $value, 16),
+            $crate::asm_mov_imm!(@movz $reg, $value, 32),
+            $crate::asm_mov_imm!(@movz $reg, $value, 48),
+            ".endif\n"
+        )
+    };
+    (x86_64, $reg: tt, $value: tt $(,)?) => {
+        concat!(
+            ".if ((", $crate::asm_block!($value), ") >= 0) && ((", $crate::asm_block!($value),
+            ") <= 0xffffffff)\n",
+            "mov ", $crate::asm_mov_imm!(@e $reg), " , (", $crate::asm_block!($value), ")\n",
+            ".elseif ((", $crate::asm_block!($value), ") >= -0x80000000) && ((",
+            $crate::asm_block!($value), ") <= 0x7fffffff)\n",
+            "mov ", $crate::asm_block!($reg), ", (", $crate::asm_block!($value), ")\n",
+            ".else\n",
+            "movabs ", $crate::asm_block!($reg), ", (", $crate::asm_block!($value), ")\n",
+            ".endif\n"
+        )
+    };
+    ($($token: tt)*) => {
+        compile_error!("expected `aarch64` or `x86_64`, a register and a value")
+    };
+}
+
+/// Place the pending literal pool here with `.ltorg`.
+///
+/// Constants loaded with `ldr <reg>, =<value>`, e.g. by [`asm_load_const!`],
+/// are collected into a pool which the assembler places at the end of the
+/// section. Since `ldr` only reaches ±4 KiB on ARM and ±1 MiB on AArch64, a
+/// long block has to place the pool earlier, at a point where execution never
+/// falls through, such as after an unconditional branch.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_load_const, asm_ltorg};
+/// let code = concat!(
+///     asm_load_const!(pool, r0, 0x12345678),
+///     asm_block!(bx lr;),
+///     asm_ltorg!(),
+/// );
+/// assert_eq!(code, "ldr r0 , =0x12345678 \nbx lr \n.ltorg\n");
+/// ```
+///
+/// [`asm_load_const!`]: macro.asm_load_const.html
+#[macro_export]
+macro_rules! asm_ltorg {
+    () => {
+        ".ltorg\n"
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_load_const, asm_mov_imm, asm_ltorg)]
+mod tests {
+    #[test]
+    fn test_load_const() {
+        assert_eq!(asm_load_const!(pool, x0, {c}), "ldr x0 , ={c}\n");
+        assert_eq!(asm_load_const!(pool, r1, sym), "ldr r1 , =sym \n");
+        assert_eq!(
+            asm_load_const!(arm, r2, (1 << 20)),
+            "movw r2 , #:lower16:((1 << 20 ) )\nmovt r2 , #:upper16:((1 << 20 ) )\n"
+        );
+        assert_eq!(
+            asm_load_const!(aarch64, x0, 0xdeadbeefcafe),
+            concat!(
+                "movz x0 , #(0xdeadbeefcafe ) & 0xffff\n",
+                "movk x0 , #((0xdeadbeefcafe ) >> 16) & 0xffff, lsl #16\n",
+                "movk x0 , #((0xdeadbeefcafe ) >> 32) & 0xffff, lsl #32\n",
+                "movk x0 , #((0xdeadbeefcafe ) >> 48) & 0xffff, lsl #48\n",
+            )
+        );
+        assert_eq!(asm_ltorg!(), ".ltorg\n");
+    }
+
+    #[test]
+    fn test_mov_imm() {
+        assert_eq!(
+            asm_mov_imm!(x86_64, {x}, {c}),
+            concat!(
+                ".if (({c}) >= 0) && (({c}) <= 0xffffffff)\n",
+                "mov {x:e} , ({c})\n",
+                ".elseif (({c}) >= -0x80000000) && (({c}) <= 0x7fffffff)\n",
+                "mov {x}, ({c})\n",
+                ".else\n",
+                "movabs {x}, ({c})\n",
+                ".endif\n",
+            )
+        );
+        assert!(asm_mov_imm!(x86_64, r10, 1).contains("mov r10d , (1 )\n"));
+        assert!(asm_mov_imm!(aarch64, x0, 1).contains("movz x0 , #(((1 ) >> 48) & 0xffff), lsl #48\n"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_mov_imm_assemble() {
+        use crate::test::{assert_disassembles, Arch};
+        let count = |arch, code| assert_disassembles(arch, code).map(|instructions| instructions.len());
+        for (code, expected) in [
+            (asm_mov_imm!(aarch64, x0, 0), 1),
+            (asm_mov_imm!(aarch64, x0, (-1)), 1),
+            (asm_mov_imm!(aarch64, x0, 0x12340000), 1),
+            (asm_mov_imm!(aarch64, x0, 0xffff1234ffffffff), 1),
+            (asm_mov_imm!(aarch64, x0, 0x0001000000000001), 2),
+            (asm_mov_imm!(aarch64, x0, 0xdeadbeefcafe), 3),
+            (asm_mov_imm!(aarch64, x0, 0x123456789abcdef0), 4),
+        ] {
+            if let Some(count) = count(Arch::AArch64, code) {
+                assert_eq!(count, expected, "{}", code);
+            }
+        }
+        for (code, expected) in [
+            (asm_mov_imm!(x86_64, rax, 0xffffffff), "mov eax, 4294967295"),
+            (asm_mov_imm!(x86_64, rax, (-5)), "mov rax, -5"),
+            (asm_mov_imm!(x86_64, r8, 0x100000000), "movabs r8, 4294967296"),
+        ] {
+            if let Some(instructions) = assert_disassembles(Arch::X86_64, code) {
+                assert_eq!(instructions[0].text, expected);
+            }
+        }
+    }
+}
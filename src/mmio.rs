@@ -0,0 +1,227 @@
+/// Read a memory-mapped device register.
+///
+/// This evaluates to a `&'static str` which loads `reg` from the address in
+/// the register `addr`, followed by the barrier which keeps later memory
+/// accesses, including to normal memory, from being done before the load, as
+/// `readl` of Linux does:
+///
+/// | Architecture | Load                  | Barrier       |
+/// |--------------|-----------------------|---------------|
+/// | x86, x86-64  | `mov reg, [addr]`     |               |
+/// | ARM          | `ldr reg, [addr]`     | `dsb`         |
+/// | AArch64      | `ldr reg, [addr]`     | `dmb oshld`   |
+/// | RISC-V       | `lw reg, 0(addr)`     | `fence i, r`  |
+///
+/// x86 orders uncached accesses by itself, so nothing is added there. The
+/// access has the width of `reg`, except on RISC-V, where it is 32 bits. Any
+/// width can be given first, e.g. `mmio_read!(16, reg, addr)`, which selects
+/// `ldrh`, `lhu` and `word ptr`, and 8-bit reads are zero-extended.
+///
+/// The compiler only keeps the access if the block may read memory, so it
+/// must not have the `nomem`, `readonly` or `pure` options, and each fragment
+/// is exactly one access, which the assembler will not merge or split.
+///
+/// # Example
+/// ```
+/// use asm_block::mmio_read;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(mmio_read!(16, {v:w}, {a}), "ldrh {v:w}, [{a}] \ndmb oshld \n");
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(mmio_read!({v:e}, {a}), "mov {v:e}, [{a}]\n");
+/// ```
+#[macro_export]
+macro_rules! mmio_read {
+    ($width: tt, $reg: tt, $addr: tt $(,)?) => {
+        $crate::__asm_mmio!(read $width $reg $addr)
+    };
+    ($reg: tt, $addr: tt $(,)?) => {
+        $crate::__asm_mmio!(read _ $reg $addr)
+    };
+}
+
+/// Write a memory-mapped device register.
+///
+/// This evaluates to a `&'static str` which stores `reg` to the address in the
+/// register `addr`, preceded by the barrier which keeps earlier memory
+/// accesses from being done after the store, as `writel` of Linux does, e.g.
+/// so that a device sees a descriptor written to normal memory before being
+/// told where it is:
+///
+/// | Architecture | Barrier       | Store                 |
+/// |--------------|---------------|-----------------------|
+/// | x86, x86-64  |               | `mov [addr], reg`     |
+/// | ARM          | `dsb st`      | `str reg, [addr]`     |
+/// | AArch64      | `dmb oshst`   | `str reg, [addr]`     |
+/// | RISC-V       | `fence w, o`  | `sw reg, 0(addr)`     |
+///
+/// The width is chosen as for [`mmio_read!`], and the same rules apply to
+/// the block, which must not have the `nomem` or `readonly` options. Writes
+/// to write-combining memory on x86 must still be flushed with `sfence`.
+///
+/// # Example
+/// ```
+/// use asm_block::mmio_write;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(mmio_write!({a}, {v:w}), "dmb oshst\nstr {v:w}, [{a}] \n");
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(mmio_write!(8, {a}, {v:l}), "mov byte ptr [{a}], {v:l}\n");
+/// ```
+///
+/// [`mmio_read!`]: macro.mmio_read.html
+#[macro_export]
+macro_rules! mmio_write {
+    ($width: tt, $addr: tt, $reg: tt $(,)?) => {
+        $crate::__asm_mmio!(write $width $reg $addr)
+    };
+    ($addr: tt, $reg: tt $(,)?) => {
+        $crate::__asm_mmio!(write _ $reg $addr)
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_mmio {
+    (@ptr _) => { "" };
+    (@ptr 8) => { "byte ptr " };
+    (@ptr 16) => { "word ptr " };
+    (@ptr 32) => { "dword ptr " };
+    (@ptr 64) => { "qword ptr " };
+    (@ptr $width: tt) => {
+        compile_error!(concat!("expected a width of 8, 16, 32 or 64 bits, found `", stringify!($width), "`"))
+    };
+
+    (read 8 $reg: tt $addr: tt) => {
+        concat!($crate::asm_block!(movzx $reg,), "byte ptr [", $crate::asm_block!($addr), "]\n")
+    };
+    (read $width: tt $reg: tt $addr: tt) => {
+        concat!($crate::asm_block!(mov $reg,), $crate::__asm_mmio!(@ptr $width), "[", $crate::asm_block!($addr), "]\n")
+    };
+    (write $width: tt $reg: tt $addr: tt) => {
+        concat!("mov ", $crate::__asm_mmio!(@ptr $width), "[", $crate::asm_block!($addr), "], ", $crate::asm_block!($reg;))
+    };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_mmio {
+    (@suffix _) => { "" };
+    (@suffix 8) => { "b" };
+    (@suffix 16) => { "h" };
+    (@suffix 32) => { "" };
+    (@suffix $width: tt) => {
+        compile_error!(concat!("expected a width of 8, 16 or 32 bits, found `", stringify!($width), "`"))
+    };
+
+    (read $width: tt $reg: tt $addr: tt) => {
+        concat!("ldr", $crate::__asm_mmio!(@suffix $width), " ", $crate::asm_block!($reg, [$addr]; dsb;))
+    };
+    (write $width: tt $reg: tt $addr: tt) => {
+        concat!("dsb st\nstr", $crate::__asm_mmio!(@suffix $width), " ", $crate::asm_block!($reg, [$addr];))
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_mmio {
+    (@suffix _) => { "" };
+    (@suffix 8) => { "b" };
+    (@suffix 16) => { "h" };
+    (@suffix 32) => { "" };
+    (@suffix 64) => { "" };
+    (@suffix $width: tt) => {
+        compile_error!(concat!("expected a width of 8, 16, 32 or 64 bits, found `", stringify!($width), "`"))
+    };
+
+    (read $width: tt $reg: tt $addr: tt) => {
+        concat!("ldr", $crate::__asm_mmio!(@suffix $width), " ", $crate::asm_block!($reg, [$addr]; dmb oshld;))
+    };
+    (write $width: tt $reg: tt $addr: tt) => {
+        concat!("dmb oshst\nstr", $crate::__asm_mmio!(@suffix $width), " ", $crate::asm_block!($reg, [$addr];))
+    };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_mmio {
+    (@load _) => { "lw " };
+    (@load 8) => { "lbu " };
+    (@load 16) => { "lhu " };
+    (@load 32) => { "lw " };
+    (@load 64) => { "ld " };
+    (@store _) => { "sw " };
+    (@store 8) => { "sb " };
+    (@store 16) => { "sh " };
+    (@store 32) => { "sw " };
+    (@store 64) => { "sd " };
+    (@load $width: tt) => {
+        compile_error!(concat!("expected a width of 8, 16, 32 or 64 bits, found `", stringify!($width), "`"))
+    };
+    (@store $width: tt) => {
+        compile_error!(concat!("expected a width of 8, 16, 32 or 64 bits, found `", stringify!($width), "`"))
+    };
+
+    (read $width: tt $reg: tt $addr: tt) => {
+        concat!($crate::__asm_mmio!(@load $width), $crate::asm_block!($reg, 0($addr);), "fence i, r\n")
+    };
+    (write $width: tt $reg: tt $addr: tt) => {
+        concat!("fence w, o\n", $crate::__asm_mmio!(@store $width), $crate::asm_block!($reg, 0($addr);))
+    };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_mmio {
+    ($($token: tt)*) => {
+        compile_error!("MMIO accesses are only supported on x86, x86_64, ARM, AArch64 and RISC-V")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(mmio_read, mmio_write)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_mmio() {
+        assert_eq!(mmio_read!(8, {v:e}, {a}), "movzx {v:e}, byte ptr [{a}]\n");
+        assert_eq!(mmio_write!({a}, rax), "mov [{a}], rax \n");
+
+        let mut register: u32 = 0x1234_5678;
+        let value: u32;
+        unsafe {
+            core::arch::asm!(
+                mmio_write!(16, {a}, {w:x}),
+                mmio_read!({v:e}, {a}),
+                a = in(reg) &mut register,
+                w = in(reg) 0xabcd,
+                v = out(reg) value,
+                options(nostack),
+            );
+        }
+        assert_eq!(value, 0x1234_abcd);
+        assert_eq!(register, value);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_mmio_assemble() {
+        use crate::test::{assert_assembles, Arch};
+        #[cfg(target_arch = "x86_64")]
+        let (arch, code) = (Arch::X86_64, concat!(mmio_read!(64, rax, rdi), mmio_write!(32, rdi, eax)));
+        #[cfg(target_arch = "aarch64")]
+        let (arch, code) = (Arch::AArch64, concat!(mmio_read!(x0, x1), mmio_write!(8, x1, w0)));
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        assert_assembles(arch, code);
+    }
+}
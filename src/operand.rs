@@ -0,0 +1,244 @@
+//! Typed operands for fragment libraries.
+//!
+//! Fragments take their operands as raw `tt`s, so nothing tells which
+//! registers a fragment reads or writes, or what they hold. [`asm_operands!`]
+//! declares the register interface of a fragment as a struct whose fields are
+//! wrapped in [`In`], [`Out`] or [`InOut`], and generates a macro invoking
+//! [`asm!`] with a template and the operands bound to the fields, so that the
+//! interface is documented and type-checked in one place:
+//! ```
+//! use asm_block::asm_operands;
+//! use asm_block::operand::{In, InOut, Out};
+//! asm_operands! {
+//!     /// The registers of a byte copy.
+//!     pub struct Copy {
+//!         /// The destination, advanced past the copied bytes.
+//!         dst: InOut<*mut u8> = "rdi",
+//!         /// The source, advanced past the copied bytes.
+//!         src: InOut<*const u8> = "rsi",
+//!         /// The number of bytes, zero afterwards.
+//!         len: InOut<usize> = "rcx",
+//!         /// The value of `len` before the copy.
+//!         copied: Out<usize>,
+//!     }
+//!     macro copy_asm;
+//! }
+//!
+//! # #[cfg(target_arch = "x86_64")]
+//! # {
+//! let src = [1u8, 2, 3];
+//! let mut dst = [0u8; 3];
+//! let mut copy = Copy {
+//!     dst: InOut(dst.as_mut_ptr()),
+//!     src: InOut(src.as_ptr()),
+//!     len: InOut(src.len()),
+//!     copied: Out::default(),
+//! };
+//! unsafe { copy_asm!(copy, "mov {copied}, rcx\nrep movsb", options(nostack)) };
+//! assert_eq!(dst, src);
+//! assert_eq!(copy.len(), 0);
+//! assert_eq!(copy.copied(), 3);
+//! # }
+//! ```
+//!
+//! [`asm_operands!`]: ../macro.asm_operands.html
+//! [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+
+use core::ptr;
+
+/// A type which fits in a register and can be an operand of [`asm!`].
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+pub trait Register: Copy {
+    /// The value of an output before the block runs.
+    const ZERO: Self;
+}
+
+macro_rules! impl_register {
+    ($($ty: ty = $zero: expr),* $(,)?) => {
+        $(
+            impl Register for $ty {
+                const ZERO: Self = $zero;
+            }
+        )*
+    };
+}
+
+impl_register! {
+    u8 = 0, u16 = 0, u32 = 0, u64 = 0, u128 = 0, usize = 0,
+    i8 = 0, i16 = 0, i32 = 0, i64 = 0, i128 = 0, isize = 0,
+    f32 = 0.0, f64 = 0.0,
+}
+
+impl<T> Register for *const T {
+    const ZERO: Self = ptr::null();
+}
+
+impl<T> Register for *mut T {
+    const ZERO: Self = ptr::null_mut();
+}
+
+/// An operand read by the block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct In<T: Register>(pub T);
+
+/// An operand written by the block, whose initial value is ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Out<T: Register>(pub T);
+
+/// An operand read and written by the block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InOut<T: Register>(pub T);
+
+impl<T: Register> Default for Out<T> {
+    fn default() -> Self {
+        Out(T::ZERO)
+    }
+}
+
+/// Declare the operands of a fragment as a struct.
+///
+/// Every field is an [`In`], [`Out`] or [`InOut`] of a [`Register`] type,
+/// optionally followed by `= <class>` or `= "<register>"`, where the class
+/// defaults to `reg`. The struct gets an accessor returning the value of
+/// every field, and the macro named after `macro` takes a place holding the
+/// struct, a template, and other arguments of [`asm!`], and invokes [`asm!`]
+/// with the fields bound to the operands:
+/// - a field in a class is a named operand, used as `{field}` in the template,
+///   which must use it like every named operand of [`asm!`].
+/// - a field in an explicit register is passed in that register, which is
+///   used directly in the template, since [`asm!`] does not name them.
+///
+/// The other arguments are placed after the named operands and before the
+/// explicit registers, so they can be options, `clobber_abi`, or more named
+/// or explicit operands.
+///
+/// See the [module documentation](operand/index.html) for an example.
+///
+/// [`In`]: operand/struct.In.html
+/// [`Out`]: operand/struct.Out.html
+/// [`InOut`]: operand/struct.InOut.html
+/// [`Register`]: operand/trait.Register.html
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+#[macro_export]
+macro_rules! asm_operands {
+    // sort the fields into named operands and explicit registers
+    (@split ($d: tt) $header: tt $named: tt $explicit: tt ($field: ident In $spec: tt) $($rest: tt)*) => {
+        $crate::asm_operands!(@split ($d) $header $named $explicit ($field in $spec) $($rest)*);
+    };
+    (@split ($d: tt) $header: tt $named: tt $explicit: tt ($field: ident Out $spec: tt) $($rest: tt)*) => {
+        $crate::asm_operands!(@split ($d) $header $named $explicit ($field out $spec) $($rest)*);
+    };
+    (@split ($d: tt) $header: tt $named: tt $explicit: tt ($field: ident InOut $spec: tt) $($rest: tt)*) => {
+        $crate::asm_operands!(@split ($d) $header $named $explicit ($field inout $spec) $($rest)*);
+    };
+    (@split ($d: tt) $header: tt $named: tt $explicit: tt ($field: ident $op: tt ()) $($rest: tt)*) => {
+        $crate::asm_operands!(@split ($d) $header $named $explicit ($field $op (reg)) $($rest)*);
+    };
+    (@split ($d: tt) [$operands: ident $($header: tt)*] [$($named: tt)*] $explicit: tt ($field: ident $op: tt ($class: ident)) $($rest: tt)*) => {
+        $crate::asm_operands!(@split ($d) [$operands $($header)*] [$($named)* , $field = $op($class) $operands.$field.0] $explicit $($rest)*);
+    };
+    (@split ($d: tt) [$operands: ident $($header: tt)*] $named: tt [$($explicit: tt)*] ($field: ident $op: tt ($register: tt)) $($rest: tt)*) => {
+        $crate::asm_operands!(@split ($d) [$operands $($header)*] $named [$($explicit)* , $op($register) $operands.$field.0] $($rest)*);
+    };
+    (@split ($d: tt) $header: tt [$($named: tt)*] [$($explicit: tt)*]) => {
+        $crate::asm_operands!(@define ($d) $header [$($named)*] [$($explicit)*]);
+    };
+
+    (@define ($d: tt) [$operands: ident $name: ident $(#[$attr: meta])* $mac: ident] [$($named: tt)*] [$($explicit: tt)*]) => {
+        $(#[$attr])*
+        macro_rules! $mac {
+            ($d operands: expr, $d template: expr $d (, $d ($d rest: tt)*)?) => {{
+                let $operands: &mut $name = &mut $d operands;
+                ::core::arch::asm!($d template $($named)* $d (, $d ($d rest)*)? $($explicit)*)
+            }};
+        }
+    };
+
+    (
+        $(#[$attr: meta])*
+        $vis: vis struct $name: ident {
+            $(
+                $(#[$field_attr: meta])*
+                $field: ident: $kind: ident<$ty: ty> $(= $class: tt)?
+            ),* $(,)?
+        }
+        $(#[$macro_attr: meta])*
+        macro $mac: ident;
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $(
+                $(#[$field_attr])*
+                pub $field: $crate::operand::$kind<$ty>,
+            )*
+        }
+
+        impl $name {
+            $(
+                #[doc = concat!("The value of `", stringify!($field), "`.")]
+                #[allow(dead_code)]
+                $vis fn $field(&self) -> $ty {
+                    self.$field.0
+                }
+            )*
+        }
+
+        $crate::asm_operands!(@split ($) [__operands $name $(#[$macro_attr])* $mac] [] [] $(($field $kind ($($class)?)))*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        assert_eq!(Out::<u32>::default(), Out(0));
+        assert_eq!(Out::<*mut u8>::default(), Out(ptr::null_mut()));
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_operands() {
+        asm_operands! {
+            struct Add {
+                /// The sum.
+                x: InOut<u64>,
+                y: In<u64> = reg_abcd,
+                carry: Out<u8> = reg_byte,
+            }
+            macro add_asm;
+        }
+
+        asm_operands! {
+            struct Swap {
+                a: InOut<*const u8> = "rax",
+                b: InOut<*const u8> = "rdx",
+            }
+            macro swap_asm;
+        }
+
+        let mut add = Add {
+            x: InOut(u64::MAX),
+            y: In(2),
+            carry: Out::default(),
+        };
+        unsafe {
+            add_asm!(
+                add,
+                "add {x}, {y}\nsetc {carry}",
+                options(pure, nomem, nostack)
+            )
+        };
+        assert_eq!((add.x(), add.y(), add.carry()), (1, 2, 1));
+
+        let mut swap = Swap {
+            a: InOut(ptr::null()),
+            b: InOut(b"".as_ptr()),
+        };
+        unsafe { swap_asm!(swap, "xchg rax, rdx") };
+        assert_eq!(swap.a(), b"".as_ptr());
+        assert!(swap.b().is_null());
+    }
+}
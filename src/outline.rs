@@ -0,0 +1,280 @@
+/// Define a block once, out of line, as a function to call from fragments.
+///
+/// A fragment instantiated identically in many functions is copied into each
+/// of them by [`asm!`]. This expands to a [`global_asm!`] item instead, which
+/// defines the block, transcribed by [`asm_block!`], as the function `name`,
+/// followed by a return. [`call_outlined!`] then emits the call in the
+/// fragments, trading the inlining of the block for the size of the code.
+///
+/// The function is placed in its own section, which the linker deduplicates,
+/// so that the crates defining the same function with the same block keep a
+/// single copy of it:
+///
+/// | Format | Section                                              | Symbol                    |
+/// |--------|------------------------------------------------------|---------------------------|
+/// | ELF    | `.text.name`, in the COMDAT group `name`             | weak and hidden           |
+/// | Mach-O | `__TEXT,__text`                                      | weak definition, private  |
+/// | COFF   | `.text$name`, with the COMDAT selection `discard`    | global                    |
+///
+/// The block runs with the registers of the caller, so it must use fixed
+/// registers rather than placeholders of register operands, which are bound
+/// to different registers at each call site, and every register it modifies
+/// must be declared by the [`asm!`] making the call, together with the return
+/// address, i.e. the stack on x86, `x30` on AArch64, `lr` on ARM and `ra` on
+/// RISC-V. `const` and `sym` operands of [`global_asm!`] can follow the block.
+/// The name should be unique to the block, e.g. prefixed by the name of the
+/// crate, since two different blocks with the same name are merged as well.
+/// A crate must define it once, e.g. a library of fragments next to the
+/// macro of the fragment, which its dependents then call without defining it.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_outline, call_outlined};
+/// # #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+/// asm_outline!(my_crate_triple, {
+///     lea rax, [rax + rax * 2];
+/// });
+///
+/// # #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+/// fn triple(mut x: u64) -> u64 {
+///     unsafe { core::arch::asm!(call_outlined!(my_crate_triple), inout("rax") x) };
+///     x
+/// }
+/// # fn main() {
+/// # #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+/// assert_eq!(triple(2), 6);
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(call_outlined!(my_crate_triple), "call my_crate_triple \n");
+/// # }
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+/// [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+/// [`asm_block!`]: macro.asm_block.html
+/// [`call_outlined!`]: macro.call_outlined.html
+#[macro_export]
+macro_rules! asm_outline {
+    ($name: ident, { $($token: tt)* } $(, $($operand: tt)*)?) => {
+        ::core::arch::global_asm!(
+            concat!(
+                $crate::__asm_outline!(@begin $name),
+                ".p2align 4\n",
+                stringify!($name), ":\n",
+                $crate::asm_block!($($token)*),
+                $crate::__asm_outline_arch!(@return),
+                $crate::__asm_outline!(@end $name)
+            )
+            $(, $($operand)*)?
+        );
+    };
+}
+
+/// Call a block defined by [`asm_outline!`].
+///
+/// This evaluates to a `&'static str` with the call of the function of the
+/// target: `call name` on x86 and RISC-V, and `bl name` on AArch64 and ARM.
+///
+/// [`asm_outline!`]: macro.asm_outline.html
+#[macro_export]
+macro_rules! call_outlined {
+    ($name: ident $(,)?) => {
+        $crate::__asm_outline_arch!(@call $name)
+    };
+}
+
+/// Wrap a fragment as a subroutine of the block which uses it.
+///
+/// A large fragment used several times in one [`asm!`] block is copied each
+/// time. This evaluates to a `&'static str` which emits the template once
+/// instead, as a subroutine under the numeric label `42100`, which is jumped
+/// over, and calls it. `outline!(call)` then calls the subroutine again, i.e.
+/// the last one defined before it, so that several fragments can be outlined
+/// one after another in the same block:
+///
+/// | Architecture | Call           | Return  | Return address |
+/// |--------------|----------------|---------|----------------|
+/// | x86, x86-64  | `call 42100b`  | `ret`   | stack          |
+/// | AArch64      | `bl 42100b`    | `ret`   | `x30`          |
+/// | ARM          | `bl 42100b`    | `bx lr` | `lr`           |
+/// | RISC-V       | `jal 42100b`   | `ret`   | `ra`           |
+///
+/// Unlike with [`asm_outline!`], the subroutine belongs to the block, so the
+/// template can use the placeholders of its operands. The block must not use
+/// `options(nostack)` on x86, and must declare the register of the return
+/// address as clobbered elsewhere, e.g. with `out("x30") _`.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, outline};
+/// macro_rules! scale {
+///     ($x: tt) => {
+///         asm_block! {
+///             lea $x, [$x + $x * 2];
+///         }
+///     };
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     concat!(outline!(scale!({x})), outline!(call)),
+///     "jmp 42101f\n42100:\nlea {x}, [{x}+ {x}* 2 ] \nret\n42101:\ncall 42100b\ncall 42100b\n"
+/// );
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+/// [`asm_outline!`]: macro.asm_outline.html
+#[macro_export]
+macro_rules! outline {
+    (call $(,)?) => {
+        $crate::__asm_outline_arch!(@local)
+    };
+    ($template: expr $(,)?) => {
+        concat!(
+            $crate::__asm_outline_arch!(@skip),
+            "42100:\n",
+            $template,
+            $crate::__asm_outline_arch!(@return),
+            "42101:\n",
+            $crate::__asm_outline_arch!(@local)
+        )
+    };
+}
+
+#[cfg(not(any(target_vendor = "apple", windows, target_os = "uefi")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline {
+    (@begin $name: ident) => {
+        concat!(
+            ".pushsection .text.", stringify!($name), ", \"axG\", %progbits, ", stringify!($name), ", comdat\n",
+            ".weak ", stringify!($name), "\n.hidden ", stringify!($name), "\n",
+            ".type ", stringify!($name), ", %function\n"
+        )
+    };
+    (@end $name: ident) => {
+        concat!(".size ", stringify!($name), ", . - ", stringify!($name), "\n.popsection\n")
+    };
+}
+
+#[cfg(target_vendor = "apple")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline {
+    (@begin $name: ident) => {
+        concat!(
+            ".pushsection __TEXT,__text\n",
+            ".globl ", stringify!($name), "\n.weak_definition ", stringify!($name), "\n",
+            ".private_extern ", stringify!($name), "\n"
+        )
+    };
+    (@end $name: ident) => { ".popsection\n" };
+}
+
+#[cfg(any(windows, target_os = "uefi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline {
+    (@begin $name: ident) => {
+        concat!(
+            ".section .text$", stringify!($name), ", \"xr\", discard, ", stringify!($name), "\n",
+            ".globl ", stringify!($name), "\n"
+        )
+    };
+    (@end $name: ident) => { ".text\n" };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline_arch {
+    (@call $name: ident) => { $crate::asm_block!(call $name;) };
+    (@local) => { "call 42100b\n" };
+    (@skip) => { "jmp 42101f\n" };
+    (@return) => { "ret\n" };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline_arch {
+    (@call $name: ident) => { $crate::asm_block!(bl $name;) };
+    (@local) => { "bl 42100b\n" };
+    (@skip) => { "b 42101f\n" };
+    (@return) => { "ret\n" };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline_arch {
+    (@call $name: ident) => { $crate::asm_block!(bl $name;) };
+    (@local) => { "bl 42100b\n" };
+    (@skip) => { "b 42101f\n" };
+    (@return) => { "bx lr\n" };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline_arch {
+    (@call $name: ident) => { $crate::asm_block!(call $name;) };
+    (@local) => { "jal 42100b\n" };
+    (@skip) => { "j 42101f\n" };
+    (@return) => { "ret\n" };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "arm",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_outline_arch {
+    ($($token: tt)*) => {
+        compile_error!("outlined blocks are only supported on x86, x86_64, ARM, AArch64 and RISC-V")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_outline, outline)]
+mod tests {
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    asm_outline!(asm_block_test_scale, {
+        lea rax, [rax + rax * 2];
+        add rax, {bias};
+    }, bias = const 1);
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_outline() {
+        let mut x: u64 = 2;
+        unsafe {
+            core::arch::asm!(
+                call_outlined!(asm_block_test_scale),
+                call_outlined!(asm_block_test_scale),
+                inout("rax") x,
+            );
+        }
+        assert_eq!(x, 22);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_outline_local() {
+        let mut x: u64 = 1;
+        unsafe {
+            core::arch::asm!(
+                outline!(crate::asm_block!(lea {x}, [{x} + {x} * 2];)),
+                outline!(call),
+                outline!(crate::asm_block!(add {x}, {y};)),
+                outline!(call),
+                x = inout(reg) x,
+                y = in(reg) 2u64,
+            );
+        }
+        assert_eq!(x, 13);
+    }
+}
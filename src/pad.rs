@@ -0,0 +1,170 @@
+/// Pad a block to an exact size.
+///
+/// This evaluates to a `&'static str` which emits the block, transcribed by
+/// [`asm_block!`], after a `/* pad_to! */` comment and the numeric label
+/// `42090`, followed by as many NOPs as needed to reach `size` bytes from the
+/// label:
+///
+/// | Architecture | Padding                                         |
+/// |--------------|-------------------------------------------------|
+/// | x86, x86-64  | `nop`, 1 byte each                              |
+/// | AArch64      | `nop`, 4 bytes each                             |
+/// | ARM          | `mov r0, r0`, or `mov r8, r8` in Thumb          |
+/// | RISC-V       | `nop`, or `c.nop` with compressed instructions  |
+///
+/// On other architectures and on big-endian ones, the padding is made of zero
+/// bytes. The padding is computed by the assembler, so it is exact even if
+/// the size of the block is only known after the relaxation of branches, as
+/// is the case on x86, and the assembler rejects a block larger than `size`
+/// with `invalid number of bytes` at the padding. [`asm_assert!`] with
+/// `max_size` rejects it at compile time instead when the size of the block
+/// is bounded, and [`asm_size!`] counts a block after the comment as `size`
+/// bytes.
+///
+/// The size is transcribed by [`asm_block!`], so it can be a placeholder of a
+/// `const` operand or anything the assembler is able to evaluate to an
+/// absolute value. Values made of several tokens must be wrapped in
+/// parentheses. Padded blocks do not nest, since the label is shared.
+///
+/// # Example
+/// ```
+/// use asm_block::pad_to;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     pad_to!(5, { call {f}; }),
+///     "/* pad_to! */\n42090:\ncall {f}\n.skip 5 - (. - 42090b), 0x90\n"
+/// );
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+/// [`asm_assert!`]: macro.asm_assert.html
+/// [`asm_size!`]: macro.asm_size.html
+#[macro_export]
+macro_rules! pad_to {
+    ($size: tt, { $($block: tt)* } $(,)?) => {
+        concat!(
+            "/* pad_to! */\n42090:\n",
+            $crate::asm_block!($($block)*),
+            $crate::__asm_pad!($crate::asm_block!($size))
+        )
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad {
+    ($size: expr) => { concat!(".skip ", $size, "- (. - 42090b), 0x90\n") };
+}
+
+// fill the padding with NOPs of `width` bytes, the last bytes being zero if
+// they are too few for one
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad_fill {
+    ($size: expr, $width: literal, $nop: literal) => {
+        concat!(
+            ".fill (", $size, "- (. - 42090b)) / ", $width, ", ", $width, ", ", $nop, "\n",
+            ".skip ", $size, "- (. - 42090b)\n"
+        )
+    };
+}
+
+#[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad {
+    ($size: expr) => { $crate::__asm_pad_fill!($size, 4, "0xd503201f") };
+}
+
+#[cfg(all(target_arch = "arm", target_endian = "little", not(target_feature = "thumb-mode")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad {
+    ($size: expr) => { $crate::__asm_pad_fill!($size, 4, "0xe1a00000") };
+}
+
+#[cfg(all(target_arch = "arm", target_endian = "little", target_feature = "thumb-mode"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad {
+    ($size: expr) => { $crate::__asm_pad_fill!($size, 2, "0x46c0") };
+}
+
+#[cfg(all(
+    any(target_arch = "riscv32", target_arch = "riscv64"),
+    target_endian = "little",
+    not(target_feature = "c")
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad {
+    ($size: expr) => { $crate::__asm_pad_fill!($size, 4, "0x00000013") };
+}
+
+#[cfg(all(
+    any(target_arch = "riscv32", target_arch = "riscv64"),
+    target_endian = "little",
+    target_feature = "c"
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad {
+    ($size: expr) => { $crate::__asm_pad_fill!($size, 2, "0x0001") };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    all(
+        any(
+            target_arch = "aarch64",
+            target_arch = "arm",
+            target_arch = "riscv32",
+            target_arch = "riscv64"
+        ),
+        target_endian = "little"
+    )
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pad {
+    ($size: expr) => { concat!(".skip ", $size, "- (. - 42090b)\n") };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(pad_to)]
+mod tests {
+    #[test]
+    fn test_pad_size() {
+        let size = crate::asm_size!(pad_to!(0x80, { .skip {n}; }));
+        assert_eq!(size.exact(), Some(0x80));
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_pad_to() {
+        let (size, padding): (u64, u8);
+        unsafe {
+            core::arch::asm!(
+                "jmp 3f",
+                pad_to!(0x10, {
+                    xor {s:e}, {s:e};
+                    jz 2f;
+                    2:
+                }),
+                "3:",
+                "lea {s}, [rip + 3b]",
+                "lea {p}, [rip + 42090b]",
+                "sub {s}, {p}",
+                "mov {b}, [{p} + 0xf]",
+                s = out(reg) size,
+                p = out(reg) _,
+                b = out(reg_byte) padding,
+                options(pure, readonly, nostack),
+            );
+        }
+        assert_eq!(size, 0x10);
+        assert_eq!(padding, 0x90);
+    }
+}
@@ -0,0 +1,979 @@
+/// Copy memory with an unrolled vector loop.
+///
+/// This evaluates to a `&'static str` which copies `len` bytes from `src` to
+/// `dst`, `chunk` bytes per iteration of a loop unrolled over vector
+/// registers, and the remaining bytes one by one. `dst`, `src` and `len` are
+/// 64-bit registers or placeholders of `reg` operands, which are advanced past
+/// the copied bytes, with `len` ending at zero. The instruction set comes
+/// first, and selects the vector registers, which are clobbered along with the
+/// flags:
+///
+/// | Set    | Target  | Registers          | `chunk`                  |
+/// |--------|---------|--------------------|--------------------------|
+/// | `sse`  | x86-64  | `xmm0` to `xmm7`   | 16, 32, ..., 128         |
+/// | `avx`  | x86-64  | `ymm0` to `ymm7`   | 32, 64, ..., 256         |
+/// | `neon` | AArch64 | `v0` to `v7`       | 32, 64, 96 or 128        |
+///
+/// The chunk defaults to 64 bytes, and takes as many registers as it needs.
+/// The remaining bytes go through `xmm0` with `pinsrb` and `pextrb` on x86-64,
+/// so `sse` needs SSE4.1, and through `b0` on AArch64. `avx` does not clear
+/// the upper halves of the `ymm` registers afterwards, which the block should
+/// do with `vzeroupper` before returning to code not using AVX.
+///
+/// The loop uses the numeric labels `42010` to `42013`, so it can be
+/// duplicated by the compiler, as long as no branch of the block jumps over
+/// it to one of these labels.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::memcpy_loop;
+/// # #[cfg(target_arch = "x86_64")]
+/// unsafe fn copy(dst: &mut [u8], src: &[u8]) {
+///     assert_eq!(dst.len(), src.len());
+///     asm!(
+///         memcpy_loop!(avx, {dst}, {src}, {len}, chunk = 128),
+///         "vzeroupper",
+///         dst = inout(reg) dst.as_mut_ptr() => _,
+///         src = inout(reg) src.as_ptr() => _,
+///         len = inout(reg) src.len() => _,
+///         out("ymm0") _, out("ymm1") _, out("ymm2") _, out("ymm3") _,
+///         options(nostack),
+///     );
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// # if std::is_x86_feature_detected!("avx") {
+/// let src = [7u8; 300];
+/// let mut dst = [0u8; 300];
+/// unsafe { copy(&mut dst, &src) };
+/// assert_eq!(dst, src);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! memcpy_loop {
+    ($set: ident, $dst: tt, $src: tt, $len: tt $(, chunk = $chunk: tt)? $(,)?) => {
+        $crate::__asm_memory_loop!(@chunk $set [$($chunk)?] copy $dst $src $len)
+    };
+}
+
+/// Fill memory with an unrolled vector loop.
+///
+/// Like [`memcpy_loop!`], but fills `len` bytes at `dst` with the low byte of
+/// `value`, which is a 32-bit register or placeholder, e.g. `{v:e}` on x86-64
+/// or `{v:w}` on AArch64, and is left as is. The byte is broadcast to `xmm0`,
+/// `ymm0` or `v0`, which is the only vector register clobbered, so `avx`
+/// needs AVX2 for the broadcast.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::memset_loop;
+/// # #[cfg(target_arch = "x86_64")]
+/// # if std::is_x86_feature_detected!("sse4.1") {
+/// let mut buffer = [0u8; 100];
+/// unsafe {
+///     asm!(
+///         memset_loop!(sse, {dst}, {v:e}, {len}),
+///         dst = inout(reg) buffer.as_mut_ptr() => _,
+///         v = in(reg) 0xa5,
+///         len = inout(reg) buffer.len() => _,
+///         out("xmm0") _,
+///         options(nostack),
+///     );
+/// }
+/// assert_eq!(buffer, [0xa5; 100]);
+/// # }
+/// ```
+///
+/// [`memcpy_loop!`]: macro.memcpy_loop.html
+#[macro_export]
+macro_rules! memset_loop {
+    ($set: ident, $dst: tt, $value: tt, $len: tt $(, chunk = $chunk: tt)? $(,)?) => {
+        $crate::__asm_memory_loop!(@chunk $set [$($chunk)?] fill $dst $value $len)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_memory_loop {
+    (@chunk $set: ident [] $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@chunk $set [64] $($argument)*)
+    };
+    (@chunk sse [16] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op sse 16 [(xmm0 0)] $($argument)*)
+    };
+    (@chunk sse [32] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op sse 32 [(xmm0 0) (xmm1 16)] $($argument)*)
+    };
+    (@chunk sse [48] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op sse 48 [(xmm0 0) (xmm1 16) (xmm2 32)] $($argument)*)
+    };
+    (@chunk sse [64] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op sse 64 [
+                (xmm0 0) (xmm1 16) (xmm2 32) (xmm3 48)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk sse [80] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op sse 80 [
+                (xmm0 0) (xmm1 16) (xmm2 32) (xmm3 48)
+                (xmm4 64)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk sse [96] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op sse 96 [
+                (xmm0 0) (xmm1 16) (xmm2 32) (xmm3 48)
+                (xmm4 64) (xmm5 80)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk sse [112] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op sse 112 [
+                (xmm0 0) (xmm1 16) (xmm2 32) (xmm3 48)
+                (xmm4 64) (xmm5 80) (xmm6 96)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk sse [128] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op sse 128 [
+                (xmm0 0) (xmm1 16) (xmm2 32) (xmm3 48)
+                (xmm4 64) (xmm5 80) (xmm6 96) (xmm7 112)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk avx [32] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op avx 32 [(ymm0 0)] $($argument)*)
+    };
+    (@chunk avx [64] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op avx 64 [(ymm0 0) (ymm1 32)] $($argument)*)
+    };
+    (@chunk avx [96] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op avx 96 [(ymm0 0) (ymm1 32) (ymm2 64)] $($argument)*)
+    };
+    (@chunk avx [128] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op avx 128 [
+                (ymm0 0) (ymm1 32) (ymm2 64) (ymm3 96)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk avx [160] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op avx 160 [
+                (ymm0 0) (ymm1 32) (ymm2 64) (ymm3 96)
+                (ymm4 128)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk avx [192] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op avx 192 [
+                (ymm0 0) (ymm1 32) (ymm2 64) (ymm3 96)
+                (ymm4 128) (ymm5 160)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk avx [224] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op avx 224 [
+                (ymm0 0) (ymm1 32) (ymm2 64) (ymm3 96)
+                (ymm4 128) (ymm5 160) (ymm6 192)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk avx [256] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(
+            @$op avx 256 [
+                (ymm0 0) (ymm1 32) (ymm2 64) (ymm3 96)
+                (ymm4 128) (ymm5 160) (ymm6 192) (ymm7 224)
+            ]
+            $($argument)*
+        )
+    };
+    (@chunk $set: ident [$($chunk: tt)*] $($argument: tt)*) => {
+        compile_error!("expected `sse` or `avx` and a chunk of 1 to 8 vector registers on x86_64")
+    };
+
+    (@load sse) => { "movups" };
+    (@load avx) => { "vmovdqu" };
+    // move single bytes through `xmm0`, with the VEX encoding for AVX
+    (@insert sse $src: tt) => {
+        concat!("pinsrb xmm0, byte ptr [", $crate::asm_block!($src), "], 0\n")
+    };
+    (@insert avx $src: tt) => {
+        concat!("vpinsrb xmm0, xmm0, byte ptr [", $crate::asm_block!($src), "], 0\n")
+    };
+    (@extract sse $dst: tt) => {
+        concat!("pextrb byte ptr [", $crate::asm_block!($dst), "], xmm0, 0\n")
+    };
+    (@extract avx $dst: tt) => {
+        concat!("vpextrb byte ptr [", $crate::asm_block!($dst), "], xmm0, 0\n")
+    };
+    (@copy $set: ident $chunk: tt [$(($register: ident $offset: tt))+] $dst: tt $src: tt $len: tt) => {
+        concat!(
+            $crate::__asm_memory_loop!(@head $chunk $len),
+            $(
+                $crate::__asm_memory_loop!(@load $set), " ", stringify!($register), ", [",
+                $crate::asm_block!($src), " + ", stringify!($offset), "]\n",
+            )+
+            $(
+                $crate::__asm_memory_loop!(@load $set), " [", $crate::asm_block!($dst), " + ",
+                stringify!($offset), "], ", stringify!($register), "\n",
+            )+
+            "add ", $crate::asm_block!($src), ", ", stringify!($chunk), "\n",
+            $crate::__asm_memory_loop!(@next $chunk $dst $len),
+            $crate::__asm_memory_loop!(@insert $set $src),
+            $crate::__asm_memory_loop!(@extract $set $dst),
+            "inc ", $crate::asm_block!($src), "\n",
+            $crate::__asm_memory_loop!(@tail $dst $len)
+        )
+    };
+    (@broadcast sse $value: tt) => {
+        concat!(
+            "pinsrb xmm0, ", $crate::asm_block!($value), ", 0\n",
+            "punpcklbw xmm0, xmm0\npshuflw xmm0, xmm0, 0\npshufd xmm0, xmm0, 0\n"
+        )
+    };
+    (@broadcast avx $value: tt) => {
+        concat!("vmovd xmm0, ", $crate::asm_block!($value), "\nvpbroadcastb ymm0, xmm0\n")
+    };
+    (@fill $set: ident $chunk: tt [$(($register: ident $offset: tt))+] $dst: tt $value: tt $len: tt) => {
+        concat!(
+            $crate::__asm_memory_loop!(@broadcast $set $value),
+            $crate::__asm_memory_loop!(@head $chunk $len),
+            $(
+                $crate::__asm_memory_loop!(@load $set), " [", $crate::asm_block!($dst), " + ",
+                stringify!($offset), "], ", $crate::__asm_memory_loop!(@zero $set), "\n",
+            )+
+            $crate::__asm_memory_loop!(@next $chunk $dst $len),
+            $crate::__asm_memory_loop!(@extract $set $dst),
+            $crate::__asm_memory_loop!(@tail $dst $len)
+        )
+    };
+    (@zero sse) => { "xmm0" };
+    (@zero avx) => { "ymm0" };
+
+    // skip the loop if there is not a whole chunk, and open it otherwise
+    (@head $chunk: tt $len: tt) => {
+        concat!(
+            "cmp ", $crate::asm_block!($len), ", ", stringify!($chunk), "\n",
+            "jb 42011f\n",
+            "42010:\n"
+        )
+    };
+    // close the loop, and open the byte loop
+    (@next $chunk: tt $dst: tt $len: tt) => {
+        concat!(
+            "add ", $crate::asm_block!($dst), ", ", stringify!($chunk), "\n",
+            "sub ", $crate::asm_block!($len), ", ", stringify!($chunk), "\n",
+            "cmp ", $crate::asm_block!($len), ", ", stringify!($chunk), "\n",
+            "jae 42010b\n",
+            "42011:\n",
+            "test ", $crate::asm_block!($len), ", ", $crate::asm_block!($len), "\n",
+            "jz 42013f\n",
+            "42012:\n"
+        )
+    };
+    // close the byte loop
+    (@tail $dst: tt $len: tt) => {
+        concat!(
+            "inc ", $crate::asm_block!($dst), "\n",
+            "dec ", $crate::asm_block!($len), "\n",
+            "jnz 42012b\n",
+            "42013:\n"
+        )
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_memory_loop {
+    (@chunk $set: ident [] $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@chunk $set [64] $($argument)*)
+    };
+    (@chunk neon [32] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op neon 32 [(q0 q1)] $($argument)*)
+    };
+    (@chunk neon [64] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op neon 64 [(q0 q1) (q2 q3)] $($argument)*)
+    };
+    (@chunk neon [96] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op neon 96 [(q0 q1) (q2 q3) (q4 q5)] $($argument)*)
+    };
+    (@chunk neon [128] $op: ident $($argument: tt)*) => {
+        $crate::__asm_memory_loop!(@$op neon 128 [(q0 q1) (q2 q3) (q4 q5) (q6 q7)] $($argument)*)
+    };
+    (@chunk $set: ident [$($chunk: tt)*] $($argument: tt)*) => {
+        compile_error!("expected `neon` and a chunk of 2, 4, 6 or 8 vector registers on AArch64")
+    };
+
+    (@copy neon $chunk: tt [$(($first: ident $second: ident))+] $dst: tt $src: tt $len: tt) => {
+        concat!(
+            $crate::__asm_memory_loop!(@head $chunk $len),
+            $(
+                "ldp ", stringify!($first), ", ", stringify!($second), ", [",
+                $crate::asm_block!($src), "], #32\n",
+            )+
+            $(
+                "stp ", stringify!($first), ", ", stringify!($second), ", [",
+                $crate::asm_block!($dst), "], #32\n",
+            )+
+            $crate::__asm_memory_loop!(@next $chunk $len),
+            "ldr b0, [", $crate::asm_block!($src), "], #1\n",
+            "str b0, [", $crate::asm_block!($dst), "], #1\n",
+            $crate::__asm_memory_loop!(@tail $len)
+        )
+    };
+    (@fill neon $chunk: tt [$(($first: ident $second: ident))+] $dst: tt $value: tt $len: tt) => {
+        concat!(
+            "dup v0.16b, ", $crate::asm_block!($value), "\n",
+            $crate::__asm_memory_loop!(@head $chunk $len),
+            $(
+                $crate::__asm_memory_loop!(@store $first), "[", $crate::asm_block!($dst), "], #32\n",
+            )+
+            $crate::__asm_memory_loop!(@next $chunk $len),
+            "str b0, [", $crate::asm_block!($dst), "], #1\n",
+            $crate::__asm_memory_loop!(@tail $len)
+        )
+    };
+    // every pair of registers of the chunk stores `v0` twice
+    (@store $register: ident) => { "stp q0, q0, " };
+
+    (@head $chunk: tt $len: tt) => {
+        concat!(
+            "cmp ", $crate::asm_block!($len), ", #", stringify!($chunk), "\n",
+            "b.lo 42011f\n",
+            "42010:\n"
+        )
+    };
+    (@next $chunk: tt $len: tt) => {
+        concat!(
+            "sub ", $crate::asm_block!($len), ", ", $crate::asm_block!($len), ", #", stringify!($chunk), "\n",
+            "cmp ", $crate::asm_block!($len), ", #", stringify!($chunk), "\n",
+            "b.hs 42010b\n",
+            "42011:\n",
+            "cbz ", $crate::asm_block!($len), ", 42013f\n",
+            "42012:\n"
+        )
+    };
+    (@tail $len: tt) => {
+        concat!(
+            "subs ", $crate::asm_block!($len), ", ", $crate::asm_block!($len), ", #1\n",
+            "b.ne 42012b\n",
+            "42013:\n"
+        )
+    };
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_memory_loop {
+    ($($token: tt)*) => {
+        compile_error!("memory loops are only supported on x86_64 and AArch64")
+    };
+}
+
+/// Find a byte with a vector scan.
+///
+/// This evaluates to a `&'static str` which advances `ptr` to the first byte
+/// equal to the low byte of `value`, scanning a vector at a time, like
+/// `memchr` without a length, or `strlen` for the byte 0. The scan loads whole
+/// aligned vectors, which never cross a page, so it does not fault past the
+/// byte found, but the byte must be there. The bytes before `ptr` in its first
+/// vector are ignored. The instruction set comes first:
+///
+/// | Set    | Target  | Vector | Needs         | Clobbers             |
+/// |--------|---------|--------|---------------|----------------------|
+/// | `sse`  | x86-64  | 16     | SSE2          | `xmm0`, `xmm1`       |
+/// | `avx`  | x86-64  | 32     | AVX2, BMI1/2  | `ymm0`, `ymm1`       |
+/// | `neon` | AArch64 | 16     |               | `v0`, `v1`, `v2`     |
+///
+/// `ptr` is a 64-bit register or placeholder, `value` a 32-bit one, e.g.
+/// `{v:e}` or `{v:w}`, which is left as is, and `mask` and `offset` are
+/// 64-bit scratch registers, clobbered along with the flags. On x86-64 with
+/// `sse`, `offset` must be `rcx`, since the first vector is shifted by `cl`.
+///
+/// The matches of a vector are turned into a bit mask with `pcmpeqb` and
+/// `pmovmskb` on x86-64, whose lowest set bit is found with `bsf` or `tzcnt`.
+/// On AArch64, `cmeq` and `umaxp` test whether a vector has a match, and only
+/// the vector with the match is narrowed into a mask with `shrn`, with 4 bits
+/// per byte. The scan uses the numeric labels `42030` to `42032`.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::byte_find;
+/// # #[cfg(target_arch = "x86_64")]
+/// unsafe fn strlen(s: *const u8) -> usize {
+///     let end: *const u8;
+///     asm!(
+///         byte_find!(sse, {p}, {v:e}, {m}, rcx),
+///         p = inout(reg) s => end,
+///         v = in(reg) 0,
+///         m = out(reg) _,
+///         out("rcx") _,
+///         out("xmm0") _,
+///         out("xmm1") _,
+///         options(pure, readonly, nostack),
+///     );
+///     end.offset_from(s) as usize
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(unsafe { strlen(b"hello, world\0".as_ptr()) }, 12);
+/// ```
+#[macro_export]
+macro_rules! byte_find {
+    ($set: ident, $ptr: tt, $value: tt, $mask: tt, $offset: tt $(,)?) => {
+        $crate::__asm_byte_find!($set, $ptr, $value, $mask, $offset)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_byte_find {
+    (@splat sse $value: tt) => {
+        concat!(
+            "movd xmm0, ", $crate::asm_block!($value), "\n",
+            "punpcklbw xmm0, xmm0\npshuflw xmm0, xmm0, 0\npshufd xmm0, xmm0, 0\n"
+        )
+    };
+    (@splat avx $value: tt) => {
+        concat!("vmovd xmm0, ", $crate::asm_block!($value), "\nvpbroadcastb ymm0, xmm0\n")
+    };
+    (@compare sse $ptr: tt $mask: tt) => {
+        concat!(
+            "movdqa xmm1, [", $crate::asm_block!($ptr), "]\n",
+            "pcmpeqb xmm1, xmm0\n",
+            "pmovmskb ", $crate::asm_block!($mask), ", xmm1\n"
+        )
+    };
+    (@compare avx $ptr: tt $mask: tt) => {
+        concat!(
+            "vmovdqa ymm1, [", $crate::asm_block!($ptr), "]\n",
+            "vpcmpeqb ymm1, ymm1, ymm0\n",
+            "vpmovmskb ", $crate::asm_block!($mask), ", ymm1\n"
+        )
+    };
+    (@shift sse $mask: tt $offset: tt) => {
+        concat!("shr ", $crate::asm_block!($mask), ", cl\n")
+    };
+    (@shift avx $mask: tt $offset: tt) => {
+        concat!(
+            "shrx ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), ", ",
+            $crate::asm_block!($offset), "\n"
+        )
+    };
+    (@lowest sse $mask: tt) => {
+        concat!("bsf ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), "\n")
+    };
+    (@lowest avx $mask: tt) => {
+        concat!("tzcnt ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), "\n")
+    };
+    (@width sse) => { "16" };
+    (@width avx) => { "32" };
+
+    (@scan $set: ident, $ptr: tt, $value: tt, $mask: tt, $offset: tt) => {
+        concat!(
+            $crate::__asm_byte_find!(@splat $set $value),
+            // ignore the bytes of the first vector before the pointer
+            "mov ", $crate::asm_block!($offset), ", ", $crate::asm_block!($ptr), "\n",
+            "and ", $crate::asm_block!($offset), ", ", $crate::__asm_byte_find!(@width $set), " - 1\n",
+            "and ", $crate::asm_block!($ptr), ", -", $crate::__asm_byte_find!(@width $set), "\n",
+            $crate::__asm_byte_find!(@compare $set $ptr $mask),
+            $crate::__asm_byte_find!(@shift $set $mask $offset),
+            "test ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), "\n",
+            "jnz 42031f\n",
+            "42030:\n",
+            "add ", $crate::asm_block!($ptr), ", ", $crate::__asm_byte_find!(@width $set), "\n",
+            $crate::__asm_byte_find!(@compare $set $ptr $mask),
+            "test ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), "\n",
+            "jz 42030b\n",
+            $crate::__asm_byte_find!(@lowest $set $mask),
+            "add ", $crate::asm_block!($ptr), ", ", $crate::asm_block!($mask), "\n",
+            "jmp 42032f\n",
+            "42031:\n",
+            $crate::__asm_byte_find!(@lowest $set $mask),
+            "add ", $crate::asm_block!($ptr), ", ", $crate::asm_block!($offset), "\n",
+            "add ", $crate::asm_block!($ptr), ", ", $crate::asm_block!($mask), "\n",
+            "42032:\n"
+        )
+    };
+    (sse, $($argument: tt)*) => {
+        $crate::__asm_byte_find!(@scan sse, $($argument)*)
+    };
+    (avx, $($argument: tt)*) => {
+        $crate::__asm_byte_find!(@scan avx, $($argument)*)
+    };
+    ($set: ident, $($argument: tt)*) => {
+        compile_error!("expected `sse` or `avx` on x86_64")
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_byte_find {
+    // narrow the matches of `v1` into a mask of 4 bits per byte
+    (@narrow $mask: tt) => {
+        concat!("shrn v1.8b, v1.8h, #4\nfmov ", $crate::asm_block!($mask), ", d1\n")
+    };
+    (@lowest $mask: tt) => {
+        concat!(
+            "rbit ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), "\n",
+            "clz ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), "\n"
+        )
+    };
+
+    (neon, $ptr: tt, $value: tt, $mask: tt, $offset: tt) => {
+        concat!(
+            "dup v0.16b, ", $crate::asm_block!($value), "\n",
+            // ignore the bytes of the first vector before the pointer
+            "and ", $crate::asm_block!($offset), ", ", $crate::asm_block!($ptr), ", #15\n",
+            "and ", $crate::asm_block!($ptr), ", ", $crate::asm_block!($ptr), ", #-16\n",
+            "ldr q1, [", $crate::asm_block!($ptr), "]\n",
+            "cmeq v1.16b, v1.16b, v0.16b\n",
+            $crate::__asm_byte_find!(@narrow $mask),
+            "lsl ", $crate::asm_block!($offset), ", ", $crate::asm_block!($offset), ", #2\n",
+            "lsr ", $crate::asm_block!($mask), ", ", $crate::asm_block!($mask), ", ",
+            $crate::asm_block!($offset), "\n",
+            "cbnz ", $crate::asm_block!($mask), ", 42031f\n",
+            "42030:\n",
+            "ldr q1, [", $crate::asm_block!($ptr), ", #16]!\n",
+            "cmeq v1.16b, v1.16b, v0.16b\n",
+            "umaxp v2.16b, v1.16b, v1.16b\n",
+            "fmov ", $crate::asm_block!($mask), ", d2\n",
+            "cbz ", $crate::asm_block!($mask), ", 42030b\n",
+            $crate::__asm_byte_find!(@narrow $mask),
+            $crate::__asm_byte_find!(@lowest $mask),
+            "add ", $crate::asm_block!($ptr), ", ", $crate::asm_block!($ptr), ", ",
+            $crate::asm_block!($mask), ", lsr #2\n",
+            "b 42032f\n",
+            "42031:\n",
+            $crate::__asm_byte_find!(@lowest $mask),
+            "add ", $crate::asm_block!($ptr), ", ", $crate::asm_block!($ptr), ", ",
+            $crate::asm_block!($offset), ", lsr #2\n",
+            "add ", $crate::asm_block!($ptr), ", ", $crate::asm_block!($ptr), ", ",
+            $crate::asm_block!($mask), ", lsr #2\n",
+            "42032:\n"
+        )
+    };
+    ($set: ident, $($argument: tt)*) => {
+        compile_error!("expected `neon` on AArch64")
+    };
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_byte_find {
+    ($($token: tt)*) => {
+        compile_error!("byte scans are only supported on x86_64 and AArch64")
+    };
+}
+
+/// Update a CRC-32 over memory with the instructions of the target.
+///
+/// This evaluates to a `&'static str` which folds `len` bytes at `ptr` into
+/// `crc`, 8 bytes per iteration of a loop, and the remaining 4, 2 and 1 bytes
+/// after it, without branching on each byte. The operands are the names of
+/// `reg` operands, like for [`read_cycles!`], since the tail accesses them
+/// with narrower widths. `ptr` is advanced past the bytes, `len` is
+/// clobbered, and so are the flags. The polynomial comes first:
+///
+/// | Polynomial   | Target  | Instructions            | Needs            |
+/// |--------------|---------|-------------------------|------------------|
+/// | `castagnoli` | x86-64  | `crc32`                 | SSE4.2           |
+/// | `castagnoli` | AArch64 | `crc32cx` to `crc32cb`  | `crc`            |
+/// | `ieee`       | AArch64 | `crc32x` to `crc32b`    | `crc`            |
+///
+/// AArch64 loads the bytes into the scratch operand `data` given last, which
+/// x86-64 does not take, since `crc32` reads memory. The assembler of AArch64
+/// rejects the instructions unless the function enables the target feature,
+/// e.g. with `#[target_feature(enable = "crc")]`.
+///
+/// Only the inner loop is emitted: the initial value and the final inversion
+/// of the CRC, usually `!0` and `!crc`, are left to the surrounding code, so
+/// that a checksum can be continued over several buffers or framed by other
+/// data. The loop uses the numeric labels `42040` to `42044`.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::crc32_loop;
+/// # #[cfg(target_arch = "x86_64")]
+/// unsafe fn crc32c(bytes: &[u8]) -> u32 {
+///     let crc: u64;
+///     asm!(
+///         crc32_loop!(castagnoli, crc, ptr, len),
+///         crc = inout(reg) 0xffff_ffffu64 => crc,
+///         ptr = inout(reg) bytes.as_ptr() => _,
+///         len = inout(reg) bytes.len() => _,
+///         options(pure, readonly, nostack),
+///     );
+///     !(crc as u32)
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// # if std::is_x86_feature_detected!("sse4.2") {
+/// assert_eq!(unsafe { crc32c(b"123456789") }, 0xe306_9283);
+/// # }
+/// ```
+///
+/// [`read_cycles!`]: macro.read_cycles.html
+#[macro_export]
+macro_rules! crc32_loop {
+    ($polynomial: ident, $crc: ident, $ptr: ident, $len: ident $(, $data: ident)? $(,)?) => {
+        $crate::__asm_crc32_loop!($polynomial, $crc, $ptr, $len $(, $data)?)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_crc32_loop {
+    (@tail $crc: ident, $ptr: ident, $len: ident, $bytes: tt, $size: tt, $label: tt) => {
+        concat!(
+            "test {", stringify!($len), "}, ", $bytes, "\n",
+            "jz ", $label, "f\n",
+            "crc32 {", stringify!($crc), ":e}, ", $size, " ptr [{", stringify!($ptr), "}]\n",
+            "add {", stringify!($ptr), "}, ", $bytes, "\n",
+            $label, ":\n"
+        )
+    };
+
+    (castagnoli, $crc: ident, $ptr: ident, $len: ident) => {
+        concat!(
+            "cmp {", stringify!($len), "}, 8\n",
+            "jb 42041f\n",
+            "42040:\n",
+            "crc32 {", stringify!($crc), "}, qword ptr [{", stringify!($ptr), "}]\n",
+            "add {", stringify!($ptr), "}, 8\n",
+            "sub {", stringify!($len), "}, 8\n",
+            "cmp {", stringify!($len), "}, 8\n",
+            "jae 42040b\n",
+            "42041:\n",
+            $crate::__asm_crc32_loop!(@tail $crc, $ptr, $len, "4", "dword", "42042"),
+            $crate::__asm_crc32_loop!(@tail $crc, $ptr, $len, "2", "word", "42043"),
+            $crate::__asm_crc32_loop!(@tail $crc, $ptr, $len, "1", "byte", "42044")
+        )
+    };
+    (castagnoli, $($argument: tt)*) => {
+        compile_error!("`crc32` reads memory on x86_64, so there is no `data` operand")
+    };
+    ($polynomial: ident, $($argument: tt)*) => {
+        compile_error!("`crc32` of x86_64 only computes the `castagnoli` polynomial")
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_crc32_loop {
+    (@instruction castagnoli) => { "crc32c" };
+    (@instruction ieee) => { "crc32" };
+    (@tail $polynomial: ident, $crc: ident, $ptr: ident, $len: ident, $data: ident,
+        $bit: tt, $load: tt, $suffix: tt, $bytes: tt, $label: tt) => {
+        concat!(
+            "tbz {", stringify!($len), "}, #", $bit, ", ", $label, "f\n",
+            $load, " {", stringify!($data), ":w}, [{", stringify!($ptr), "}], #", $bytes, "\n",
+            $crate::__asm_crc32_loop!(@instruction $polynomial), $suffix,
+            " {", stringify!($crc), ":w}, {", stringify!($crc), ":w}, {", stringify!($data), ":w}\n",
+            $label, ":\n"
+        )
+    };
+
+    (@loop $polynomial: ident, $crc: ident, $ptr: ident, $len: ident, $data: ident) => {
+        concat!(
+            "cmp {", stringify!($len), "}, #8\n",
+            "b.lo 42041f\n",
+            "42040:\n",
+            "ldr {", stringify!($data), "}, [{", stringify!($ptr), "}], #8\n",
+            $crate::__asm_crc32_loop!(@instruction $polynomial),
+            "x {", stringify!($crc), ":w}, {", stringify!($crc), ":w}, {", stringify!($data), "}\n",
+            "sub {", stringify!($len), "}, {", stringify!($len), "}, #8\n",
+            "cmp {", stringify!($len), "}, #8\n",
+            "b.hs 42040b\n",
+            "42041:\n",
+            $crate::__asm_crc32_loop!(@tail $polynomial, $crc, $ptr, $len, $data, "2", "ldr", "w", "4", "42042"),
+            $crate::__asm_crc32_loop!(@tail $polynomial, $crc, $ptr, $len, $data, "1", "ldrh", "h", "2", "42043"),
+            $crate::__asm_crc32_loop!(@tail $polynomial, $crc, $ptr, $len, $data, "0", "ldrb", "b", "1", "42044")
+        )
+    };
+    (castagnoli, $crc: ident, $ptr: ident, $len: ident, $data: ident) => {
+        $crate::__asm_crc32_loop!(@loop castagnoli, $crc, $ptr, $len, $data)
+    };
+    (ieee, $crc: ident, $ptr: ident, $len: ident, $data: ident) => {
+        $crate::__asm_crc32_loop!(@loop ieee, $crc, $ptr, $len, $data)
+    };
+    ($polynomial: ident, $crc: ident, $ptr: ident, $len: ident) => {
+        compile_error!("AArch64 loads the bytes into a `data` operand, which must be given last")
+    };
+    ($polynomial: ident, $($argument: tt)*) => {
+        compile_error!("expected `castagnoli` or `ieee` on AArch64")
+    };
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_crc32_loop {
+    ($($token: tt)*) => {
+        compile_error!("CRC-32 loops are only supported on x86_64 and AArch64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(memcpy_loop, memset_loop, byte_find, crc32_loop)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_memcpy() {
+        let code = memcpy_loop!(sse, rdi, rsi, {n}, chunk = 32);
+        let expected = concat!(
+            "cmp {n}, 32\n",
+            "jb 42011f\n",
+            "42010:\n",
+            "movups xmm0, [rsi  + 0]\n",
+            "movups xmm1, [rsi  + 16]\n",
+            "movups [rdi  + 0], xmm0\n",
+            "movups [rdi  + 16], xmm1\n",
+            "add rsi , 32\n",
+            "add rdi , 32\n",
+            "sub {n}, 32\n",
+            "cmp {n}, 32\n",
+            "jae 42010b\n",
+            "42011:\n",
+            "test {n}, {n}\n",
+            "jz 42013f\n",
+            "42012:\n",
+            "pinsrb xmm0, byte ptr [rsi ], 0\n",
+            "pextrb byte ptr [rdi ], xmm0, 0\n",
+            "inc rsi \n",
+            "inc rdi \n",
+            "dec {n}\n",
+            "jnz 42012b\n",
+            "42013:\n",
+        );
+        assert_eq!(code, expected);
+        assert_eq!(memcpy_loop!(avx, {d}, {s}, {n}), memcpy_loop!(avx, {d}, {s}, {n}, chunk = 64));
+        #[cfg(feature = "std")]
+        {
+            use crate::test::{assert_assembles, Arch};
+            for code in [
+                memcpy_loop!(avx, rdi, rsi, rcx, chunk = 256),
+                memset_loop!(sse, rdi, eax, rcx, chunk = 128),
+                memset_loop!(avx, rdi, eax, rcx),
+            ] {
+                assert_assembles(Arch::X86_64, code);
+            }
+        }
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_memory_loops() {
+        let mut src = [0u8; 300];
+        for (i, byte) in src.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        for len in [0, 5, 16, 64, 100, 299] {
+            let mut dst = [0xffu8; 300];
+            if std::is_x86_feature_detected!("sse4.1") {
+                unsafe {
+                    core::arch::asm!(
+                        memcpy_loop!(sse, {dst}, {src}, {len}, chunk = 48),
+                        dst = inout(reg) dst.as_mut_ptr() => _,
+                        src = inout(reg) src.as_ptr() => _,
+                        len = inout(reg) len => _,
+                        out("xmm0") _, out("xmm1") _, out("xmm2") _,
+                        options(nostack),
+                    );
+                }
+                assert_eq!(dst[..len], src[..len]);
+                assert!(dst[len..].iter().all(|&byte| byte == 0xff));
+            }
+            if std::is_x86_feature_detected!("avx2") {
+                unsafe {
+                    core::arch::asm!(
+                        memset_loop!(avx, {dst}, {v:e}, {len}),
+                        "vzeroupper",
+                        dst = inout(reg) dst.as_mut_ptr() => _,
+                        v = in(reg) 0x1234u32,
+                        len = inout(reg) len => _,
+                        out("ymm0") _,
+                        options(nostack),
+                    );
+                }
+                assert!(dst[..len].iter().all(|&byte| byte == 0x34));
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_byte_find() {
+        let code = byte_find!(avx, rdi, {v:e}, rax, rdx);
+        let expected = concat!(
+            "vmovd xmm0, {v:e}\n",
+            "vpbroadcastb ymm0, xmm0\n",
+            "mov rdx , rdi \n",
+            "and rdx , 32 - 1\n",
+            "and rdi , -32\n",
+            "vmovdqa ymm1, [rdi ]\n",
+            "vpcmpeqb ymm1, ymm1, ymm0\n",
+            "vpmovmskb rax , ymm1\n",
+            "shrx rax , rax , rdx \n",
+            "test rax , rax \n",
+            "jnz 42031f\n",
+            "42030:\n",
+            "add rdi , 32\n",
+            "vmovdqa ymm1, [rdi ]\n",
+            "vpcmpeqb ymm1, ymm1, ymm0\n",
+            "vpmovmskb rax , ymm1\n",
+            "test rax , rax \n",
+            "jz 42030b\n",
+            "tzcnt rax , rax \n",
+            "add rdi , rax \n",
+            "jmp 42032f\n",
+            "42031:\n",
+            "tzcnt rax , rax \n",
+            "add rdi , rdx \n",
+            "add rdi , rax \n",
+            "42032:\n",
+        );
+        assert_eq!(code, expected);
+        #[cfg(feature = "std")]
+        crate::test::assert_assembles(crate::test::Arch::X86_64, byte_find!(sse, rdi, esi, rax, rcx));
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_byte_scan() {
+        #[repr(align(64))]
+        struct Buffer([u8; 192]);
+        let mut buffer = Buffer([0; 192]);
+        for (i, byte) in buffer.0.iter_mut().enumerate() {
+            *byte = i as u8 ^ 0x55;
+        }
+        for start in [0, 1, 15, 17, 31, 33] {
+            for found in [start, start + 1, start + 15, start + 16, start + 40, 191] {
+                let ptr = buffer.0[start..].as_ptr();
+                let needle = u32::from(buffer.0[found]) | 0x100;
+                let end: *const u8;
+                unsafe {
+                    core::arch::asm!(
+                        byte_find!(sse, {p}, {v:e}, {m}, rcx),
+                        p = inout(reg) ptr => end,
+                        v = in(reg) needle,
+                        m = out(reg) _,
+                        out("rcx") _, out("xmm0") _, out("xmm1") _,
+                        options(readonly, nostack),
+                    );
+                }
+                assert_eq!(end, buffer.0[found..].as_ptr());
+                if std::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("bmi2") {
+                    let end: *const u8;
+                    unsafe {
+                        core::arch::asm!(
+                            byte_find!(avx, {p}, {v:e}, {m}, {o}),
+                            "vzeroupper",
+                            p = inout(reg) ptr => end,
+                            v = in(reg) needle,
+                            m = out(reg) _,
+                            o = out(reg) _,
+                            out("ymm0") _, out("ymm1") _,
+                            options(readonly, nostack),
+                        );
+                    }
+                    assert_eq!(end, buffer.0[found..].as_ptr());
+                }
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_crc32() {
+        let expected = concat!(
+            "cmp {n}, 8\n",
+            "jb 42041f\n",
+            "42040:\n",
+            "crc32 {c}, qword ptr [{p}]\n",
+            "add {p}, 8\n",
+            "sub {n}, 8\n",
+            "cmp {n}, 8\n",
+            "jae 42040b\n",
+            "42041:\n",
+            "test {n}, 4\n",
+            "jz 42042f\n",
+            "crc32 {c:e}, dword ptr [{p}]\n",
+            "add {p}, 4\n",
+            "42042:\n",
+            "test {n}, 2\n",
+            "jz 42043f\n",
+            "crc32 {c:e}, word ptr [{p}]\n",
+            "add {p}, 2\n",
+            "42043:\n",
+            "test {n}, 1\n",
+            "jz 42044f\n",
+            "crc32 {c:e}, byte ptr [{p}]\n",
+            "add {p}, 1\n",
+            "42044:\n",
+        );
+        assert_eq!(crc32_loop!(castagnoli, c, p, n), expected);
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_crc32_loop() {
+        if !std::is_x86_feature_detected!("sse4.2") {
+            return;
+        }
+        let mut bytes = [0u8; 100];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(73);
+        }
+        for start in [0, 3] {
+            for len in [0, 1, 7, 8, 15, 64, 97] {
+                let bytes = &bytes[start..start + len];
+                let crc = bytes.iter().fold(!0u32, |mut crc, &byte| {
+                    crc ^= u32::from(byte);
+                    for _ in 0..8 {
+                        crc = (crc >> 1) ^ (0x82f6_3b78 & (crc & 1).wrapping_neg());
+                    }
+                    crc
+                });
+                let (actual, end): (u64, *const u8);
+                unsafe {
+                    core::arch::asm!(
+                        crc32_loop!(castagnoli, crc, ptr, len),
+                        crc = inout(reg) u64::from(!0u32) => actual,
+                        ptr = inout(reg) bytes.as_ptr() => end,
+                        len = inout(reg) len => _,
+                        options(pure, readonly, nostack),
+                    );
+                }
+                assert_eq!(actual, u64::from(crc));
+                assert_eq!(end, bytes.as_ptr_range().end);
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/// Bracket a block with the markers of a profiler or a simulator.
+///
+/// Tools like Intel SDE or gem5 start and stop measuring when a marker
+/// instruction is executed, which delimits exactly the composed code instead
+/// of the whole function. This evaluates to a `&'static str` with the block
+/// translated by [`asm_block!`] between the two markers, which come from the
+/// marker macro given first, invoked as `marker!(begin, name)` and
+/// `marker!(end, name)`. The marker macro can be any macro evaluating to a
+/// `&'static str`, and `name` is passed to it as is, e.g. to select the marks
+/// of the region. [`ssc_region!`] is the marker of Intel SDE.
+///
+/// # Example
+/// ```
+/// use asm_block::perf_region;
+/// macro_rules! gem5_work {
+///     (begin, $id: tt) => {
+///         concat!("mov rdi, ", $id, "\nxor esi, esi\n.byte 0x0f, 0x04, 0x5a, 0x00\n")
+///     };
+///     (end, $id: tt) => {
+///         concat!("mov rdi, ", $id, "\nxor esi, esi\n.byte 0x0f, 0x04, 0x5b, 0x00\n")
+///     };
+/// }
+/// let code = perf_region!(gem5_work, 7, { vaddps ymm0, ymm1, ymm2; });
+/// assert_eq!(
+///     code,
+///     concat!(
+///         "mov rdi, 7\nxor esi, esi\n.byte 0x0f, 0x04, 0x5a, 0x00\n",
+///         "vaddps ymm0 , ymm1 , ymm2 \n",
+///         "mov rdi, 7\nxor esi, esi\n.byte 0x0f, 0x04, 0x5b, 0x00\n",
+///     )
+/// );
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+/// [`ssc_region!`]: macro.ssc_region.html
+#[macro_export]
+macro_rules! perf_region {
+    ($($marker: ident)::+, $name: tt, { $($token: tt)* } $(,)?) => {
+        concat!(
+            $($marker)::+!(begin, $name),
+            $crate::asm_block!($($token)*),
+            $($marker)::+!(end, $name)
+        )
+    };
+}
+
+/// Emit an SSC mark of Intel SDE.
+///
+/// This evaluates to a `&'static str` which executes the marker `mov ebx,
+/// <value>` followed by `addr32 fs nop`, as `__SSC_MARK` of the Intel
+/// compiler does. The marker is a no-op outside of SDE, and `rbx`, which
+/// cannot be an operand of [`asm!`], is saved on the stack around it, so the
+/// block must not have the `nostack` option.
+///
+/// # Example
+/// ```
+/// use asm_block::ssc_mark;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     ssc_mark!(0x111),
+///     "push rbx\nmov ebx, 0x111\n.byte 0x64, 0x67, 0x90\npop rbx\n"
+/// );
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+#[macro_export]
+macro_rules! ssc_mark {
+    ($value: tt $(,)?) => {
+        $crate::__asm_ssc_mark!($value)
+    };
+}
+
+/// The markers of a region measured by Intel SDE, for [`perf_region!`].
+///
+/// The name of the region is the pair of the marks starting and stopping the
+/// measurement, as passed to `-start_ssc_mark` and `-stop_ssc_mark`, and
+/// each mark is emitted with [`ssc_mark!`].
+///
+/// # Example
+/// ```
+/// use asm_block::{perf_region, ssc_region};
+/// let code = perf_region!(ssc_region, (0x111, 0x222), { vfmadd231ps zmm0, zmm1, zmm2; });
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     code,
+///     concat!(
+///         "push rbx\nmov ebx, 0x111\n.byte 0x64, 0x67, 0x90\npop rbx\n",
+///         "vfmadd231ps zmm0 , zmm1 , zmm2 \n",
+///         "push rbx\nmov ebx, 0x222\n.byte 0x64, 0x67, 0x90\npop rbx\n",
+///     )
+/// );
+/// ```
+///
+/// [`perf_region!`]: macro.perf_region.html
+/// [`ssc_mark!`]: macro.ssc_mark.html
+#[macro_export]
+macro_rules! ssc_region {
+    (begin, ($start: tt, $stop: tt)) => {
+        $crate::ssc_mark!($start)
+    };
+    (end, ($start: tt, $stop: tt)) => {
+        $crate::ssc_mark!($stop)
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_ssc_mark {
+    ($value: tt) => {
+        concat!("push rbx\nmov ebx, ", stringify!($value), "\n.byte 0x64, 0x67, 0x90\npop rbx\n")
+    };
+}
+
+#[cfg(target_arch = "x86")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_ssc_mark {
+    ($value: tt) => {
+        concat!("push ebx\nmov ebx, ", stringify!($value), "\n.byte 0x64, 0x67, 0x90\npop ebx\n")
+    };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_ssc_mark {
+    ($value: tt) => {
+        compile_error!("SSC marks are only supported on x86 and x86_64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(perf_region)]
+mod tests {
+    macro_rules! marker {
+        (begin, $name: ident) => {
+            concat!("/* begin ", stringify!($name), " */\n")
+        };
+        (end, $name: ident) => {
+            concat!("/* end ", stringify!($name), " */\n")
+        };
+    }
+
+    #[test]
+    fn test_region() {
+        assert_eq!(
+            perf_region!(marker, kernel, { nop; }),
+            "/* begin kernel */\nnop \n/* end kernel */\n"
+        );
+        assert_eq!(perf_region!(marker, empty, {}), "/* begin empty */\n/* end empty */\n");
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_ssc_mark() {
+        let (before, after): (u64, u64);
+        unsafe {
+            core::arch::asm!(
+                "mov {a}, rbx",
+                perf_region!(crate::ssc_region, (1, 2), { nop; }),
+                "mov {b}, rbx",
+                a = out(reg) before,
+                b = out(reg) after,
+            );
+        }
+        assert_eq!(before, after);
+        #[cfg(feature = "std")]
+        if let Some(instructions) = crate::test::assert_disassembles(crate::test::Arch::X86_64, ssc_mark!(0x111)) {
+            let mnemonics = instructions.iter().map(|i| i.mnemonic()).collect::<Vec<_>>();
+            assert_eq!(mnemonics, ["push", "mov", "pop"]);
+            assert_eq!(instructions[1].bytes, [0xbb, 0x11, 0x01, 0x00, 0x00]);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+/// Load a 64-bit constant into a register from a shared constant pool.
+///
+/// This evaluates to a `&'static str` which places the constant in the
+/// section of mergeable 8-byte literals of the object format, under the
+/// numeric label `42080`, and loads it into the general purpose register
+/// `reg` from there:
+///
+/// | Architecture | Load                                               |
+/// |--------------|----------------------------------------------------|
+/// | x86-64       | `mov reg, qword ptr [rip + 42080b]`                |
+/// | AArch64      | `adrp reg, 42080b`, `ldr reg, [reg, :lo12:42080b]` |
+/// | RISC-V 64    | `ld reg, 42080b`                                   |
+///
+/// `macro_rules!` has no state shared between fragments, so the constants are
+/// not collected by the crate. Instead, every use emits its own entry into
+/// `.rodata.cst8` on ELF, or `__literal8` on Mach-O, whose identical entries
+/// are merged by the linker, so each distinct constant is stored once in the
+/// image however many fragments and functions load it, and the code is not
+/// interrupted by a data island. COFF has no such section, so the entries are
+/// placed in `.rdata` there, and are not deduplicated.
+///
+/// The value is transcribed by [`asm_block!`], so it can be a placeholder of
+/// a `const` operand or anything the assembler is able to evaluate to an
+/// absolute value. Values made of several tokens must be wrapped in
+/// parentheses. On 32-bit ARM, [`asm_load_const!`] with `pool` serves the
+/// same purpose.
+///
+/// # Example
+/// ```
+/// use asm_block::pool_const;
+/// # #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+/// assert_eq!(
+///     pool_const!(rax, 0x0123456789abcdef),
+///     ".pushsection .rodata.cst8, \"aM\", %progbits, 8\n.balign 8\n42080:\n\
+///      .quad 0x0123456789abcdef \n.popsection\nmov rax , qword ptr [rip + 42080b]\n"
+/// );
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+/// [`asm_load_const!`]: macro.asm_load_const.html
+#[macro_export]
+macro_rules! pool_const {
+    ($reg: tt, $value: tt $(,)?) => {
+        concat!(
+            $crate::__asm_pool_section!(),
+            ".balign 8\n42080:\n",
+            $crate::asm_block!(.quad $value;),
+            $crate::__asm_section!(@end),
+            $crate::__asm_pool!($reg)
+        )
+    };
+}
+
+#[cfg(not(any(target_vendor = "apple", windows, target_os = "uefi")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pool_section {
+    () => { ".pushsection .rodata.cst8, \"aM\", %progbits, 8\n" };
+}
+
+#[cfg(target_vendor = "apple")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pool_section {
+    () => { ".pushsection __TEXT,__literal8, 8byte_literals\n" };
+}
+
+#[cfg(any(windows, target_os = "uefi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pool_section {
+    () => { ".section .rdata, \"dr\"\n" };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pool {
+    ($reg: tt) => { concat!($crate::asm_block!(mov $reg,), "qword ptr [rip + 42080b]\n") };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pool {
+    ($reg: tt) => {
+        concat!(
+            $crate::asm_block!(adrp $reg,), "42080b\n",
+            $crate::asm_block!(ldr $reg,), "[", $crate::asm_block!($reg), ", :lo12:42080b]\n"
+        )
+    };
+}
+
+#[cfg(target_arch = "riscv64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pool {
+    ($reg: tt) => { concat!($crate::asm_block!(ld $reg,), "42080b\n") };
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_pool {
+    ($($token: tt)*) => {
+        compile_error!("constant pools are only supported on x86_64, AArch64 and RISC-V 64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(pool_const)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_pool_const() {
+        let (x, y, z): (u64, u64, u64);
+        unsafe {
+            core::arch::asm!(
+                pool_const!({x}, 0x0123456789abcdef),
+                pool_const!({y}, {c}),
+                pool_const!({z}, 0x0123456789abcdef),
+                c = const -2i64,
+                x = out(reg) x,
+                y = out(reg) y,
+                z = out(reg) z,
+                options(pure, readonly, nostack),
+            );
+        }
+        assert_eq!(x, 0x0123_4567_89ab_cdef);
+        assert_eq!(y, -2i64 as u64);
+        assert_eq!(z, x);
+    }
+}
@@ -0,0 +1,153 @@
+/// Swap the base of `gs` on an entry from or an exit to user mode on x86-64.
+///
+/// This evaluates to a `&'static str` which executes `swapgs` only if the
+/// code segment saved in the interrupt frame has a requested privilege level
+/// other than 0, i.e. if the interrupt came from user mode, or returns to it,
+/// so that nested or kernel-mode interrupts keep the base of the kernel. The
+/// saved `cs` is `offset` bytes above the stack pointer: 8 at the entry of an
+/// exception without an error code, 16 with one, and 8 right before `iretq`.
+///
+/// Both paths end with an `lfence`, so that the following accesses through
+/// `gs` are not done speculatively with the wrong base, as the mitigation of
+/// Spectre v1 for `swapgs` requires. The numeric label `42050` is used, and
+/// the flags are clobbered.
+///
+/// # Example
+/// ```
+/// use asm_block::swapgs_if_user;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     swapgs_if_user!(16),
+///     "test byte ptr [rsp + 16], 3\njz 42050f\nswapgs\n42050:\nlfence\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! swapgs_if_user {
+    ($offset: tt $(,)?) => {
+        $crate::__asm_swapgs!($offset)
+    };
+}
+
+/// Return from an exception on AArch64 to a given address and state.
+///
+/// This evaluates to a `&'static str` which writes the return address `elr`
+/// and the saved program status `spsr` to the registers of the exception
+/// level given first, `EL1`, `EL2` or `EL3`, with [`write_sysreg!`], and
+/// executes `eret`. `eret` is followed by `dsb nsh` and `isb`, which are never
+/// executed, but stop the straight-line speculation past it.
+///
+/// `spsr` selects the level and the stack pointer returned to, and the masks
+/// of the interrupts, e.g. `0x3c5` for EL1 with `SP_EL1` and all of them
+/// masked, or `0` for EL0.
+///
+/// # Example
+/// ```
+/// use asm_block::eret_to;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(
+///     eret_to!(EL1, {pc}, xzr),
+///     "msr ELR_EL1, {pc}\nmsr SPSR_EL1, xzr \neret\ndsb nsh\nisb\n"
+/// );
+/// ```
+///
+/// [`write_sysreg!`]: macro.write_sysreg.html
+#[macro_export]
+macro_rules! eret_to {
+    (EL1, $elr: tt, $spsr: tt $(,)?) => {
+        concat!(
+            $crate::write_sysreg!(ELR_EL1, $elr),
+            $crate::write_sysreg!(SPSR_EL1, $spsr),
+            $crate::eret_to!(@eret)
+        )
+    };
+    (EL2, $elr: tt, $spsr: tt $(,)?) => {
+        concat!(
+            $crate::write_sysreg!(ELR_EL2, $elr),
+            $crate::write_sysreg!(SPSR_EL2, $spsr),
+            $crate::eret_to!(@eret)
+        )
+    };
+    (EL3, $elr: tt, $spsr: tt $(,)?) => {
+        concat!(
+            $crate::write_sysreg!(ELR_EL3, $elr),
+            $crate::write_sysreg!(SPSR_EL3, $spsr),
+            $crate::eret_to!(@eret)
+        )
+    };
+    (@eret) => { "eret\ndsb nsh\nisb\n" };
+    ($level: tt, $($argument: tt)*) => {
+        compile_error!(concat!("expected an exception level of `EL1`, `EL2` or `EL3`, found `", stringify!($level), "`"))
+    };
+}
+
+/// Return from a trap in machine mode on RISC-V to a given address.
+///
+/// This evaluates to a `&'static str` which writes the return address to
+/// `mepc`, and the status to `mstatus` if given, whose `MPP` and `MPIE`
+/// fields select the mode returned to and whether interrupts are enabled
+/// there, with [`write_csr!`], and executes `mret`.
+///
+/// # Example
+/// ```
+/// use asm_block::mret_to;
+/// # #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+/// assert_eq!(mret_to!({pc}, {s}), "csrw mepc, {pc}\ncsrw mstatus, {s}\nmret\n");
+/// ```
+///
+/// [`write_csr!`]: macro.write_csr.html
+#[macro_export]
+macro_rules! mret_to {
+    ($epc: tt $(, $status: tt)? $(,)?) => {
+        concat!($crate::write_csr!(mepc, $epc), $($crate::write_csr!(mstatus, $status),)? "mret\n")
+    };
+}
+
+/// Return from a trap in supervisor mode on RISC-V to a given address.
+///
+/// This is the counterpart of [`mret_to!`] for supervisor mode, which writes
+/// `sepc` and `sstatus`, whose `SPP` and `SPIE` fields select the mode
+/// returned to, and executes `sret`.
+///
+/// # Example
+/// ```
+/// use asm_block::sret_to;
+/// # #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+/// assert_eq!(sret_to!(a0), "csrw sepc, a0 \nsret\n");
+/// ```
+///
+/// [`mret_to!`]: macro.mret_to.html
+#[macro_export]
+macro_rules! sret_to {
+    ($epc: tt $(, $status: tt)? $(,)?) => {
+        concat!($crate::write_csr!(sepc, $epc), $($crate::write_csr!(sstatus, $status),)? "sret\n")
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_swapgs {
+    ($offset: tt) => {
+        concat!("test byte ptr [rsp + ", stringify!($offset), "], 3\njz 42050f\nswapgs\n42050:\nlfence\n")
+    };
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_swapgs {
+    ($($token: tt)*) => {
+        compile_error!("`swapgs` is only available on x86_64")
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[test]
+    fn test_swapgs() {
+        use crate::test::{assert_assembles, Arch};
+        let code = concat!(swapgs_if_user!(8), "push rax\n", "pop rax\n", swapgs_if_user!(8), "iretq\n");
+        assert_assembles(Arch::X86_64, code);
+    }
+}
@@ -0,0 +1,336 @@
+/// Refer to a general-purpose register operand, optionally as a sub-register.
+///
+/// This evaluates to a `&'static str` with the placeholder of the operand
+/// `name`, declared in the class `reg`, carrying the template modifier which
+/// selects the given width on the target:
+///
+/// | Call            | x86-64   | x86      | AArch64 |
+/// |-----------------|----------|----------|---------|
+/// | `gpr!(x)`       | `{x}`    | `{x}`    | `{x}`   |
+/// | `gpr!(x, 8)`    | `{x:l}`  | `{x:l}`  | error   |
+/// | `gpr!(x, 16)`   | `{x:x}`  | `{x:x}`  | error   |
+/// | `gpr!(x, 32)`   | `{x:e}`  | `{x:e}`  | `{x:w}` |
+/// | `gpr!(x, 64)`   | `{x:r}`  | error    | `{x:x}` |
+///
+/// Only `reg` has a sub-register of every width on x86, so `gpr!(x, 8)` needs
+/// the class `reg_abcd` on 32-bit x86. Other architectures have no modifiers
+/// for general-purpose registers, so only `gpr!(x)` is available there.
+///
+/// The result can be spliced into a block with `@inline(...)`. The operand is
+/// declared as usual, or as a field of a struct declared with
+/// [`asm_operands!`] in the same class.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, gpr};
+/// let code = asm_block! {
+///     mov @inline(gpr!(x, 32)), @inline(gpr!(y, 32));
+/// };
+/// # #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// assert_eq!(code, "mov {x:e}, {y:e}\n");
+/// ```
+///
+/// [`asm_operands!`]: macro.asm_operands.html
+#[macro_export]
+macro_rules! gpr {
+    ($name: ident $(,)?) => {
+        concat!("{", stringify!($name), "}")
+    };
+    ($name: ident, $bits: tt $(,)?) => {
+        $crate::width!($name, $bits)
+    };
+}
+
+/// Refer to a general-purpose register operand accessed with a given width.
+///
+/// This evaluates to a `&'static str` with the placeholder of the operand
+/// `name` and the modifier of the target for the width in bits, as listed for
+/// [`gpr!`], e.g. `{x:e}` on x86-64 and `{x:w}` on AArch64 for `32`. A width
+/// which the target cannot access fails to compile.
+///
+/// A fragment whose instructions are spelled the same on several
+/// architectures, but whose registers are not, can be written once with it:
+/// ```
+/// use asm_block::{asm_block, width};
+/// let code = asm_block! {
+///     mov @inline(width!(x, 32)), @inline(width!(y, 32));
+/// };
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(code, "mov {x:e}, {y:e}\n");
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(code, "mov {x:w}, {y:w}\n");
+/// ```
+///
+/// [`gpr!`]: macro.gpr.html
+#[macro_export]
+macro_rules! width {
+    ($name: ident, $bits: tt $(,)?) => {
+        concat!("{", stringify!($name), $crate::__asm_width!($bits), "}")
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_width {
+    (8) => { ":l" };
+    (16) => { ":x" };
+    (32) => { ":e" };
+    (64) => { ":r" };
+    ($bits: tt) => {
+        compile_error!("general-purpose registers are 8, 16, 32 or 64 bits wide on x86_64")
+    };
+}
+
+#[cfg(target_arch = "x86")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_width {
+    (8) => { ":l" };
+    (16) => { ":x" };
+    (32) => { ":e" };
+    ($bits: tt) => {
+        compile_error!("general-purpose registers are 8, 16 or 32 bits wide on x86")
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_width {
+    (32) => { ":w" };
+    (64) => { ":x" };
+    ($bits: tt) => {
+        compile_error!("general-purpose registers are 32 or 64 bits wide on AArch64")
+    };
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_width {
+    ($bits: tt) => {
+        compile_error!("sub-registers are only supported on x86, x86_64 and AArch64")
+    };
+}
+
+/// Add the operand-size suffix of the AT&T syntax to an x86 mnemonic.
+///
+/// In the AT&T syntax, the size of an instruction without a register operand
+/// is given by a suffix of the mnemonic, e.g. `incl (%rdi)` or `addq`,
+/// which `macro_rules!` cannot paste from a parameter. This evaluates to a
+/// `&'static str` with the mnemonic followed by the suffix for the width in
+/// bits, `b` for `8`, `w` for `16`, `l` for `32` and `q` for `64`, or for the
+/// suffix itself, so that a fragment generic over the width can pass it to
+/// both `sz!` and [`gpr!`]. Other widths fail to compile. The result can be
+/// spliced into a block with `@inline(...)`.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, sz};
+/// macro_rules! increment {
+///     ($bits: tt, $ptr: tt) => {
+///         asm_block! {
+///             @inline(sz!(inc, $bits)) ($ptr);
+///         }
+///     };
+/// }
+/// assert_eq!(increment!(32, {p}), "incl ({p}) \n");
+/// assert_eq!(sz!(add, q), "addq ");
+/// ```
+///
+/// [`gpr!`]: macro.gpr.html
+#[macro_export]
+macro_rules! sz {
+    (@suffix 8) => { "b " };
+    (@suffix 16) => { "w " };
+    (@suffix 32) => { "l " };
+    (@suffix 64) => { "q " };
+    (@suffix b) => { "b " };
+    (@suffix w) => { "w " };
+    (@suffix l) => { "l " };
+    (@suffix q) => { "q " };
+    (@suffix $width: tt) => {
+        compile_error!(concat!(
+            "expected a width of 8, 16, 32 or 64 bits, or a suffix `b`, `w`, `l` or `q`, found `",
+            stringify!($width),
+            "`"
+        ))
+    };
+
+    ($mnemonic: ident, $width: tt $(,)?) => {
+        concat!(stringify!($mnemonic), $crate::sz!(@suffix $width))
+    };
+}
+
+/// Refer to a vector register operand of x86 as an `xmm` register.
+///
+/// This evaluates to a `&'static str` with the placeholder of the operand
+/// `name` with the modifier `x`, so that the operand is formatted as `xmm0`
+/// whether it is declared in the class `xmm_reg`, `ymm_reg` or `zmm_reg`.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, xmm, ymm};
+/// let code = asm_block! {
+///     vpxor @inline(xmm!(v)), @inline(xmm!(v)), @inline(xmm!(v));
+///     vpbroadcastb @inline(ymm!(w)), @inline(xmm!(v));
+/// };
+/// assert_eq!(code, "vpxor {v:x}, {v:x}, {v:x}\nvpbroadcastb {w:y}, {v:x}\n");
+/// ```
+#[macro_export]
+macro_rules! xmm {
+    ($name: ident $(,)?) => {
+        concat!("{", stringify!($name), ":x}")
+    };
+}
+
+/// Refer to a vector register operand of x86 as a `ymm` register.
+///
+/// Like [`xmm!`], with the modifier `y`, for operands declared in the class
+/// `ymm_reg` or `zmm_reg`.
+///
+/// [`xmm!`]: macro.xmm.html
+#[macro_export]
+macro_rules! ymm {
+    ($name: ident $(,)?) => {
+        concat!("{", stringify!($name), ":y}")
+    };
+}
+
+/// Refer to a vector register operand of x86 as a `zmm` register.
+///
+/// Like [`xmm!`], with the modifier `z`, for operands declared in the class
+/// `zmm_reg`.
+///
+/// [`xmm!`]: macro.xmm.html
+#[macro_export]
+macro_rules! zmm {
+    ($name: ident $(,)?) => {
+        concat!("{", stringify!($name), ":z}")
+    };
+}
+
+/// Refer to an AVX-512 mask register operand of x86.
+///
+/// This evaluates to a `&'static str` with the placeholder of the operand
+/// `name`, declared in the class `kreg`, which has no modifiers. The mask of
+/// an instruction is written in braces after its destination, which must be
+/// escaped around the placeholder in the template:
+/// ```
+/// use asm_block::{asm_block, kreg, zmm};
+/// let code = concat!(
+///     asm_block!(vmovdqu64 @inline(zmm!(v))),
+///     " {{", kreg!(m), "}}, ",
+///     asm_block!(@inline(zmm!(w)))
+/// );
+/// assert_eq!(code, "vmovdqu64 {v:z} {{{m}}}, {w:z}");
+/// ```
+#[macro_export]
+macro_rules! kreg {
+    ($name: ident $(,)?) => {
+        concat!("{", stringify!($name), "}")
+    };
+}
+
+/// Refer to a vector register operand of AArch64 as a scalar or a vector.
+///
+/// This evaluates to a `&'static str` with the placeholder of the operand
+/// `name`, declared in the class `vreg`, with the modifier selecting the
+/// scalar of the given width, or the whole vector without a width:
+///
+/// | Call            | Modifier | Formatted |
+/// |-----------------|----------|-----------|
+/// | `vreg!(v)`      | `v`      | `v0`      |
+/// | `vreg!(v, 8)`   | `b`      | `b0`      |
+/// | `vreg!(v, 16)`  | `h`      | `h0`      |
+/// | `vreg!(v, 32)`  | `s`      | `s0`      |
+/// | `vreg!(v, 64)`  | `d`      | `d0`      |
+/// | `vreg!(v, 128)` | `q`      | `q0`      |
+///
+/// The arrangement of a vector, e.g. `.4s`, follows the placeholder.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, vreg};
+/// let code = asm_block! {
+///     fadd @inline(vreg!(a, 64)), @inline(vreg!(a, 64)), @inline(vreg!(b, 64));
+/// };
+/// assert_eq!(code, "fadd {a:d}, {a:d}, {b:d}\n");
+/// ```
+#[macro_export]
+macro_rules! vreg {
+    ($name: ident $(,)?) => {
+        concat!("{", stringify!($name), ":v}")
+    };
+    ($name: ident, 8 $(,)?) => {
+        concat!("{", stringify!($name), ":b}")
+    };
+    ($name: ident, 16 $(,)?) => {
+        concat!("{", stringify!($name), ":h}")
+    };
+    ($name: ident, 32 $(,)?) => {
+        concat!("{", stringify!($name), ":s}")
+    };
+    ($name: ident, 64 $(,)?) => {
+        concat!("{", stringify!($name), ":d}")
+    };
+    ($name: ident, 128 $(,)?) => {
+        concat!("{", stringify!($name), ":q}")
+    };
+    ($name: ident, $bits: tt $(,)?) => {
+        compile_error!("vector registers are accessed as 8, 16, 32, 64 or 128 bits on AArch64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block)]
+mod tests {
+    #[test]
+    fn test_gpr() {
+        assert_eq!(gpr!(x), "{x}");
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(width!(x, 64), "{x:r}");
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        assert_eq!(
+            asm_block!(movzx @inline(gpr!(x, 32)), @inline(gpr!(y, 8))),
+            "movzx {x:e}, {y:l}"
+        );
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            asm_block!(add @inline(gpr!(x, 64)), @inline(gpr!(y, 64)), @inline(gpr!(z, 32)), uxtw),
+            "add {x:x}, {y:x}, {z:w}, uxtw"
+        );
+    }
+
+    #[test]
+    fn test_sz() {
+        assert_eq!(sz!(push, 16), "pushw ");
+        assert_eq!(sz!(cmp, l,), "cmpl ");
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let mut x: u64 = 0x10f;
+            unsafe {
+                core::arch::asm!(
+                    concat!(sz!(add, 8), "$1, {x:l}"),
+                    concat!(sz!(shl, 64), "$4, {x}"),
+                    x = inout(reg) x,
+                    options(att_syntax, pure, nomem, nostack),
+                );
+            }
+            assert_eq!(x, 0x1100);
+        }
+    }
+
+    #[test]
+    fn test_vector() {
+        assert_eq!(xmm!(v), "{v:x}");
+        assert_eq!(ymm!(v), "{v:y}");
+        assert_eq!(zmm!(v,), "{v:z}");
+        assert_eq!(kreg!(k), "{k}");
+        assert_eq!(vreg!(v), "{v:v}");
+        assert_eq!(vreg!(v, 8), "{v:b}");
+        assert_eq!(vreg!(v, 128), "{v:q}");
+    }
+}
@@ -0,0 +1,270 @@
+//! Enumerating the fragments shipped by a project.
+//!
+//! Fragments are expanded at their call sites, so there is no central place
+//! listing which ones exist. [`register_fragment!`] turns an instantiation of
+//! a fragment into a [`Fragment`] in `const` context, so that a project can
+//! collect them in a `const` slice and audit the assembly it ships, e.g. in a
+//! test:
+//! ```
+//! use asm_block::asm_block;
+//! use asm_block::register_fragment;
+//! use asm_block::registry::Fragment;
+//! macro_rules! mad {
+//!     ($x: tt, $y: tt) => {
+//!         asm_block! {
+//!             imul $x, $y;
+//!             lea $x, [$x + $y];
+//!         }
+//!     };
+//! }
+//! macro_rules! zero {
+//!     ($x: tt) => {
+//!         asm_block! { xor $x, $x; }
+//!     };
+//! }
+//!
+//! const FRAGMENTS: &[Fragment] = &[
+//!     register_fragment!(mad!({x}, 5)),
+//!     register_fragment!(zero_eax, zero!(eax)),
+//! ];
+//!
+//! assert_eq!(FRAGMENTS[0].name, "mad");
+//! assert_eq!(FRAGMENTS[0].placeholders().collect::<Vec<_>>(), ["x", "x", "x"]);
+//! assert_eq!(FRAGMENTS[1].name, "zero_eax");
+//! assert_eq!(FRAGMENTS[1].len(), "xor eax , eax \n".len());
+//! ```
+//!
+//! The lines of a block made of several fragments can be traced back to them
+//! with [`locate`], given the fragments in the order they are concatenated,
+//! e.g. to report which fragment an error of the assembler at a given line,
+//! or a faulting instruction found at that line in a listing, comes from:
+//! ```
+//! # use asm_block::{asm_block, register_fragment};
+//! # use asm_block::registry::{locate, Fragment, Origin};
+//! # macro_rules! mad {
+//! #     ($x: tt, $y: tt) => {
+//! #         asm_block! {
+//! #             imul $x, $y;
+//! #             lea $x, [$x + $y];
+//! #         }
+//! #     };
+//! # }
+//! # macro_rules! zero {
+//! #     ($x: tt) => {
+//! #         asm_block! { xor $x, $x; }
+//! #     };
+//! # }
+//! // the template is `concat!(zero!(eax), mad!({x}, 5))`
+//! const BLOCK: &[Fragment] = &[register_fragment!(zero!(eax)), register_fragment!(mad!({x}, 5))];
+//! const ORIGIN: Option<Origin> = locate(BLOCK, 2);
+//! assert_eq!(ORIGIN, Some(Origin { fragment: "mad", statement: 1 }));
+//! assert_eq!(locate(BLOCK, 3), None);
+//! ```
+//!
+//! [`register_fragment!`]: ../macro.register_fragment.html
+//! [`locate`]: fn.locate.html
+
+/// A named expansion of a fragment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fragment {
+    /// The name of the fragment.
+    pub name: &'static str,
+    /// The expansion.
+    pub code: &'static str,
+}
+
+impl Fragment {
+    /// Create a fragment, see [`register_fragment!`] for the usual way.
+    ///
+    /// [`register_fragment!`]: ../macro.register_fragment.html
+    pub const fn new(name: &'static str, code: &'static str) -> Self {
+        Fragment { name, code }
+    }
+
+    /// The length of the expansion in bytes.
+    pub const fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    /// Whether the expansion is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    /// The number of lines of the expansion, where a last line without a
+    /// newline counts as well.
+    pub const fn lines(&self) -> usize {
+        let code = self.code.as_bytes();
+        let mut lines = 0;
+        let mut i = 0;
+        while i < code.len() {
+            if code[i] == b'\n' {
+                lines += 1;
+            }
+            i += 1;
+        }
+        if !code.is_empty() && code[code.len() - 1] != b'\n' {
+            lines += 1;
+        }
+        lines
+    }
+
+    /// The placeholders used by the expansion in order, e.g. `x` and `x:e` for
+    /// `{x}` and `{x:e}`. Escaped braces `{{` and `}}` are skipped.
+    pub fn placeholders(&self) -> Placeholders {
+        Placeholders { rest: self.code }
+    }
+}
+
+/// Iterator over the placeholders of a [`Fragment`].
+#[derive(Clone, Debug)]
+pub struct Placeholders {
+    rest: &'static str,
+}
+
+impl Iterator for Placeholders {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.rest.find('{')?;
+            let after = &self.rest[start + 1..];
+            if let Some(escaped) = after.strip_prefix('{') {
+                self.rest = escaped;
+                continue;
+            }
+            let end = after.find('}')?;
+            self.rest = &after[end + 1..];
+            return Some(&after[..end]);
+        }
+    }
+}
+
+/// The fragment and the statement a line of a block comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Origin {
+    /// The name of the fragment.
+    pub fragment: &'static str,
+    /// The index of the line in the fragment, starting from 0.
+    pub statement: usize,
+}
+
+/// Find where a line of the concatenation of `fragments` comes from.
+///
+/// `line` is the index of the line in the template, starting from 0, so that
+/// line `n` reported by the assembler is `n - 1`. [`asm_block!`] emits one
+/// line per statement and never reorders them, so the indices are stable
+/// across builds and targets, except for the branches of `@cfg(...)`, and a
+/// statement is a line of a fragment spliced with `@inline(...)` as well.
+/// Every fragment but the last one is expected to end with a newline, as the
+/// expansions of [`asm_block!`] do, and lines past the end are `None`.
+///
+/// [`asm_block!`]: ../macro.asm_block.html
+pub const fn locate(fragments: &[Fragment], line: usize) -> Option<Origin> {
+    let mut start = 0;
+    let mut i = 0;
+    while i < fragments.len() {
+        let lines = fragments[i].lines();
+        if line < start + lines {
+            return Some(Origin {
+                fragment: fragments[i].name,
+                statement: line - start,
+            });
+        }
+        start += lines;
+        i += 1;
+    }
+    None
+}
+
+/// Declare a [`Fragment`] from an instantiation of a fragment.
+///
+/// The name defaults to the name of the macro, but can be given explicitly to
+/// tell apart several instantiations of the same fragment. This can be used in
+/// `const` context.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, register_fragment};
+/// macro_rules! zero {
+///     ($x: tt) => {
+///         asm_block! { xor $x, $x; }
+///     };
+/// }
+/// assert_eq!(register_fragment!(zero!({x})).name, "zero");
+/// assert_eq!(register_fragment!(zero_rax, zero!(rax)).code, "xor rax , rax \n");
+/// ```
+///
+/// [`Fragment`]: registry/struct.Fragment.html
+#[macro_export]
+macro_rules! register_fragment {
+    ($name: ident, $code: expr $(,)?) => {
+        $crate::registry::Fragment::new(stringify!($name), $code)
+    };
+    ($fragment: ident ! $args: tt) => {
+        $crate::registry::Fragment::new(stringify!($fragment), $fragment!$args)
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block, register_fragment)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders() {
+        let placeholders = |code, expected: &[&str]| {
+            Fragment::new("f", code)
+                .placeholders()
+                .eq(expected.iter().copied())
+        };
+        assert!(placeholders("", &[]));
+        assert!(placeholders("push rax \n", &[]));
+        assert!(placeholders("mov {x:e}, [{p}+ 8 ] \n", &["x:e", "p"]));
+        assert!(placeholders("vaddps zmm0 {{k1}}, {a}, {b}\n", &["a", "b"]));
+        assert!(placeholders("mov {x}, {", &["x"]));
+    }
+
+    #[test]
+    fn test_register() {
+        macro_rules! push {
+            ($r: tt) => {
+                asm_block! { push $r; }
+            };
+        }
+        const FRAGMENTS: &[Fragment] = &[
+            register_fragment!(push!({x})),
+            register_fragment!(push_rax, push!(rax)),
+            register_fragment!(empty, asm_block!()),
+        ];
+        assert_eq!(FRAGMENTS[0], Fragment::new("push", "push {x}\n"));
+        assert_eq!(FRAGMENTS[1], Fragment::new("push_rax", "push rax \n"));
+        assert_eq!(FRAGMENTS[1].len(), 10);
+        assert!(FRAGMENTS[2].is_empty());
+    }
+
+    #[test]
+    fn test_locate() {
+        let origin = |fragment, statement| {
+            Some(Origin {
+                fragment,
+                statement,
+            })
+        };
+        const BLOCK: &[Fragment] = &[
+            Fragment::new("a", "push rax \npop rax \n"),
+            Fragment::new("empty", ""),
+            Fragment::new("b", "ret \n"),
+            Fragment::new("c", "nop \nud2"),
+        ];
+        assert_eq!(BLOCK[0].lines(), 2);
+        assert_eq!(BLOCK[1].lines(), 0);
+        assert_eq!(BLOCK[3].lines(), 2);
+        assert_eq!(locate(BLOCK, 0), origin("a", 0));
+        assert_eq!(locate(BLOCK, 1), origin("a", 1));
+        assert_eq!(locate(BLOCK, 2), origin("b", 0));
+        assert_eq!(locate(BLOCK, 4), origin("c", 1));
+        assert_eq!(locate(BLOCK, 5), None);
+        assert_eq!(locate(&[], 0), None);
+    }
+}
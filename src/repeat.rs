@@ -0,0 +1,332 @@
+/// Repeat a body or a fragment once per element of a token list.
+///
+/// This evaluates to a `&'static str` concatenating the code for every element
+/// in order, in one of two forms:
+/// - `asm_foreach!(<var> in [<elements>] { <body> })` transcribes the body
+///   with [`asm_block!`], after replacing every occurrence of the variable,
+///   also inside groups, with the element. The variable is a single lowercase
+///   letter: a template of `asm!` must expand to a literal, so no macro can be
+///   defined on the fly to recognize the variable, and the rules of this macro
+///   list the letters instead.
+/// - `asm_foreach!(<fragment>, [<elements>])` concatenates `fragment!(element)`.
+///   Extra leading arguments can be bound by writing them after the fragment
+///   name, which will be passed before the element.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_foreach};
+/// macro_rules! push {
+///     ($r: tt) => {
+///         asm_block! { push $r; }
+///     };
+/// }
+/// macro_rules! add_limb {
+///     ($acc: tt, $base: tt, $offset: literal) => {
+///         asm_block! { adc $acc, [$base + $offset]; }
+///     };
+/// }
+///
+/// assert_eq!(
+///     asm_foreach!(r in [rax, rbx, {x}] { push r; }),
+///     "push rax \npush rbx \npush {x}\n"
+/// );
+/// assert_eq!(
+///     asm_foreach!(i in [0, 8, 16] { adc {acc}, [{p} + i]; }),
+///     "adc {acc}, [{p}+ 0 ] \nadc {acc}, [{p}+ 8 ] \nadc {acc}, [{p}+ 16 ] \n"
+/// );
+/// assert_eq!(
+///     asm_foreach!(push, [rax, rbx, {x}]),
+///     asm_foreach!(r in [rax, rbx, {x}] { push r; })
+/// );
+/// assert_eq!(
+///     asm_foreach!(add_limb({acc}, {p}), [0, 8, 16]),
+///     asm_foreach!(i in [0, 8, 16] { adc {acc}, [{p} + i]; })
+/// );
+/// ```
+///
+/// A variable which is not a single lowercase letter is rejected:
+/// ```compile_fail
+/// use asm_block::asm_foreach;
+/// asm_foreach!(reg in [rax, rbx] { push reg; });
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! asm_foreach {
+    // invoke the fragment on one element
+    (@call [$($fragment: ident)::+] () $item: tt) => {
+        $($fragment)::+!($item)
+    };
+    (@call [$($fragment: ident)::+] ($($arg: tt)+) $item: tt) => {
+        $($fragment)::+!($($arg)+, $item)
+    };
+
+    // the fragment and the arguments are packed into single tokens so that
+    // they can be repeated alongside the elements
+    (@each $fragment: tt $args: tt [$($item: tt),* $(,)?]) => {
+        concat!($($crate::asm_foreach!(@call $fragment $args $item)),*)
+    };
+
+    // the body is packed likewise
+    (@bind $var: ident $body: tt [$($item: tt),* $(,)?]) => {
+        concat!($($crate::__asm_foreach_body!(@start $var $item $body)),*)
+    };
+
+    ($var: ident in $items: tt { $($body: tt)* } $(,)?) => {
+        $crate::asm_foreach!(@bind $var { $($body)* } $items)
+    };
+    ($($fragment: ident)::+, $items: tt) => {
+        $crate::asm_foreach!(@each [$($fragment)::+] () $items)
+    };
+    ($($fragment: ident)::+ ($($arg: tt)*), $items: tt) => {
+        $crate::asm_foreach!(@each [$($fragment)::+] ($($arg)*) $items)
+    };
+}
+
+// Replace the variable of `asm_foreach!` in a body. The tokens are moved to
+// the output one by one, and a group is entered by pushing the output so far,
+// its delimiter and the tokens after it on the stack, which are restored at
+// its end. `macro_rules!` cannot compare two identifiers, so every letter has
+// its rules.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_foreach_body {
+    (@start a $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body a $item $body) };
+    (@start b $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body b $item $body) };
+    (@start c $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body c $item $body) };
+    (@start d $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body d $item $body) };
+    (@start e $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body e $item $body) };
+    (@start f $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body f $item $body) };
+    (@start g $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body g $item $body) };
+    (@start h $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body h $item $body) };
+    (@start i $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body i $item $body) };
+    (@start j $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body j $item $body) };
+    (@start k $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body k $item $body) };
+    (@start l $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body l $item $body) };
+    (@start m $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body m $item $body) };
+    (@start n $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body n $item $body) };
+    (@start o $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body o $item $body) };
+    (@start p $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body p $item $body) };
+    (@start q $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body q $item $body) };
+    (@start r $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body r $item $body) };
+    (@start s $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body s $item $body) };
+    (@start t $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body t $item $body) };
+    (@start u $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body u $item $body) };
+    (@start v $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body v $item $body) };
+    (@start w $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body w $item $body) };
+    (@start x $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body x $item $body) };
+    (@start y $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body y $item $body) };
+    (@start z $item: tt $body: tt) => { $crate::__asm_foreach_body!(@body z $item $body) };
+    (@start $var: tt $item: tt $body: tt) => {
+        compile_error!("the variable of `asm_foreach!` must be a single lowercase letter")
+    };
+    (@body $var: ident $item: tt { $($body: tt)* }) => {
+        $crate::__asm_foreach_body!(@sub $var $item [] [] $($body)*)
+    };
+
+    // the end of the body, or of a group
+    (@sub $var: ident $item: tt [$($out: tt)*] []) => {
+        $crate::asm_block!($($out)*)
+    };
+    (@sub $var: ident $item: tt [$($out: tt)*] [(paren [$($parent: tt)*] [$($rest: tt)*]) $($stack: tt)*]) => {
+        $crate::__asm_foreach_body!(@sub $var $item [$($parent)* ($($out)*)] [$($stack)*] $($rest)*)
+    };
+    (@sub $var: ident $item: tt [$($out: tt)*] [(bracket [$($parent: tt)*] [$($rest: tt)*]) $($stack: tt)*]) => {
+        $crate::__asm_foreach_body!(@sub $var $item [$($parent)* [$($out)*]] [$($stack)*] $($rest)*)
+    };
+    (@sub $var: ident $item: tt [$($out: tt)*] [(brace [$($parent: tt)*] [$($rest: tt)*]) $($stack: tt)*]) => {
+        $crate::__asm_foreach_body!(@sub $var $item [$($parent)* {$($out)*}] [$($stack)*] $($rest)*)
+    };
+
+    // the variable
+    (@sub a $item: tt $out: tt $stack: tt a $($rest: tt)*) => { $crate::__asm_foreach_body!(@item a $item $out $stack $($rest)*) };
+    (@sub b $item: tt $out: tt $stack: tt b $($rest: tt)*) => { $crate::__asm_foreach_body!(@item b $item $out $stack $($rest)*) };
+    (@sub c $item: tt $out: tt $stack: tt c $($rest: tt)*) => { $crate::__asm_foreach_body!(@item c $item $out $stack $($rest)*) };
+    (@sub d $item: tt $out: tt $stack: tt d $($rest: tt)*) => { $crate::__asm_foreach_body!(@item d $item $out $stack $($rest)*) };
+    (@sub e $item: tt $out: tt $stack: tt e $($rest: tt)*) => { $crate::__asm_foreach_body!(@item e $item $out $stack $($rest)*) };
+    (@sub f $item: tt $out: tt $stack: tt f $($rest: tt)*) => { $crate::__asm_foreach_body!(@item f $item $out $stack $($rest)*) };
+    (@sub g $item: tt $out: tt $stack: tt g $($rest: tt)*) => { $crate::__asm_foreach_body!(@item g $item $out $stack $($rest)*) };
+    (@sub h $item: tt $out: tt $stack: tt h $($rest: tt)*) => { $crate::__asm_foreach_body!(@item h $item $out $stack $($rest)*) };
+    (@sub i $item: tt $out: tt $stack: tt i $($rest: tt)*) => { $crate::__asm_foreach_body!(@item i $item $out $stack $($rest)*) };
+    (@sub j $item: tt $out: tt $stack: tt j $($rest: tt)*) => { $crate::__asm_foreach_body!(@item j $item $out $stack $($rest)*) };
+    (@sub k $item: tt $out: tt $stack: tt k $($rest: tt)*) => { $crate::__asm_foreach_body!(@item k $item $out $stack $($rest)*) };
+    (@sub l $item: tt $out: tt $stack: tt l $($rest: tt)*) => { $crate::__asm_foreach_body!(@item l $item $out $stack $($rest)*) };
+    (@sub m $item: tt $out: tt $stack: tt m $($rest: tt)*) => { $crate::__asm_foreach_body!(@item m $item $out $stack $($rest)*) };
+    (@sub n $item: tt $out: tt $stack: tt n $($rest: tt)*) => { $crate::__asm_foreach_body!(@item n $item $out $stack $($rest)*) };
+    (@sub o $item: tt $out: tt $stack: tt o $($rest: tt)*) => { $crate::__asm_foreach_body!(@item o $item $out $stack $($rest)*) };
+    (@sub p $item: tt $out: tt $stack: tt p $($rest: tt)*) => { $crate::__asm_foreach_body!(@item p $item $out $stack $($rest)*) };
+    (@sub q $item: tt $out: tt $stack: tt q $($rest: tt)*) => { $crate::__asm_foreach_body!(@item q $item $out $stack $($rest)*) };
+    (@sub r $item: tt $out: tt $stack: tt r $($rest: tt)*) => { $crate::__asm_foreach_body!(@item r $item $out $stack $($rest)*) };
+    (@sub s $item: tt $out: tt $stack: tt s $($rest: tt)*) => { $crate::__asm_foreach_body!(@item s $item $out $stack $($rest)*) };
+    (@sub t $item: tt $out: tt $stack: tt t $($rest: tt)*) => { $crate::__asm_foreach_body!(@item t $item $out $stack $($rest)*) };
+    (@sub u $item: tt $out: tt $stack: tt u $($rest: tt)*) => { $crate::__asm_foreach_body!(@item u $item $out $stack $($rest)*) };
+    (@sub v $item: tt $out: tt $stack: tt v $($rest: tt)*) => { $crate::__asm_foreach_body!(@item v $item $out $stack $($rest)*) };
+    (@sub w $item: tt $out: tt $stack: tt w $($rest: tt)*) => { $crate::__asm_foreach_body!(@item w $item $out $stack $($rest)*) };
+    (@sub x $item: tt $out: tt $stack: tt x $($rest: tt)*) => { $crate::__asm_foreach_body!(@item x $item $out $stack $($rest)*) };
+    (@sub y $item: tt $out: tt $stack: tt y $($rest: tt)*) => { $crate::__asm_foreach_body!(@item y $item $out $stack $($rest)*) };
+    (@sub z $item: tt $out: tt $stack: tt z $($rest: tt)*) => { $crate::__asm_foreach_body!(@item z $item $out $stack $($rest)*) };
+    (@item $var: ident $item: tt [$($out: tt)*] $stack: tt $($rest: tt)*) => {
+        $crate::__asm_foreach_body!(@sub $var $item [$($out)* $item] $stack $($rest)*)
+    };
+
+    // groups, and the other tokens
+    (@sub $var: ident $item: tt $out: tt [$($stack: tt)*] ($($inner: tt)*) $($rest: tt)*) => {
+        $crate::__asm_foreach_body!(@sub $var $item [] [(paren $out [$($rest)*]) $($stack)*] $($inner)*)
+    };
+    (@sub $var: ident $item: tt $out: tt [$($stack: tt)*] [$($inner: tt)*] $($rest: tt)*) => {
+        $crate::__asm_foreach_body!(@sub $var $item [] [(bracket $out [$($rest)*]) $($stack)*] $($inner)*)
+    };
+    (@sub $var: ident $item: tt $out: tt [$($stack: tt)*] {$($inner: tt)*} $($rest: tt)*) => {
+        $crate::__asm_foreach_body!(@sub $var $item [] [(brace $out [$($rest)*]) $($stack)*] $($inner)*)
+    };
+    (@sub $var: ident $item: tt [$($out: tt)*] $stack: tt $token: tt $($rest: tt)*) => {
+        $crate::__asm_foreach_body!(@sub $var $item [$($out)* $token] $stack $($rest)*)
+    };
+}
+
+/// Repeat a block at the assembler level with `.rept`.
+///
+/// This evaluates to a `&'static str` which wraps the translation of the block
+/// in a `.rept <count>` and `.endr` pair. The count can be any expression the
+/// assembler is able to evaluate. `.endr` is on a line of its own: it follows
+/// the block directly if the block ends with `;`, and a newline is inserted
+/// before it otherwise.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_rept;
+/// assert_eq!(asm_rept!(4, { nop; }), ".rept 4\nnop \n.endr\n");
+/// assert_eq!(asm_rept!(64 - 8, { .byte 0 }), ".rept 64 - 8\n.byte 0 \n.endr\n");
+/// ```
+#[macro_export]
+macro_rules! asm_rept {
+    ($count: expr, { $($token: tt)* }) => {
+        concat!(".rept ", stringify!($count), "\n", $crate::asm_block!($($token)*), $crate::__asm_endr!($($token)*))
+    };
+}
+
+/// Repeat a block at the assembler level with `.irp`.
+///
+/// This evaluates to a `&'static str` which wraps the translation of the block
+/// in a `.irp <var>, <items>` and `.endr` pair. The assembler substitutes
+/// `\<var>`, written as `@arg(<var>)`, in the block with each of the items in
+/// turn. As with [`asm_rept!`], `.endr` is on a line of its own.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_irp;
+/// assert_eq!(
+///     asm_irp!(reg, [rax, rbx, rcx], { push @arg(reg); }),
+///     ".irp reg , rax , rbx , rcx \npush \\reg\n.endr\n"
+/// );
+/// ```
+///
+/// [`asm_rept!`]: macro.asm_rept.html
+#[macro_export]
+macro_rules! asm_irp {
+    ($var: ident, [$($item: tt),+ $(,)?], { $($token: tt)* }) => {
+        concat!(
+            $crate::asm_block!(.irp $var, $($item),*;),
+            $crate::asm_block!($($token)*),
+            $crate::__asm_endr!($($token)*)
+        )
+    };
+}
+
+// Close a block of `asm_rept!` or `asm_irp!`, on a new line unless the last
+// token of the block is a `;`, which `asm_block!` already turns into one.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_endr {
+    () => { ".endr\n" };
+    (;) => { ".endr\n" };
+    ($last: tt) => { "\n.endr\n" };
+    ($first: tt $($rest: tt)+) => { $crate::__asm_endr!($($rest)+) };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block, asm_foreach, asm_rept, asm_irp)]
+mod tests {
+    macro_rules! push {
+        ($r: tt) => {
+            asm_block! { push $r; }
+        };
+    }
+
+    macro_rules! load {
+        ($dst: tt, $base: tt, $offset: tt) => {
+            asm_block! { mov $dst, [$base + $offset]; }
+        };
+    }
+
+    #[test]
+    fn test_foreach() {
+        assert_eq!(asm_foreach!(push, []), "");
+        assert_eq!(asm_foreach!(push, [rax]), "push rax \n");
+        assert_eq!(
+            asm_foreach!(push, [rax, {x}, [rsp + 8],]),
+            "push rax \npush {x}\npush [rsp + 8 ] \n"
+        );
+        assert_eq!(asm_foreach!(crate::asm_foreach(push), [[rbp]]), "push rbp \n");
+    }
+
+    #[test]
+    fn test_foreach_args() {
+        assert_eq!(asm_foreach!(push(), [rax]), "push rax \n");
+        assert_eq!(
+            asm_foreach!(load({t}, {p}), [0, 8, {off}]),
+            "mov {t}, [{p}+ 0 ] \nmov {t}, [{p}+ 8 ] \nmov {t}, [{p}+ {off}] \n"
+        );
+        assert_eq!(
+            asm_foreach!(asm_foreach(push), [[rax, rbx], [rcx]]),
+            "push rax \npush rbx \npush rcx \n"
+        );
+    }
+
+    #[test]
+    fn test_foreach_inline() {
+        assert_eq!(asm_foreach!(r in [] { push r; }), "");
+        assert_eq!(
+            asm_foreach!(r in [rax, {x}, [rsp + 8],] { push r; }),
+            asm_foreach!(push, [rax, {x}, [rsp + 8]])
+        );
+        assert_eq!(
+            asm_foreach!(i in [0, 8, {off}] { mov {t}, [{p} + i]; }),
+            asm_foreach!(load({t}, {p}), [0, 8, {off}])
+        );
+        // in groups, several times, and next to other letters
+        assert_eq!(
+            asm_foreach!(i in [1, 2] { lea r, [r + i * (i + q)]; .byte i, {i}; }),
+            "lea r , [r + 1 * (1 + q ) ] \n.byte 1 , {1}\n\
+             lea r , [r + 2 * (2 + q ) ] \n.byte 2 , {2}\n"
+        );
+        assert_eq!(asm_foreach!(x in [a, b] { x r }), "a r b r ");
+    }
+
+    #[test]
+    fn test_rept() {
+        assert_eq!(asm_rept!(0, {}), ".rept 0\n.endr\n");
+        assert_eq!(asm_rept!(4, { nop; }), ".rept 4\nnop \n.endr\n");
+        assert_eq!(asm_rept!(4, { nop }), ".rept 4\nnop \n.endr\n");
+        assert_eq!(
+            asm_rept!((1 << 4) - 2, { .byte 0x90; pause; }),
+            ".rept (1 << 4) - 2\n.byte 0x90 \npause \n.endr\n"
+        );
+    }
+
+    #[test]
+    fn test_irp() {
+        assert_eq!(
+            asm_irp!(reg, [rax, rbx], { push @raw("\\reg"); }),
+            ".irp reg , rax , rbx \npush \\reg\n.endr\n"
+        );
+        assert_eq!(
+            asm_irp!(off, [0, 8, 16,], { mov rax, [rsp + @arg(off)]; }),
+            ".irp off , 0 , 8 , 16 \nmov rax , [rsp + \\off] \n.endr\n"
+        );
+        assert_eq!(
+            asm_irp!(reg, [rax], { push @arg(reg) }),
+            ".irp reg , rax \npush \\reg\n.endr\n"
+        );
+    }
+}
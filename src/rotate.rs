@@ -0,0 +1,235 @@
+/// Rotate a register left by a constant number of bits.
+///
+/// This evaluates to a `&'static str` which rotates `reg` left by `n` bits,
+/// with the instruction of the target, or the sequence emulating it where the
+/// target has no such instruction:
+///
+/// | Architecture       | `rol!(reg, n)`                        | `ror!(reg, n)`            |
+/// |--------------------|---------------------------------------|---------------------------|
+/// | x86, x86-64        | `rol reg, n`                          | `ror reg, n`              |
+/// | ARM, AArch64       | `ror reg, reg, #(width - n)`          | `ror reg, reg, #n`        |
+/// | RISC-V with Zbb    | `rori reg, reg, xlen - n`             | `rori reg, reg, n`        |
+/// | RISC-V without Zbb | `srli`, `slli` and `or` through `tmp` | likewise                  |
+///
+/// `reg` and `n` are transcribed by [`asm_block!`], so they can be registers,
+/// placeholders or immediates, and the width on AArch64 is taken from the
+/// name of the register, i.e. 32 bits for `w0` to `w30` and placeholders with
+/// the modifier `w`, e.g. `{x:w}`, and 64 bits otherwise. Where the rotation
+/// is emulated, `n` must be between 1 and the width minus one.
+///
+/// RISC-V has no rotation before the Zbb extension, which is detected with
+/// the target features of the crate, so a temporary register must then be
+/// given third, e.g. `rol!(a0, 7, t0)`, which is ignored elsewhere. The
+/// rotation is of the whole register, where `roriw` would rotate the low word
+/// on RISC-V 64.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::rol;
+/// # #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+/// # {
+/// let mut x: u64 = 0x8000_0000_0000_0001;
+/// unsafe {
+///     asm!(rol!({x}, 4), x = inout(reg) x, options(pure, nomem, nostack));
+/// }
+/// assert_eq!(x, 0x18);
+/// # }
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! rol {
+    ($reg: tt, $n: tt $(, $tmp: tt)? $(,)?) => {
+        $crate::__asm_rotate!(rol $reg $n $($tmp)?)
+    };
+}
+
+/// Rotate a register right by a constant number of bits.
+///
+/// This is the counterpart of [`rol!`], with the same arguments and the
+/// sequences listed there.
+///
+/// # Example
+/// ```
+/// use asm_block::ror;
+/// # #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// assert_eq!(ror!(eax, 13), "ror eax , 13 \n");
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(ror!({x:w}, 13), "ror {x:w}, {x:w}, # 13 \n");
+/// ```
+///
+/// [`rol!`]: macro.rol.html
+#[macro_export]
+macro_rules! ror {
+    ($reg: tt, $n: tt $(, $tmp: tt)? $(,)?) => {
+        $crate::__asm_rotate!(ror $reg $n $($tmp)?)
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_rotate {
+    (rol $reg: tt $n: tt $($tmp: tt)?) => {
+        $crate::asm_block!(rol $reg, $n;)
+    };
+    (ror $reg: tt $n: tt $($tmp: tt)?) => {
+        $crate::asm_block!(ror $reg, $n;)
+    };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_rotate {
+    (rol $reg: tt $n: tt $($tmp: tt)?) => {
+        $crate::asm_block!(ror $reg, $reg, #(32 - $n);)
+    };
+    (ror $reg: tt $n: tt $($tmp: tt)?) => {
+        $crate::asm_block!(ror $reg, $reg, #$n;)
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_rotate {
+    (@width {$name: ident : w}) => { "32" };
+    (@width wzr) => { "32" };
+    (@width w0) => { "32" };
+    (@width w1) => { "32" };
+    (@width w2) => { "32" };
+    (@width w3) => { "32" };
+    (@width w4) => { "32" };
+    (@width w5) => { "32" };
+    (@width w6) => { "32" };
+    (@width w7) => { "32" };
+    (@width w8) => { "32" };
+    (@width w9) => { "32" };
+    (@width w10) => { "32" };
+    (@width w11) => { "32" };
+    (@width w12) => { "32" };
+    (@width w13) => { "32" };
+    (@width w14) => { "32" };
+    (@width w15) => { "32" };
+    (@width w16) => { "32" };
+    (@width w17) => { "32" };
+    (@width w18) => { "32" };
+    (@width w19) => { "32" };
+    (@width w20) => { "32" };
+    (@width w21) => { "32" };
+    (@width w22) => { "32" };
+    (@width w23) => { "32" };
+    (@width w24) => { "32" };
+    (@width w25) => { "32" };
+    (@width w26) => { "32" };
+    (@width w27) => { "32" };
+    (@width w28) => { "32" };
+    (@width w29) => { "32" };
+    (@width w30) => { "32" };
+    (@width $reg: tt) => { "64" };
+
+    (rol $reg: tt $n: tt $($tmp: tt)?) => {
+        concat!(
+            $crate::asm_block!(ror $reg, $reg,),
+            "#(", $crate::__asm_rotate!(@width $reg), " - ", $crate::asm_block!($n), ")\n"
+        )
+    };
+    (ror $reg: tt $n: tt $($tmp: tt)?) => {
+        $crate::asm_block!(ror $reg, $reg, #$n;)
+    };
+}
+
+#[cfg(target_arch = "riscv32")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_xlen {
+    () => { "32" };
+}
+
+#[cfg(target_arch = "riscv64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_xlen {
+    () => { "64" };
+}
+
+#[cfg(all(any(target_arch = "riscv32", target_arch = "riscv64"), target_feature = "zbb"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_rotate {
+    (rol $reg: tt $n: tt $($tmp: tt)?) => {
+        concat!($crate::asm_block!(rori $reg, $reg,), $crate::__asm_xlen!(), " - ", $crate::asm_block!($n), "\n")
+    };
+    (ror $reg: tt $n: tt $($tmp: tt)?) => {
+        $crate::asm_block!(rori $reg, $reg, $n;)
+    };
+}
+
+#[cfg(all(any(target_arch = "riscv32", target_arch = "riscv64"), not(target_feature = "zbb")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_rotate {
+    (@shift $to: ident $from: ident $reg: tt $n: tt $tmp: tt) => {
+        concat!(
+            $crate::asm_block!($from $tmp, $reg,), $crate::__asm_xlen!(), " - ", $crate::asm_block!($n), "\n",
+            $crate::asm_block!($to $reg, $reg, $n; or $reg, $reg, $tmp;)
+        )
+    };
+    (rol $reg: tt $n: tt $tmp: tt) => {
+        $crate::__asm_rotate!(@shift slli srli $reg $n $tmp)
+    };
+    (ror $reg: tt $n: tt $tmp: tt) => {
+        $crate::__asm_rotate!(@shift srli slli $reg $n $tmp)
+    };
+    ($direction: ident $reg: tt $n: tt) => {
+        compile_error!("rotating without Zbb needs a temporary register, e.g. `rol!(a0, 7, t0)`")
+    };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_rotate {
+    ($($token: tt)*) => {
+        compile_error!("rotations are only supported on x86, x86_64, ARM, AArch64 and RISC-V")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(rol, ror)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_rotate() {
+        assert_eq!(rol!({x:e}, 7), "rol {x:e}, 7 \n");
+        let (mut a, mut b): (u32, u64) = (0x8000_0001, 0x1234_5678_9abc_def0);
+        unsafe {
+            core::arch::asm!(
+                rol!({a:e}, 3),
+                ror!({b}, 12),
+                a = inout(reg) a,
+                b = inout(reg) b,
+                options(pure, nomem, nostack),
+            );
+        }
+        assert_eq!(a, 0x8000_0001u32.rotate_left(3));
+        assert_eq!(b, 0x1234_5678_9abc_def0u64.rotate_right(12));
+    }
+
+    #[cfg(all(feature = "std", target_arch = "aarch64"))]
+    #[test]
+    fn test_rotate_assemble() {
+        assert_eq!(rol!(w3, 5), "ror w3 , w3 , #(32 - 5 )\n");
+        assert_eq!(rol!({x}, 5), "ror {x}, {x}, #(64 - 5 )\n");
+        crate::test::assert_assembles(crate::test::Arch::AArch64, concat!(rol!(w3, 5), rol!(x3, 5), ror!(w3, 5)));
+    }
+}
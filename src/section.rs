@@ -0,0 +1,100 @@
+/// Place a block in a custom section.
+///
+/// This evaluates to a `&'static str` which switches to the section, aligns
+/// it if requested, translates the block, and switches back to the previous
+/// section, with the directives of the object format of the target:
+///
+/// | Format | Switch                       | Switch back   |
+/// |--------|------------------------------|---------------|
+/// | ELF    | `.pushsection name, "flags"` | `.popsection` |
+/// | Mach-O | `.pushsection name`          | `.popsection` |
+/// | COFF   | `.section name, "flags"`     | `.text`       |
+///
+/// The name is emitted as written, so it must follow the syntax of the format,
+/// e.g. `".note.myapp"` on ELF, or `"__DATA,__myapp"` on Mach-O where the
+/// section type goes in the name as well and the flags are ignored. COFF does
+/// not remember the previous section, so the block is assumed to be placed in
+/// `.text`, which is where [`asm!`] and [`global_asm!`] start.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_section;
+/// let code = asm_section!(".note.myapp", flags = "a", align = 4, {
+///     .long 6, 1f - 0f, 1;
+///     .asciz "MYAPP";
+///     .balign 4;
+///     0:
+///     .quad 0x2a;
+///     1:
+///     .balign 4;
+/// });
+/// # #[cfg(target_os = "linux")]
+/// assert_eq!(
+///     code,
+///     ".pushsection .note.myapp, \"a\"\n.balign 4\n\
+///      .long 6 , 1f - 0f , 1 \n.asciz \"MYAPP\" \n.balign 4 \n\
+///      0 :.quad 0x2a \n1 :.balign 4 \n.popsection\n"
+/// );
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+/// [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+#[macro_export]
+macro_rules! asm_section {
+    ($name: literal $(, flags = $flags: literal)? $(, align = $align: literal)?, { $($token: tt)* } $(,)?) => {
+        concat!(
+            $crate::__asm_section!(@begin $name $(, $flags)?),
+            $(".balign ", $align, "\n",)?
+            $crate::asm_block!($($token)*),
+            $crate::__asm_section!(@end)
+        )
+    };
+}
+
+#[cfg(not(any(target_vendor = "apple", windows, target_os = "uefi")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_section {
+    (@begin $name: literal) => { concat!(".pushsection ", $name, "\n") };
+    (@begin $name: literal, $flags: literal) => {
+        concat!(".pushsection ", $name, ", \"", $flags, "\"\n")
+    };
+    (@end) => { ".popsection\n" };
+}
+
+#[cfg(target_vendor = "apple")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_section {
+    (@begin $name: literal $(, $flags: literal)?) => { concat!(".pushsection ", $name, "\n") };
+    (@end) => { ".popsection\n" };
+}
+
+#[cfg(any(windows, target_os = "uefi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_section {
+    (@begin $name: literal) => { concat!(".section ", $name, "\n") };
+    (@begin $name: literal, $flags: literal) => {
+        concat!(".section ", $name, ", \"", $flags, "\"\n")
+    };
+    (@end) => { ".text\n" };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_section)]
+mod tests {
+    #[cfg(not(any(target_vendor = "apple", windows, target_os = "uefi")))]
+    #[test]
+    fn test_section() {
+        assert_eq!(asm_section!(".data.rel.ro", {}), ".pushsection .data.rel.ro\n.popsection\n");
+        assert_eq!(
+            asm_section!(".rodata.table", align = 8, { .quad 1, 2; }),
+            ".pushsection .rodata.table\n.balign 8\n.quad 1 , 2 \n.popsection\n"
+        );
+        assert_eq!(
+            asm_section!(".init_array", flags = "aw", { .quad {f}; },),
+            ".pushsection .init_array, \"aw\"\n.quad {f}\n.popsection\n"
+        );
+    }
+}
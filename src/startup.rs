@@ -0,0 +1,376 @@
+/// Load the address of a symbol into a register.
+///
+/// This evaluates to a `&'static str` which loads the address of `symbol`,
+/// e.g. one defined by the linker script, into `reg`:
+///
+/// | Architecture | Sequence                                              |
+/// |--------------|-------------------------------------------------------|
+/// | x86          | `lea reg, [symbol]`                                   |
+/// | x86-64       | `lea reg, [rip + symbol]`                             |
+/// | ARM          | `ldr reg, =symbol`                                    |
+/// | AArch64      | `adrp reg, symbol`, `add reg, reg, :lo12:symbol`      |
+/// | RISC-V       | `lla reg, symbol`                                     |
+///
+/// The address is relative to the program counter, except on x86 and on ARM,
+/// where it is an absolute address, from the next literal pool on ARM. It is
+/// never loaded from the global offset table, which is not relocated yet
+/// when the startup code runs.
+///
+/// # Example
+/// ```
+/// use asm_block::load_address;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(load_address!({p}, __bss_start), "lea {p}, [rip + __bss_start ]\n");
+/// ```
+#[macro_export]
+macro_rules! load_address {
+    ($reg: tt, $symbol: tt $(,)?) => {
+        $crate::__asm_startup!(address $reg $symbol)
+    };
+}
+
+/// Point the stack pointer at a symbol.
+///
+/// This evaluates to a `&'static str` which sets the stack pointer to the
+/// address of `symbol`, usually the end of the stack defined by the linker
+/// script, as the first thing a reset handler does. AArch64 and ARMv6-M
+/// cannot load an address into `sp` directly, so a temporary register must
+/// be given second there, e.g. `set_stack!(__stack_top, x9)`, through which
+/// the address is moved on ARM if given, and which is ignored elsewhere.
+///
+/// The address is loaded as with [`load_address!`], and the symbol must be
+/// aligned as the ABI of the target requires, i.e. to 16 bytes except on
+/// 32-bit ARM, where 8 bytes are enough.
+///
+/// # Example
+/// ```
+/// use asm_block::set_stack;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(
+///     set_stack!(__stack_top, x9),
+///     "adrp x9 , __stack_top \nadd x9 , x9 , :lo12:__stack_top \nmov sp , x9 \n"
+/// );
+/// ```
+///
+/// [`load_address!`]: macro.load_address.html
+#[macro_export]
+macro_rules! set_stack {
+    ($symbol: tt $(, $tmp: tt)? $(,)?) => {
+        $crate::__asm_startup!(stack $symbol $($tmp)?)
+    };
+}
+
+/// Zero the memory between two symbols.
+///
+/// This evaluates to a `&'static str` which loads the addresses of `start`
+/// and `end` into `ptr` and `last` with [`load_address!`], and zeroes the
+/// words from `start` up to `end`, e.g. `zero_bss!(__bss_start, __bss_end,
+/// r0, r1, r2)` for the `.bss` of a reset handler. The words are of the
+/// natural width, 4 bytes on 32-bit and 8 bytes on 64-bit targets, so both
+/// symbols must be aligned to it, as linker scripts usually do. ARM has no
+/// zero register, so a temporary register holding zero must be given last
+/// there, which is ignored elsewhere.
+///
+/// The loop stores one word at a time, with no vector register, since the
+/// floating-point unit may still be disabled when the startup code runs, and
+/// the instructions are available on every profile, including ARMv6-M, with
+/// low registers. `ptr` ends at `end`, the flags are clobbered, and the loop
+/// uses the numeric labels `42060` and `42061`.
+///
+/// # Example
+/// ```
+/// use asm_block::zero_bss;
+/// # #[cfg(target_arch = "riscv64")]
+/// assert_eq!(
+///     zero_bss!(__bss_start, __bss_end, a0, a1),
+///     concat!(
+///         "lla a0 , __bss_start \n",
+///         "lla a1 , __bss_end \n",
+///         "42060:\n",
+///         "bgeu a0 , a1 , 42061f\n",
+///         "sd zero, 0(a0 )\n",
+///         "addi a0 , a0 , 8\n",
+///         "j 42060b\n",
+///         "42061:\n",
+///     )
+/// );
+/// ```
+///
+/// [`load_address!`]: macro.load_address.html
+#[macro_export]
+macro_rules! zero_bss {
+    ($start: tt, $end: tt, $ptr: tt, $last: tt $(, $tmp: tt)? $(,)?) => {
+        concat!(
+            $crate::load_address!($ptr, $start),
+            $crate::load_address!($last, $end),
+            $crate::__asm_startup!(zero $ptr $last $($tmp)?)
+        )
+    };
+}
+
+/// Copy the initial values of the data between two symbols from another one.
+///
+/// This evaluates to a `&'static str` which copies the words from `start` up
+/// to `end` from the ones at `load`, e.g. `copy_data!(__data_load,
+/// __data_start, __data_end, r0, r1, r2, r3)` for the `.data` of a reset
+/// handler, whose initial values are stored in flash at the load address of
+/// the section. The addresses are loaded into `src`, `dst` and `last`, and
+/// every word goes through `tmp`, like [`zero_bss!`] zeroes them, with the
+/// same alignment and the same numeric labels.
+///
+/// # Example
+/// ```
+/// use asm_block::copy_data;
+/// # #[cfg(target_arch = "x86_64")]
+/// assert!(copy_data!(__data_load, __data_start, __data_end, rsi, rdi, rcx, rax).contains("mov rax , [rsi ] \n"));
+/// ```
+///
+/// [`zero_bss!`]: macro.zero_bss.html
+#[macro_export]
+macro_rules! copy_data {
+    ($load: tt, $start: tt, $end: tt, $src: tt, $dst: tt, $last: tt, $tmp: tt $(,)?) => {
+        concat!(
+            $crate::load_address!($src, $load),
+            $crate::load_address!($dst, $start),
+            $crate::load_address!($last, $end),
+            $crate::__asm_startup!(copy $src $dst $last $tmp)
+        )
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup {
+    (address $reg: tt $symbol: tt) => {
+        concat!($crate::asm_block!(lea $reg,), "[rip + ", $crate::asm_block!($symbol), "]\n")
+    };
+    (stack $symbol: tt $($tmp: tt)?) => {
+        $crate::__asm_startup!(address rsp $symbol)
+    };
+    (zero $ptr: tt $last: tt $($tmp: tt)?) => {
+        $crate::__asm_startup!(@loop $ptr $last { mov qword ptr [$ptr], 0; add $ptr, 8; })
+    };
+    (copy $src: tt $dst: tt $last: tt $tmp: tt) => {
+        $crate::__asm_startup!(@loop $dst $last { mov $tmp, [$src]; mov [$dst], $tmp; add $src, 8; add $dst, 8; })
+    };
+    (@loop $ptr: tt $last: tt { $($body: tt)* }) => {
+        concat!(
+            "42060:\n",
+            $crate::asm_block!(cmp $ptr, $last;),
+            "jae 42061f\n",
+            $crate::asm_block!($($body)*),
+            "jmp 42060b\n",
+            "42061:\n"
+        )
+    };
+}
+
+#[cfg(target_arch = "x86")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup {
+    (address $reg: tt $symbol: tt) => {
+        concat!($crate::asm_block!(lea $reg,), "[", $crate::asm_block!($symbol), "]\n")
+    };
+    (stack $symbol: tt $($tmp: tt)?) => {
+        $crate::__asm_startup!(address esp $symbol)
+    };
+    (zero $ptr: tt $last: tt $($tmp: tt)?) => {
+        $crate::__asm_startup!(@loop $ptr $last { mov dword ptr [$ptr], 0; add $ptr, 4; })
+    };
+    (copy $src: tt $dst: tt $last: tt $tmp: tt) => {
+        $crate::__asm_startup!(@loop $dst $last { mov $tmp, [$src]; mov [$dst], $tmp; add $src, 4; add $dst, 4; })
+    };
+    (@loop $ptr: tt $last: tt { $($body: tt)* }) => {
+        concat!(
+            "42060:\n",
+            $crate::asm_block!(cmp $ptr, $last;),
+            "jae 42061f\n",
+            $crate::asm_block!($($body)*),
+            "jmp 42060b\n",
+            "42061:\n"
+        )
+    };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup {
+    (address $reg: tt $symbol: tt) => {
+        $crate::asm_load_const!(pool, $reg, $symbol)
+    };
+    (stack $symbol: tt $tmp: tt) => {
+        concat!($crate::asm_load_const!(pool, $tmp, $symbol), $crate::asm_block!(mov sp, $tmp;))
+    };
+    (stack $symbol: tt) => {
+        $crate::asm_load_const!(pool, sp, $symbol)
+    };
+    (zero $ptr: tt $last: tt $tmp: tt) => {
+        concat!(
+            $crate::asm_block!(movs $tmp, #0;),
+            $crate::__asm_startup!(@loop $ptr $last { str $tmp, [$ptr]; adds $ptr, #4; })
+        )
+    };
+    (zero $ptr: tt $last: tt) => {
+        compile_error!("zeroing memory on ARM needs a temporary register, e.g. `zero_bss!(start, end, r0, r1, r2)`")
+    };
+    (copy $src: tt $dst: tt $last: tt $tmp: tt) => {
+        $crate::__asm_startup!(@loop $dst $last { ldr $tmp, [$src]; str $tmp, [$dst]; adds $src, #4; adds $dst, #4; })
+    };
+    (@loop $ptr: tt $last: tt { $($body: tt)* }) => {
+        concat!(
+            "42060:\n",
+            $crate::asm_block!(cmp $ptr, $last;),
+            "bhs 42061f\n",
+            $crate::asm_block!($($body)*),
+            "b 42060b\n",
+            "42061:\n"
+        )
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup {
+    (address $reg: tt $symbol: tt) => {
+        concat!(
+            $crate::asm_block!(adrp $reg,), $crate::asm_block!($symbol), "\n",
+            $crate::asm_block!(add $reg, $reg,), ":lo12:", $crate::asm_block!($symbol), "\n"
+        )
+    };
+    (stack $symbol: tt $tmp: tt) => {
+        concat!($crate::__asm_startup!(address $tmp $symbol), $crate::asm_block!(mov sp, $tmp;))
+    };
+    (stack $symbol: tt) => {
+        compile_error!("setting the stack pointer on AArch64 needs a temporary register, e.g. `set_stack!(top, x9)`")
+    };
+    (zero $ptr: tt $last: tt $($tmp: tt)?) => {
+        $crate::__asm_startup!(@loop $ptr $last { str xzr, [$ptr], #8; })
+    };
+    (copy $src: tt $dst: tt $last: tt $tmp: tt) => {
+        $crate::__asm_startup!(@loop $dst $last { ldr $tmp, [$src], #8; str $tmp, [$dst], #8; })
+    };
+    (@loop $ptr: tt $last: tt { $($body: tt)* }) => {
+        concat!(
+            "42060:\n",
+            $crate::asm_block!(cmp $ptr, $last;),
+            "b.hs 42061f\n",
+            $crate::asm_block!($($body)*),
+            "b 42060b\n",
+            "42061:\n"
+        )
+    };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup {
+    (address $reg: tt $symbol: tt) => {
+        concat!($crate::asm_block!(lla $reg,), $crate::asm_block!($symbol), "\n")
+    };
+    (stack $symbol: tt $($tmp: tt)?) => {
+        $crate::__asm_startup!(address sp $symbol)
+    };
+    (zero $ptr: tt $last: tt $($tmp: tt)?) => {
+        $crate::__asm_startup!(@loop $ptr $last {
+            concat!($crate::__asm_startup_word!(store), "zero, 0(", $crate::asm_block!($ptr), ")\n")
+        })
+    };
+    (copy $src: tt $dst: tt $last: tt $tmp: tt) => {
+        $crate::__asm_startup!(@loop $dst $last {
+            concat!(
+                $crate::__asm_startup_word!(load), $crate::asm_block!($tmp,), "0(", $crate::asm_block!($src), ")\n",
+                $crate::__asm_startup_word!(store), $crate::asm_block!($tmp,), "0(", $crate::asm_block!($dst), ")\n",
+                $crate::asm_block!(addi $src, $src,), $crate::__asm_startup_word!(size), "\n"
+            )
+        })
+    };
+    (@loop $ptr: tt $last: tt { $body: expr }) => {
+        concat!(
+            "42060:\n",
+            $crate::asm_block!(bgeu $ptr, $last,), "42061f\n",
+            $body,
+            $crate::asm_block!(addi $ptr, $ptr,), $crate::__asm_startup_word!(size), "\n",
+            "j 42060b\n",
+            "42061:\n"
+        )
+    };
+}
+
+#[cfg(target_arch = "riscv32")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup_word {
+    (load) => { "lw " };
+    (store) => { "sw " };
+    (size) => { "4" };
+}
+
+#[cfg(target_arch = "riscv64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup_word {
+    (load) => { "ld " };
+    (store) => { "sd " };
+    (size) => { "8" };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_startup {
+    ($($token: tt)*) => {
+        compile_error!("startup code is only supported on x86, x86_64, ARM, AArch64 and RISC-V")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(zero_bss, copy_data)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_startup() {
+        assert_eq!(
+            zero_bss!(start, end, {p}, {e}),
+            concat!(
+                "lea {p}, [rip + start ]\n",
+                "lea {e}, [rip + end ]\n",
+                "42060:\n",
+                "cmp {p}, {e}\n",
+                "jae 42061f\n",
+                "mov qword ptr [{p}] , 0 \n",
+                "add {p}, 8 \n",
+                "jmp 42060b\n",
+                "42061:\n",
+            )
+        );
+
+        static SOURCE: [u64; 4] = [1, 2, 3, 4];
+        static mut MEMORY: [u64; 8] = [u64::MAX; 8];
+        unsafe {
+            core::arch::asm!(
+                copy_data!({load}, {memory}, ({memory} + 32), {s}, {d}, {e}, {t}),
+                zero_bss!(({memory} + 32), ({memory} + 64), {d}, {e}),
+                load = sym SOURCE,
+                memory = sym MEMORY,
+                s = out(reg) _,
+                d = out(reg) _,
+                e = out(reg) _,
+                t = out(reg) _,
+                options(nostack),
+            );
+        }
+        assert_eq!(unsafe { core::ptr::addr_of!(MEMORY).read() }, [1, 2, 3, 4, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,177 @@
+/// Emit string literals as data with `.ascii`, `.asciz` or `.string`.
+///
+/// This evaluates to a `&'static str` with the directive followed by the
+/// literals, as [`asm_block!`] transcribes them. `.asciz` and `.string` append
+/// a NUL byte to every literal, while `.ascii` does not.
+///
+/// The bytes emitted are the UTF-8 encoding of the literals, the same as
+/// [`str::as_bytes`]. `macro_rules!` cannot look inside a literal, so there is
+/// no way to pick another encoding like Latin-1, or to emit a `.byte` list
+/// instead. Text in another encoding has to be written with the escapes of
+/// the assembler through `@raw(...)`, e.g. `.ascii @raw(r#""h\351llo""#)` for
+/// `héllo` in Latin-1.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_string;
+/// assert_eq!(asm_string!(asciz, "héllo"), ".asciz \"héllo\" \n");
+/// assert_eq!(asm_string!(ascii, "a\n", "b"), ".ascii \"a\\n\" , \"b\" \n");
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! asm_string {
+    (ascii, $($string: literal),+ $(,)?) => {
+        $crate::asm_block!(.ascii $($string),+;)
+    };
+    (asciz, $($string: literal),+ $(,)?) => {
+        $crate::asm_block!(.asciz $($string),+;)
+    };
+    (string, $($string: literal),+ $(,)?) => {
+        $crate::asm_block!(.string $($string),+;)
+    };
+    ($($token: tt)*) => {
+        compile_error!("expected `ascii`, `asciz` or `string` followed by string literals")
+    };
+}
+
+/// Emit a table of NUL-terminated strings under labels.
+///
+/// This evaluates to a `&'static str` which places each string with `.asciz`
+/// under its label in the section of mergeable strings of the object format,
+/// `.rodata.str1.1` on ELF and `__cstring` on Mach-O, whose identical strings
+/// are merged by the linker, so that a message is stored once in the image
+/// whichever tables and fragments emit it. COFF has no such section, so the
+/// strings are placed in `.rdata` there, and are not deduplicated.
+///
+/// The labels are transcribed by [`asm_block!`], and can be referred to by
+/// the rest of the block, e.g. with [`load_address!`], or by other blocks if
+/// they are made global. Like any other named label, they must not be emitted
+/// more than once, so the table belongs in [`global_asm!`]. One-off strings of
+/// [`asm!`] are better loaded with [`load_string!`].
+///
+/// # Example
+/// ```
+/// use asm_block::asm_string_table;
+/// # #[cfg(target_os = "linux")]
+/// assert_eq!(
+///     asm_string_table!([hello = "hello\n", bye = "bye"]),
+///     ".pushsection .rodata.str1.1, \"aMS\", %progbits, 1\n\
+///      hello:.asciz \"hello\\n\" \nbye:.asciz \"bye\" \n.popsection\n"
+/// );
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+/// [`load_address!`]: macro.load_address.html
+/// [`load_string!`]: macro.load_string.html
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+/// [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+#[macro_export]
+macro_rules! asm_string_table {
+    ([$($label: tt = $string: literal),* $(,)?] $(,)?) => {
+        concat!(
+            $crate::__asm_string_section!(),
+            $($crate::asm_block!($label: .asciz $string;),)*
+            $crate::__asm_section!(@end)
+        )
+    };
+}
+
+/// Load the address of a NUL-terminated string into a register.
+///
+/// This evaluates to a `&'static str` which places the string in the section
+/// of mergeable strings as [`asm_string_table!`] does, under the numeric label
+/// `42082`, and loads its address into `reg` with [`load_address!`]. As the
+/// linker merges identical strings, a diagnostic loaded at every call site of
+/// a fragment is stored once in the image, and no label has to be named.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::load_string;
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// let message: *const u8;
+/// unsafe {
+///     asm!(load_string!({p}, "oops"), p = out(reg) message, options(pure, nomem, nostack));
+///     assert_eq!(std::ffi::CStr::from_ptr(message.cast()).to_bytes(), b"oops");
+/// }
+/// # }
+/// ```
+///
+/// [`asm_string_table!`]: macro.asm_string_table.html
+/// [`load_address!`]: macro.load_address.html
+#[macro_export]
+macro_rules! load_string {
+    ($reg: tt, $string: literal $(,)?) => {
+        concat!(
+            $crate::__asm_string_section!(),
+            "42082:\n",
+            $crate::asm_block!(.asciz $string;),
+            $crate::__asm_section!(@end),
+            $crate::load_address!($reg, 42082b)
+        )
+    };
+}
+
+#[cfg(not(any(target_vendor = "apple", windows, target_os = "uefi")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_string_section {
+    () => { ".pushsection .rodata.str1.1, \"aMS\", %progbits, 1\n" };
+}
+
+#[cfg(target_vendor = "apple")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_string_section {
+    () => { ".pushsection __TEXT,__cstring, cstring_literals\n" };
+}
+
+#[cfg(any(windows, target_os = "uefi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_string_section {
+    () => { ".section .rdata, \"dr\"\n" };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_string, asm_string_table, load_string)]
+mod tests {
+    #[test]
+    fn test_string() {
+        assert_eq!(asm_string!(ascii, "abc"), ".ascii \"abc\" \n");
+        assert_eq!(asm_string!(asciz, "héllo",), ".asciz \"héllo\" \n");
+        assert_eq!(
+            asm_string!(string, "\t\"\\", "\x41"),
+            concat!(r#".string "\t\"\\" , "\x41" "#, "\n")
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_string_table() {
+        if cfg!(target_os = "linux") {
+            assert_eq!(
+                asm_string_table!([{a} = "a", b = "b",]),
+                ".pushsection .rodata.str1.1, \"aMS\", %progbits, 1\n{a}:.asciz \"a\" \nb:.asciz \"b\" \n.popsection\n"
+            );
+        }
+
+        let (first, second, third): (*const u8, *const u8, *const u8);
+        unsafe {
+            core::arch::asm!(
+                load_string!({x}, "same"),
+                load_string!({y}, "other\n"),
+                load_string!({z}, "same"),
+                x = out(reg) first,
+                y = out(reg) second,
+                z = out(reg) third,
+                options(pure, nomem, nostack),
+            );
+            assert_eq!(core::ffi::CStr::from_ptr(first.cast()).to_bytes(), b"same");
+            assert_eq!(core::ffi::CStr::from_ptr(second.cast()).to_bytes(), b"other\n");
+            assert_eq!(core::ffi::CStr::from_ptr(third.cast()).to_bytes(), b"same");
+        }
+    }
+}
@@ -0,0 +1,524 @@
+/// Dispatch on the value of a register on `x86_64`.
+///
+/// This evaluates to a `&'static str` which runs the block of the case equal
+/// to the register, or the `default` block if there is none, and then
+/// continues after the switch. The cases are non-negative integer literals,
+/// and there are two lowerings:
+/// - `ladder` compares the register with every case in order, which suits a
+///   few sparse cases.
+/// - `table(<scratch>)` jumps through a table of relative offsets, which
+///   suits many dense cases. The cases must be `0, 1, 2, ...` in order, which
+///   is checked by the assembler. Both the register and the scratch register
+///   must be 64-bit, and both are clobbered.
+///
+/// The switch uses numeric labels starting with `4202`, so it can be
+/// duplicated by the compiler, or appear several times in the same block. For
+/// the same reason, switches cannot be nested, and the blocks must not use
+/// these labels. The label of a case is `42024` followed by its index, so a
+/// switch has at most 256 cases. Every case is a level of recursion of the
+/// macro, so large switches may need a larger `#![recursion_limit]`.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::asm_switch;
+/// # #[cfg(target_arch = "x86_64")]
+/// fn classify(x: u64) -> u64 {
+///     let r: u64;
+///     unsafe {
+///         asm!(
+///             asm_switch!(table({t}), {x}, [
+///                 0 => { mov {r}, 10; },
+///                 1 => { mov {r}, 20; },
+///                 2 => { mov {r}, 30; },
+///                 default => { xor {r:e}, {r:e}; },
+///             ]),
+///             x = inout(reg) x => _,
+///             t = out(reg) _,
+///             r = out(reg) r,
+///         );
+///     }
+///     r
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// assert_eq!(classify(1), 20);
+/// assert_eq!(classify(7), 0);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! asm_switch {
+    (ladder, $x: tt, [
+        $($value: literal => { $($body: tt)* }),+,
+        default => { $($default: tt)* } $(,)?
+    ] $(,)?) => {
+        $crate::__asm_switch!(@indices @ladder $x, { $($default)* }, [$($value { $($body)* })+])
+    };
+    (table($t: tt), $x: tt, [
+        $($value: literal => { $($body: tt)* }),+,
+        default => { $($default: tt)* } $(,)?
+    ] $(,)?) => {
+        $crate::__asm_switch!(@indices @table $t, $x, { $($default)* }, [$($value { $($body)* })+])
+    };
+    ($($token: tt)*) => {
+        compile_error!("expected `ladder` or `table(<scratch>)`, a register and a list of cases ending with `default`")
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_switch {
+    // the label of a case is `42024` followed by its index, which is taken
+    // from the list appended by `@indices`
+    (@indices $($token: tt)*) => {
+        $crate::__asm_switch!($($token)* [
+            0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30
+            31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58
+            59 60 61 62 63 64 65 66 67 68 69 70 71 72 73 74 75 76 77 78 79 80 81 82 83 84 85 86
+            87 88 89 90 91 92 93 94 95 96 97 98 99 100 101 102 103 104 105 106 107 108 109 110
+            111 112 113 114 115 116 117 118 119 120 121 122 123 124 125 126 127 128 129 130 131
+            132 133 134 135 136 137 138 139 140 141 142 143 144 145 146 147 148 149 150 151 152
+            153 154 155 156 157 158 159 160 161 162 163 164 165 166 167 168 169 170 171 172 173
+            174 175 176 177 178 179 180 181 182 183 184 185 186 187 188 189 190 191 192 193 194
+            195 196 197 198 199 200 201 202 203 204 205 206 207 208 209 210 211 212 213 214 215
+            216 217 218 219 220 221 222 223 224 225 226 227 228 229 230 231 232 233 234 235 236
+            237 238 239 240 241 242 243 244 245 246 247 248 249 250 251 252 253 254 255
+        ])
+    };
+
+    (@compare $x: tt [$($index: literal)*]) => {
+        "jmp 42021f\n"
+    };
+    (@compare $x: tt [$index: literal $($next: literal)*] $value: literal $($rest: literal)*) => {
+        concat!(
+            "cmp ", $crate::asm_block!($x), ", ", stringify!($value), "\n",
+            "je 42024", $index, "f\n",
+            $crate::__asm_switch!(@compare $x [$($next)*] $($rest)*)
+        )
+    };
+    (@compare $x: tt [] $($rest: literal)+) => {
+        compile_error!("a switch has at most 256 cases")
+    };
+    (@one $value: literal) => {
+        " + 1"
+    };
+
+    // check the position of every case while emitting its offset
+    (@entries [$($index: literal)*]) => {
+        ""
+    };
+    (@entries [$index: literal $($next: literal)*] $value: literal $($rest: literal)*) => {
+        concat!(
+            ".if (", stringify!($value), ") != ", $index, "\n",
+            ".error \"the cases of a jump table must be 0, 1, 2, ... in order\"\n",
+            ".endif\n",
+            ".long 42024", $index, "f - 42022b\n",
+            $crate::__asm_switch!(@entries [$($next)*] $($rest)*)
+        )
+    };
+    (@entries [] $($rest: literal)+) => {
+        compile_error!("a switch has at most 256 cases")
+    };
+
+    (@bodies [$($index: literal)*] { $($default: tt)* }) => {
+        concat!("42021:\n", $crate::asm_block!($($default)*), "42020:\n")
+    };
+    (@bodies [$index: literal $($next: literal)*] { $($default: tt)* } $value: literal { $($body: tt)* } $($rest: tt)*) => {
+        concat!(
+            "42024", $index, ":\n",
+            $crate::asm_block!($($body)*),
+            "jmp 42020f\n",
+            $crate::__asm_switch!(@bodies [$($next)*] { $($default)* } $($rest)*)
+        )
+    };
+
+    (@ladder $x: tt, $default: tt, [$($value: literal $body: tt)+] $indices: tt) => {
+        concat!(
+            $crate::__asm_switch!(@compare $x $indices $($value)+),
+            $crate::__asm_switch!(@bodies $indices $default $($value $body)+)
+        )
+    };
+    (@table $t: tt, $x: tt, $default: tt, [$($value: literal $body: tt)+] $indices: tt) => {
+        concat!(
+            "cmp ", $crate::asm_block!($x), ", 0", $($crate::__asm_switch!(@one $value),)+ "\n",
+            "jae 42021f\n",
+            "lea ", $crate::asm_block!($t), ", [rip + 42022f]\n",
+            "movsxd ", $crate::asm_block!($x), ", dword ptr [", $crate::asm_block!($t), " + ",
+            $crate::asm_block!($x), " * 4]\n",
+            "add ", $crate::asm_block!($t), ", ", $crate::asm_block!($x), "\n",
+            "jmp ", $crate::asm_block!($t), "\n",
+            ".p2align 2\n",
+            "42022:\n",
+            $crate::__asm_switch!(@entries $indices $($value)+),
+            $crate::__asm_switch!(@bodies $indices $default $($value $body)+)
+        )
+    };
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_switch {
+    ($($token: tt)*) => {
+        compile_error!("`asm_switch!` is only supported on x86_64")
+    };
+}
+
+/// Specialize a fragment for several constants and dispatch on a register on
+/// `x86_64`.
+///
+/// This evaluates to a `&'static str` with an [`asm_switch!`] on the register,
+/// whose case for every constant is the fragment instantiated with that
+/// constant as its last argument, and whose `default` block handles the other
+/// values, e.g. with a generic loop. This automates the copies of a loop for
+/// the common lengths of a buffer, each of them unrolled or with immediates,
+/// and the switch choosing among them. The lowering is the one of
+/// [`asm_switch!`], usually `ladder`, since the constants are rarely `0, 1, 2,
+/// ...`, and its limit of 256 cases applies to the constants.
+///
+/// The fragment is a macro in scope at the call site, and it is invoked as
+/// `fragment!(<args>, <constant>)`, or `fragment!(<constant>)` without other
+/// arguments.
+///
+/// # Example
+/// ```
+/// # use std::arch::asm;
+/// use asm_block::{asm_block, asm_specialize};
+/// macro_rules! copy {
+///     ($len: literal) => {
+///         asm_block! {
+///             mov ecx, $len;
+///             rep movsb;
+///         }
+///     };
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// fn copy_small(dst: &mut [u8], src: &[u8]) {
+///     assert_eq!(dst.len(), src.len());
+///     unsafe {
+///         asm!(
+///             asm_specialize!(ladder, {n}, copy!(), [16, 32, 64], default => {
+///                 mov rcx, {n};
+///                 rep movsb;
+///             }),
+///             n = in(reg) src.len(),
+///             inout("rdi") dst.as_mut_ptr() => _,
+///             inout("rsi") src.as_ptr() => _,
+///             out("rcx") _,
+///         );
+///     }
+/// }
+/// # #[cfg(target_arch = "x86_64")]
+/// # {
+/// let mut dst = [0; 32];
+/// copy_small(&mut dst, &[7; 32]);
+/// assert_eq!(dst, [7; 32]);
+/// # }
+/// ```
+///
+/// [`asm_switch!`]: macro.asm_switch.html
+#[macro_export]
+macro_rules! asm_specialize {
+    (
+        $lowering: ident $(($scratch: tt))?, $x: tt, $fragment: ident!$args: tt,
+        [$($value: literal),+ $(,)?],
+        default => { $($default: tt)* } $(,)?
+    ) => {
+        $crate::__asm_specialize!($lowering $(($scratch))?, $x, $fragment $args, [$($value)+], { $($default)* })
+    };
+    ($($token: tt)*) => {
+        compile_error!(
+            "expected a lowering of `asm_switch!`, a register, a fragment, a list of constants and `default`"
+        )
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_specialize {
+    (@case $fragment: ident () $value: literal) => {
+        $fragment!($value)
+    };
+    (@case $fragment: ident ($($arg: tt)+) $value: literal) => {
+        $fragment!($($arg)+, $value)
+    };
+
+    ($lowering: ident $(($scratch: tt))?, $x: tt, $fragment: ident $args: tt, [$($value: literal)+], { $($default: tt)* }) => {
+        $crate::asm_switch!($lowering $(($scratch))?, $x, [
+            $($value => { @inline($crate::__asm_specialize!(@case $fragment $args $value)) },)+
+            default => { $($default)* }
+        ])
+    };
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_specialize {
+    ($($token: tt)*) => {
+        compile_error!("`asm_specialize!` is only supported on x86_64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_switch, asm_specialize)]
+mod tests {
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_ladder() {
+        let code = asm_switch!(ladder, {x}, [
+            3 => { inc rax; },
+            0x10 => {},
+            default => { xor eax, eax; },
+        ]);
+        let expected = concat!(
+            "cmp {x}, 3\n",
+            "je 420240f\n",
+            "cmp {x}, 0x10\n",
+            "je 420241f\n",
+            "jmp 42021f\n",
+            "420240:\n",
+            "inc rax \n",
+            "jmp 42020f\n",
+            "420241:\n",
+            "jmp 42020f\n",
+            "42021:\n",
+            "xor eax , eax \n",
+            "42020:\n",
+        );
+        assert_eq!(code, expected);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_specialize() {
+        macro_rules! add {
+            ($x: tt, $value: literal) => { asm_block!(add $x, $value;) };
+        }
+        macro_rules! fill {
+            ($value: literal) => { asm_block!(.fill $value, 1, 0x90;) };
+        }
+        let code = asm_specialize!(ladder, {n}, add!({x}), [4, 8], default => { add {x}, {n}; });
+        let expected = asm_switch!(ladder, {n}, [
+            4 => { add {x}, 4; },
+            8 => { add {x}, 8; },
+            default => { add {x}, {n}; },
+        ]);
+        assert_eq!(code, expected);
+        let code = asm_specialize!(table({t}), {n}, fill!(), [0, 1], default => {});
+        let expected = asm_switch!(table({t}), {n}, [
+            0 => { .fill 0, 1, 0x90; },
+            1 => { .fill 1, 1, 0x90; },
+            default => {},
+        ]);
+        assert_eq!(code, expected);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_table() {
+        let code = asm_switch!(table({t}), {x}, [0 => { nop; }, 1 => {}, default => {}]);
+        let expected = concat!(
+            "cmp {x}, 0 + 1 + 1\n",
+            "jae 42021f\n",
+            "lea {t}, [rip + 42022f]\n",
+            "movsxd {x}, dword ptr [{t} + {x} * 4]\n",
+            "add {t}, {x}\n",
+            "jmp {t}\n",
+            ".p2align 2\n",
+            "42022:\n",
+            ".if (0) != 0\n",
+            ".error \"the cases of a jump table must be 0, 1, 2, ... in order\"\n",
+            ".endif\n",
+            ".long 420240f - 42022b\n",
+            ".if (1) != 1\n",
+            ".error \"the cases of a jump table must be 0, 1, 2, ... in order\"\n",
+            ".endif\n",
+            ".long 420241f - 42022b\n",
+            "420240:\n",
+            "nop \n",
+            "jmp 42020f\n",
+            "420241:\n",
+            "jmp 42020f\n",
+            "42021:\n",
+            "42020:\n",
+        );
+        assert_eq!(code, expected);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_large_switch() {
+        fn table(x: u64) -> u64 {
+            let mut r: u64 = 1000;
+            unsafe {
+                core::arch::asm!(
+                    asm_switch!(table({t}), {x}, [
+                        0 => { mov {r}, 0; },
+                        1 => { mov {r}, 3; },
+                        2 => { mov {r}, 6; },
+                        3 => { mov {r}, 9; },
+                        4 => { mov {r}, 12; },
+                        5 => { mov {r}, 15; },
+                        6 => { mov {r}, 18; },
+                        7 => { mov {r}, 21; },
+                        8 => { mov {r}, 24; },
+                        9 => { mov {r}, 27; },
+                        10 => { mov {r}, 30; },
+                        11 => { mov {r}, 33; },
+                        12 => { mov {r}, 36; },
+                        13 => { mov {r}, 39; },
+                        14 => { mov {r}, 42; },
+                        15 => { mov {r}, 45; },
+                        16 => { mov {r}, 48; },
+                        17 => { mov {r}, 51; },
+                        18 => { mov {r}, 54; },
+                        19 => { mov {r}, 57; },
+                        20 => { mov {r}, 60; },
+                        21 => { mov {r}, 63; },
+                        22 => { mov {r}, 66; },
+                        23 => { mov {r}, 69; },
+                        24 => { mov {r}, 72; },
+                        25 => { mov {r}, 75; },
+                        26 => { mov {r}, 78; },
+                        27 => { mov {r}, 81; },
+                        28 => { mov {r}, 84; },
+                        29 => { mov {r}, 87; },
+                        30 => { mov {r}, 90; },
+                        31 => { mov {r}, 93; },
+                        32 => { mov {r}, 96; },
+                        33 => { mov {r}, 99; },
+                        34 => { mov {r}, 102; },
+                        35 => { mov {r}, 105; },
+                        36 => { mov {r}, 108; },
+                        37 => { mov {r}, 111; },
+                        38 => { mov {r}, 114; },
+                        39 => { mov {r}, 117; },
+                        default => {},
+                    ]),
+                    x = inout(reg) x => _,
+                    t = out(reg) _,
+                    r = inout(reg) r,
+                );
+            }
+            r
+        }
+        fn ladder(x: u64) -> u64 {
+            let mut r: u64 = 1000;
+            unsafe {
+                core::arch::asm!(
+                    asm_switch!(ladder, {x}, [
+                        0 => { mov {r}, 0; },
+                        7 => { mov {r}, 1; },
+                        14 => { mov {r}, 2; },
+                        21 => { mov {r}, 3; },
+                        28 => { mov {r}, 4; },
+                        35 => { mov {r}, 5; },
+                        42 => { mov {r}, 6; },
+                        49 => { mov {r}, 7; },
+                        56 => { mov {r}, 8; },
+                        63 => { mov {r}, 9; },
+                        70 => { mov {r}, 10; },
+                        77 => { mov {r}, 11; },
+                        84 => { mov {r}, 12; },
+                        91 => { mov {r}, 13; },
+                        98 => { mov {r}, 14; },
+                        105 => { mov {r}, 15; },
+                        112 => { mov {r}, 16; },
+                        119 => { mov {r}, 17; },
+                        126 => { mov {r}, 18; },
+                        133 => { mov {r}, 19; },
+                        140 => { mov {r}, 20; },
+                        147 => { mov {r}, 21; },
+                        154 => { mov {r}, 22; },
+                        161 => { mov {r}, 23; },
+                        168 => { mov {r}, 24; },
+                        175 => { mov {r}, 25; },
+                        182 => { mov {r}, 26; },
+                        189 => { mov {r}, 27; },
+                        196 => { mov {r}, 28; },
+                        203 => { mov {r}, 29; },
+                        210 => { mov {r}, 30; },
+                        217 => { mov {r}, 31; },
+                        224 => { mov {r}, 32; },
+                        231 => { mov {r}, 33; },
+                        238 => { mov {r}, 34; },
+                        245 => { mov {r}, 35; },
+                        252 => { mov {r}, 36; },
+                        259 => { mov {r}, 37; },
+                        266 => { mov {r}, 38; },
+                        273 => { mov {r}, 39; },
+                        default => {},
+                    ]),
+                    x = in(reg) x,
+                    r = inout(reg) r,
+                );
+            }
+            r
+        }
+        for x in 0..40 {
+            assert_eq!(table(x), x * 3);
+            assert_eq!(ladder(x * 7), x);
+        }
+        assert_eq!(table(40), 1000);
+        assert_eq!(ladder(8), 1000);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_large_specialize() {
+        macro_rules! add {
+            ($r: tt, $value: literal) => { asm_block!(add $r, $value;) };
+        }
+        fn scale(n: u64) -> u64 {
+            let mut r: u64 = 0;
+            unsafe {
+                core::arch::asm!(
+                    asm_specialize!(ladder, {n}, add!({r}), [0, 5, 10, 15, 20, 25, 30, 35, 40, 45, 50, 55, 60, 65, 70, 75, 80, 85, 90, 95, 100, 105, 110, 115, 120, 125, 130, 135, 140, 145, 150, 155, 160, 165, 170, 175], default => {
+                        mov {r}, {n};
+                        not {r};
+                    }),
+                    n = in(reg) n,
+                    r = inout(reg) r,
+                );
+            }
+            r
+        }
+        for n in 0..36 {
+            assert_eq!(scale(n * 5), n * 5);
+        }
+        assert_eq!(scale(3), !3);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_switch() {
+        fn classify(x: u64) -> u64 {
+            let mut r: u64 = 0;
+            unsafe {
+                core::arch::asm!(
+                    asm_switch!(table({t}), {x}, [
+                        0 => { mov {r}, 10; },
+                        1 => { mov {r}, 20; },
+                        default => {},
+                    ]),
+                    x = inout(reg) x => _,
+                    t = out(reg) _,
+                    r = inout(reg) r,
+                );
+                core::arch::asm!(
+                    asm_switch!(ladder, {x}, [7 => { add {r}, 7; }, default => { add {r}, 1; }]),
+                    x = in(reg) x,
+                    r = inout(reg) r,
+                );
+            }
+            r
+        }
+        assert_eq!(classify(0), 11);
+        assert_eq!(classify(1), 21);
+        assert_eq!(classify(7), 7);
+        assert_eq!(classify(3), 1);
+    }
+}
@@ -0,0 +1,258 @@
+/// Declare the linkage or the visibility of a symbol.
+///
+/// This evaluates to a `&'static str` with the directive of the object format
+/// of the target, so that the same fragment works for ELF, Mach-O and COFF:
+///
+/// | Call                            | ELF               | Mach-O                  | COFF             |
+/// |---------------------------------|-------------------|-------------------------|------------------|
+/// | `asm_symbol!(weak name)`        | `.weak name`      | `.weak_definition name` | `.weak name`     |
+/// | `asm_symbol!(hidden name)`      | `.hidden name`    | `.private_extern name`  | nothing          |
+/// | `asm_symbol!(protected name)`   | `.protected name` | error                   | error            |
+/// | `asm_symbol!(alias name = sym)` | `.set name, sym`  | `.set name, sym`        | `.set name, sym` |
+///
+/// `weak` is meant for definitions, e.g. a default implementation which can
+/// be overridden by another object. COFF symbols are not exported unless
+/// marked with `dllexport`, so `hidden` emits nothing there. Protected
+/// visibility only exists in ELF, so it fails to compile on other formats.
+///
+/// The symbol names are transcribed by [`asm_block!`], so they can be
+/// placeholders of `sym` operands, which also take care of the `_` prefix of
+/// Mach-O for symbols defined in Rust.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_symbol};
+/// let code = concat!(
+///     asm_symbol!(weak my_hook),
+///     asm_symbol!(hidden my_hook),
+///     asm_block! {
+///         .globl my_hook;
+///         my_hook:
+///         ret;
+///     },
+/// );
+/// # #[cfg(target_os = "linux")]
+/// assert_eq!(code, ".weak my_hook \n.hidden my_hook \n.globl my_hook \nmy_hook:ret \n");
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! asm_symbol {
+    (weak $name: tt) => {
+        $crate::__asm_symbol!(weak $name)
+    };
+    (hidden $name: tt) => {
+        $crate::__asm_symbol!(hidden $name)
+    };
+    (protected $name: tt) => {
+        $crate::__asm_symbol!(protected $name)
+    };
+    (alias $name: tt = $target: tt) => {
+        $crate::asm_block!(.set $name, $target;)
+    };
+    ($($token: tt)*) => {
+        compile_error!("expected `weak`, `hidden` or `protected` followed by a symbol, or `alias <name> = <symbol>`")
+    };
+}
+
+#[cfg(not(any(target_vendor = "apple", windows, target_os = "uefi")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_symbol {
+    (weak $name: tt) => { $crate::asm_block!(.weak $name;) };
+    (hidden $name: tt) => { $crate::asm_block!(.hidden $name;) };
+    (protected $name: tt) => { $crate::asm_block!(.protected $name;) };
+}
+
+#[cfg(target_vendor = "apple")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_symbol {
+    (weak $name: tt) => { $crate::asm_block!(.weak_definition $name;) };
+    (hidden $name: tt) => { $crate::asm_block!(.private_extern $name;) };
+    (protected $name: tt) => {
+        compile_error!("protected visibility is not supported by Mach-O")
+    };
+}
+
+#[cfg(any(windows, target_os = "uefi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_symbol {
+    (weak $name: tt) => { $crate::asm_block!(.weak $name;) };
+    (hidden $name: tt) => { "" };
+    (protected $name: tt) => {
+        compile_error!("protected visibility is not supported by COFF")
+    };
+}
+
+/// Name a label with the local symbol prefix of the target.
+///
+/// Symbols starting with the prefix are resolved by the assembler and never
+/// reach the symbol table of the object. The prefix is `L` on Mach-O and on
+/// 32-bit Windows, and `.L` elsewhere. The result can be spliced into a block
+/// with `@inline(...)`.
+///
+/// There is no counter involved, so the label is the same in every build,
+/// which keeps the generated assembly diffable. Names are only unique if they
+/// are chosen so, e.g. by prefixing them with the name of the crate. Deriving
+/// a suffix from [`module_path!`] is not possible, since `macro_rules!` cannot
+/// rewrite the `::` in it into characters allowed in a symbol.
+///
+/// Like any other named label, the label must not be emitted more than once,
+/// which the compiler may do with [`asm!`] by inlining or unrolling. Prefer
+/// numeric labels in [`asm!`], and this macro in [`global_asm!`].
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_local};
+/// let code = asm_block! {
+///     @inline(asm_local!(done)):
+///     jmp @inline(asm_local!(done));
+/// };
+/// # #[cfg(target_os = "linux")]
+/// assert_eq!(code, ".Ldone:jmp .Ldone\n");
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+/// [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+/// [`module_path!`]: https://doc.rust-lang.org/stable/core/macro.module_path.html
+#[macro_export]
+macro_rules! asm_local {
+    ($name: ident) => {
+        concat!($crate::__asm_local_prefix!(), stringify!($name))
+    };
+}
+
+/// Paste pieces into one name, e.g. of a label.
+///
+/// Labels of unrolled code have to be told apart, e.g. by the index of the
+/// iteration, but [`asm_block!`] adds a space after every identifier and
+/// literal, and `macro_rules!` cannot create new identifiers. This evaluates
+/// to a `&'static str` concatenating the pieces separated by `~` without any
+/// space, where a piece is:
+/// - an identifier or a literal, transcribed as-is, e.g. a parameter of the
+///   fragment or an element of [`asm_foreach!`].
+/// - a placeholder `{...}`, e.g. of a `const` operand, which [`asm!`]
+///   substitutes with its value.
+/// - `@arg(<arg>)`, a reference to an argument of an assembler macro or a
+///   `.irp` loop, followed by `\()` so that it ends where the argument does.
+/// - `@inline(<fragment>!(<args>))`, the output of a macro, e.g. a local
+///   label prefixed by [`asm_local!`].
+///
+/// The result can be spliced into a block with `@inline(...)`.
+///
+/// # Example
+/// ```
+/// use asm_block::{asm_block, asm_paste};
+/// macro_rules! round {
+///     ($i: literal) => {
+///         asm_block! {
+///             @inline(asm_paste!(round_ ~ $i ~ _done)):
+///             dec ecx;
+///             jnz @inline(asm_paste!(round_ ~ $i ~ _done));
+///         }
+///     };
+/// }
+/// assert_eq!(round!(3), "round_3_done:dec ecx \njnz round_3_done\n");
+/// assert_eq!(asm_paste!(table_ ~ {n}), "table_{n}");
+/// assert_eq!(asm_paste!(round_ ~ @arg(i) ~ _done), "round_\\i\\()_done");
+/// ```
+///
+/// [`asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.asm.html
+/// [`asm_block!`]: macro.asm_block.html
+/// [`asm_foreach!`]: macro.asm_foreach.html
+/// [`asm_local!`]: macro.asm_local.html
+#[macro_export]
+macro_rules! asm_paste {
+    // translate the pieces one by one, each followed by `~` or the end
+    (@paste [$($done: expr,)*]) => {
+        concat!($($done),*)
+    };
+    (@paste [$($done: expr,)*] @ arg $arg: tt $(~ $($rest: tt)+)?) => {
+        $crate::asm_paste!(@paste [$($done,)* concat!($crate::asm_block!(@ arg $arg), "\\()"),] $($($rest)+)?)
+    };
+    (@paste [$($done: expr,)*] @ inline ($($fragment: ident)::+ ! $args: tt) $(~ $($rest: tt)+)?) => {
+        $crate::asm_paste!(@paste [$($done,)* $($fragment)::+ ! $args,] $($($rest)+)?)
+    };
+    (@paste [$($done: expr,)*] { $($placeholder: tt)* } $(~ $($rest: tt)+)?) => {
+        $crate::asm_paste!(@paste [$($done,)* $crate::asm_block!({ $($placeholder)* }),] $($($rest)+)?)
+    };
+    (@paste [$($done: expr,)*] $piece: tt $(~ $($rest: tt)+)?) => {
+        $crate::asm_paste!(@paste [$($done,)* stringify!($piece),] $($($rest)+)?)
+    };
+    (@paste $done: tt $($rest: tt)*) => {
+        compile_error!("`asm_paste!` expects single tokens, placeholders, `@arg(...)` or `@inline(...)` separated by `~`")
+    };
+
+    ($($token: tt)+) => {
+        $crate::asm_paste!(@paste [] $($token)+)
+    };
+}
+
+#[cfg(any(target_vendor = "apple", all(windows, target_arch = "x86")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_local_prefix {
+    () => { "L" };
+}
+
+#[cfg(not(any(target_vendor = "apple", all(windows, target_arch = "x86"))))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_local_prefix {
+    () => { ".L" };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_symbol, asm_local, asm_paste)]
+mod tests {
+    #[test]
+    fn test_symbol() {
+        assert_eq!(asm_symbol!(alias memcpy = my_memcpy), ".set memcpy , my_memcpy \n");
+        assert_eq!(asm_symbol!(alias {a} = {f}), ".set {a}, {f}\n");
+        if cfg!(target_vendor = "apple") {
+            assert_eq!(asm_symbol!(weak {f}), ".weak_definition {f}\n");
+            assert_eq!(asm_symbol!(hidden f), ".private_extern f \n");
+        } else if cfg!(any(windows, target_os = "uefi")) {
+            assert_eq!(asm_symbol!(weak {f}), ".weak {f}\n");
+            assert_eq!(asm_symbol!(hidden f), "");
+        } else {
+            assert_eq!(asm_symbol!(weak {f}), ".weak {f}\n");
+            assert_eq!(asm_symbol!(hidden f), ".hidden f \n");
+        }
+    }
+
+    #[test]
+    fn test_local() {
+        let prefix = if cfg!(any(target_vendor = "apple", all(windows, target_arch = "x86"))) {
+            "L"
+        } else {
+            ".L"
+        };
+        assert_eq!(asm_local!(loop_head).strip_prefix(prefix), Some("loop_head"));
+    }
+
+    #[test]
+    fn test_paste() {
+        macro_rules! label {
+            ($name: ident, $i: literal) => {
+                asm_paste!($name ~ _ ~ $i)
+            };
+        }
+        assert_eq!(asm_paste!(done), "done");
+        assert_eq!(label!(loop, 0x10), "loop_0x10");
+        assert_eq!(asm_paste!(block ~ {id:e} ~ 2), "block{id:e}2");
+        assert_eq!(
+            asm_paste!(@inline(asm_local!(block)) ~ 1),
+            concat!(asm_local!(block), "1")
+        );
+        assert_eq!(asm_paste!(@arg(name) ~ _end), "\\name\\()_end");
+    }
+
+    #[cfg(not(any(target_vendor = "apple", windows, target_os = "uefi")))]
+    #[test]
+    fn test_protected() {
+        assert_eq!(asm_symbol!(protected f), ".protected f \n");
+    }
+}
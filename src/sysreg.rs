@@ -0,0 +1,214 @@
+/// Read a system register of AArch64.
+///
+/// This evaluates to a `&'static str` which reads the system register `name`
+/// into `reg` with `mrs`, e.g. `read_sysreg!(x0, TTBR0_EL1)`. `reg` is
+/// transcribed by [`asm_block!`], so it can be a register or the placeholder
+/// of a 64-bit operand, and `name` can be any name the assembler knows, or
+/// the generic `S<op0>_<op1>_C<n>_C<m>_<op2>` form.
+///
+/// # Example
+/// ```
+/// use asm_block::read_sysreg;
+/// # #[cfg(target_arch = "aarch64")]
+/// assert_eq!(read_sysreg!({x}, MPIDR_EL1), "mrs {x}, MPIDR_EL1\n");
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! read_sysreg {
+    ($reg: tt, $name: ident $(,)?) => {
+        $crate::__asm_sysreg!(read $reg $name)
+    };
+}
+
+/// Write a system register of AArch64.
+///
+/// This evaluates to a `&'static str` which writes `reg` to the system
+/// register `name` with `msr`, like [`read_sysreg!`] reads it. A write is
+/// only guaranteed to be seen by later instructions after a context
+/// synchronization event, so the write of a register controlling the
+/// translation, the exceptions, the traps or the timers is followed by an
+/// `isb`, i.e. of `SCTLR_ELx`, `TCR_ELx`, `TTBR0_ELx`, `TTBR1_ELx`,
+/// `MAIR_ELx`, `VBAR_ELx`, `CPACR_EL1`, `CPTR_ELx`, `HCR_EL2`, `SCR_EL3`,
+/// `CONTEXTIDR_EL1`, `CNTKCTL_EL1`, `MDSCR_EL1`, and the control and compare
+/// registers of the physical and virtual timers.
+///
+/// The names are matched as written in the Arm Architecture Reference
+/// Manual, in upper case, and the write of another register can be followed
+/// by an `isb` with `sync` after it, e.g. `write_sysreg!(ZCR_EL1, x0, sync)`.
+/// The maintenance of the TLBs after a write of a translation table base is
+/// left to the block, since it depends on how the tables change.
+///
+/// # Example
+/// ```
+/// use asm_block::write_sysreg;
+/// # #[cfg(target_arch = "aarch64")]
+/// # {
+/// assert_eq!(write_sysreg!(TTBR0_EL1, {x}), "msr TTBR0_EL1, {x}\nisb\n");
+/// assert_eq!(write_sysreg!(TPIDR_EL0, xzr), "msr TPIDR_EL0, xzr \n");
+/// # }
+/// ```
+///
+/// [`read_sysreg!`]: macro.read_sysreg.html
+#[macro_export]
+macro_rules! write_sysreg {
+    ($name: ident, $reg: tt, sync $(,)?) => {
+        concat!($crate::__asm_sysreg!(write $name $reg), "isb\n")
+    };
+    ($name: ident, $reg: tt $(,)?) => {
+        concat!($crate::__asm_sysreg!(write $name $reg), $crate::__asm_sysreg!(@sync $name))
+    };
+}
+
+/// Read a control and status register of RISC-V.
+///
+/// This evaluates to a `&'static str` which reads the CSR `name` into `reg`
+/// with `csrr`, e.g. `read_csr!(a0, sstatus)`, with `reg` transcribed by
+/// [`asm_block!`] and `name` any name or number the assembler knows.
+///
+/// # Example
+/// ```
+/// use asm_block::read_csr;
+/// # #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+/// assert_eq!(read_csr!({x}, scause), "csrr {x}, scause\n");
+/// ```
+///
+/// [`asm_block!`]: macro.asm_block.html
+#[macro_export]
+macro_rules! read_csr {
+    ($reg: tt, $name: tt $(,)?) => {
+        $crate::__asm_csr!(read $reg $name)
+    };
+}
+
+/// Write a control and status register of RISC-V.
+///
+/// This evaluates to a `&'static str` which writes `reg` to the CSR `name`
+/// with `csrw`, like [`read_csr!`] reads it. The write of `satp` is followed
+/// by `sfence.vma`, so that no translation cached from the previous table is
+/// used, and the write of another CSR can be followed by a `fence.i` with
+/// `sync` after it, e.g. after changing the execution environment.
+///
+/// # Example
+/// ```
+/// use asm_block::write_csr;
+/// # #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+/// # {
+/// assert_eq!(write_csr!(satp, {x}), "csrw satp, {x}\nsfence.vma\n");
+/// assert_eq!(write_csr!(sscratch, zero), "csrw sscratch, zero \n");
+/// # }
+/// ```
+///
+/// [`read_csr!`]: macro.read_csr.html
+#[macro_export]
+macro_rules! write_csr {
+    ($name: tt, $reg: tt, sync $(,)?) => {
+        concat!($crate::__asm_csr!(write $name $reg), "fence.i\n")
+    };
+    ($name: tt, $reg: tt $(,)?) => {
+        concat!($crate::__asm_csr!(write $name $reg), $crate::__asm_csr!(@sync $name))
+    };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_sysreg {
+    (@sync SCTLR_EL1) => { "isb\n" };
+    (@sync SCTLR_EL2) => { "isb\n" };
+    (@sync SCTLR_EL3) => { "isb\n" };
+    (@sync TCR_EL1) => { "isb\n" };
+    (@sync TCR_EL2) => { "isb\n" };
+    (@sync TCR_EL3) => { "isb\n" };
+    (@sync TTBR0_EL1) => { "isb\n" };
+    (@sync TTBR0_EL2) => { "isb\n" };
+    (@sync TTBR0_EL3) => { "isb\n" };
+    (@sync TTBR1_EL1) => { "isb\n" };
+    (@sync TTBR1_EL2) => { "isb\n" };
+    (@sync MAIR_EL1) => { "isb\n" };
+    (@sync MAIR_EL2) => { "isb\n" };
+    (@sync MAIR_EL3) => { "isb\n" };
+    (@sync VBAR_EL1) => { "isb\n" };
+    (@sync VBAR_EL2) => { "isb\n" };
+    (@sync VBAR_EL3) => { "isb\n" };
+    (@sync CPACR_EL1) => { "isb\n" };
+    (@sync CPTR_EL2) => { "isb\n" };
+    (@sync CPTR_EL3) => { "isb\n" };
+    (@sync HCR_EL2) => { "isb\n" };
+    (@sync SCR_EL3) => { "isb\n" };
+    (@sync CONTEXTIDR_EL1) => { "isb\n" };
+    (@sync CNTKCTL_EL1) => { "isb\n" };
+    (@sync CNTP_CTL_EL0) => { "isb\n" };
+    (@sync CNTP_CVAL_EL0) => { "isb\n" };
+    (@sync CNTV_CTL_EL0) => { "isb\n" };
+    (@sync CNTV_CVAL_EL0) => { "isb\n" };
+    (@sync MDSCR_EL1) => { "isb\n" };
+    (@sync $name: ident) => { "" };
+
+    (read $reg: tt $name: ident) => {
+        concat!($crate::asm_block!(mrs $reg,), stringify!($name), "\n")
+    };
+    (write $name: ident $reg: tt) => {
+        concat!("msr ", stringify!($name), ", ", $crate::asm_block!($reg;))
+    };
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_sysreg {
+    ($($token: tt)*) => {
+        compile_error!("system registers are only supported on AArch64, see `read_csr!` for RISC-V")
+    };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_csr {
+    (@sync satp) => { "sfence.vma\n" };
+    (@sync $name: tt) => { "" };
+
+    (read $reg: tt $name: tt) => {
+        concat!($crate::asm_block!(csrr $reg,), stringify!($name), "\n")
+    };
+    (write $name: tt $reg: tt) => {
+        concat!("csrw ", stringify!($name), ", ", $crate::asm_block!($reg;))
+    };
+}
+
+#[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_csr {
+    ($($token: tt)*) => {
+        compile_error!("CSRs are only supported on RISC-V, see `read_sysreg!` for AArch64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(read_sysreg, write_sysreg, read_csr, write_csr)]
+mod tests {
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn test_sysreg() {
+        assert_eq!(write_sysreg!(SCTLR_EL1, x0), "msr SCTLR_EL1, x0 \nisb\n");
+        assert_eq!(write_sysreg!(ZCR_EL1, x0, sync), "msr ZCR_EL1, x0 \nisb\n");
+        let id: u64;
+        unsafe {
+            core::arch::asm!(read_sysreg!({x}, MIDR_EL1), x = out(reg) id, options(pure, nomem, nostack));
+        }
+        assert_ne!(id, 0);
+        #[cfg(feature = "std")]
+        crate::test::assert_assembles(crate::test::Arch::AArch64, write_sysreg!(TTBR0_EL1, x1));
+    }
+
+    #[cfg(target_arch = "riscv64")]
+    #[test]
+    fn test_csr() {
+        assert_eq!(read_csr!(a0, 0x140), "csrr a0 , 0x140\n");
+        assert_eq!(write_csr!(stvec, a0, sync), "csrw stvec, a0 \nfence.i\n");
+        #[cfg(feature = "std")]
+        crate::test::assert_assembles(crate::test::Arch::RiscV64, write_csr!(satp, a1));
+    }
+}
@@ -0,0 +1,321 @@
+//! Utilities for testing fragments against a real assembler.
+//!
+//! This requires the `std` feature. The assembler is `llvm-mc`, which is the
+//! same assembler used by `rustc`, and is looked up in `PATH` unless the
+//! `ASM_BLOCK_LLVM_MC` environment variable points to it. Since `llvm-mc`
+//! supports all the targets at once, fragments of every architecture can be
+//! tested on any host.
+//!
+//! Besides checking that an expansion is accepted with [`assemble`], the
+//! encoded instructions can be inspected with [`disassemble`], e.g. to assert
+//! that a constant-time fragment contains no branch, or that a patch fits in
+//! a given number of bytes. Unit tests use [`assert_assembles`] and
+//! [`assert_disassembles`] instead, which panic on errors and report the
+//! tests skipped for lack of an assembler.
+//!
+//! # Example
+//! ```
+//! use asm_block::asm_block;
+//! use asm_block::test::{assert_assembles, Arch};
+//! macro_rules! mad {
+//!     ($x: tt, $y: tt) => {
+//!         asm_block! {
+//!             imul $x, $y;
+//!             lea $x, [$x + $y];
+//!         }
+//!     };
+//! }
+//! // skipped if the assembler is not installed
+//! assert_assembles(Arch::X86_64, mad!(rax, 5));
+//! ```
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Target architecture to assemble for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arch {
+    /// `x86`, in Intel syntax.
+    X86,
+    /// `x86_64`, in Intel syntax.
+    X86_64,
+    /// `arm`.
+    Arm,
+    /// `aarch64`.
+    AArch64,
+    /// `riscv64`.
+    RiscV64,
+}
+
+impl Arch {
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            Arch::X86 => &["-triple=i686", "-x86-asm-syntax=intel"],
+            Arch::X86_64 => &["-triple=x86_64", "-x86-asm-syntax=intel"],
+            Arch::Arm => &["-triple=armv7"],
+            Arch::AArch64 => &["-triple=aarch64"],
+            Arch::RiscV64 => &["-triple=riscv64"],
+        }
+    }
+}
+
+/// Error returned by [`assemble`].
+#[derive(Debug)]
+pub enum Error {
+    /// The assembler is not installed.
+    NotFound,
+    /// The assembler could not be run.
+    Io(io::Error),
+    /// The assembler rejected the code, with its diagnostics.
+    Rejected(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "llvm-mc is not found"),
+            Error::Io(e) => write!(f, "failed to run llvm-mc: {}", e),
+            Error::Rejected(diagnostics) => write!(f, "assembly is rejected:\n{}", diagnostics),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An instruction encoded by the assembler.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// The instruction as printed back by the assembler, e.g. `mov rax, 1`.
+    pub text: String,
+    /// The encoding. Bytes to be fixed up by the linker, like branch offsets,
+    /// are zeros.
+    pub bytes: Vec<u8>,
+}
+
+impl Instruction {
+    /// The mnemonic of the instruction.
+    pub fn mnemonic(&self) -> &str {
+        self.text.split_whitespace().next().unwrap_or("")
+    }
+}
+
+/// Assemble the code for `arch` and discard the output.
+///
+/// The code must not contain any placeholder, since there is no operand to
+/// substitute it with. Fragments should be instantiated with registers
+/// instead, e.g. `mad!(rax, 5)` rather than `mad!({x}, 5)`.
+pub fn assemble(arch: Arch, code: &str) -> Result<(), Error> {
+    run(arch, &["-filetype=null"], code).map(|_| ())
+}
+
+/// Assemble the code for `arch` and return the encoded instructions in order.
+///
+/// Directives and labels are not part of the result, but the data they emit
+/// still counts toward the size of the code. The same restriction on
+/// placeholders as [`assemble`] applies.
+///
+/// # Example
+/// ```
+/// use asm_block::asm_block;
+/// use asm_block::test::{disassemble, Arch, Error};
+/// let code = asm_block! {
+///     xor eax, eax;
+///     cmp rdi, rsi;
+///     sete al;
+/// };
+/// match disassemble(Arch::X86_64, code) {
+///     Ok(instructions) => {
+///         assert_eq!(instructions.len(), 3);
+///         assert!(instructions.iter().all(|i| !i.mnemonic().starts_with('j')));
+///         assert_eq!(instructions.iter().map(|i| i.bytes.len()).sum::<usize>(), 8);
+///     }
+///     Err(Error::NotFound) => {}
+///     Err(e) => panic!("{}", e),
+/// }
+/// ```
+pub fn disassemble(arch: Arch, code: &str) -> Result<Vec<Instruction>, Error> {
+    let output = run(arch, &["-show-encoding", "-output-asm-variant=1"], code)?;
+    Ok(output.lines().filter_map(parse_instruction).collect())
+}
+
+/// Parse a line like `mov\trax, 1    # encoding: [0x48,0xc7,0xc0,0x01,...]`.
+fn parse_instruction(line: &str) -> Option<Instruction> {
+    let (text, encoding) = line.split_once("encoding: [")?;
+    let text = text.trim_end();
+    // strip the comment starter, which differs between targets
+    let text = text.trim_end_matches(['#', '/', '@', ';']);
+    let text = text.split_whitespace().collect::<Vec<_>>();
+    let text = match text.split_first() {
+        Some((mnemonic, operands)) if !operands.is_empty() => {
+            format!("{} {}", mnemonic, operands.join(" "))
+        }
+        _ => text.join(" "),
+    };
+    let bytes = encoding
+        .trim_end_matches(']')
+        .split(',')
+        .map(|byte| {
+            if let Some(hex) = byte.strip_prefix("0x") {
+                u8::from_str_radix(hex, 16).unwrap_or(0)
+            } else if let Some(bits) = byte.strip_prefix("0b") {
+                // bits to be fixed up are marked by `A`
+                u8::from_str_radix(&bits.replace('A', "0"), 2).unwrap_or(0)
+            } else {
+                0
+            }
+        })
+        .collect();
+    Some(Instruction { text, bytes })
+}
+
+/// Assert that the code is accepted by the assembler for `arch`.
+///
+/// This is [`assemble`] for unit tests, which panics with the diagnostics and
+/// the code if the assembler rejects it. If the assembler is not installed,
+/// the skip is reported on the standard error and the assertion passes,
+/// unless the `ASM_BLOCK_REQUIRE_LLVM_MC` environment variable is set, e.g.
+/// in CI, in which case it panics as well.
+#[track_caller]
+pub fn assert_assembles(arch: Arch, code: &str) {
+    if let Err(e) = assemble(arch, code) {
+        fail(e, code);
+    }
+}
+
+/// Assert that the code is accepted by the assembler for `arch`, and return
+/// the encoded instructions.
+///
+/// This is [`disassemble`] for unit tests, with the behavior of
+/// [`assert_assembles`]. It returns `None` if the assembler is not installed,
+/// so that the checks of the instructions are skipped.
+#[track_caller]
+pub fn assert_disassembles(arch: Arch, code: &str) -> Option<Vec<Instruction>> {
+    match disassemble(arch, code) {
+        Ok(instructions) => Some(instructions),
+        Err(e) => {
+            fail(e, code);
+            None
+        }
+    }
+}
+
+#[track_caller]
+fn fail(e: Error, code: &str) {
+    match e {
+        Error::NotFound if env::var_os("ASM_BLOCK_REQUIRE_LLVM_MC").is_none() => {
+            eprintln!("skipped: {}", e)
+        }
+        e => panic!("{}\nin:\n{}", e, code),
+    }
+}
+
+/// Run the assembler and return its output.
+fn run(arch: Arch, args: &[&str], code: &str) -> Result<String, Error> {
+    let program = env::var_os("ASM_BLOCK_LLVM_MC").unwrap_or_else(|| OsString::from("llvm-mc"));
+    let mut child = Command::new(program)
+        .args(arch.args())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::NotFound,
+            _ => Error::Io(e),
+        })?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(code.as_bytes()).map_err(Error::Io)?;
+    // close stdin so that the assembler sees the end of input
+    drop(stdin);
+    let output = child.wait_with_output().map_err(Error::Io)?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(Error::Rejected(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ))
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble() {
+        let code = asm_block! {
+            mov rax, [rsp + 8];
+            lea rax, [rax + rbx*4];
+        };
+        assert_assembles(Arch::X86_64, code);
+        let code = asm_block! {
+            stp x29, x30, [sp, -16]!;
+            ldp x29, x30, [sp], 16;
+        };
+        assert_assembles(Arch::AArch64, code);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let code = asm_block! {
+            2:
+            mov rax, 1;
+            .p2align 4;
+            lea rax, [rax + rbx*4];
+            jne 2b;
+        };
+        if let Some(instructions) = assert_disassembles(Arch::X86_64, code) {
+            let texts = instructions
+                .iter()
+                .map(|i| i.text.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                texts,
+                ["mov rax, 1", "lea rax, [rax + 4*rbx]", "jne .Ltmp0"]
+            );
+            assert_eq!(instructions[1].bytes, [0x48, 0x8d, 0x04, 0x98]);
+            assert_eq!(instructions[2].mnemonic(), "jne");
+            assert_eq!(instructions[2].bytes, [0x75, 0]);
+        }
+        let code = asm_block! {
+            2:
+            b.ne 2b;
+            ret;
+        };
+        if let Some(instructions) = assert_disassembles(Arch::AArch64, code) {
+            assert_eq!(instructions[0].mnemonic(), "b.ne");
+            assert_eq!(instructions[0].bytes, [0b00000001, 0, 0, 0x54]);
+            assert_eq!(instructions[1].text, "ret");
+            assert_eq!(instructions[1].bytes, [0xc0, 0x03, 0x5f, 0xd6]);
+        }
+    }
+
+    #[test]
+    fn test_assert_assembles() {
+        assert_assembles(Arch::X86_64, asm_block!(nop;));
+        if let Some(instructions) = assert_disassembles(Arch::AArch64, asm_block!(nop; ret;)) {
+            assert_eq!(instructions.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_rejected() {
+        let code = asm_block! { mvo rax, rbx; };
+        match assemble(Arch::X86_64, code) {
+            Err(Error::Rejected(diagnostics)) => assert!(diagnostics.contains("mvo")),
+            Err(e @ Error::NotFound) => fail(e, code),
+            result => panic!("unexpected {:?}", result),
+        }
+    }
+}
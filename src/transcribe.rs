@@ -0,0 +1,727 @@
+//! Transcription of blocks at runtime.
+//!
+//! This requires the `std` feature. Build scripts and code generators which
+//! write `.s` files or Rust source cannot invoke [`asm_block!`] on the blocks
+//! they generate. [`asm_block`](fn.asm_block.html) translates the source of
+//! a block, as it would be written inside the macro, with the same rules, so
+//! that the generated code is spaced exactly as the expansion of the macro.
+//! The result can be written into Rust source as a literal with `{:?}`.
+//!
+//! Groups in `{}` are spaced as `stringify!` prints them, after the spacing
+//! of the source, except that `stringify!` breaks groups longer than a line
+//! into several lines, which is not reproduced.
+//!
+//! # Example
+//! ```
+//! use asm_block::asm_block;
+//! use asm_block::transcribe;
+//! let source = "mov {x}, [rsp + 8]; lea {x}, [{x} + {x} * 2];";
+//! let code = transcribe::asm_block(source).unwrap();
+//! assert_eq!(code, asm_block!(mov {x}, [rsp + 8]; lea {x}, [{x} + {x} * 2];));
+//! assert_eq!(
+//!     format!("{:?}", code),
+//!     r#""mov {x}, [rsp + 8 ] \nlea {x}, [{x}+ {x}* 2 ] \n""#
+//! );
+//! ```
+//!
+//! [`asm_block!`]: ../macro.asm_block.html
+
+use std::fmt;
+
+/// Error returned by [`asm_block`](fn.asm_block.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The source is not made of Rust tokens, with the offset in bytes where
+    /// lexing failed.
+    Lex(usize, &'static str),
+    /// The block is rejected by [`asm_block!`], with its message.
+    ///
+    /// [`asm_block!`]: ../macro.asm_block.html
+    Rejected(String),
+    /// The block contains `@inline(...)` or `@cfg(...)`. There is no macro to
+    /// invoke at runtime, and the target of a build script is not the target
+    /// of the code it generates.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Lex(offset, message) => write!(f, "{} at byte {}", message, offset),
+            Error::Rejected(message) => write!(f, "{}", message),
+            Error::Unsupported(construct) => {
+                write!(f, "`{}` cannot be transcribed at runtime", construct)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Translate the source of a block to assembly, as [`asm_block!`] does.
+///
+/// The source is lexed as Rust tokens, so that comments are dropped and
+/// delimiters must be balanced. `@origin(...)` follows the features of this
+/// crate, and the [module documentation](index.html) describes what cannot
+/// be transcribed.
+///
+/// [`asm_block!`]: ../macro.asm_block.html
+pub fn asm_block(source: &str) -> Result<String, Error> {
+    let tokens = Lexer { source, pos: 0 }.tokens(None)?;
+    let mut code = String::new();
+    transcribe(&tokens, &mut code)?;
+    Ok(code)
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+    Ident(&'a str),
+    Literal(&'a str),
+    Lifetime(&'a str),
+    Punct(&'a str),
+    /// The delimiter, the source of the group including it, and its tokens.
+    Group(char, &'a str, Vec<Token<'a>>),
+}
+
+impl<'a> Token<'a> {
+    /// The token as written by `stringify!`.
+    fn source(&self) -> &'a str {
+        match self {
+            Token::Ident(s) | Token::Literal(s) | Token::Lifetime(s) | Token::Punct(s) => s,
+            Token::Group(_, s, _) => s,
+        }
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self, Token::Punct(s) if *s == punct)
+    }
+
+    /// Whether the token matches the `ident` fragment specifier.
+    fn is_ident(&self) -> bool {
+        matches!(self, Token::Ident(s) if *s != "_")
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Ident(s) if *s == keyword)
+    }
+
+    fn parenthesized(&self) -> Option<&[Token<'a>]> {
+        match self {
+            Token::Group('(', _, tokens) => Some(tokens),
+            _ => None,
+        }
+    }
+}
+
+// longest first, so that the first match is the token
+const PUNCTS: &[&str] = &[
+    "<<=", ">>=", "...", "..=", "::", "->", "=>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "^=", "&=", "|=", "<<", ">>", "..", "~", "!", "@", ".", ",", ";", ":", "#",
+    "$", "?", "=", "<", ">", "-", "&", "|", "+", "*", "/", "^", "%",
+];
+
+struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn error<T>(&self, message: &'static str) -> Result<T, Error> {
+        Err(Error::Lex(self.pos, message))
+    }
+
+    /// Lex the tokens until the closing delimiter, or the end of the source.
+    fn tokens(&mut self, close: Option<char>) -> Result<Vec<Token<'a>>, Error> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip()?;
+            let start = self.pos;
+            let c = match self.peek(0) {
+                Some(c) => c,
+                None if close.is_none() => return Ok(tokens),
+                None => return self.error("unclosed delimiter"),
+            };
+            let token = match c {
+                ')' | ']' | '}' if Some(c) == close => {
+                    self.pos += 1;
+                    return Ok(tokens);
+                }
+                ')' | ']' | '}' => return self.error("unexpected closing delimiter"),
+                '(' | '[' | '{' => {
+                    self.pos += 1;
+                    let inner = self.tokens(Some(match c {
+                        '(' => ')',
+                        '[' => ']',
+                        _ => '}',
+                    }))?;
+                    Token::Group(c, &self.source[start..self.pos], inner)
+                }
+                '"' => self.string(start)?,
+                '\'' => self.quote(start)?,
+                'b' | 'c' | 'r' if self.is_prefixed_literal() => self.prefixed_literal(start)?,
+                c if c.is_ascii_digit() => self.number(start),
+                c if c == '_' || c.is_alphabetic() => {
+                    if self.rest().starts_with("r#") {
+                        self.pos += 2;
+                    }
+                    self.ident();
+                    Token::Ident(&self.source[start..self.pos])
+                }
+                _ => match PUNCTS.iter().find(|p| self.rest().starts_with(*p)) {
+                    Some(p) => {
+                        self.pos += p.len();
+                        Token::Punct(&self.source[start..self.pos])
+                    }
+                    None => return self.error("unknown token"),
+                },
+            };
+            tokens.push(token);
+        }
+    }
+
+    /// Skip whitespaces and comments, which the macro never sees.
+    fn skip(&mut self) -> Result<(), Error> {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("///") && !rest.starts_with("////")
+                || rest.starts_with("//!")
+                || rest.starts_with("/**") && !rest.starts_with("/**/") && !rest.starts_with("/***")
+                || rest.starts_with("/*!")
+            {
+                return Err(Error::Rejected(
+                    "doc comments are not supported inside `asm_block!`, use `//` instead".into(),
+                ));
+            } else if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                let mut depth = 0;
+                loop {
+                    let rest = self.rest();
+                    if rest.starts_with("/*") {
+                        depth += 1;
+                        self.pos += 2;
+                    } else if rest.starts_with("*/") {
+                        depth -= 1;
+                        self.pos += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else if let Some(c) = rest.chars().next() {
+                        self.pos += c.len_utf8();
+                    } else {
+                        return self.error("unterminated block comment");
+                    }
+                }
+            } else if let Some(c) = rest.chars().next().filter(|c| c.is_whitespace()) {
+                self.pos += c.len_utf8();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn ident(&mut self) {
+        while let Some(c) = self.peek(0).filter(|&c| c == '_' || c.is_alphanumeric()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn number(&mut self, start: usize) -> Token<'a> {
+        let radix = self.rest().starts_with("0x")
+            || self.rest().starts_with("0o")
+            || self.rest().starts_with("0b");
+        self.ident();
+        // a fraction must be followed by a digit, or `1.max` would be a float
+        if !radix && self.peek(0) == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+            self.ident();
+        }
+        let lexeme = &self.source[start..self.pos];
+        if !radix
+            && lexeme.ends_with(['e', 'E'])
+            && matches!(self.peek(0), Some('+' | '-'))
+            && self.peek(1).is_some_and(|c| c.is_ascii_digit())
+        {
+            self.pos += 1;
+            self.ident();
+        }
+        Token::Literal(&self.source[start..self.pos])
+    }
+
+    fn string(&mut self, start: usize) -> Result<Token<'a>, Error> {
+        self.pos += 1;
+        loop {
+            match self.peek(0) {
+                Some('"') => break,
+                Some('\\') => self.pos += 1 + self.peek(1).map_or(0, char::len_utf8),
+                Some(c) => self.pos += c.len_utf8(),
+                None => return self.error("unterminated string"),
+            }
+        }
+        self.pos += 1;
+        self.ident();
+        Ok(Token::Literal(&self.source[start..self.pos]))
+    }
+
+    fn raw_string(&mut self, start: usize) -> Result<Token<'a>, Error> {
+        let hashes = self.rest().len() - self.rest().trim_start_matches('#').len();
+        self.pos += hashes;
+        if self.peek(0) != Some('"') {
+            return self.error("expected a raw string");
+        }
+        let end = format!("\"{}", "#".repeat(hashes));
+        match self.rest()[1..].find(&end) {
+            Some(offset) => self.pos += 1 + offset + end.len(),
+            None => return self.error("unterminated raw string"),
+        }
+        self.ident();
+        Ok(Token::Literal(&self.source[start..self.pos]))
+    }
+
+    /// A character literal, or a lifetime.
+    fn quote(&mut self, start: usize) -> Result<Token<'a>, Error> {
+        let is_char = matches!(
+            (self.peek(1), self.peek(2)),
+            (Some('\\'), _) | (Some(_), Some('\''))
+        );
+        self.pos += 1;
+        if !is_char {
+            self.ident();
+            return Ok(Token::Lifetime(&self.source[start..self.pos]));
+        }
+        loop {
+            match self.peek(0) {
+                Some('\'') => break,
+                Some('\\') => self.pos += 1 + self.peek(1).map_or(0, char::len_utf8),
+                Some(c) => self.pos += c.len_utf8(),
+                None => return self.error("unterminated character"),
+            }
+        }
+        self.pos += 1;
+        self.ident();
+        Ok(Token::Literal(&self.source[start..self.pos]))
+    }
+
+    fn is_prefixed_literal(&self) -> bool {
+        let rest = self.rest();
+        ["b\"", "b'", "c\"", "br\"", "br#", "cr\"", "cr#", "r\""]
+            .iter()
+            .any(|p| rest.starts_with(p))
+            || rest.starts_with("r#") && rest[2..].starts_with(['"', '#'])
+    }
+
+    fn prefixed_literal(&mut self, start: usize) -> Result<Token<'a>, Error> {
+        let rest = self.rest();
+        let prefix = rest.len() - rest.trim_start_matches(['b', 'c']).len();
+        self.pos += prefix;
+        match self.peek(0) {
+            Some('r') => {
+                self.pos += 1;
+                self.raw_string(start)
+            }
+            Some('\'') => self.quote(start),
+            _ => self.string(start),
+        }
+    }
+}
+
+/// Transcribe the tokens, following the arms of `asm_block!` in order.
+fn transcribe(tokens: &[Token<'_>], code: &mut String) -> Result<(), Error> {
+    let mut i = 0;
+    while let Some(first) = tokens.get(i) {
+        let rest = &tokens[i + 1..];
+        i += 1;
+        if first.is_punct("#")
+            && matches!(rest.first(), Some(Token::Group('[', _, attr)) if is_doc(attr))
+        {
+            return Err(Error::Rejected(
+                "doc comments are not supported inside `asm_block!`, use `//` instead".into(),
+            ));
+        } else if first.is_punct(";") {
+            code.push('\n');
+        } else if first.is_ident()
+            && rest
+                .first()
+                .is_some_and(|t| t.is_punct(":") || t.is_punct("."))
+        {
+            code.push_str(first.source());
+        } else if first.is_ident() && rest.first().is_some_and(|t| t.is_punct("@")) {
+            code.push_str(first.source());
+            let spaced = ["arg", "raw", "inline", "cfg"]
+                .iter()
+                .any(|k| rest.get(1).is_some_and(|t| t.is_keyword(k)))
+                && rest.get(2).and_then(Token::parenthesized).is_some();
+            match rest.get(2).and_then(Token::parenthesized) {
+                Some([at]) if rest[1].is_keyword("arg") && at.is_punct("@") => {
+                    code.push_str("\\@");
+                    i += 3;
+                }
+                _ if spaced => code.push(' '),
+                _ => {}
+            }
+        } else if first.is_punct("@") && rest.first().is_some_and(|t| t.is_keyword("cfg")) {
+            return Err(Error::Unsupported("@cfg(...)"));
+        } else if first.is_punct("@") && rest.len() >= 2 && rest[1].parenthesized().is_some() {
+            let args = rest[1].parenthesized().unwrap_or_default();
+            i += 2;
+            match rest[0].source() {
+                "arg" => match args {
+                    [] => code.push_str("\\()"),
+                    [arg] => {
+                        code.push('\\');
+                        code.push_str(arg.source());
+                    }
+                    _ => {
+                        return Err(Error::Rejected(
+                            "`@arg(...)` accepts a single argument name, or `@` for `\\@`".into(),
+                        ));
+                    }
+                },
+                "raw" => raw(args, code)?,
+                "inline" => return Err(Error::Unsupported("@inline(...)")),
+                "origin" => {
+                    let name = origin(args)?;
+                    if cfg!(feature = "origin-comments") {
+                        code.push_str(&format!("/* {} */\n", name));
+                    }
+                    transcribe(&rest[2..], code)?;
+                    if cfg!(feature = "origin-markers") {
+                        code.push_str(&format!("/* end {} */\n", name));
+                    }
+                    return Ok(());
+                }
+                _ => {
+                    code.push('@');
+                    i -= 2;
+                }
+            }
+        } else if first.is_punct(":") || first.is_punct("@") {
+            code.push_str(first.source());
+        } else if first.is_punct(".")
+            && rest
+                .first()
+                .is_some_and(|t| ["+", "-", ";", ",", "="].iter().any(|p| t.is_punct(p)))
+        {
+            code.push_str(". ");
+        } else if first.is_punct(".") && !rest.is_empty() {
+            code.push('.');
+            stringify(&rest[0], code);
+            code.push(' ');
+            i += 1;
+        } else if let Token::Group('{', _, inner) = first {
+            code.push('{');
+            inner.iter().for_each(|t| stringify(t, code));
+            code.push('}');
+        } else if let Token::Group(open @ ('[' | '('), _, inner) = first {
+            code.push(*open);
+            transcribe(inner, code)?;
+            code.push_str(if *open == '[' { "] " } else { ") " });
+        } else {
+            code.push_str(first.source());
+            code.push(' ');
+        }
+    }
+    Ok(())
+}
+
+/// Write a token as `stringify!` does, which spaces the tokens of a group
+/// after the source, except for the cases where the printer of rustc never
+/// puts a space.
+fn stringify(token: &Token<'_>, code: &mut String) {
+    let (open, source, inner) = match token {
+        Token::Group(open, source, inner) => (*open, *source, inner),
+        _ => return code.push_str(token.source()),
+    };
+    // only braces keep a space inside, if there is one after the opening brace
+    let padded = open == '{' && inner.first().is_some_and(|t| gap(&source[..1], t.source()));
+    code.push(open);
+    if padded {
+        code.push(' ');
+    }
+    for (i, token) in inner.iter().enumerate() {
+        stringify(token, code);
+        match inner.get(i + 1) {
+            Some(next) if gap(token.source(), next.source()) && space_between(token, next) => {
+                code.push(' ')
+            }
+            _ => {}
+        }
+    }
+    if padded {
+        code.push(' ');
+    }
+    code.push(match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    });
+}
+
+/// Whether there are whitespaces or comments between two consecutive tokens,
+/// which are both slices of the source.
+fn gap(token: &str, next: &str) -> bool {
+    next.as_ptr() as usize > token.as_ptr() as usize + token.len()
+}
+
+/// Whether the printer of rustc puts a space between two tokens which are
+/// apart in the source.
+fn space_between(token: &Token<'_>, next: &Token<'_>) -> bool {
+    let is_reserved = |ident: &str| !ident.starts_with("r#") && RESERVED.contains(&ident);
+    match (token, next) {
+        (Token::Punct("."), _) => matches!(next, Token::Punct(_)),
+        (Token::Punct("$"), Token::Ident(_)) => false,
+        (_, Token::Punct(",")) | (_, Token::Punct(";")) | (_, Token::Punct(".")) => {
+            matches!(token, Token::Punct(_))
+        }
+        (Token::Ident(ident), Token::Group('(', ..)) => {
+            is_reserved(ident) && !["fn", "Self", "pub"].contains(ident)
+        }
+        (Token::Ident(ident), Token::Punct("!")) => is_reserved(ident),
+        (Token::Punct("#"), Token::Group('[', ..)) => false,
+        _ => true,
+    }
+}
+
+// the identifiers reserved in the 2018 edition
+const RESERVED: &[&str] = &[
+    "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Whether an attribute is `doc = "..."`, as lowered from a doc comment.
+fn is_doc(attr: &[Token<'_>]) -> bool {
+    matches!(attr, [doc, eq, Token::Literal(_)] if doc.is_keyword("doc") && eq.is_punct("="))
+}
+
+/// The name of a fragment in `@origin(...)`, a path of identifiers.
+// `is_multiple_of` is too recent for the compilers supported by the crate
+#[allow(clippy::manual_is_multiple_of)]
+fn origin(args: &[Token<'_>]) -> Result<String, Error> {
+    let mut name = String::new();
+    for (i, token) in args.iter().enumerate() {
+        let valid = if i % 2 == 0 {
+            token.is_ident()
+        } else {
+            token.is_punct("::")
+        };
+        if !valid {
+            return Err(Error::Rejected(format!(
+                "no rules expected `{}` in `@origin(...)`",
+                token.source()
+            )));
+        }
+        name.push_str(token.source());
+    }
+    // a path has an odd number of tokens, ending with an identifier
+    if args.len() % 2 == 0 {
+        return Err(Error::Rejected(
+            "`@origin(...)` expects the name of a fragment".into(),
+        ));
+    }
+    Ok(name)
+}
+
+/// Splice the literals of `@raw(...)` as `concat!` does.
+fn raw(args: &[Token<'_>], code: &mut String) -> Result<(), Error> {
+    let invalid = || {
+        Error::Rejected("`@raw(...)` only accepts string literals, e.g. `@raw(\"\\\\reg\")`".into())
+    };
+    let mut i = 0;
+    while i < args.len() {
+        let negative = args[i].is_punct("-");
+        if negative {
+            code.push('-');
+            i += 1;
+        }
+        match args.get(i) {
+            Some(Token::Literal(literal)) => code.push_str(&value(literal).ok_or_else(invalid)?),
+            Some(token) if !negative && (token.is_keyword("true") || token.is_keyword("false")) => {
+                code.push_str(token.source())
+            }
+            _ => return Err(invalid()),
+        }
+        match args.get(i + 1) {
+            Some(comma) if comma.is_punct(",") => i += 2,
+            None => i += 1,
+            Some(_) => return Err(invalid()),
+        }
+    }
+    Ok(())
+}
+
+/// The value of a literal in `concat!`, where numbers are written in decimal.
+fn value(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let end = raw.rfind('"')?;
+        return Some(raw[hashes + 1..end].to_string());
+    }
+    if literal.starts_with('"') || literal.starts_with('\'') {
+        let quote = literal.as_bytes()[0] as char;
+        let end = literal.rfind(quote)?;
+        return unescape(&literal[1..end]);
+    }
+    if literal.starts_with(['b', 'c']) || !literal.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let digits = literal.replace('_', "");
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, &digits[..]),
+    };
+    if radix == 10 && digits.contains(['.', 'e', 'E'])
+        || digits.ends_with("f32")
+        || digits.ends_with("f64")
+    {
+        return Some(
+            digits
+                .trim_end_matches("f32")
+                .trim_end_matches("f64")
+                .to_string(),
+        );
+    }
+    let end = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(digits.len());
+    let value = u128::from_str_radix(&digits[..end], radix).ok()?;
+    Some(value.to_string())
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            '\\' => value.push('\\'),
+            '\'' => value.push('\''),
+            '"' => value.push('"'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                value.push(u8::from_str_radix(&hex, 16).ok().filter(u8::is_ascii)? as char);
+            }
+            'u' => {
+                let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                value.push(char::from_u32(
+                    u32::from_str_radix(&hex.replace('_', ""), 16).ok()?,
+                )?);
+            }
+            '\n' => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(asm_block, same, spaced)]
+mod tests {
+    use super::*;
+
+    // transcribe the tokens at runtime and compare with the macro
+    macro_rules! same {
+        ($($token: tt)*) => {
+            assert_eq!(asm_block(stringify!($($token)*)).as_deref(), Ok(asm_block!($($token)*)))
+        };
+    }
+
+    #[test]
+    fn test_asm_block() {
+        same!();
+        same!(mov rax, [rsp + 8]; lea {x}, [{x} + {x:e} * 2];);
+        same!(2: jne 2b; loop: jmp loop;);
+        same!(.p2align 4; .quad . - 2b, .+8; x.y; a ., .);
+        same!(ldr x0, [sp, -16]!; b.ne 1f; movk x1, 0x1234, lsl 16;);
+        same!(add a1, r1 << 2; cmp rax, -1; mov eax, 'x; a::b => c;);
+        same!(.macro m a; mov @arg(a), @arg(); x @arg(@); movq @arg(@); .endm;);
+        same!(mov x @raw("\\", "a\tb"), @raw(0x10, -1, 1.5, 'c', true, r"\r"); y@x;);
+        same!(({x}) [(a, b)] {(a, b)});
+        same!(@origin(a::b) nop;);
+        same!(foo @ bar(x));
+    }
+
+    // the spacing of the source reaches the macro in groups, which `same!`
+    // normalizes
+    macro_rules! spaced {
+        ($source: literal, $($token: tt)*) => {
+            assert_eq!(asm_block($source).as_deref(), Ok(asm_block!($($token)*)))
+        };
+    }
+
+    #[test]
+    fn test_spacing() {
+        spaced!("{[x  +  y]}", {[x  +  y]});
+        spaced!("{[x+y] [x +y] [x+ -1] [x- -1]}", {[x+y] [x +y] [x+ -1] [x- -1]});
+        spaced!("{(a , b) (a ,b) [a ; b] [a . b] [. a]}", {(a , b) (a ,b) [a ; b] [a . b] [. a]});
+        spaced!("{{ a } {a } { a} { } [ a ]}", {{ a } {a } { a} { } [ a ]});
+        spaced!("{[f (x)] [if (x)] [r#if (x)] [Self (x)] [a ! b] [if ! b]}", {[f (x)] [if (x)] [r#if (x)] [Self (x)] [a ! b] [if ! b]});
+        spaced!("{[$ x] [$ 1] [# [a]] [# a] [a :: b] [(a) (b)] [(a)(b)]}", {[$ x] [$ 1] [# [a]] [# a] [a :: b] [(a) (b)] [(a)(b)]});
+        spaced!("{[a /* b */ c\n, d]}", {[a /* b */ c
+, d]});
+        spaced!(".[a  ,  b]  .(x)", .[a  ,  b]  .(x));
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_eq!(
+            asm_block("nop; // nop\n/* a /* b */ */ ret"),
+            Ok("nop \nret ".into())
+        );
+        assert!(matches!(asm_block("/// nop\nnop"), Err(Error::Rejected(_))));
+        assert!(matches!(
+            asm_block("#[doc = \"nop\"] nop"),
+            Err(Error::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            asm_block("mov [rax"),
+            Err(Error::Lex(8, "unclosed delimiter"))
+        );
+        assert_eq!(
+            asm_block("mov rax]"),
+            Err(Error::Lex(7, "unexpected closing delimiter"))
+        );
+        assert_eq!(
+            asm_block("mov \"rax"),
+            Err(Error::Lex(8, "unterminated string"))
+        );
+        assert!(matches!(asm_block("@arg(a b)"), Err(Error::Rejected(_))));
+        assert!(matches!(asm_block("@raw(a)"), Err(Error::Rejected(_))));
+        assert!(matches!(asm_block("@origin(1)"), Err(Error::Rejected(_))));
+        assert_eq!(
+            asm_block("@inline(f!())"),
+            Err(Error::Unsupported("@inline(...)"))
+        );
+        assert_eq!(
+            asm_block("nop @cfg(unix) { nop; }"),
+            Err(Error::Unsupported("@cfg(...)"))
+        );
+    }
+}
@@ -0,0 +1,186 @@
+/// Generate an interrupt or exception vector table.
+///
+/// This evaluates to a `&'static str` which places the table in its own
+/// section with [`asm_section!`]'s directives, aligns it, and emits one entry
+/// per symbol of the list, where `_` stands for the `default` handler. The
+/// first argument selects the layout of the entries:
+///
+/// | Layout              | Entry                              | Alignment | Flags  |
+/// |---------------------|------------------------------------|-----------|--------|
+/// | `words`             | `.long handler` or `.quad handler` | pointer   | `"a"`  |
+/// | `branches`, ARM     | `b handler`                        | 32        | `"ax"` |
+/// | `branches`, AArch64 | `b handler`, `.balign 128`         | 2048      | `"ax"` |
+///
+/// `words` is the table of addresses of Cortex-M, and of most interrupt
+/// controllers, whose entries have the width of a pointer. The low bit of the
+/// address of a Thumb function is set by the linker, and the first entry of
+/// Cortex-M is the initial stack pointer, which is given as a symbol like any
+/// other, e.g. `__stack_top`. `branches` is the table of instructions of
+/// Cortex-A, which is executed rather than read: ARM has eight entries of one
+/// instruction, starting with reset, and AArch64 sixteen entries of 128
+/// bytes, which are jumped to by `VBAR_EL1` and friends.
+///
+/// The alignment can be raised with `align = N`, which `VTOR` of Cortex-M
+/// needs to be the size of the table rounded up to a power of two. With
+/// `len = N`, the table is filled up to `N` entries with the default handler
+/// by `.rept`, so that only the first ones need to be listed, and the
+/// assembler rejects a list longer than that. With `label = name`, a global
+/// label is defined at the start of the table, so that it can be loaded with
+/// [`load_address!`] or referred to by a `sym` operand.
+///
+/// The symbols are transcribed by [`asm_block!`], so they can be placeholders
+/// of `sym` operands, which only [`global_asm!`] allows in a section switch.
+///
+/// # Example
+/// ```
+/// use asm_block::vector_table;
+/// let code = vector_table!(words, ".vector_table", default = DefaultHandler, len = 16, label = VECTORS, [
+///     __stack_top, Reset, Nmi, HardFault, _, _, {usage},
+/// ]);
+/// # #[cfg(all(target_os = "linux", target_pointer_width = "32"))]
+/// assert_eq!(
+///     code,
+///     ".pushsection .vector_table, \"a\"\n.balign 4\n.globl VECTORS \nVECTORS:\
+///      .long __stack_top \n.long Reset \n.long Nmi \n.long HardFault \n\
+///      .long DefaultHandler \n.long DefaultHandler \n.long {usage}\n\
+///      .rept 16 - (0+1+1+1+1+1+1+1)\n.long DefaultHandler \n.endr\n.popsection\n"
+/// );
+/// # #[cfg(target_arch = "aarch64")]
+/// assert!(vector_table!(branches, ".vectors", default = unexpected, [sync_el1, irq_el1])
+///     .starts_with(".pushsection .vectors, \"ax\"\n.balign 2048\nb sync_el1 \n.balign 128\n"));
+/// ```
+///
+/// [`asm_section!`]: macro.asm_section.html
+/// [`asm_block!`]: macro.asm_block.html
+/// [`load_address!`]: macro.load_address.html
+/// [`global_asm!`]: https://doc.rust-lang.org/stable/core/arch/macro.global_asm.html
+#[macro_export]
+macro_rules! vector_table {
+    // one entry, with `_` replaced by the default handler
+    (@entry $layout: ident $default: tt _) => {
+        $crate::vector_table!(@entry $layout $default $default)
+    };
+    (@entry words $default: tt $handler: tt) => {
+        concat!($crate::__asm_vector_word!(), $crate::asm_block!($handler), "\n")
+    };
+    (@entry branches $default: tt $handler: tt) => {
+        $crate::__asm_vector!(@branch $handler)
+    };
+    (@entry $layout: ident $default: tt $handler: tt) => { "" };
+
+    (@begin words $name: literal) => { $crate::__asm_section!(@begin $name, "a") };
+    (@begin branches $name: literal) => { $crate::__asm_section!(@begin $name, "ax") };
+    (@begin $layout: ident $name: literal) => {
+        compile_error!(concat!("expected a layout of `words` or `branches`, found `", stringify!($layout), "`"))
+    };
+
+    (@align words) => { $crate::__asm_vector_word!(@size) };
+    (@align branches) => { $crate::__asm_vector!(@align) };
+    (@align $layout: ident) => { "" };
+    (@align $layout: ident $align: literal) => { $align };
+
+    // fill the table up to `len` entries, whose count is summed by the assembler
+    (@fill $layout: ident $default: tt [$($entry: tt)*]) => { "" };
+    (@fill $layout: ident $default: tt [$($entry: tt)*] $len: literal) => {
+        concat!(
+            ".rept ", $len, " - (0", $($crate::vector_table!(@one $entry),)* ")\n",
+            $crate::vector_table!(@entry $layout $default $default),
+            ".endr\n"
+        )
+    };
+    (@one $entry: tt) => { "+1" };
+
+    (
+        $layout: ident, $name: literal, default = $default: tt $(, len = $len: literal)? $(, align = $align: literal)?
+        $(, label = $label: tt)?, [$($entry: tt),* $(,)?] $(,)?
+    ) => {
+        concat!(
+            $crate::vector_table!(@begin $layout $name),
+            ".balign ", $crate::vector_table!(@align $layout $($align)?), "\n",
+            $($crate::asm_block!(.globl $label; $label:),)?
+            $($crate::vector_table!(@entry $layout $default $entry),)*
+            $crate::vector_table!(@fill $layout $default [$($entry)*] $($len)?),
+            $crate::__asm_section!(@end)
+        )
+    };
+}
+
+#[cfg(target_pointer_width = "32")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_vector_word {
+    () => { ".long " };
+    (@size) => { 4 };
+}
+
+#[cfg(target_pointer_width = "64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_vector_word {
+    () => { ".quad " };
+    (@size) => { 8 };
+}
+
+#[cfg(not(any(target_pointer_width = "32", target_pointer_width = "64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_vector_word {
+    ($($token: tt)*) => {
+        compile_error!("vector tables of words are only supported on 32-bit and 64-bit targets")
+    };
+}
+
+#[cfg(target_arch = "arm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_vector {
+    (@align) => { 32 };
+    (@branch $handler: tt) => { $crate::asm_block!(b $handler;) };
+}
+
+#[cfg(target_arch = "aarch64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_vector {
+    (@align) => { 2048 };
+    (@branch $handler: tt) => { $crate::asm_block!(b $handler; .balign 128;) };
+}
+
+#[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __asm_vector {
+    ($($token: tt)*) => {
+        compile_error!("vector tables of branches are only supported on ARM and AArch64")
+    };
+}
+
+#[cfg(test)]
+#[rustfmt::skip::macros(vector_table)]
+mod tests {
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_vector_table() {
+        assert_eq!(
+            vector_table!(words, ".rodata.vectors", default = fault, [_, {f}, handler]),
+            ".pushsection .rodata.vectors, \"a\"\n.balign 8\n\
+             .quad fault \n.quad {f}\n.quad handler \n.popsection\n"
+        );
+        assert_eq!(
+            vector_table!(words, ".rodata.vectors", default = fault, len = 2, align = 128, [],),
+            ".pushsection .rodata.vectors, \"a\"\n.balign 128\n\
+             .rept 2 - (0)\n.quad fault \n.endr\n.popsection\n"
+        );
+    }
+
+    #[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_vector_table_assemble() {
+        use crate::test::{assert_assembles, Arch};
+        let code = concat!(
+            "fault:\nhandler:\nret\n",
+            vector_table!(words, ".rodata.vectors", default = fault, len = 8, label = vectors, [handler, _, handler]),
+        );
+        assert_assembles(Arch::X86_64, code);
+    }
+}